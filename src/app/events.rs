@@ -8,10 +8,15 @@ use std::cell::RefCell;
 use std::rc::Rc;
 use std::path::Path;
 
-use crate::app::menu::MenuItems;
+use crate::app::menu::{self, MenuItems};
 use crate::config;
 use crate::db_viewer;
 use crate::export;
+use crate::keyboard_layout::KeyboardLayout;
+use crate::ui::common::{fill_layout_choice, selected_layout};
+use crate::ui::inventory::components::{
+    show_categories_window, show_locations_window, show_reorder_list, show_stocktake_window, show_trash_window,
+};
 use crate::sync::gdrive_sync;
 use crate::sync::check_for_import_files;
 
@@ -19,10 +24,10 @@ use crate::sync::check_for_import_files;
 pub fn run_event_loop(
     app: app::App,
     receiver: app::Receiver<String>,
-    keyboard_layout: Rc<RefCell<i32>>,
+    keyboard_layout: Rc<RefCell<KeyboardLayout>>,
     app_config: Rc<RefCell<config::AppConfig>>,
     card_data_buffer: Rc<RefCell<fltk::text::TextBuffer>>,
-    inventory_ui: Rc<crate::inventory::InventoryUI>,
+    inventory_ui: Rc<crate::ui::inventory::InventoryUI>,
     mut menu_items: MenuItems
 ) {
     // this is where we update menu items with actual data
@@ -47,6 +52,14 @@ fn handle_menu_event(msg: String, menu_items: &MenuItems) {
     let card_buffer = &menu_items.card_buffer;
     let inventory_ui = &menu_items.inventory_ui;
     
+    if let Some(name) = msg.strip_prefix(menu::KEYBOARD_LAYOUT_MESSAGE) {
+        let layout = KeyboardLayout::from_name(name);
+        *keyboard_layout.borrow_mut() = layout.clone();
+        config.borrow_mut().default_keyboard_layout = layout;
+        let _ = config::save_config(&config.borrow());
+        return;
+    }
+    
     match msg.as_str() {
         "exit" => {
             app::quit();
@@ -57,32 +70,27 @@ fn handle_menu_event(msg: String, menu_items: &MenuItems) {
         "preferences" => {
             show_preferences_dialog(keyboard_layout, config);
         },
-        "kb_auto" => {
-            *keyboard_layout.borrow_mut() = 0;
-            config.borrow_mut().default_keyboard_layout = 0;
-            let _ = config::save_config(&config.borrow());
-        },
-        "kb_windows" => {
-            *keyboard_layout.borrow_mut() = 1;
-            config.borrow_mut().default_keyboard_layout = 1;
-            let _ = config::save_config(&config.borrow());
-        },
-        "kb_mac_us" => {
-            *keyboard_layout.borrow_mut() = 2;
-            config.borrow_mut().default_keyboard_layout = 2;
-            let _ = config::save_config(&config.borrow());
-        },
-        "kb_mac_intl" => {
-            *keyboard_layout.borrow_mut() = 3;
-            config.borrow_mut().default_keyboard_layout = 3;
-            let _ = config::save_config(&config.borrow());
-        },
         "export_csv" => handle_export_csv(card_buffer),
         "export_json" => handle_export_json(card_buffer),
         "export_text" => handle_export_text(card_buffer),
         "view_database" => {
             db_viewer::show_database_viewer(inventory_ui);
         },
+        "locations" => {
+            show_locations_window(inventory_ui.inventory_db.clone(), keyboard_layout.clone());
+        },
+        "categories" => {
+            show_categories_window(inventory_ui.inventory_db.clone());
+        },
+        "stocktake" => {
+            show_stocktake_window(inventory_ui.inventory_db.clone(), keyboard_layout.clone());
+        },
+        "reorder" => {
+            show_reorder_list(&inventory_ui.inventory_db.borrow());
+        },
+        "trash" => {
+            show_trash_window(inventory_ui.inventory_db.clone());
+        },
         "check_files" => handle_check_files(inventory_ui),
         "gdrive_export" => handle_gdrive_export(inventory_ui, config),
         "gdrive_import" => handle_gdrive_import(inventory_ui, config),
@@ -128,12 +136,12 @@ fn handle_export_text(card_buffer: &Rc<RefCell<fltk::text::TextBuffer>>) {
     }
 }
 
-fn handle_check_files(inventory_ui: &Rc<crate::inventory::InventoryUI>) {
+fn handle_check_files(inventory_ui: &Rc<crate::ui::inventory::InventoryUI>) {
     let import_dir = "./import";
     let processed_dir = "./processed";
     let error_dir = "./error";
     
-    match check_for_import_files(import_dir, processed_dir, error_dir, inventory_ui) {
+    match check_for_import_files(import_dir, processed_dir, error_dir, &inventory_ui.inventory_db.borrow()) {
         Ok(count) => {
             if count > 0 {
                 dialog::message(300, 300, &format!("Successfully processed {} files.", count));
@@ -148,7 +156,7 @@ fn handle_check_files(inventory_ui: &Rc<crate::inventory::InventoryUI>) {
 }
 
 fn handle_gdrive_export(
-    inventory_ui: &Rc<crate::inventory::InventoryUI>,
+    inventory_ui: &Rc<crate::ui::inventory::InventoryUI>,
     config: &Rc<RefCell<config::AppConfig>>
 ) {
     if config.borrow().gdrive_sync_enabled {
@@ -168,7 +176,7 @@ fn handle_gdrive_export(
 }
 
 fn handle_gdrive_import(
-    inventory_ui: &Rc<crate::inventory::InventoryUI>,
+    inventory_ui: &Rc<crate::ui::inventory::InventoryUI>,
     config: &Rc<RefCell<config::AppConfig>>
 ) {
     if config.borrow().gdrive_sync_enabled {
@@ -187,7 +195,7 @@ fn handle_gdrive_import(
     }
 }
 
-fn handle_import_data(inventory_ui: &Rc<crate::inventory::InventoryUI>) {
+fn handle_import_data(inventory_ui: &Rc<crate::ui::inventory::InventoryUI>) {
     if let Some(path) = dialog::file_chooser("Import data", "*.{json,csv}", ".", true) {
         if !Path::new(&path).exists() {
             dialog::alert(300, 300, &format!("File does not exist: {}", path));
@@ -219,7 +227,7 @@ fn handle_import_data(inventory_ui: &Rc<crate::inventory::InventoryUI>) {
 }
 
 fn show_preferences_dialog(
-    keyboard_layout: &Rc<RefCell<i32>>,
+    keyboard_layout: &Rc<RefCell<KeyboardLayout>>,
     config: &Rc<RefCell<config::AppConfig>>
 ) {
     // create the preferences window and its components
@@ -243,11 +251,7 @@ fn show_preferences_dialog(
     let _layout_choice_text = fltk::frame::Frame::new(20, 105, 120, 25, "Keyboard Layout:");
     
     let mut layout_choice = fltk::menu::Choice::new(140, 105, 240, 25, "");
-    layout_choice.add_choice("Auto-detect");
-    layout_choice.add_choice("Windows");
-    layout_choice.add_choice("Mac US");
-    layout_choice.add_choice("Mac International");
-    layout_choice.set_value(config.borrow().default_keyboard_layout);
+    fill_layout_choice(&mut layout_choice, &config.borrow().default_keyboard_layout);
     
     general_tab.end();
     
@@ -298,7 +302,7 @@ fn show_preferences_dialog(
         let mut config = config_clone_ok.borrow_mut();
         config.save_logs = save_logs_check.is_checked();
         config.log_directory = log_dir_input.value();
-        config.default_keyboard_layout = layout_choice.value();
+        config.default_keyboard_layout = selected_layout(&layout_choice);
         
         // these are the Google Drive sync settings
         config.gdrive_sync_enabled = gdrive_enable_check.is_checked();
@@ -318,7 +322,7 @@ fn show_preferences_dialog(
         let _ = config::save_config(&config);
         
         // updates the keyboard layout and mutable because we are changing it
-        *keyboard_layout_ok.borrow_mut() = config.default_keyboard_layout;
+        *keyboard_layout_ok.borrow_mut() = config.default_keyboard_layout.clone();
         
         prefs_win_ok.borrow_mut().hide();
     });
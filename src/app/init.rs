@@ -12,7 +12,7 @@ use std::rc::Rc;
 use crate::config;
 use crate::app::menu;
 use crate::app::events;
-use crate::inventory::InventoryUI;
+use crate::ui::inventory::InventoryUI;
 use crate::reader;
 
 pub fn run() {
@@ -31,13 +31,13 @@ pub fn run() {
     let app_config = Rc::new(RefCell::new(config::load_config()));
     
     // Create shared state for keyboard layout selection
-    let keyboard_layout = Rc::new(RefCell::new(app_config.borrow().default_keyboard_layout));
+    let keyboard_layout = Rc::new(RefCell::new(app_config.borrow().default_keyboard_layout.clone()));
     
     // Create card data buffer to share between tabs
     let card_data_buffer = Rc::new(RefCell::new(fltk::text::TextBuffer::default()));
     
     // Create the basic UI tabs first
-    crate::ui::create_reader_tab(&mut tabs, keyboard_layout.clone(), card_data_buffer.clone());
+    crate::ui::create_reader_tab(&mut tabs, keyboard_layout.clone(), card_data_buffer.clone(), app_config.clone());
     crate::ui::create_conversion_tab(&mut tabs, keyboard_layout.clone());
     crate::ui::create_batch_tab(&mut tabs, keyboard_layout.clone());
     
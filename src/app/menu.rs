@@ -1,17 +1,25 @@
 // app/menu.rs
 use fltk::{
     app,
+    enums::Event,
     prelude::*,
     menu::{MenuBar, MenuFlag},
 };
 use std::rc::Rc;
 use std::cell::RefCell;
 
+use crate::keyboard_layout::KeyboardLayout;
+
+const KEYBOARD_LAYOUT_MENU: &str = "&Edit/&Keyboard Layout";
+
+/// Prefix of the menu message that selects a keyboard layout; the layout name follows
+pub const KEYBOARD_LAYOUT_MESSAGE: &str = "keyboard_layout:";
+
 pub struct MenuItems {
-    pub keyboard_layout: Rc<RefCell<i32>>,
+    pub keyboard_layout: Rc<RefCell<KeyboardLayout>>,
     pub config: Rc<RefCell<crate::config::AppConfig>>,
     pub card_buffer: Rc<RefCell<fltk::text::TextBuffer>>,
-    pub inventory_ui: Rc<crate::inventory::InventoryUI>,
+    pub inventory_ui: Rc<crate::ui::inventory::InventoryUI>,
     
 }
 
@@ -33,10 +41,10 @@ pub fn create_menu(wind: &mut fltk::window::Window) -> (app::Receiver<String>, M
     
     // Return the receiver and empty menu items (to be populated later)
     (receiver, MenuItems {
-        keyboard_layout: Rc::new(RefCell::new(0)),
+        keyboard_layout: Rc::new(RefCell::new(KeyboardLayout::AutoDetect)),
         config: Rc::new(RefCell::new(crate::config::AppConfig::default())),
         card_buffer: Rc::new(RefCell::new(fltk::text::TextBuffer::default())),
-        inventory_ui: Rc::new(crate::inventory::InventoryUI::new("").unwrap()), // This will be replaced
+        inventory_ui: Rc::new(crate::ui::inventory::InventoryUI::new("").unwrap()), // This will be replaced
     })
 }
 
@@ -52,6 +60,11 @@ fn add_file_menu(menu: &mut MenuBar, sender: &app::Sender<String>) {
     let sender_check_files = sender.clone();
     let sender_gdrive_export = sender.clone();
     let sender_gdrive_import = sender.clone();
+    let sender_stocktake = sender.clone();
+    let sender_locations = sender.clone();
+    let sender_categories = sender.clone();
+    let sender_reorder = sender.clone();
+    let sender_trash = sender.clone();
     
     // Add menu items
     menu.add(
@@ -89,6 +102,41 @@ fn add_file_menu(menu: &mut MenuBar, sender: &app::Sender<String>) {
         move |_| { sender_view_db.send("view_database".to_string()); }
     );
     
+    menu.add(
+        "&File/&Locations...\t",
+        fltk::enums::Shortcut::None,
+        MenuFlag::Normal,
+        move |_| { sender_locations.send("locations".to_string()); }
+    );
+    
+    menu.add(
+        "&File/&Categories...\t",
+        fltk::enums::Shortcut::None,
+        MenuFlag::Normal,
+        move |_| { sender_categories.send("categories".to_string()); }
+    );
+    
+    menu.add(
+        "&File/Stock&take...\t",
+        fltk::enums::Shortcut::None,
+        MenuFlag::Normal,
+        move |_| { sender_stocktake.send("stocktake".to_string()); }
+    );
+    
+    menu.add(
+        "&File/&Reorder List...\t",
+        fltk::enums::Shortcut::None,
+        MenuFlag::Normal,
+        move |_| { sender_reorder.send("reorder".to_string()); }
+    );
+    
+    menu.add(
+        "&File/&Trash...\t",
+        fltk::enums::Shortcut::None,
+        MenuFlag::Normal,
+        move |_| { sender_trash.send("trash".to_string()); }
+    );
+    
     menu.add(
         "&File/&Check Import Files\t",
         fltk::enums::Shortcut::Ctrl | 'r',
@@ -127,10 +175,6 @@ fn add_file_menu(menu: &mut MenuBar, sender: &app::Sender<String>) {
 
 fn add_edit_menu(menu: &mut MenuBar, sender: &app::Sender<String>) {
     let sender_pref = sender.clone();
-    let sender_kb_auto = sender.clone();
-    let sender_kb_win = sender.clone();
-    let sender_kb_mac = sender.clone();
-    let sender_kb_intl = sender.clone();
     
     menu.add(
         "&Edit/&Preferences\t",
@@ -139,33 +183,39 @@ fn add_edit_menu(menu: &mut MenuBar, sender: &app::Sender<String>) {
         move |_| { sender_pref.send("preferences".to_string()); }
     );
     
-    menu.add(
-        "&Edit/&Keyboard Layout/&Auto-detect\t",
-        fltk::enums::Shortcut::None,
-        MenuFlag::Normal,
-        move |_| { sender_kb_auto.send("kb_auto".to_string()); }
-    );
-    
-    menu.add(
-        "&Edit/&Keyboard Layout/&Windows\t",
-        fltk::enums::Shortcut::None,
-        MenuFlag::Normal,
-        move |_| { sender_kb_win.send("kb_windows".to_string()); }
-    );
-    
-    menu.add(
-        "&Edit/&Keyboard Layout/&Mac US\t",
-        fltk::enums::Shortcut::None,
-        MenuFlag::Normal,
-        move |_| { sender_kb_mac.send("kb_mac_us".to_string()); }
-    );
-    
-    menu.add(
-        "&Edit/&Keyboard Layout/Mac &International\t",
-        fltk::enums::Shortcut::None,
-        MenuFlag::Normal,
-        move |_| { sender_kb_intl.send("kb_mac_intl".to_string()); }
-    );
+    add_keyboard_layout_menu(menu, sender);
+}
+
+/// Add Edit > Keyboard Layout with one entry per known layout
+pub fn add_keyboard_layout_menu(menu: &mut MenuBar, sender: &app::Sender<String>) {
+    fill_keyboard_layout_menu(menu, sender);
+    
+    // Layouts can be added while running (calibration), so rebuild the entries before the menu opens
+    let sender = sender.clone();
+    menu.handle(move |m, ev| {
+        if ev == Event::Push {
+            fill_keyboard_layout_menu(m, &sender);
+        }
+        false
+    });
+}
+
+fn fill_keyboard_layout_menu(menu: &mut MenuBar, sender: &app::Sender<String>) {
+    let index = menu.find_index(KEYBOARD_LAYOUT_MENU);
+    if index >= 0 {
+        let _ = menu.clear_submenu(index);
+    }
+    
+    for layout in KeyboardLayout::all() {
+        let sender = sender.clone();
+        let message = format!("{}{}", KEYBOARD_LAYOUT_MESSAGE, layout.name());
+        menu.add(
+            &format!("{}/{}\t", KEYBOARD_LAYOUT_MENU, layout.name()),
+            fltk::enums::Shortcut::None,
+            MenuFlag::Normal,
+            move |_| { sender.send(message.clone()); }
+        );
+    }
 }
 
 fn add_help_menu(menu: &mut MenuBar, sender: &app::Sender<String>) {
@@ -0,0 +1,38 @@
+// /batch/decode.rs
+use crate::export::CardRecord;
+use crate::keyboard_layout::KeyboardLayout;
+use crate::uid::{Uid, UidError};
+use crate::utils;
+
+/// Outcome of decoding one line of batch input
+#[derive(Debug, Clone)]
+pub struct BatchLine {
+    /// 1-based line number in the original input
+    pub line_number: usize,
+    pub raw: String,
+    pub result: Result<CardRecord, UidError>,
+}
+
+/// Decode every non-empty line of `text` into a card record
+pub fn decode_batch(text: &str, kb_layout: &KeyboardLayout) -> Vec<BatchLine> {
+    let (_, human_timestamp) = utils::get_timestamps();
+
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| BatchLine {
+            line_number: i + 1,
+            raw: line.to_string(),
+            result: decode_line(line, kb_layout, &human_timestamp),
+        })
+        .collect()
+}
+
+/// Decode a single raw UID into a card record
+pub fn decode_line(line: &str, kb_layout: &KeyboardLayout, timestamp: &str) -> Result<CardRecord, UidError> {
+    let uid = Uid::from_raw(line, kb_layout)?;
+    let manufacturer = utils::identify_manufacturer(&uid);
+    let format = utils::interpret_format_code(line);
+
+    Ok(CardRecord::new(timestamp.to_string(), line.to_string(), uid, manufacturer, format))
+}
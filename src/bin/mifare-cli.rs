@@ -0,0 +1,1627 @@
+// bin/mifare-cli.rs - headless frontend for scripting and servers without a display
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::process;
+
+use chrono::{Local, NaiveDate};
+
+use mifare_reader_utility::batch::{self, BatchLine};
+use mifare_reader_utility::card_reader::{pn532, CardEvent, CardReader, Pn532Reader, SimulatedReader};
+use mifare_reader_utility::error::Error;
+use mifare_reader_utility::export::{self, CardRecord, ExportFormat};
+use mifare_reader_utility::inventory::scan;
+use mifare_reader_utility::inventory::model::{normalize_path, DATE_FORMAT};
+use mifare_reader_utility::inventory::{
+    create_inventory_item, items_to_csv, migrations, movements_to_csv, reorder_to_csv, Actor, Category, CategoryField, FieldType,
+    InventoryDB, InventoryItem, InventoryView, ItemTag, Location, ScanMode, ScanOptions, ScanOutcome, SearchQuery, Stocktake,
+    StocktakeScope,
+};
+use mifare_reader_utility::keyboard_layout::{self, KeyboardLayout};
+use mifare_reader_utility::manufacturer;
+use mifare_reader_utility::uid::Uid;
+use mifare_reader_utility::utils::{self, csv_field};
+use mifare_reader_utility::wiegand::{self, WiegandCredential, WiegandFormat};
+
+// println! and print! for command output; those panic once stdout is a closed pipe, e.g.
+// `mifare-cli batch log.txt | head`
+macro_rules! outln {
+    ($($arg:tt)*) => { write_out(format_args!("{}\n", format_args!($($arg)*)))? };
+}
+macro_rules! out {
+    ($($arg:tt)*) => { write_out(format_args!($($arg)*))? };
+}
+
+const USAGE: &str = "Usage: mifare-cli [options] <command> [args]
+
+Commands:
+  decode <uid>...                 Decode one or more raw UIDs
+  batch [file|-]                  Decode every line of a file (or stdin)
+  replay <script|log>             Replay a simulated reader script or saved reader log
+  listen <device>                 Read cards from a PN532 on a serial port until interrupted
+  layouts [raw]                   List keyboard layouts, or score each one against a raw read
+  wiegand decode <bits>...        Decode 26/34/37-bit Wiegand frames, checking parity
+  wiegand encode <26|34|37> <fc> <cn>
+                                  Build a Wiegand frame from facility code and card number
+  inventory list                  List all inventory items, or those in --location/--category and below,
+                                  narrowed with --search, --qty-min/--qty-max and --since, in --sort order
+  inventory search <query>...     Search items, best match first, e.g. cat:tools loc:\"shelf b\" drill qty<5
+  inventory add <tag> <name> [field=value]...
+                                  Add or replace an item, with values for its category's custom fields
+  inventory set <tag> <field> <value>
+                                  Set a custom field of an item's category ('' clears it)
+  inventory stock <tag> <min|none> [reorder|none]
+                                  Set an item's minimum and reorder quantity ('none' uses its category's)
+  inventory reorder               List items below their minimum stock with the quantity to order
+  inventory scan <uid>            Decode a scanned UID and apply it in the scan mode (see --mode)
+  inventory delete <tag>          Move an item to the trash
+  inventory move <tag> <path>     Move an item to a location
+  inventory bulk category <path|none> <tag>...
+                                  Set or clear the category of several items at once
+  inventory bulk location <path|none> <tag>...
+                                  Move several items to a location, or clear theirs
+  inventory bulk adjust <delta> <tag>...
+                                  Add to (or, negative, take from) the quantity of several items
+  inventory bulk delete <tag>...  Move several items to the trash
+  inventory undo                  Undo the last change (save, delete, quantity change, import...), all of its
+                                  items at once
+  inventory redo                  Apply the last undone change again
+  inventory trash                 List deleted items, most recently deleted first
+  inventory restore <tag>...      Bring deleted items back from the trash with their tags and stock
+  inventory purge <days>          Remove items deleted more than that many days ago for good (0 empties the trash)
+  inventory locations             List locations with the items and stock they hold, sub-locations included
+  inventory location add <path>   Add a location, e.g. Site/Room/Shelf, and any missing parents
+  inventory location rename <path> <name>
+  inventory location move <path> <parent|/>
+                                  Move a location and everything in it under another one ('/' for the top level)
+  inventory location delete <path>
+                                  Delete a location that holds no items or sub-locations
+  inventory location tag <path> <uid>
+                                  Mark a location with a tag; scanning it sets the location for the next scans
+  inventory location untag <path>
+  inventory categories            List categories with their colour, items and stock, sub-categories included
+  inventory category add <path>   Add a category, e.g. Tools/Meters, and any missing parents
+  inventory category rename <path> <name>
+  inventory category move <path> <parent|/>
+  inventory category delete <path>
+                                  Delete a category that has no items or sub-categories
+  inventory category color <path> <#RRGGBB|none>
+                                  Set the colour shown for the category and its sub-categories
+  inventory category stock <path> <min|none> [reorder|none]
+                                  Set the stock levels of a category's items that don't set their own
+  inventory category fields <path>
+                                  List the custom fields of a category, inherited ones included
+  inventory category field add <path> <name> <text|number|date> [required]
+  inventory category field remove <path> <name>
+  inventory tags <tag|text>       List the tags and text identifiers an item has carried, retired ones included
+  inventory tag attach <tag> <uid>
+                                  Attach another tag to an item; any of its tags finds it. A tag may be on
+                                  several items, scanning it then asks which one (see --item)
+  inventory tag label <tag> <text>
+                                  Attach a text identifier, e.g. from an NDEF record
+  inventory tag replace <tag> <uid>
+                                  Re-tag an item: retire its tag and show the new one instead
+  inventory tag retire <uid|text> Retire a tag or identifier so it can be attached to another item
+  inventory history <tag>         Show every stock movement of an item with the running balance
+  inventory reconcile             Reset quantities that disagree with the movement ledger
+  inventory loans                 List checked-out tags with their borrower, due date and overdue state
+  inventory replay <script|log>   Replay scans against the inventory in the scan mode
+  inventory listen <device>       Scan from a PN532 serial reader into the inventory
+  inventory stocktake <script|log> [apply]
+                                  Count the scans against --location/--category and report variances;
+                                  'apply' writes the counted quantities back
+  export                          Export the inventory database
+  schema                          Show the database schema version and its migrations
+  import <file.json>              Import items from a JSON export
+
+Options:
+  --format json|csv               Output format (default: json)
+  --layout <name|n>               Keyboard layout name or number from 'layouts' (default: auto-detect)
+  --layout-dir <path>             Extra keyboard layout definitions (default: ./keyboard_layouts)
+  --db <path>                     Inventory database (default: inventory.db)
+  --output <path>                 Write export or reorder list output to a file instead of stdout
+  --qty <n>                       Quantity for 'inventory add' (default: 1)
+  --description <text>            Description for 'inventory add'
+  --location <path>               Location for 'inventory add', the location being counted or listed,
+                                  or where scans start out (received and counted items move there)
+  --category <path>               Category for 'inventory add', or the category being counted or listed
+  --search <query>                Search query for 'inventory list', as for 'inventory search'
+  --qty-min <n>, --qty-max <n>    Quantity range for 'inventory list'
+  --since <date|days>             Only list items changed since a date (YYYY-MM-DD) or that many days ago
+  --sort <columns>                Sort order for 'inventory list', e.g. category,-qty (tag, name, qty,
+                                  category, location, updated; '-' sorts descending; default: name)
+  --speed <factor>                Replay speed: 1 keeps recorded timing, 0 skips delays (default: 1)
+  --baud <rate>                   Serial baud rate for 'listen' (default: 115200)
+  --user <name>                   User recorded on stock movements (default: $USER)
+  --station <name>                Station recorded on stock movements (default: host name)
+  --mode <mode>                   Scan mode: receive, issue, set, lookup or checkout (default: receive)
+  --count <n>                     Counted quantity for '--mode set'
+  --borrower <name>               Borrower for '--mode checkout'
+  --due <date|days>               Due date (YYYY-MM-DD) or days from today for '--mode checkout'
+  --item <tag>                    Tag shown for the item 'inventory scan' means when the scanned tag is
+                                  attached to several items
+";
+
+// Flags that take a value; everything else starting with "--" is rejected
+const VALUE_FLAGS: [&str; 23] = [
+    "format", "layout", "layout-dir", "db", "output", "qty", "description", "location", "category", "speed", "baud",
+    "user", "station", "mode", "count", "borrower", "due", "search", "qty-min", "qty-max", "since", "sort", "item",
+];
+
+enum OutputFormat {
+    Json,
+    Csv,
+}
+
+struct Args {
+    positional: Vec<String>,
+    flags: HashMap<String, String>,
+}
+
+impl Args {
+    fn parse(raw: Vec<String>) -> Result<Self, String> {
+        let mut positional = Vec::new();
+        let mut flags = HashMap::new();
+        let mut iter = raw.into_iter();
+
+        while let Some(arg) = iter.next() {
+            if arg == "-h" || arg == "--help" {
+                return Err(String::new());
+            }
+
+            if let Some(name) = arg.strip_prefix("--") {
+                // Accept both "--flag value" and "--flag=value"
+                let (name, value) = match name.split_once('=') {
+                    Some((name, value)) => (name.to_string(), Some(value.to_string())),
+                    None => (name.to_string(), None),
+                };
+
+                if !VALUE_FLAGS.contains(&name.as_str()) {
+                    return Err(format!("Unknown option: --{}", name));
+                }
+
+                let value = match value.or_else(|| iter.next()) {
+                    Some(value) => value,
+                    None => return Err(format!("Missing value for --{}", name)),
+                };
+
+                flags.insert(name, value);
+            } else {
+                positional.push(arg);
+            }
+        }
+
+        Ok(Args { positional, flags })
+    }
+
+    fn flag(&self, name: &str) -> Option<&str> {
+        self.flags.get(name).map(String::as_str)
+    }
+
+    fn format(&self) -> Result<OutputFormat, String> {
+        match self.flag("format").unwrap_or("json") {
+            "json" => Ok(OutputFormat::Json),
+            "csv" => Ok(OutputFormat::Csv),
+            other => Err(format!("Unsupported format '{}', expected json or csv", other)),
+        }
+    }
+
+    fn layout(&self) -> Result<KeyboardLayout, String> {
+        match self.flag("layout") {
+            Some(value) => value
+                .parse::<KeyboardLayout>()
+                .map_err(|e| format!("Invalid keyboard layout: {} (see 'layouts')", e)),
+            None => Ok(KeyboardLayout::AutoDetect),
+        }
+    }
+
+    fn scan_mode(&self) -> Result<ScanMode, String> {
+        match self.flag("mode") {
+            Some(mode) => mode.parse::<ScanMode>(),
+            None => Ok(ScanMode::Receive),
+        }
+    }
+
+    fn scan_options(&self) -> Result<ScanOptions, String> {
+        let count = match self.flag("count") {
+            Some(count) => Some(count.parse::<i32>().map_err(|_| format!("Invalid count '{}'", count))?),
+            None => None,
+        };
+        let due_date = match self.flag("due") {
+            Some(due) => Some(
+                scan::parse_due_date(due, Local::now().date_naive())
+                    .ok_or_else(|| format!("Invalid due date '{}', expected YYYY-MM-DD or a number of days", due))?,
+            ),
+            None => None,
+        };
+
+        Ok(ScanOptions { count, borrower: self.flag("borrower").map(str::to_string), due_date, location: None })
+    }
+
+    // Scan options with the --location scans start out at
+    fn scan_options_in(&self, db: &InventoryDB) -> Result<ScanOptions, String> {
+        let mut options = self.scan_options()?;
+        if let Some(path) = self.flag("location") {
+            options.location = Some(find_location(db, path)?);
+        }
+        Ok(options)
+    }
+
+    fn open_db(&self) -> Result<InventoryDB, String> {
+        let path = self.flag("db").unwrap_or("inventory.db");
+        let mut db = InventoryDB::new(path).map_err(|e| format!("Error opening inventory database {}: {}", path, e))?;
+        if let Some(report) = db.migration().filter(|report| !report.quarantined.is_empty()) {
+            eprintln!(
+                "Moved {} row(s) with tags that aren't valid UIDs or clash with another tag to the quarantine table: {}",
+                report.quarantined.len(),
+                report.quarantined.join(", ")
+            );
+        }
+
+        // Movements are attributed to the environment's user and host unless overridden
+        let default = Actor::from_env();
+        db.set_actor(Actor {
+            user: self.flag("user").map(str::to_string).or(default.user),
+            station: self.flag("station").map(str::to_string).or(default.station),
+        });
+        Ok(db)
+    }
+}
+
+fn main() {
+    let args = match Args::parse(env::args().skip(1).collect()) {
+        Ok(args) => args,
+        Err(e) => {
+            if !e.is_empty() {
+                eprintln!("{}\n", e);
+            }
+            eprint!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+
+    if let Err(e) = run(&args) {
+        eprintln!("Error: {}", e);
+        process::exit(1);
+    }
+}
+
+// Write command output through the locked stdout. A reader that has gone away ends the
+// program quietly, the way other command-line tools stop in a pipeline
+fn write_out(text: fmt::Arguments) -> Result<(), String> {
+    match io::stdout().lock().write_fmt(text) {
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => process::exit(0),
+        result => result.map_err(|e| format!("Error writing output: {}", e)),
+    }
+}
+
+fn run(args: &Args) -> Result<(), String> {
+    let command: Vec<&str> = args.positional.iter().map(String::as_str).collect();
+
+    let layout_dir = args.flag("layout-dir").unwrap_or(keyboard_layout::DEFAULT_USER_DIRECTORY);
+    keyboard_layout::load_user_layouts(layout_dir).map_err(|e| e.to_string())?;
+
+    match command.as_slice() {
+        ["decode", uids @ ..] if !uids.is_empty() => decode(args, uids),
+        ["batch"] => decode_file(args, "-"),
+        ["batch", path] => decode_file(args, path),
+        ["layouts"] => list_layouts(args),
+        ["layouts", raw] => score_layouts(args, raw),
+        ["wiegand", "decode", frames @ ..] if !frames.is_empty() => wiegand_decode(args, frames),
+        ["wiegand", "encode", bits, fc, cn] => wiegand_encode(args, bits, fc, cn),
+        ["inventory", "list"] => inventory_list(args),
+        ["inventory", "search", words @ ..] if !words.is_empty() => inventory_search(args, &words.join(" ")),
+        ["inventory", "add", tag_id, name, fields @ ..] => inventory_add(args, tag_id, name, fields),
+        ["inventory", "scan", uid] => inventory_scan(args, uid),
+        ["inventory", "delete", tag_id] => inventory_delete(args, tag_id),
+        ["inventory", "move", tag_id, path] => inventory_move(args, tag_id, path),
+        ["inventory", "bulk", "category", path, keys @ ..] if !keys.is_empty() => bulk_category(args, path, keys),
+        ["inventory", "bulk", "location", path, keys @ ..] if !keys.is_empty() => bulk_location(args, path, keys),
+        ["inventory", "bulk", "adjust", delta, keys @ ..] if !keys.is_empty() => bulk_adjust(args, delta, keys),
+        ["inventory", "bulk", "delete", keys @ ..] if !keys.is_empty() => bulk_delete(args, keys),
+        ["inventory", "undo"] => inventory_undo(args, false),
+        ["inventory", "redo"] => inventory_undo(args, true),
+        ["inventory", "trash"] => inventory_trash(args),
+        ["inventory", "restore", keys @ ..] if !keys.is_empty() => inventory_restore(args, keys),
+        ["inventory", "purge", days] => inventory_purge(args, days),
+        ["inventory", "locations"] => inventory_locations(args),
+        ["inventory", "location", "add", path] => location_add(args, path),
+        ["inventory", "location", "rename", path, name] => location_rename(args, path, name),
+        ["inventory", "location", "move", path, parent] => location_move(args, path, parent),
+        ["inventory", "location", "delete", path] => location_delete(args, path),
+        ["inventory", "location", "tag", path, uid] => location_tag(args, path, Some(uid)),
+        ["inventory", "location", "untag", path] => location_tag(args, path, None),
+        ["inventory", "set", tag_id, field, value] => inventory_set(args, tag_id, field, value),
+        ["inventory", "stock", tag_id, min] => inventory_stock(args, tag_id, min, "none"),
+        ["inventory", "stock", tag_id, min, reorder] => inventory_stock(args, tag_id, min, reorder),
+        ["inventory", "reorder"] => inventory_reorder(args),
+        ["inventory", "categories"] => inventory_categories(args),
+        ["inventory", "category", "add", path] => category_add(args, path),
+        ["inventory", "category", "rename", path, name] => category_rename(args, path, name),
+        ["inventory", "category", "move", path, parent] => category_move(args, path, parent),
+        ["inventory", "category", "delete", path] => category_delete(args, path),
+        ["inventory", "category", "color", path, color] => category_color(args, path, color),
+        ["inventory", "category", "stock", path, min] => category_stock(args, path, min, "none"),
+        ["inventory", "category", "stock", path, min, reorder] => category_stock(args, path, min, reorder),
+        ["inventory", "category", "fields", path] => category_fields(args, path),
+        ["inventory", "category", "field", "add", path, name, field_type] => {
+            category_field_add(args, path, name, field_type, false)
+        }
+        ["inventory", "category", "field", "add", path, name, field_type, "required"] => {
+            category_field_add(args, path, name, field_type, true)
+        }
+        ["inventory", "category", "field", "remove", path, name] => category_field_remove(args, path, name),
+        ["inventory", "tags", key] => inventory_tags(args, key),
+        ["inventory", "tag", "attach", key, uid] => tag_attach(args, key, Some(uid), None),
+        ["inventory", "tag", "label", key, text] => tag_attach(args, key, None, Some(text)),
+        ["inventory", "tag", "replace", key, uid] => tag_replace(args, key, uid),
+        ["inventory", "tag", "retire", key] => tag_retire(args, key),
+        ["inventory", "history", tag_id] => inventory_history(args, tag_id),
+        ["inventory", "reconcile"] => inventory_reconcile(args),
+        ["inventory", "loans"] => inventory_loans(args),
+        ["inventory", "replay", path] => replay(args, path, true),
+        ["inventory", "listen", device] => listen(args, device, true),
+        ["inventory", "stocktake", path] => stocktake(args, path, false),
+        ["inventory", "stocktake", path, "apply"] => stocktake(args, path, true),
+        ["replay", path] => replay(args, path, false),
+        ["listen", device] => listen(args, device, false),
+        ["export"] => export_inventory(args),
+        ["schema"] => schema(args),
+        ["import", path] => import_inventory(args, path),
+        _ => Err(format!("Invalid command\n\n{}", USAGE)),
+    }
+}
+
+fn decode(args: &Args, uids: &[&str]) -> Result<(), String> {
+    let layout = args.layout()?;
+    let (_, timestamp) = utils::get_timestamps();
+
+    let lines: Vec<BatchLine> = uids
+        .iter()
+        .enumerate()
+        .map(|(i, uid)| BatchLine {
+            line_number: i + 1,
+            raw: uid.to_string(),
+            result: batch::decode_line(uid, &layout, &timestamp),
+        })
+        .collect();
+
+    print_batch(args, lines)
+}
+
+fn decode_file(args: &Args, path: &str) -> Result<(), String> {
+    let layout = args.layout()?;
+    let text = read_input(path)?;
+    let lines = batch::decode_batch(&text, &layout);
+
+    print_batch(args, lines)
+}
+
+fn wiegand_decode(args: &Args, frames: &[&str]) -> Result<(), String> {
+    let credentials = frames
+        .iter()
+        .map(|frame| wiegand::parse_frame(frame).map_err(|e| format!("Invalid Wiegand frame '{}': {}", frame, e)))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    print_credentials(args, &credentials)
+}
+
+fn wiegand_encode(args: &Args, bits: &str, fc: &str, cn: &str) -> Result<(), String> {
+    let format = bits
+        .parse::<usize>()
+        .ok()
+        .and_then(WiegandFormat::from_bit_length)
+        .ok_or_else(|| format!("Unsupported Wiegand format '{}', expected 26, 34 or 37", bits))?;
+    let facility_code = fc.parse::<u32>().map_err(|_| format!("Invalid facility code '{}'", fc))?;
+    let card_number = cn.parse::<u32>().map_err(|_| format!("Invalid card number '{}'", cn))?;
+
+    let credential = wiegand::encode(format, facility_code, card_number).map_err(|e| e.to_string())?;
+    print_credentials(args, &[credential])
+}
+
+fn inventory_list(args: &Args) -> Result<(), String> {
+    let db = args.open_db()?;
+    let mut view = InventoryView::all();
+
+    if let Some(path) = args.flag("location") {
+        view.filter.location = Some(find_location(&db, path)?.path);
+    }
+    if let Some(path) = args.flag("category") {
+        view.filter.category = Some(find_category(&db, path)?.path);
+    }
+    if let Some(query) = args.flag("search") {
+        view.search = query.to_string();
+    }
+    let quantity = |name: &str| -> Result<Option<i32>, String> {
+        args.flag(name)
+            .map(|value| value.parse::<i32>().map_err(|_| format!("Invalid quantity '{}' for --{}", value, name)))
+            .transpose()
+    };
+    view.filter.min_quantity = quantity("qty-min")?;
+    view.filter.max_quantity = quantity("qty-max")?;
+    if let Some(since) = args.flag("since") {
+        // A plain number counts days back from today
+        let today = Local::now().date_naive();
+        let date = match since.parse::<u32>() {
+            Ok(days) => today - chrono::Duration::days(days.into()),
+            Err(_) => NaiveDate::parse_from_str(since, DATE_FORMAT)
+                .map_err(|_| format!("Invalid date '{}', expected YYYY-MM-DD or a number of days", since))?,
+        };
+        view.filter.updated_since = Some(date.format(DATE_FORMAT).to_string());
+    }
+    if let Some(sort) = args.flag("sort") {
+        view.sort = InventoryView::parse_sort(sort)?;
+    }
+
+    let items = db.get_view_items(&view).map_err(|e| format!("Error loading inventory: {}", e))?;
+    print_items(args, &items)
+}
+
+fn inventory_search(args: &Args, query: &str) -> Result<(), String> {
+    let db = args.open_db()?;
+    let query = SearchQuery::parse(query).map_err(|e| e.to_string())?;
+    let items = db.search_items(&query).map_err(|e| format!("Error searching inventory: {}", e))?;
+
+    print_items(args, &items)
+}
+
+fn inventory_add(args: &Args, tag_id: &str, name: &str, fields: &[&str]) -> Result<(), String> {
+    let tag_id = parse_tag_id(tag_id)?;
+    let quantity = match args.flag("qty") {
+        Some(qty) => qty.parse::<i32>().map_err(|_| format!("Invalid quantity '{}'", qty))?,
+        None => 1,
+    };
+
+    let db = args.open_db()?;
+    let mut item = create_inventory_item(
+        &tag_id,
+        name,
+        args.flag("description"),
+        quantity,
+        args.flag("location"),
+        args.flag("category"),
+    );
+
+    // Keep the original creation date when replacing an existing item, and its field values while the category stays
+    if let Ok(Some(existing)) = db.get_item(&tag_id) {
+        item.created_at = existing.created_at;
+        item.min_quantity = existing.min_quantity;
+        item.reorder_quantity = existing.reorder_quantity;
+        let category_key = |path: &Option<String>| path.as_deref().and_then(normalize_path).map(|path| path.to_lowercase());
+        if category_key(&existing.category) == category_key(&item.category) {
+            item.attributes = existing.attributes;
+        }
+    }
+    for field in fields {
+        let (field, value) = field.split_once('=').ok_or_else(|| format!("Expected field=value, got '{}'", field))?;
+        item.attributes.insert(field.trim().to_string(), value.to_string());
+    }
+
+    db.save_item(&item).map_err(|e| format!("Error saving item: {}", e))?;
+
+    // Print it as stored, with the location, category and field values in their canonical form
+    let item = db.get_item(&tag_id).map_err(|e| format!("Error loading item: {}", e))?;
+    print_items(args, &item.into_iter().collect::<Vec<_>>())
+}
+
+fn inventory_scan(args: &Args, uid: &str) -> Result<(), String> {
+    let layout = args.layout()?;
+    let tag_id = Uid::from_raw(uid, &layout).map_err(|e| format!("Could not decode UID '{}': {}", uid, e))?;
+
+    let db = args.open_db()?;
+    let (mode, options) = (args.scan_mode()?, args.scan_options_in(&db)?);
+
+    let outcome = match scan::apply_scan(&db, &tag_id, mode, &options) {
+        Ok(ScanOutcome::Shared(items)) => {
+            let item = shared_item(args, &tag_id, items)?;
+            scan::apply_scan_to(&db, item, mode, &options)
+        }
+        outcome => outcome,
+    }
+    .map_err(|e| format!("Error applying scan: {}", e))?;
+    if let ScanOutcome::LocationSelected(location) = &outcome {
+        eprintln!("{}", outcome.message());
+        return print_locations(args, std::slice::from_ref(location));
+    }
+    let item = match outcome.item() {
+        Some(item) => item.clone(),
+        None => return Err(format!("Tag ID {} not found in inventory", tag_id)),
+    };
+
+    eprintln!("{}", outcome.message());
+    print_items(args, &[item])
+}
+
+// The item --item picks out of those a scanned tag is attached to
+fn shared_item(args: &Args, tag_id: &Uid, items: Vec<InventoryItem>) -> Result<InventoryItem, String> {
+    let choices: Vec<String> = items.iter().map(|item| format!("{} ({})", item.tag_id, item.name)).collect();
+    let Some(shown) = args.flag("item") else {
+        return Err(format!(
+            "Tag ID {} is attached to {} items, choose one with --item: {}",
+            tag_id,
+            items.len(),
+            choices.join(", ")
+        ));
+    };
+    let shown = parse_tag_id(shown)?;
+
+    items
+        .into_iter()
+        .find(|item| item.tag_id == shown)
+        .ok_or_else(|| format!("--item {} isn't one of the items tag {} is attached to: {}", shown, tag_id, choices.join(", ")))
+}
+
+fn replay(args: &Args, path: &str, update_inventory: bool) -> Result<(), String> {
+    let speed = match args.flag("speed") {
+        Some(speed) => speed.parse::<f64>().map_err(|_| format!("Invalid speed '{}'", speed))?,
+        None => 1.0,
+    };
+    let mut reader = SimulatedReader::from_file(path, args.layout()?)
+        .and_then(|reader| reader.with_speed(speed))
+        .map_err(|e| e.to_string())?;
+
+    read_cards(args, &mut reader, update_inventory)
+}
+
+fn listen(args: &Args, device: &str, update_inventory: bool) -> Result<(), String> {
+    let baud_rate = match args.flag("baud") {
+        Some(baud) => baud.parse::<u32>().map_err(|_| format!("Invalid baud rate '{}'", baud))?,
+        None => pn532::DEFAULT_BAUD_RATE,
+    };
+    let mut reader = Pn532Reader::open(device, baud_rate).map_err(|e| e.to_string())?;
+
+    match reader.firmware_version() {
+        Ok([ic, version, revision, _]) => eprintln!("Connected to PN5{:02X} firmware {}.{}", ic, version, revision),
+        Err(e) => return Err(e.to_string()),
+    }
+
+    read_cards(args, &mut reader, update_inventory)
+}
+
+// Print every event from the reader, optionally counting each tap into the inventory
+fn read_cards(args: &Args, reader: &mut dyn CardReader, update_inventory: bool) -> Result<(), String> {
+    let db = if update_inventory { Some(args.open_db()?) } else { None };
+    let format = args.format()?;
+    let mode = args.scan_mode()?;
+    let mut options = match &db {
+        Some(db) => args.scan_options_in(db)?,
+        None => args.scan_options()?,
+    };
+
+    if let OutputFormat::Csv = format {
+        outln!("Event,UID,Raw,Quantity,Card Type,Result");
+    }
+
+    while let Some(event) = reader.next_event().map_err(|e| e.to_string())? {
+        let (kind, uid, raw, family) = match &event {
+            CardEvent::CardPresent(read) => (
+                "present",
+                Some(read.uid.clone()),
+                read.raw.clone(),
+                utils::identify_card_type(read.atqa, read.sak, read.version.as_deref()),
+            ),
+            CardEvent::CardRemoved { uid } => ("removed", uid.clone(), String::new(), None),
+            CardEvent::ReadError { raw, error } => {
+                eprintln!("Could not decode '{}': {}", raw, error);
+                ("error", None, raw.clone(), None)
+            }
+        };
+
+        // Each tap is applied in the scan mode, the same as 'inventory scan'
+        let mut quantity = None;
+        let mut result = String::new();
+        if let (Some(db), CardEvent::CardPresent(read)) = (&db, &event) {
+            if manufacturer::identify(&read.uid).is_random_uid() {
+                // A random UID changes on every read, so it never matches an item
+                eprintln!("Tag ID {} is a random UID and can't be tracked", read.uid);
+            } else {
+                match scan::apply_scan(db, &read.uid, mode, &options) {
+                    Ok(ScanOutcome::NotFound) => eprintln!("Tag ID {} not found in inventory", read.uid),
+                    // Nobody is there to say which item a shared tag meant, so the tap is skipped
+                    Ok(outcome @ ScanOutcome::Shared(_)) => {
+                        result = outcome.message();
+                        eprintln!("Tag ID {}: {}, skipped", read.uid, result);
+                    }
+                    // Later taps are put away here
+                    Ok(ScanOutcome::LocationSelected(location)) => {
+                        result = format!("Current location: {}", location.path);
+                        eprintln!("{}", result);
+                        options.location = Some(location);
+                    }
+                    Ok(outcome) => {
+                        let item = outcome.item().cloned();
+                        quantity = item.as_ref().map(|item| item.quantity);
+                        result = outcome.message();
+
+                        if let (Some(None), Some(family)) = (item.map(|item| item.card_family), family) {
+                            db.update_card_family(&read.uid, family)
+                                .map_err(|e| format!("Error updating card type: {}", e))?;
+                        }
+                    }
+                    // A rejected scan (e.g. nothing left to issue) doesn't stop the reader
+                    Err(Error::Scan(reason)) => {
+                        eprintln!("Tag ID {}: {}", read.uid, reason);
+                        result = reason;
+                    }
+                    Err(e) => return Err(format!("Error applying scan: {}", e)),
+                }
+            }
+        }
+
+        let uid = uid.map(|uid| uid.to_hex()).unwrap_or_default();
+        match format {
+            OutputFormat::Json => outln!(
+                "{}",
+                serde_json::json!({
+                    "event": kind,
+                    "uid": uid,
+                    "raw": raw,
+                    "quantity": quantity,
+                    "card_family": family,
+                    "result": result,
+                })
+            ),
+            OutputFormat::Csv => outln!(
+                "{},{},{},{},{},{}",
+                csv_field(kind),
+                csv_field(uid),
+                csv_field(raw),
+                csv_field(quantity.map(|q| q.to_string()).unwrap_or_default()),
+                csv_field(family.map(|f| f.name()).unwrap_or_default()),
+                csv_field(result)
+            ),
+        }
+    }
+
+    Ok(())
+}
+
+// Count every read in a script or log against the items expected in the scope; stock is only changed by 'apply'
+fn stocktake(args: &Args, path: &str, apply: bool) -> Result<(), String> {
+    let scope = StocktakeScope::new(args.flag("location"), args.flag("category"));
+    let mut reader = SimulatedReader::from_file(path, args.layout()?)
+        .and_then(|reader| reader.with_speed(0.0))
+        .map_err(|e| e.to_string())?;
+
+    let db = args.open_db()?;
+    let mut session = Stocktake::start(&db, scope).map_err(|e| format!("Error starting stocktake: {}", e))?;
+
+    while let Some(event) = reader.next_event().map_err(|e| e.to_string())? {
+        match event {
+            CardEvent::CardPresent(read) => {
+                session.record_scan(&read.uid);
+            }
+            CardEvent::ReadError { raw, error } => eprintln!("Could not decode '{}': {}", raw, error),
+            CardEvent::CardRemoved { .. } => {}
+        }
+    }
+
+    let report = session.report(&db).map_err(|e| format!("Error comparing counts: {}", e))?;
+    eprintln!("{}", report.summary());
+
+    match args.format()? {
+        OutputFormat::Json => {
+            let json = serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?;
+            outln!("{}", json);
+        }
+        OutputFormat::Csv => out!("{}", report.to_csv()),
+    }
+
+    if apply {
+        let changed = db
+            .apply_stocktake(&report.scope, &report.variances)
+            .map_err(|e| format!("Error applying stocktake: {}", e))?;
+        eprintln!("Adjusted {} item(s)", changed);
+    }
+
+    Ok(())
+}
+
+fn inventory_delete(args: &Args, tag_id: &str) -> Result<(), String> {
+    let tag_id = parse_tag_id(tag_id)?;
+    let db = args.open_db()?;
+    let deleted = db.delete_item(&tag_id).map_err(|e| format!("Error deleting item: {}", e))?;
+
+    match args.format()? {
+        OutputFormat::Json => outln!("{}", serde_json::json!({ "tag_id": tag_id.to_hex(), "deleted": deleted })),
+        OutputFormat::Csv => out!("Tag ID,Deleted\n{},{}\n", csv_field(&tag_id), csv_field(deleted)),
+    }
+
+    Ok(())
+}
+
+fn inventory_history(args: &Args, tag_id: &str) -> Result<(), String> {
+    let tag_id = parse_tag_id(tag_id)?;
+    let db = args.open_db()?;
+    let movements = db.get_movements(&tag_id).map_err(|e| format!("Error loading movements: {}", e))?;
+
+    match args.format()? {
+        OutputFormat::Json => {
+            let json = serde_json::to_string_pretty(&movements).map_err(|e| e.to_string())?;
+            outln!("{}", json);
+        }
+        OutputFormat::Csv => out!("{}", movements_to_csv(&movements)),
+    }
+
+    Ok(())
+}
+
+fn inventory_reconcile(args: &Args) -> Result<(), String> {
+    let db = args.open_db()?;
+    let corrected = db.reconcile_quantities().map_err(|e| format!("Error reconciling quantities: {}", e))?;
+
+    eprintln!("Corrected {} item(s) to match the movement ledger", corrected.len());
+    match args.format()? {
+        OutputFormat::Json => {
+            let json = serde_json::to_string_pretty(&corrected).map_err(|e| e.to_string())?;
+            outln!("{}", json);
+        }
+        OutputFormat::Csv => {
+            outln!("Tag ID,Stored,Ledger");
+            for mismatch in &corrected {
+                outln!("{},{},{}", csv_field(&mismatch.tag_id), csv_field(mismatch.stored), csv_field(mismatch.ledger));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn inventory_loans(args: &Args) -> Result<(), String> {
+    let db = args.open_db()?;
+    let loans = db.get_open_loans().map_err(|e| format!("Error loading loans: {}", e))?;
+    let today = Local::now().date_naive();
+
+    match args.format()? {
+        OutputFormat::Json => {
+            let loans: Vec<_> = loans
+                .iter()
+                .map(|loan| {
+                    serde_json::json!({
+                        "tag_id": loan.tag_id,
+                        "borrower": loan.borrower,
+                        "checked_out_at": loan.checked_out_at,
+                        "due_date": loan.due_date,
+                        "overdue": loan.is_overdue(today),
+                    })
+                })
+                .collect();
+            let json = serde_json::to_string_pretty(&loans).map_err(|e| e.to_string())?;
+            outln!("{}", json);
+        }
+        OutputFormat::Csv => {
+            outln!("Tag ID,Borrower,Checked Out,Due,Overdue");
+            for loan in &loans {
+                outln!(
+                    "{},{},{},{},{}",
+                    csv_field(&loan.tag_id),
+                    csv_field(&loan.borrower),
+                    csv_field(&loan.checked_out_at),
+                    csv_field(loan.due_date.as_deref().unwrap_or("")),
+                    csv_field(loan.is_overdue(today))
+                );
+            }
+        }
+    }
+
+    let overdue = loans.iter().filter(|loan| loan.is_overdue(today)).count();
+    if overdue > 0 {
+        eprintln!("{} of {} checked-out tag(s) overdue", overdue, loans.len());
+    }
+
+    Ok(())
+}
+
+fn inventory_move(args: &Args, tag_id: &str, path: &str) -> Result<(), String> {
+    let tag_id = parse_tag_id(tag_id)?;
+    let db = args.open_db()?;
+    let location = find_location(&db, path)?;
+
+    if !db.move_item(&tag_id, Some(location.id)).map_err(|e| format!("Error moving item: {}", e))? {
+        return Err(format!("Tag ID {} not found in inventory", tag_id));
+    }
+
+    let item = db.get_item(&tag_id).map_err(|e| format!("Error loading item: {}", e))?;
+    print_items(args, &item.into_iter().collect::<Vec<_>>())
+}
+
+// Items of a bulk change by tag or identifier; one unknown key cancels the whole change
+fn find_items(db: &InventoryDB, keys: &[&str]) -> Result<Vec<InventoryItem>, String> {
+    keys.iter().map(|key| find_item(db, key)).collect()
+}
+
+fn item_ids(items: &[InventoryItem]) -> Result<Vec<i64>, String> {
+    items.iter().map(item_id).collect()
+}
+
+// The same items as they are after a bulk change
+fn reload_items(db: &InventoryDB, ids: &[i64]) -> Result<Vec<InventoryItem>, String> {
+    let mut items = Vec::new();
+    for id in ids {
+        items.extend(db.get_item_by_id(*id).map_err(|e| format!("Error loading item: {}", e))?);
+    }
+    Ok(items)
+}
+
+fn bulk_category(args: &Args, path: &str, keys: &[&str]) -> Result<(), String> {
+    let db = args.open_db()?;
+    let category = match path {
+        "none" => None,
+        path => Some(find_category(&db, path)?),
+    };
+    let ids = item_ids(&find_items(&db, keys)?)?;
+
+    let changed = db
+        .bulk_set_category(&ids, category.as_ref().map(|category| category.id))
+        .map_err(|e| format!("Error setting category: {}", e))?;
+    eprintln!("Set the category of {} item(s); 'inventory undo' takes it back", changed);
+    print_items(args, &reload_items(&db, &ids)?)
+}
+
+fn bulk_location(args: &Args, path: &str, keys: &[&str]) -> Result<(), String> {
+    let db = args.open_db()?;
+    let location = match path {
+        "none" => None,
+        path => Some(find_location(&db, path)?),
+    };
+    let ids = item_ids(&find_items(&db, keys)?)?;
+
+    let moved = db
+        .bulk_set_location(&ids, location.as_ref().map(|location| location.id))
+        .map_err(|e| format!("Error moving items: {}", e))?;
+    eprintln!("Moved {} item(s); 'inventory undo' takes it back", moved);
+    print_items(args, &reload_items(&db, &ids)?)
+}
+
+fn bulk_adjust(args: &Args, delta: &str, keys: &[&str]) -> Result<(), String> {
+    let delta = delta
+        .trim_start_matches('+')
+        .parse::<i32>()
+        .map_err(|_| format!("Invalid quantity change '{}', expected e.g. 5 or -2", delta))?;
+    let db = args.open_db()?;
+    let ids = item_ids(&find_items(&db, keys)?)?;
+
+    let adjusted = db.bulk_adjust_quantity(&ids, delta).map_err(|e| format!("Error adjusting quantities: {}", e))?;
+    eprintln!("Adjusted the quantity of {} item(s) by {:+}; 'inventory undo' takes it back", adjusted, delta);
+    print_items(args, &reload_items(&db, &ids)?)
+}
+
+fn bulk_delete(args: &Args, keys: &[&str]) -> Result<(), String> {
+    let db = args.open_db()?;
+    let ids = item_ids(&find_items(&db, keys)?)?;
+    let deleted = db.bulk_delete(&ids).map_err(|e| format!("Error deleting items: {}", e))?;
+
+    match args.format()? {
+        OutputFormat::Json => outln!("{}", serde_json::json!({ "deleted": deleted })),
+        OutputFormat::Csv => out!("Deleted\n{}\n", csv_field(deleted)),
+    }
+
+    Ok(())
+}
+
+fn inventory_undo(args: &Args, redo: bool) -> Result<(), String> {
+    let db = args.open_db()?;
+    let (step, verb) = if redo {
+        (db.redo().map_err(|e| format!("Error redoing: {}", e))?, "Redid")
+    } else {
+        (db.undo().map_err(|e| format!("Error undoing: {}", e))?, "Undid")
+    };
+    let Some(step) = step else {
+        return Err(format!("Nothing to {}", if redo { "redo" } else { "undo" }));
+    };
+
+    eprintln!("{} \"{}\"", verb, step.description);
+    let ids: Vec<i64> = step.changes.iter().map(|change| change.item_id).collect();
+    print_items(args, &reload_items(&db, &ids)?)
+}
+
+fn inventory_trash(args: &Args) -> Result<(), String> {
+    let db = args.open_db()?;
+    let trash = db.get_trash().map_err(|e| format!("Error loading trash: {}", e))?;
+
+    match args.format()? {
+        OutputFormat::Json => {
+            let json = serde_json::to_string_pretty(&trash).map_err(|e| e.to_string())?;
+            outln!("{}", json);
+        }
+        OutputFormat::Csv => {
+            outln!("Tag ID,Name,Quantity,Location,Category,Deleted At,Deleted By");
+            for trashed in &trash {
+                let item = trashed.item();
+                outln!(
+                    "{},{},{},{},{},{},{}",
+                    csv_field(&item.tag_id),
+                    csv_field(&item.name),
+                    csv_field(item.quantity),
+                    csv_field(item.location.as_deref().unwrap_or("")),
+                    csv_field(item.category.as_deref().unwrap_or("")),
+                    csv_field(&trashed.deleted_at),
+                    csv_field(trashed.deleted_by.as_deref().unwrap_or(""))
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Deleted items by any tag or identifier they had; one unknown key cancels the whole restore
+fn inventory_restore(args: &Args, keys: &[&str]) -> Result<(), String> {
+    let db = args.open_db()?;
+    let trash = db.get_trash().map_err(|e| format!("Error loading trash: {}", e))?;
+
+    let mut ids = Vec::new();
+    for key in keys {
+        let uid = key.parse::<Uid>().ok();
+        let trashed = trash
+            .iter()
+            .find(|trashed| {
+                uid.as_ref() == Some(&trashed.item().tag_id)
+                    || trashed.state.tags.iter().any(|tag| {
+                        (uid.is_some() && tag.uid == uid)
+                            || tag.identifier.as_deref().is_some_and(|identifier| identifier.eq_ignore_ascii_case(key))
+                    })
+            })
+            .ok_or_else(|| format!("No item in the trash has the tag or identifier '{}'", key))?;
+        ids.push(item_id(trashed.item())?);
+    }
+
+    let restored = db.restore_items(&ids).map_err(|e| format!("Error restoring items: {}", e))?;
+    eprintln!("Restored {} item(s); 'inventory undo' puts them back in the trash", restored);
+    print_items(args, &reload_items(&db, &ids)?)
+}
+
+fn inventory_purge(args: &Args, days: &str) -> Result<(), String> {
+    let days = days
+        .parse::<u32>()
+        .map_err(|_| format!("Invalid number of days '{}'", days))?;
+    let db = args.open_db()?;
+    let purged = db.purge_trash(days).map_err(|e| format!("Error purging trash: {}", e))?;
+
+    match args.format()? {
+        OutputFormat::Json => outln!("{}", serde_json::json!({ "purged": purged })),
+        OutputFormat::Csv => out!("Purged\n{}\n", csv_field(purged)),
+    }
+
+    Ok(())
+}
+
+fn inventory_locations(args: &Args) -> Result<(), String> {
+    let db = args.open_db()?;
+    let stats = db.location_stats().map_err(|e| format!("Error loading locations: {}", e))?;
+
+    match args.format()? {
+        OutputFormat::Json => {
+            let json = serde_json::to_string_pretty(&stats).map_err(|e| e.to_string())?;
+            outln!("{}", json);
+        }
+        OutputFormat::Csv => {
+            outln!("Path,Tag ID,Items,Quantity");
+            for entry in &stats {
+                outln!(
+                    "{},{},{},{}",
+                    csv_field(&entry.location.path),
+                    csv_field(entry.location.tag_id.as_ref().map(Uid::to_hex).unwrap_or_default()),
+                    csv_field(entry.items),
+                    csv_field(entry.quantity)
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn location_add(args: &Args, path: &str) -> Result<(), String> {
+    let db = args.open_db()?;
+    let location = db.ensure_location(path).map_err(|e| format!("Error adding location: {}", e))?;
+
+    print_locations(args, &[location])
+}
+
+fn location_rename(args: &Args, path: &str, name: &str) -> Result<(), String> {
+    let db = args.open_db()?;
+    let location = find_location(&db, path)?;
+    let location = db.rename_location(location.id, name).map_err(|e| format!("Error renaming location: {}", e))?;
+
+    print_locations(args, &[location])
+}
+
+fn location_move(args: &Args, path: &str, parent: &str) -> Result<(), String> {
+    let db = args.open_db()?;
+    let location = find_location(&db, path)?;
+    let parent_id = match parent.trim() {
+        "/" => None,
+        parent => Some(find_location(&db, parent)?.id),
+    };
+    let location = db.move_location(location.id, parent_id).map_err(|e| format!("Error moving location: {}", e))?;
+
+    print_locations(args, &[location])
+}
+
+fn location_delete(args: &Args, path: &str) -> Result<(), String> {
+    let db = args.open_db()?;
+    let location = find_location(&db, path)?;
+    db.delete_location(location.id).map_err(|e| format!("Error deleting location: {}", e))?;
+
+    eprintln!("Deleted location {}", location.path);
+    Ok(())
+}
+
+fn location_tag(args: &Args, path: &str, uid: Option<&str>) -> Result<(), String> {
+    let db = args.open_db()?;
+    let location = find_location(&db, path)?;
+    let tag_id = match uid {
+        Some(uid) => {
+            let layout = args.layout()?;
+            Some(Uid::from_raw(uid, &layout).map_err(|e| format!("Could not decode UID '{}': {}", uid, e))?)
+        }
+        None => None,
+    };
+    let location = db
+        .set_location_tag(location.id, tag_id.as_ref())
+        .map_err(|e| format!("Error tagging location: {}", e))?;
+
+    print_locations(args, &[location])
+}
+
+// An existing location by path; explicit location commands never create one by accident
+fn find_location(db: &InventoryDB, path: &str) -> Result<Location, String> {
+    db.find_location(path)
+        .map_err(|e| format!("Error loading locations: {}", e))?
+        .ok_or_else(|| format!("Unknown location '{}' (see 'inventory locations')", path))
+}
+
+fn print_locations(args: &Args, locations: &[Location]) -> Result<(), String> {
+    match args.format()? {
+        OutputFormat::Json => {
+            let json = serde_json::to_string_pretty(locations).map_err(|e| e.to_string())?;
+            outln!("{}", json);
+        }
+        OutputFormat::Csv => {
+            outln!("ID,Path,Tag ID");
+            for location in locations {
+                outln!(
+                    "{},{},{}",
+                    csv_field(location.id),
+                    csv_field(&location.path),
+                    csv_field(location.tag_id.as_ref().map(Uid::to_hex).unwrap_or_default())
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn inventory_set(args: &Args, tag_id: &str, field: &str, value: &str) -> Result<(), String> {
+    let tag_id = parse_tag_id(tag_id)?;
+    let db = args.open_db()?;
+    let value = Some(value).filter(|value| !value.trim().is_empty());
+
+    if !db.set_attribute(&tag_id, field, value).map_err(|e| format!("Error setting {}: {}", field, e))? {
+        return Err(format!("Tag ID {} not found in inventory", tag_id));
+    }
+
+    let item = db.get_item(&tag_id).map_err(|e| format!("Error loading item: {}", e))?;
+    print_items(args, &item.into_iter().collect::<Vec<_>>())
+}
+
+fn inventory_stock(args: &Args, tag_id: &str, min: &str, reorder: &str) -> Result<(), String> {
+    let tag_id = parse_tag_id(tag_id)?;
+    let (min, reorder) = (parse_stock_level(min)?, parse_stock_level(reorder)?);
+    let db = args.open_db()?;
+
+    if !db.set_stock_levels(&tag_id, min, reorder).map_err(|e| format!("Error setting stock levels: {}", e))? {
+        return Err(format!("Tag ID {} not found in inventory", tag_id));
+    }
+
+    let item = db.get_item(&tag_id).map_err(|e| format!("Error loading item: {}", e))?;
+    print_items(args, &item.into_iter().collect::<Vec<_>>())
+}
+
+fn inventory_reorder(args: &Args) -> Result<(), String> {
+    let db = args.open_db()?;
+    let reorder = db.needs_reorder().map_err(|e| format!("Error loading reorder list: {}", e))?;
+
+    let content = match args.format()? {
+        OutputFormat::Json => serde_json::to_string_pretty(&reorder).map_err(|e| e.to_string())?,
+        OutputFormat::Csv => reorder_to_csv(&reorder),
+    };
+    match args.flag("output") {
+        Some(path) => {
+            fs::write(path, content).map_err(|e| format!("Error writing file {}: {}", path, e))?;
+            eprintln!("Reorder list written to {}", path);
+        }
+        None => outln!("{}", content.trim_end()),
+    }
+
+    let out_of_stock = reorder.iter().filter(|entry| entry.is_out_of_stock()).count();
+    eprintln!("{} item(s) to reorder, {} out of stock", reorder.len(), out_of_stock);
+    Ok(())
+}
+
+// A minimum or reorder quantity, 'none' to use the category's (or the parent category's)
+fn parse_stock_level(text: &str) -> Result<Option<i32>, String> {
+    if text.trim().eq_ignore_ascii_case("none") {
+        return Ok(None);
+    }
+    text.trim().parse::<i32>().map(Some).map_err(|_| format!("Invalid quantity '{}'", text))
+}
+
+fn inventory_categories(args: &Args) -> Result<(), String> {
+    let db = args.open_db()?;
+    let stats = db.category_stats().map_err(|e| format!("Error loading categories: {}", e))?;
+
+    match args.format()? {
+        OutputFormat::Json => {
+            let json = serde_json::to_string_pretty(&stats).map_err(|e| e.to_string())?;
+            outln!("{}", json);
+        }
+        OutputFormat::Csv => {
+            outln!("Path,Color,Items,Quantity");
+            for entry in &stats {
+                outln!(
+                    "{},{},{},{}",
+                    csv_field(&entry.category.path),
+                    csv_field(entry.category.display_color.as_deref().unwrap_or("")),
+                    csv_field(entry.items),
+                    csv_field(entry.quantity)
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn category_add(args: &Args, path: &str) -> Result<(), String> {
+    let db = args.open_db()?;
+    let category = db.ensure_category(path).map_err(|e| format!("Error adding category: {}", e))?;
+
+    print_categories(args, &[category])
+}
+
+fn category_rename(args: &Args, path: &str, name: &str) -> Result<(), String> {
+    let db = args.open_db()?;
+    let category = find_category(&db, path)?;
+    let category = db.rename_category(category.id, name).map_err(|e| format!("Error renaming category: {}", e))?;
+
+    print_categories(args, &[category])
+}
+
+fn category_move(args: &Args, path: &str, parent: &str) -> Result<(), String> {
+    let db = args.open_db()?;
+    let category = find_category(&db, path)?;
+    let parent_id = match parent.trim() {
+        "/" => None,
+        parent => Some(find_category(&db, parent)?.id),
+    };
+    let category = db.move_category(category.id, parent_id).map_err(|e| format!("Error moving category: {}", e))?;
+
+    print_categories(args, &[category])
+}
+
+fn category_delete(args: &Args, path: &str) -> Result<(), String> {
+    let db = args.open_db()?;
+    let category = find_category(&db, path)?;
+    db.delete_category(category.id).map_err(|e| format!("Error deleting category: {}", e))?;
+
+    eprintln!("Deleted category {}", category.path);
+    Ok(())
+}
+
+fn category_color(args: &Args, path: &str, color: &str) -> Result<(), String> {
+    let db = args.open_db()?;
+    let category = find_category(&db, path)?;
+    let color = Some(color).filter(|color| !color.eq_ignore_ascii_case("none"));
+    let category = db.set_category_color(category.id, color).map_err(|e| format!("Error setting colour: {}", e))?;
+
+    print_categories(args, &[category])
+}
+
+fn category_stock(args: &Args, path: &str, min: &str, reorder: &str) -> Result<(), String> {
+    let (min, reorder) = (parse_stock_level(min)?, parse_stock_level(reorder)?);
+    let db = args.open_db()?;
+    let category = find_category(&db, path)?;
+    let category = db
+        .set_category_stock_levels(category.id, min, reorder)
+        .map_err(|e| format!("Error setting stock levels: {}", e))?;
+
+    print_categories(args, &[category])
+}
+
+fn category_fields(args: &Args, path: &str) -> Result<(), String> {
+    let db = args.open_db()?;
+    let category = find_category(&db, path)?;
+    let fields = db.get_category_fields(category.id).map_err(|e| format!("Error loading fields: {}", e))?;
+
+    print_fields(args, &db, &fields)
+}
+
+fn category_field_add(args: &Args, path: &str, name: &str, field_type: &str, required: bool) -> Result<(), String> {
+    let field_type: FieldType = field_type.parse()?;
+    let db = args.open_db()?;
+    let category = find_category(&db, path)?;
+    let field = db
+        .add_category_field(category.id, name, field_type, required)
+        .map_err(|e| format!("Error adding field: {}", e))?;
+
+    print_fields(args, &db, &[field])
+}
+
+fn category_field_remove(args: &Args, path: &str, name: &str) -> Result<(), String> {
+    let db = args.open_db()?;
+    let category = find_category(&db, path)?;
+    db.remove_category_field(category.id, name).map_err(|e| format!("Error removing field: {}", e))?;
+
+    eprintln!("Removed field {} from {}", name, category.path);
+    Ok(())
+}
+
+// An existing category by path; explicit category commands never create one by accident
+fn find_category(db: &InventoryDB, path: &str) -> Result<Category, String> {
+    db.find_category(path)
+        .map_err(|e| format!("Error loading categories: {}", e))?
+        .ok_or_else(|| format!("Unknown category '{}' (see 'inventory categories')", path))
+}
+
+fn print_categories(args: &Args, categories: &[Category]) -> Result<(), String> {
+    match args.format()? {
+        OutputFormat::Json => {
+            let json = serde_json::to_string_pretty(categories).map_err(|e| e.to_string())?;
+            outln!("{}", json);
+        }
+        OutputFormat::Csv => {
+            outln!("ID,Path,Color,Minimum,Reorder");
+            for category in categories {
+                outln!(
+                    "{},{},{},{},{}",
+                    csv_field(category.id),
+                    csv_field(&category.path),
+                    csv_field(category.color.as_deref().unwrap_or("")),
+                    csv_field(category.min_quantity.map(|min| min.to_string()).unwrap_or_default()),
+                    csv_field(category.reorder_quantity.map(|reorder| reorder.to_string()).unwrap_or_default())
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Fields with the path of the category that declares them
+fn print_fields(args: &Args, db: &InventoryDB, fields: &[CategoryField]) -> Result<(), String> {
+    match args.format()? {
+        OutputFormat::Json => {
+            let json = serde_json::to_string_pretty(fields).map_err(|e| e.to_string())?;
+            outln!("{}", json);
+        }
+        OutputFormat::Csv => {
+            outln!("Category,Name,Type,Required");
+            for field in fields {
+                let owner = db
+                    .get_category(field.category_id)
+                    .map_err(|e| format!("Error loading categories: {}", e))?
+                    .map(|category| category.path)
+                    .unwrap_or_default();
+                outln!(
+                    "{},{},{},{}",
+                    csv_field(owner),
+                    csv_field(&field.name),
+                    csv_field(field.field_type.key()),
+                    csv_field(field.required)
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn inventory_tags(args: &Args, key: &str) -> Result<(), String> {
+    let db = args.open_db()?;
+    let item = find_item(&db, key)?;
+    let tags = db.get_item_tags(item_id(&item)?).map_err(|e| format!("Error loading tags: {}", e))?;
+
+    print_tags(args, &tags)
+}
+
+fn tag_attach(args: &Args, key: &str, uid: Option<&str>, text: Option<&str>) -> Result<(), String> {
+    let uid = uid.map(parse_tag_id).transpose()?;
+    let db = args.open_db()?;
+    let item = find_item(&db, key)?;
+    let tag = db
+        .attach_tag(item_id(&item)?, uid.as_ref(), text)
+        .map_err(|e| format!("Error attaching tag: {}", e))?;
+
+    eprintln!("Attached {} to {}", tag, item.name);
+    print_tags(args, &[tag])
+}
+
+fn tag_replace(args: &Args, key: &str, uid: &str) -> Result<(), String> {
+    let new = parse_tag_id(uid)?;
+    let db = args.open_db()?;
+    let item = find_item(&db, key)?;
+    let item = db.retag_item(&item.tag_id, &new).map_err(|e| format!("Error re-tagging item: {}", e))?;
+
+    print_items(args, &[item])
+}
+
+fn tag_retire(args: &Args, key: &str) -> Result<(), String> {
+    let db = args.open_db()?;
+    let item = find_item(&db, key)?;
+    let uid = key.parse::<Uid>().ok();
+    let tag = db
+        .get_item_tags(item_id(&item)?)
+        .map_err(|e| format!("Error loading tags: {}", e))?
+        .into_iter()
+        .filter(ItemTag::is_active)
+        .find(|tag| {
+            (uid.is_some() && tag.uid == uid)
+                || tag.identifier.as_deref().is_some_and(|identifier| identifier.eq_ignore_ascii_case(key.trim()))
+        })
+        .ok_or_else(|| format!("'{}' is not an active tag of {}", key, item.name))?;
+    let tag = db.retire_tag(tag.id).map_err(|e| format!("Error retiring tag: {}", e))?;
+
+    eprintln!("Retired {} from {}", tag, item.name);
+    print_tags(args, &[tag])
+}
+
+// The item an active tag (hex UID) or text identifier is attached to
+fn find_item(db: &InventoryDB, key: &str) -> Result<InventoryItem, String> {
+    let by_uid = match key.parse::<Uid>() {
+        Ok(uid) => db.get_item(&uid).map_err(|e| format!("Error loading item: {}", e))?,
+        Err(_) => None,
+    };
+    let item = match by_uid {
+        Some(item) => Some(item),
+        None => db.get_item_by_identifier(key).map_err(|e| format!("Error loading item: {}", e))?,
+    };
+
+    item.ok_or_else(|| format!("No item has the tag or identifier '{}'", key))
+}
+
+fn item_id(item: &InventoryItem) -> Result<i64, String> {
+    item.id.ok_or_else(|| format!("{} has not been saved", item.name))
+}
+
+fn print_tags(args: &Args, tags: &[ItemTag]) -> Result<(), String> {
+    match args.format()? {
+        OutputFormat::Json => {
+            let json = serde_json::to_string_pretty(tags).map_err(|e| e.to_string())?;
+            outln!("{}", json);
+        }
+        OutputFormat::Csv => {
+            outln!("ID,UID,Identifier,Attached,Retired");
+            for tag in tags {
+                outln!(
+                    "{},{},{},{},{}",
+                    csv_field(tag.id),
+                    csv_field(tag.uid.as_ref().map(Uid::to_hex).unwrap_or_default()),
+                    csv_field(tag.identifier.as_deref().unwrap_or("")),
+                    csv_field(&tag.attached_at),
+                    csv_field(tag.retired_at.as_deref().unwrap_or(""))
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn schema(args: &Args) -> Result<(), String> {
+    // Opening the database applies any pending migrations
+    let db = args.open_db()?;
+    let version = db.schema_version().map_err(|e| format!("Error reading schema version: {}", e))?;
+
+    match db.migration() {
+        Some(report) if report.backup.is_none() => eprintln!("Created schema version {}", report.to_version),
+        Some(report) => eprintln!(
+            "Upgraded schema from version {} to {}, previous file saved as {}",
+            report.from_version,
+            report.to_version,
+            report.backup.as_ref().map(|b| b.display().to_string()).unwrap_or_default()
+        ),
+        None => {}
+    }
+
+    match args.format()? {
+        OutputFormat::Json => {
+            let steps: Vec<_> = migrations::migrations()
+                .iter()
+                .map(|m| serde_json::json!({ "version": m.version, "description": m.description, "applied": m.version <= version }))
+                .collect();
+            let json = serde_json::json!({ "version": version, "latest": migrations::latest_version(), "migrations": steps });
+            outln!("{}", serde_json::to_string_pretty(&json).map_err(|e| e.to_string())?);
+        }
+        OutputFormat::Csv => {
+            outln!("Version,Description,Applied");
+            for m in migrations::migrations() {
+                outln!("{},{},{}", csv_field(m.version), csv_field(m.description), csv_field(m.version <= version));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn export_inventory(args: &Args) -> Result<(), String> {
+    let db = args.open_db()?;
+    let content = match args.format()? {
+        OutputFormat::Json => db.export_json(),
+        OutputFormat::Csv => db.export_csv(),
+    }
+    .map_err(|e| format!("Error exporting data: {}", e))?;
+
+    match args.flag("output") {
+        Some(path) => {
+            fs::write(path, content).map_err(|e| format!("Error writing file {}: {}", path, e))?;
+            eprintln!("Data exported to {}", path);
+        }
+        None => outln!("{}", content.trim_end()),
+    }
+
+    Ok(())
+}
+
+fn import_inventory(args: &Args, path: &str) -> Result<(), String> {
+    let content = read_input(path)?;
+    let db = args.open_db()?;
+    let count = match db.import_json(&content) {
+        Ok(count) => count,
+        Err(Error::Parse { source, .. }) => return Err(format!("{} is not valid inventory JSON: {}", path, source)),
+        Err(e) => return Err(format!("Error importing JSON data: {}", e)),
+    };
+
+    match args.format()? {
+        OutputFormat::Json => outln!("{}", serde_json::json!({ "imported": count })),
+        OutputFormat::Csv => out!("Imported\n{}\n", csv_field(count)),
+    }
+
+    Ok(())
+}
+
+fn read_input(path: &str) -> Result<String, String> {
+    if path == "-" {
+        let mut text = String::new();
+        io::stdin()
+            .read_to_string(&mut text)
+            .map_err(|e| format!("Error reading stdin: {}", e))?;
+        Ok(text)
+    } else {
+        fs::read_to_string(path).map_err(|e| format!("Error reading file {}: {}", path, e))
+    }
+}
+
+fn parse_tag_id(tag_id: &str) -> Result<Uid, String> {
+    tag_id.parse::<Uid>().map_err(|e| format!("Invalid tag ID '{}': {}", tag_id, e))
+}
+
+// Print the lines that decoded, then fail if any did not so scripts notice
+fn print_batch(args: &Args, lines: Vec<BatchLine>) -> Result<(), String> {
+    let mut records = Vec::new();
+    let mut failed = 0;
+
+    for line in lines {
+        match line.result {
+            Ok(record) => records.push(record),
+            Err(e) => {
+                eprintln!("Line {}: could not decode '{}': {}", line.line_number, line.raw, e);
+                failed += 1;
+            }
+        }
+    }
+
+    print_records(args, &records)?;
+
+    if failed > 0 {
+        return Err(format!("{} UID(s) could not be decoded", failed));
+    }
+    Ok(())
+}
+
+fn list_layouts(args: &Args) -> Result<(), String> {
+    // Numbered as accepted by --layout, auto-detect first
+    let layouts: Vec<(usize, KeyboardLayout, String)> = KeyboardLayout::all()
+        .into_iter()
+        .enumerate()
+        .map(|(i, layout)| {
+            let description = match layout.definition() {
+                Some(definition) => definition.description,
+                None => "Try every layout and keep the most plausible result".to_string(),
+            };
+            (i, layout, description)
+        })
+        .collect();
+
+    match args.format()? {
+        OutputFormat::Json => {
+            let json: Vec<_> = layouts
+                .iter()
+                .map(|(i, layout, description)| serde_json::json!({ "layout": i, "name": layout, "description": description }))
+                .collect();
+            outln!("{}", serde_json::to_string_pretty(&json).map_err(|e| e.to_string())?);
+        }
+        OutputFormat::Csv => {
+            outln!("Layout,Name,Description");
+            for (i, layout, description) in &layouts {
+                outln!("{},{},{}", csv_field(i), csv_field(layout), csv_field(description));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn score_layouts(args: &Args, raw: &str) -> Result<(), String> {
+    let scores = keyboard_layout::score_layouts(raw);
+
+    match args.format()? {
+        OutputFormat::Json => {
+            let json: Vec<_> = scores
+                .iter()
+                .map(|s| serde_json::json!({ "layout": s.layout, "score": s.score, "uid": s.uid }))
+                .collect();
+            outln!("{}", serde_json::to_string_pretty(&json).map_err(|e| e.to_string())?);
+        }
+        OutputFormat::Csv => {
+            outln!("Layout,Score,UID");
+            for s in &scores {
+                outln!(
+                    "{},{},{}",
+                    csv_field(&s.layout),
+                    csv_field(s.score),
+                    csv_field(s.uid.as_ref().map(Uid::to_hex).unwrap_or_default())
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_credentials(args: &Args, credentials: &[WiegandCredential]) -> Result<(), String> {
+    match args.format()? {
+        OutputFormat::Json => {
+            let json = serde_json::to_string_pretty(credentials).map_err(|e| e.to_string())?;
+            outln!("{}", json);
+        }
+        OutputFormat::Csv => {
+            outln!("Format,Facility Code,Card Number,Frame Hex,Frame Bits");
+            for c in credentials {
+                outln!(
+                    "{},{},{},{},{}",
+                    csv_field(c.format.bit_length()),
+                    csv_field(c.facility_code),
+                    csv_field(c.card_number),
+                    csv_field(c.to_hex()),
+                    csv_field(c.to_binary())
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_records(args: &Args, records: &[CardRecord]) -> Result<(), String> {
+    let format = match args.format()? {
+        OutputFormat::Json => ExportFormat::JSON,
+        OutputFormat::Csv => ExportFormat::CSV,
+    };
+
+    out!("{}", export::render(records, &format));
+    Ok(())
+}
+
+fn print_items(args: &Args, items: &[InventoryItem]) -> Result<(), String> {
+    match args.format()? {
+        OutputFormat::Json => {
+            let json = serde_json::to_string_pretty(items).map_err(|e| e.to_string())?;
+            outln!("{}", json);
+        }
+        OutputFormat::Csv => out!("{}", items_to_csv(items)),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,285 @@
+// card_family.rs
+// Card type identification from the ISO14443-A anticollision response,
+// following NXP AN10833 "MIFARE type identification procedure".
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// NXP card families that can be told apart by ATQA/SAK (plus GetVersion where needed)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CardFamily {
+    MifareMini,
+    MifareClassic1K,
+    MifareClassic4K,
+    /// MIFARE Ultralight (any variant) or NTAG 21x; both answer SAK 0x00
+    UltralightNtag,
+    /// DESFire whose generation has not been read with GetVersion
+    Desfire,
+    DesfireEv1,
+    DesfireEv2,
+    DesfireEv3,
+    /// MIFARE Plus in security level 1 (Classic-compatible)
+    PlusSl1,
+    /// MIFARE Plus in security level 2
+    PlusSl2,
+    /// MIFARE Plus in security level 3 (AES, ISO14443-4)
+    PlusSl3,
+    /// SmartMX or another ISO14443-4 smart card, possibly emulating MIFARE Classic
+    SmartMx,
+    Unknown,
+}
+
+/// Crypto used to protect the card memory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CryptoType {
+    None,
+    /// Proprietary MIFARE Classic Crypto1 (broken, treat as unprotected)
+    Crypto1,
+    /// 32-bit password (Ultralight EV1 / NTAG PWD_AUTH) or 3DES on Ultralight C
+    Password,
+    /// DES, 3DES and AES
+    DesAes,
+    Aes,
+    /// Depends on the applet
+    Applet,
+    Unknown,
+}
+
+impl fmt::Display for CryptoType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            CryptoType::None => "none",
+            CryptoType::Crypto1 => "Crypto1",
+            CryptoType::Password => "password / 3DES",
+            CryptoType::DesAes => "DES/3DES/AES",
+            CryptoType::Aes => "AES",
+            CryptoType::Applet => "applet-defined",
+            CryptoType::Unknown => "unknown",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// What a card family can do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CardCapabilities {
+    /// User memory in bytes; `None` when it differs between variants of the family
+    pub memory_bytes: Option<u32>,
+    pub crypto: CryptoType,
+    /// Speaks ISO/IEC 14443-4 (RATS, APDUs)
+    pub iso14443_4: bool,
+}
+
+impl CardFamily {
+    pub const ALL: [CardFamily; 13] = [
+        CardFamily::MifareMini,
+        CardFamily::MifareClassic1K,
+        CardFamily::MifareClassic4K,
+        CardFamily::UltralightNtag,
+        CardFamily::Desfire,
+        CardFamily::DesfireEv1,
+        CardFamily::DesfireEv2,
+        CardFamily::DesfireEv3,
+        CardFamily::PlusSl1,
+        CardFamily::PlusSl2,
+        CardFamily::PlusSl3,
+        CardFamily::SmartMx,
+        CardFamily::Unknown,
+    ];
+
+    /// Identify the family from ATQA and SAK alone (AN10833 §3.2, table 5)
+    pub fn identify(atqa: u16, sak: u8) -> CardFamily {
+        match sak {
+            0x00 => CardFamily::UltralightNtag,
+            0x08 => CardFamily::MifareClassic1K,
+            0x09 => CardFamily::MifareMini,
+            0x10 | 0x11 => CardFamily::PlusSl2,
+            0x18 => CardFamily::MifareClassic4K,
+            0x20 => match atqa {
+                0x0344 | 0x0304 => CardFamily::Desfire,
+                0x0002 | 0x0004 | 0x0042 | 0x0044 => CardFamily::PlusSl3,
+                _ => CardFamily::SmartMx,
+            },
+            // SmartMX with Classic 1K/4K emulation
+            0x28 | 0x38 => CardFamily::SmartMx,
+            _ => CardFamily::Unknown,
+        }
+    }
+
+    /// Refine an ATQA/SAK identification with a GetVersion (0x60) response.
+    ///
+    /// GetVersion is the only way to tell DESFire generations apart, and to see
+    /// that a "Classic" SAK actually belongs to a MIFARE Plus in SL1.
+    pub fn identify_with_version(atqa: u16, sak: u8, version: &[u8]) -> CardFamily {
+        let family = CardFamily::identify(atqa, sak);
+
+        // Vendor, type, subtype, major, minor, ... ; only NXP layouts are known
+        let (vendor, product_type, major) = match version {
+            [vendor, product_type, _, major, ..] => (*vendor, *product_type, *major),
+            _ => return family,
+        };
+        if vendor != 0x04 {
+            return family;
+        }
+
+        match (family, product_type) {
+            (CardFamily::Desfire, 0x01) => match major {
+                0x01 => CardFamily::DesfireEv1,
+                0x12 => CardFamily::DesfireEv2,
+                0x33 => CardFamily::DesfireEv3,
+                _ => CardFamily::Desfire,
+            },
+            (CardFamily::MifareClassic1K | CardFamily::MifareClassic4K, 0x02) => CardFamily::PlusSl1,
+            _ => family,
+        }
+    }
+
+    /// Short machine-readable identifier, stored in the database and exports
+    pub fn key(&self) -> &'static str {
+        match self {
+            CardFamily::MifareMini => "mifare_mini",
+            CardFamily::MifareClassic1K => "mifare_classic_1k",
+            CardFamily::MifareClassic4K => "mifare_classic_4k",
+            CardFamily::UltralightNtag => "ultralight_ntag",
+            CardFamily::Desfire => "desfire",
+            CardFamily::DesfireEv1 => "desfire_ev1",
+            CardFamily::DesfireEv2 => "desfire_ev2",
+            CardFamily::DesfireEv3 => "desfire_ev3",
+            CardFamily::PlusSl1 => "plus_sl1",
+            CardFamily::PlusSl2 => "plus_sl2",
+            CardFamily::PlusSl3 => "plus_sl3",
+            CardFamily::SmartMx => "smartmx",
+            CardFamily::Unknown => "unknown",
+        }
+    }
+
+    /// Human-readable name for logs and the inventory table
+    pub fn name(&self) -> &'static str {
+        match self {
+            CardFamily::MifareMini => "MIFARE Mini",
+            CardFamily::MifareClassic1K => "MIFARE Classic 1K",
+            CardFamily::MifareClassic4K => "MIFARE Classic 4K",
+            CardFamily::UltralightNtag => "MIFARE Ultralight / NTAG",
+            CardFamily::Desfire => "MIFARE DESFire",
+            CardFamily::DesfireEv1 => "MIFARE DESFire EV1",
+            CardFamily::DesfireEv2 => "MIFARE DESFire EV2",
+            CardFamily::DesfireEv3 => "MIFARE DESFire EV3",
+            CardFamily::PlusSl1 => "MIFARE Plus (SL1)",
+            CardFamily::PlusSl2 => "MIFARE Plus (SL2)",
+            CardFamily::PlusSl3 => "MIFARE Plus (SL3)",
+            CardFamily::SmartMx => "SmartMX / ISO14443-4",
+            CardFamily::Unknown => "Unknown card type",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<CardFamily> {
+        CardFamily::ALL.iter().copied().find(|family| family.key() == key)
+    }
+
+    pub fn capabilities(&self) -> CardCapabilities {
+        let (memory_bytes, crypto, iso14443_4) = match self {
+            CardFamily::MifareMini => (Some(320), CryptoType::Crypto1, false),
+            CardFamily::MifareClassic1K => (Some(1024), CryptoType::Crypto1, false),
+            CardFamily::MifareClassic4K => (Some(4096), CryptoType::Crypto1, false),
+            // 48 bytes (Ultralight) up to 888 bytes (NTAG216)
+            CardFamily::UltralightNtag => (None, CryptoType::Password, false),
+            CardFamily::Desfire => (None, CryptoType::DesAes, true),
+            // 2, 4 or 8 KB depending on the part
+            CardFamily::DesfireEv1 | CardFamily::DesfireEv2 | CardFamily::DesfireEv3 => {
+                (None, CryptoType::DesAes, true)
+            }
+            // 2 or 4 KB; SL1 still authenticates with Crypto1
+            CardFamily::PlusSl1 => (None, CryptoType::Crypto1, false),
+            CardFamily::PlusSl2 => (None, CryptoType::Aes, false),
+            CardFamily::PlusSl3 => (None, CryptoType::Aes, true),
+            CardFamily::SmartMx => (None, CryptoType::Applet, true),
+            CardFamily::Unknown => (None, CryptoType::Unknown, false),
+        };
+
+        CardCapabilities { memory_bytes, crypto, iso14443_4 }
+    }
+
+    /// One-line summary of the capabilities, e.g. "1024 bytes, Crypto1"
+    pub fn describe_capabilities(&self) -> String {
+        let caps = self.capabilities();
+        let memory = match caps.memory_bytes {
+            Some(bytes) => format!("{} bytes", bytes),
+            None => "variable memory".to_string(),
+        };
+        let protocol = if caps.iso14443_4 { ", ISO14443-4" } else { "" };
+
+        format!("{}, {}{}", memory, caps.crypto, protocol)
+    }
+}
+
+impl fmt::Display for CardFamily {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for CardFamily {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        CardFamily::from_key(s).ok_or_else(|| format!("unknown card family '{}'", s))
+    }
+}
+
+// Stored and exchanged by key, so names can change without breaking old data
+impl Serialize for CardFamily {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.key())
+    }
+}
+
+impl<'de> Deserialize<'de> for CardFamily {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let key = String::deserialize(deserializer)?;
+        CardFamily::from_key(&key).ok_or_else(|| serde::de::Error::custom(format!("unknown card family '{}'", key)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn atqa_and_sak_identify_the_family() {
+        // Known answers from AN10833 table 5
+        assert_eq!(CardFamily::identify(0x0004, 0x08), CardFamily::MifareClassic1K);
+        assert_eq!(CardFamily::identify(0x0002, 0x18), CardFamily::MifareClassic4K);
+        assert_eq!(CardFamily::identify(0x0044, 0x00), CardFamily::UltralightNtag);
+        assert_eq!(CardFamily::identify(0x0344, 0x20), CardFamily::Desfire);
+        assert_eq!(CardFamily::identify(0x0004, 0x09), CardFamily::MifareMini);
+        assert_eq!(CardFamily::identify(0x0044, 0x20), CardFamily::PlusSl3);
+        assert_eq!(CardFamily::identify(0x0004, 0x28), CardFamily::SmartMx);
+        assert_eq!(CardFamily::identify(0x0004, 0x01), CardFamily::Unknown);
+        assert_eq!(CardFamily::identify(0xFFFF, 0xFF), CardFamily::Unknown);
+    }
+
+    #[test]
+    fn get_version_refines_desfire_and_classic() {
+        let version = |product_type: u8, major: u8| [0x04, product_type, 0x01, major, 0x00, 0x18, 0x05];
+
+        assert_eq!(CardFamily::identify_with_version(0x0344, 0x20, &version(0x01, 0x01)), CardFamily::DesfireEv1);
+        assert_eq!(CardFamily::identify_with_version(0x0344, 0x20, &version(0x01, 0x12)), CardFamily::DesfireEv2);
+        assert_eq!(CardFamily::identify_with_version(0x0344, 0x20, &version(0x01, 0x33)), CardFamily::DesfireEv3);
+        assert_eq!(CardFamily::identify_with_version(0x0344, 0x20, &version(0x01, 0x00)), CardFamily::Desfire);
+        assert_eq!(CardFamily::identify_with_version(0x0004, 0x08, &version(0x02, 0x11)), CardFamily::PlusSl1);
+
+        // Other vendors and short answers leave the ATQA/SAK identification alone
+        let mut other_vendor = version(0x01, 0x01);
+        other_vendor[0] = 0x05;
+        assert_eq!(CardFamily::identify_with_version(0x0344, 0x20, &other_vendor), CardFamily::Desfire);
+        assert_eq!(CardFamily::identify_with_version(0x0344, 0x20, &[0x04, 0x01]), CardFamily::Desfire);
+    }
+
+    #[test]
+    fn keys_round_trip() {
+        for family in CardFamily::ALL {
+            assert_eq!(family.key().parse::<CardFamily>(), Ok(family));
+        }
+    }
+}
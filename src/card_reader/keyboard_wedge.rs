@@ -0,0 +1,94 @@
+// card_reader/keyboard_wedge.rs
+use std::collections::VecDeque;
+use std::io::BufRead;
+
+use crate::card_reader::{event_from_raw, CardEvent, CardReader};
+use crate::error::{Error, Result};
+use crate::keyboard_layout::KeyboardLayout;
+
+/// A USB reader that "types" each UID followed by Enter.
+///
+/// In the GUI the frontend owns the text field and hands every completed line
+/// to `push_line`; headless, the reader types into a terminal and lines are read
+/// from `input`. Wedge readers can't report removal, so each read is followed
+/// straight away by a `CardRemoved` event.
+pub struct KeyboardWedgeReader {
+    keyboard_layout: KeyboardLayout,
+    input: Option<Box<dyn BufRead>>,
+    pending: VecDeque<CardEvent>,
+}
+
+impl KeyboardWedgeReader {
+    /// A reader fed by the frontend through `push_line`
+    pub fn new(keyboard_layout: KeyboardLayout) -> Self {
+        KeyboardWedgeReader {
+            keyboard_layout,
+            input: None,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// A reader that reads typed lines from `input`, e.g. stdin
+    pub fn with_input(input: Box<dyn BufRead>, keyboard_layout: KeyboardLayout) -> Self {
+        KeyboardWedgeReader {
+            keyboard_layout,
+            input: Some(input),
+            pending: VecDeque::new(),
+        }
+    }
+
+    pub fn set_keyboard_layout(&mut self, keyboard_layout: KeyboardLayout) {
+        self.keyboard_layout = keyboard_layout;
+    }
+
+    /// Queue the events for one line of typed input
+    pub fn push_line(&mut self, line: &str) {
+        let line = line.trim();
+
+        // Configuration barcodes scanned from the reader manual echo back as text
+        if line.is_empty() || line.contains("config") || line.contains("Buz") {
+            return;
+        }
+
+        let event = event_from_raw(line, &self.keyboard_layout);
+        let uid = match &event {
+            CardEvent::CardPresent(read) => Some(read.uid.clone()),
+            _ => None,
+        };
+
+        self.pending.push_back(event);
+        self.pending.push_back(CardEvent::CardRemoved { uid });
+    }
+}
+
+impl CardReader for KeyboardWedgeReader {
+    fn name(&self) -> &str {
+        "Keyboard wedge"
+    }
+
+    fn poll(&mut self) -> Result<Option<CardEvent>> {
+        Ok(self.pending.pop_front())
+    }
+
+    fn next_event(&mut self) -> Result<Option<CardEvent>> {
+        while self.pending.is_empty() {
+            let input = match self.input.as_mut() {
+                Some(input) => input,
+                // Lines arrive through the frontend's event loop, so blocking here would never wake up
+                None => return Err(Error::Reader("keyboard wedge input is pushed by the frontend; use poll".to_string())),
+            };
+
+            let mut line = String::new();
+            let read = input
+                .read_line(&mut line)
+                .map_err(|e| Error::io("Failed to read keyboard wedge input", e))?;
+            if read == 0 {
+                return Ok(None);
+            }
+
+            self.push_line(&line);
+        }
+
+        Ok(self.pending.pop_front())
+    }
+}
@@ -0,0 +1,63 @@
+// card_reader/mod.rs
+// Reader backends turn whatever the hardware (or a script) produces into typed
+// card events, so the capture window, the CLI and CI runs share one code path.
+pub mod keyboard_wedge;
+pub mod pn532;
+pub mod simulated;
+
+use crate::error::Result;
+use crate::keyboard_layout::KeyboardLayout;
+use crate::uid::{Uid, UidError};
+
+pub use keyboard_wedge::KeyboardWedgeReader;
+pub use pn532::Pn532Reader;
+pub use simulated::SimulatedReader;
+
+/// A card that was successfully read
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CardRead {
+    pub uid: Uid,
+    /// The data exactly as the reader delivered it, before decoding
+    pub raw: String,
+    /// ATQA (SENS_RES) and SAK (SEL_RES), for readers that report the anticollision response
+    pub atqa: Option<u16>,
+    pub sak: Option<u8>,
+    /// GetVersion response, for readers that can talk to the card and a card that answered
+    pub version: Option<Vec<u8>>,
+}
+
+/// Something that happened at the reader
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CardEvent {
+    /// A card entered the field and its UID was read
+    CardPresent(CardRead),
+    /// The card left the field; `None` if the last read could not be decoded
+    CardRemoved { uid: Option<Uid> },
+    /// The reader delivered data that is not a valid UID
+    ReadError { raw: String, error: UidError },
+}
+
+/// A source of card events
+pub trait CardReader {
+    /// Short description for logs and window titles
+    fn name(&self) -> &str;
+
+    /// Return the next event if one is ready, without blocking
+    fn poll(&mut self) -> Result<Option<CardEvent>>;
+
+    /// Block until the next event; `None` means the reader has nothing more to deliver
+    fn next_event(&mut self) -> Result<Option<CardEvent>>;
+
+    /// True when no further events will ever arrive, e.g. a replayed script has ended
+    fn is_finished(&self) -> bool {
+        false
+    }
+}
+
+// Decode a raw read into the matching event
+pub(crate) fn event_from_raw(raw: &str, keyboard_layout: &KeyboardLayout) -> CardEvent {
+    match Uid::from_raw(raw, keyboard_layout) {
+        Ok(uid) => CardEvent::CardPresent(CardRead { uid, raw: raw.to_string(), atqa: None, sak: None, version: None }),
+        Err(error) => CardEvent::ReadError { raw: raw.to_string(), error },
+    }
+}
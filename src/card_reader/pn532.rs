@@ -0,0 +1,481 @@
+// card_reader/pn532.rs
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::thread;
+use std::time::Duration;
+
+use serialport::SerialPort;
+
+use crate::card_family::CardFamily;
+use crate::card_reader::{CardEvent, CardRead, CardReader};
+use crate::error::{Error, Result};
+use crate::uid::Uid;
+
+// Frame layout (PN532 user manual §6.2.1):
+// 00 00 FF LEN LCS TFI PD0..PDn DCS 00
+const PREAMBLE: [u8; 3] = [0x00, 0x00, 0xFF];
+const POSTAMBLE: u8 = 0x00;
+const ACK_FRAME: [u8; 6] = [0x00, 0x00, 0xFF, 0x00, 0xFF, 0x00];
+const TFI_HOST_TO_PN532: u8 = 0xD4;
+const TFI_PN532_TO_HOST: u8 = 0xD5;
+// Application-level error frame body
+const ERROR_FRAME_BODY: u8 = 0x7F;
+
+const CMD_GET_FIRMWARE_VERSION: u8 = 0x02;
+const CMD_SAM_CONFIGURATION: u8 = 0x14;
+const CMD_RF_CONFIGURATION: u8 = 0x32;
+const CMD_IN_DATA_EXCHANGE: u8 = 0x40;
+const CMD_IN_LIST_PASSIVE_TARGET: u8 = 0x4A;
+
+// Native DESFire GetVersion, answered with 0xAF (more frames follow) and the hardware info
+const DESFIRE_GET_VERSION: u8 = 0x60;
+const DESFIRE_ADDITIONAL_FRAME: u8 = 0xAF;
+
+// InListPassiveTarget baud rate / modulation for ISO14443-A at 106 kbps
+const BRTY_106_TYPE_A: u8 = 0x00;
+
+pub const DEFAULT_BAUD_RATE: u32 = 115_200;
+
+/// A target found by InListPassiveTarget
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TargetInfo {
+    /// SENS_RES, as the two bytes the PN532 reports, big-endian
+    pub atqa: u16,
+    /// SEL_RES
+    pub sak: u8,
+    pub uid: Uid,
+}
+
+/// Wrap a command and its parameters in a host-to-PN532 information frame
+pub fn build_frame(command: u8, params: &[u8]) -> Vec<u8> {
+    let mut body = Vec::with_capacity(params.len() + 2);
+    body.push(TFI_HOST_TO_PN532);
+    body.push(command);
+    body.extend_from_slice(params);
+
+    let len = body.len() as u8;
+    let mut frame = PREAMBLE.to_vec();
+    frame.push(len);
+    frame.push(len.wrapping_neg());
+    frame.extend_from_slice(&body);
+    frame.push(checksum(&body));
+    frame.push(POSTAMBLE);
+    frame
+}
+
+// Value that makes the bytes sum to zero modulo 256
+fn checksum(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0u8, |acc, b| acc.wrapping_add(*b)).wrapping_neg()
+}
+
+/// Check the length and data checksums of a frame read after its 00 00 FF preamble.
+///
+/// `rest` is the LEN bytes of TFI and data followed by DCS. Returns the bytes after the TFI.
+pub fn parse_frame_body(len: u8, lcs: u8, rest: &[u8]) -> Result<Vec<u8>> {
+    if len.wrapping_add(lcs) != 0 {
+        return Err(Error::Reader(format!("PN532 length checksum mismatch (LEN {:02X}, LCS {:02X})", len, lcs)));
+    }
+    if rest.len() != len as usize + 1 {
+        return Err(Error::Reader(format!("PN532 frame truncated: expected {} bytes, got {}", len as usize + 1, rest.len())));
+    }
+
+    let (body, dcs) = rest.split_at(len as usize);
+    if checksum(body) != dcs[0] {
+        return Err(Error::Reader("PN532 data checksum mismatch".to_string()));
+    }
+
+    match body.first() {
+        Some(&TFI_PN532_TO_HOST) => Ok(body[1..].to_vec()),
+        Some(&ERROR_FRAME_BODY) => Err(Error::Reader("PN532 reported an application error".to_string())),
+        Some(tfi) => Err(Error::Reader(format!("unexpected PN532 frame identifier {:02X}", tfi))),
+        None => Err(Error::Reader("empty PN532 frame".to_string())),
+    }
+}
+
+/// Parse the data of an InListPassiveTarget response (after D5 4B) for a type A target
+pub fn parse_in_list_passive_target(data: &[u8]) -> Result<Option<TargetInfo>> {
+    let truncated = || Error::Reader("truncated InListPassiveTarget response".to_string());
+
+    // NbTg, then Tg SENS_RES(2) SEL_RES NFCIDLength NFCID1...
+    let targets = *data.first().ok_or_else(truncated)?;
+    if targets == 0 {
+        return Ok(None);
+    }
+
+    let target = data.get(1..).ok_or_else(truncated)?;
+    if target.len() < 5 {
+        return Err(truncated());
+    }
+
+    let atqa = u16::from_be_bytes([target[1], target[2]]);
+    let sak = target[3];
+    let uid_len = target[4] as usize;
+    let uid_bytes = target.get(5..5 + uid_len).ok_or_else(truncated)?;
+
+    let uid = Uid::from_bytes(uid_bytes)?;
+    Ok(Some(TargetInfo { atqa, sak, uid }))
+}
+
+/// A PN532 on a serial port (HSU mode, including USB-CDC bridges).
+///
+/// Every `poll` runs one InListPassiveTarget; a new UID produces `CardPresent`,
+/// an empty field after a card produces `CardRemoved`.
+pub struct Pn532Reader {
+    name: String,
+    port: Box<dyn SerialPort>,
+    current: Option<Uid>,
+    pending: VecDeque<CardEvent>,
+    poll_interval: Duration,
+}
+
+impl Pn532Reader {
+    /// Open the device at `path` and bring the PN532 into normal mode
+    pub fn open(path: &str, baud_rate: u32) -> Result<Self> {
+        let port = serialport::new(path, baud_rate)
+            .timeout(Duration::from_millis(500))
+            .open()
+            .map_err(|e| Error::Reader(format!("could not open {}: {}", path, e)))?;
+
+        Pn532Reader::with_port(&format!("PN532 ({})", path), port)
+    }
+
+    /// Use an already-open port, e.g. one half of a pseudo-terminal pair
+    pub fn with_port(name: &str, port: Box<dyn SerialPort>) -> Result<Self> {
+        let mut reader = Pn532Reader {
+            name: name.to_string(),
+            port,
+            current: None,
+            pending: VecDeque::new(),
+            poll_interval: Duration::from_millis(200),
+        };
+
+        reader.wake_up()?;
+        reader.command(CMD_SAM_CONFIGURATION, &[0x01, 0x14, 0x01])?;
+        // MxRtyATR, MxRtyPSL, MxRtyPassiveActivation: give up quickly when the field is empty
+        reader.command(CMD_RF_CONFIGURATION, &[0x05, 0xFF, 0x01, 0x02])?;
+
+        Ok(reader)
+    }
+
+    /// IC, version, revision and supported-protocol bytes from GetFirmwareVersion
+    pub fn firmware_version(&mut self) -> Result<[u8; 4]> {
+        let data = self.command(CMD_GET_FIRMWARE_VERSION, &[])?;
+        if data.len() < 4 {
+            return Err(Error::Reader("truncated GetFirmwareVersion response".to_string()));
+        }
+        Ok([data[0], data[1], data[2], data[3]])
+    }
+
+    /// Look for one ISO14443-A target in the field
+    pub fn in_list_passive_target(&mut self) -> Result<Option<TargetInfo>> {
+        let data = self.command(CMD_IN_LIST_PASSIVE_TARGET, &[0x01, BRTY_106_TYPE_A])?;
+        parse_in_list_passive_target(&data)
+    }
+
+    /// Vendor, type, subtype, major and minor version, storage size and protocol
+    /// from the first GetVersion frame of the target listed last.
+    ///
+    /// `None` when the card doesn't answer GetVersion, which plenty of cards don't.
+    pub fn get_version(&mut self) -> Result<Option<Vec<u8>>> {
+        let data = self.command(CMD_IN_DATA_EXCHANGE, &[0x01, DESFIRE_GET_VERSION])?;
+
+        // Status byte (lower six bits are the error code), then the card's answer
+        match data.split_first() {
+            Some((status, answer)) if status & 0x3F == 0 => match answer.split_first() {
+                Some((&DESFIRE_ADDITIONAL_FRAME, version)) if !version.is_empty() => Ok(Some(version.to_vec())),
+                _ => Ok(None),
+            },
+            _ => Ok(None),
+        }
+    }
+
+    fn wake_up(&mut self) -> Result<()> {
+        // HSU wake-up: a long 0x55 preamble followed by zeros (user manual §7.2.11)
+        let mut wake = vec![0x55, 0x55];
+        wake.extend_from_slice(&[0x00; 14]);
+        self.port.write_all(&wake).map_err(|e| self.io_error(e))?;
+        Ok(())
+    }
+
+    // Send a command, wait for the ACK and return the response data after D5 <cmd+1>
+    fn command(&mut self, command: u8, params: &[u8]) -> Result<Vec<u8>> {
+        let frame = build_frame(command, params);
+        self.port.write_all(&frame).map_err(|e| self.io_error(e))?;
+        self.port.flush().map_err(|e| self.io_error(e))?;
+
+        let ack = self.read_frame()?;
+        if ack.is_some() {
+            return Err(Error::Reader("PN532 answered without acknowledging the command".to_string()));
+        }
+
+        let response = self.read_frame()?.ok_or_else(|| Error::Reader("PN532 sent a second ACK instead of a response".to_string()))?;
+        match response.split_first() {
+            Some((code, data)) if *code == command.wrapping_add(1) => Ok(data.to_vec()),
+            _ => Err(Error::Reader(format!("unexpected PN532 response to command {:02X}", command))),
+        }
+    }
+
+    // Read one frame; None for an ACK, Some(data after TFI) for an information frame
+    fn read_frame(&mut self) -> Result<Option<Vec<u8>>> {
+        // Skip wake-up echoes and noise until the 00 FF start code
+        let mut previous = 0xFFu8;
+        loop {
+            let byte = self.read_byte()?;
+            if previous == 0x00 && byte == 0xFF {
+                break;
+            }
+            previous = byte;
+        }
+
+        let len = self.read_byte()?;
+        let lcs = self.read_byte()?;
+        if [len, lcs] == ACK_FRAME[3..5] {
+            self.read_byte()?; // postamble
+            return Ok(None);
+        }
+
+        let mut rest = vec![0u8; len as usize + 1];
+        self.port.read_exact(&mut rest).map_err(|e| self.io_error(e))?;
+        self.read_byte()?; // postamble
+
+        parse_frame_body(len, lcs, &rest).map(Some)
+    }
+
+    fn read_byte(&mut self) -> Result<u8> {
+        let mut byte = [0u8; 1];
+        self.port.read_exact(&mut byte).map_err(|e| self.io_error(e))?;
+        Ok(byte[0])
+    }
+
+    fn io_error(&self, e: io::Error) -> Error {
+        if e.kind() == io::ErrorKind::TimedOut {
+            Error::Reader(format!("{} did not respond", self.name))
+        } else {
+            Error::io(format!("Serial I/O error on {}", self.name), e)
+        }
+    }
+}
+
+impl CardReader for Pn532Reader {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn poll(&mut self) -> Result<Option<CardEvent>> {
+        if let Some(event) = self.pending.pop_front() {
+            return Ok(Some(event));
+        }
+
+        let target = self.in_list_passive_target()?;
+        let uid = target.as_ref().map(|t| t.uid.clone());
+        if uid == self.current {
+            // Same card still in the field, or still empty
+            return Ok(None);
+        }
+
+        // A different card replaces the old one without an empty poll in between
+        if let Some(previous) = self.current.take() {
+            self.pending.push_back(CardEvent::CardRemoved { uid: Some(previous) });
+        }
+        if let Some(target) = target {
+            // Only GetVersion tells DESFire generations apart; other cards aren't asked
+            let version = match CardFamily::identify(target.atqa, target.sak) {
+                CardFamily::Desfire => self.get_version()?,
+                _ => None,
+            };
+
+            self.current = Some(target.uid.clone());
+            self.pending.push_back(CardEvent::CardPresent(CardRead {
+                raw: target.uid.to_hex(),
+                uid: target.uid,
+                atqa: Some(target.atqa),
+                sak: Some(target.sak),
+                version,
+            }));
+        }
+
+        Ok(self.pending.pop_front())
+    }
+
+    fn next_event(&mut self) -> Result<Option<CardEvent>> {
+        loop {
+            if let Some(event) = self.poll()? {
+                return Ok(Some(event));
+            }
+            thread::sleep(self.poll_interval);
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::thread::JoinHandle;
+    use serialport::TTYPort;
+
+    // Play a PN532 on the master side of a pty until the reader closes its side. `reply` gets the
+    // command code and parameters of each frame and returns the bytes to send back
+    fn fake_pn532<F>(mut port: TTYPort, mut reply: F) -> JoinHandle<()>
+    where
+        F: FnMut(u8, &[u8]) -> Vec<u8> + Send + 'static,
+    {
+        thread::spawn(move || {
+            let read_byte = |port: &mut TTYPort| loop {
+                let mut byte = [0u8; 1];
+                match port.read(&mut byte) {
+                    Ok(1) => return Some(byte[0]),
+                    Err(e) if e.kind() == io::ErrorKind::TimedOut => continue,
+                    _ => return None,
+                }
+            };
+
+            loop {
+                // Skip the wake-up bytes up to the 00 FF start code
+                let mut previous = 0xFFu8;
+                loop {
+                    let Some(byte) = read_byte(&mut port) else { return };
+                    if previous == 0x00 && byte == 0xFF {
+                        break;
+                    }
+                    previous = byte;
+                }
+                let Some(len) = read_byte(&mut port) else { return };
+                let mut rest = Vec::new();
+                for _ in 0..len as usize + 3 {
+                    let Some(byte) = read_byte(&mut port) else { return };
+                    rest.push(byte);
+                }
+
+                // LCS, TFI, command, parameters..., DCS, postamble
+                let body = &rest[1..rest.len() - 2];
+                assert_eq!(body[0], TFI_HOST_TO_PN532);
+                port.write_all(&reply(body[1], &body[2..])).unwrap();
+            }
+        })
+    }
+
+    // ACK followed by the response frame D5 <command + 1> <data>
+    fn answer(command: u8, data: &[u8]) -> Vec<u8> {
+        let mut body = vec![TFI_PN532_TO_HOST, command.wrapping_add(1)];
+        body.extend_from_slice(data);
+
+        let len = body.len() as u8;
+        let mut bytes = ACK_FRAME.to_vec();
+        bytes.extend_from_slice(&PREAMBLE);
+        bytes.extend_from_slice(&[len, len.wrapping_neg()]);
+        bytes.extend_from_slice(&body);
+        bytes.extend_from_slice(&[checksum(&body), POSTAMBLE]);
+        bytes
+    }
+
+    fn open_reader() -> (TTYPort, Box<dyn SerialPort>) {
+        let (master, mut slave) = TTYPort::pair().expect("pseudo-terminal pair");
+        slave.set_timeout(Duration::from_secs(2)).unwrap();
+        (master, Box::new(slave))
+    }
+
+    #[test]
+    fn reads_firmware_and_reports_cards_coming_and_going() {
+        let (master, port) = open_reader();
+        let card = [0x04, 0xA1, 0xB2, 0xC3];
+        // NbTg, then Tg, SENS_RES, SEL_RES, NFCIDLength and the UID
+        let mut field: VecDeque<Vec<u8>> = VecDeque::from(vec![
+            vec![0x00],
+            [&[0x01, 0x01, 0x00, 0x04, 0x08, 0x04][..], &card].concat(),
+            [&[0x01, 0x01, 0x00, 0x04, 0x08, 0x04][..], &card].concat(),
+            vec![0x00],
+        ]);
+        let pn532 = fake_pn532(master, move |command, params| match command {
+            CMD_SAM_CONFIGURATION | CMD_RF_CONFIGURATION => answer(command, &[]),
+            CMD_GET_FIRMWARE_VERSION => answer(command, &[0x32, 0x01, 0x06, 0x07]),
+            CMD_IN_LIST_PASSIVE_TARGET => {
+                assert_eq!(params, [0x01, BRTY_106_TYPE_A]);
+                answer(command, &field.pop_front().expect("no more polls expected"))
+            }
+            _ => panic!("unexpected command {:02X}", command),
+        });
+
+        let mut reader = Pn532Reader::with_port("fake PN532", port).unwrap();
+        assert_eq!(reader.firmware_version().unwrap(), [0x32, 0x01, 0x06, 0x07]);
+
+        let uid = Uid::from_bytes(&card).unwrap();
+        assert_eq!(reader.poll().unwrap(), None);
+        assert_eq!(
+            reader.poll().unwrap(),
+            Some(CardEvent::CardPresent(CardRead {
+                uid: uid.clone(),
+                raw: "04A1B2C3".to_string(),
+                atqa: Some(0x0004),
+                sak: Some(0x08),
+                version: None,
+            }))
+        );
+        assert_eq!(reader.poll().unwrap(), None);
+        assert_eq!(reader.poll().unwrap(), Some(CardEvent::CardRemoved { uid: Some(uid) }));
+
+        drop(reader);
+        pn532.join().unwrap();
+    }
+
+    #[test]
+    fn asks_a_desfire_card_for_its_version() {
+        let (master, port) = open_reader();
+        let card = [0x04, 0x52, 0x2C, 0x6A, 0x8B, 0x5C, 0x80];
+        let pn532 = fake_pn532(master, move |command, params| match command {
+            CMD_SAM_CONFIGURATION | CMD_RF_CONFIGURATION => answer(command, &[]),
+            // ATQA 0344, SAK 20; the ATS the PN532 appends for ISO14443-4 cards isn't read
+            CMD_IN_LIST_PASSIVE_TARGET => {
+                answer(command, &[&[0x01, 0x01, 0x03, 0x44, 0x20, 0x07][..], &card, &[0x06, 0x75, 0x77, 0x81, 0x02, 0x80]].concat())
+            }
+            CMD_IN_DATA_EXCHANGE => {
+                assert_eq!(params, [0x01, DESFIRE_GET_VERSION]);
+                // Status OK, then an EV1 answering NXP, type 01, subtype 01, version 1.0
+                answer(command, &[0x00, DESFIRE_ADDITIONAL_FRAME, 0x04, 0x01, 0x01, 0x01, 0x00, 0x18, 0x05])
+            }
+            _ => panic!("unexpected command {:02X}", command),
+        });
+
+        let mut reader = Pn532Reader::with_port("fake PN532", port).unwrap();
+        let Some(CardEvent::CardPresent(read)) = reader.poll().unwrap() else { panic!("expected a card") };
+        assert_eq!(read.version.as_deref(), Some(&[0x04, 0x01, 0x01, 0x01, 0x00, 0x18, 0x05][..]));
+        assert_eq!(
+            crate::utils::identify_card_type(read.atqa, read.sak, read.version.as_deref()),
+            Some(CardFamily::DesfireEv1)
+        );
+
+        drop(reader);
+        pn532.join().unwrap();
+    }
+
+    #[test]
+    fn rejects_a_bad_checksum_and_a_missing_ack() {
+        let (master, port) = open_reader();
+        let mut firmware_requests = 0;
+        let pn532 = fake_pn532(master, move |command, _| match command {
+            CMD_SAM_CONFIGURATION | CMD_RF_CONFIGURATION => answer(command, &[]),
+            CMD_GET_FIRMWARE_VERSION => {
+                firmware_requests += 1;
+                let mut bytes = answer(command, &[0x32, 0x01, 0x06, 0x07]);
+                if firmware_requests == 1 {
+                    // Corrupt the data checksum
+                    let dcs = bytes.len() - 2;
+                    bytes[dcs] ^= 0xFF;
+                    bytes
+                } else {
+                    // The response without the ACK in front of it
+                    bytes.split_off(ACK_FRAME.len())
+                }
+            }
+            _ => panic!("unexpected command {:02X}", command),
+        });
+
+        let mut reader = Pn532Reader::with_port("fake PN532", port).unwrap();
+        let error = reader.firmware_version().unwrap_err().to_string();
+        assert!(error.contains("data checksum mismatch"), "{}", error);
+        let error = reader.firmware_version().unwrap_err().to_string();
+        assert!(error.contains("without acknowledging"), "{}", error);
+
+        drop(reader);
+        pn532.join().unwrap();
+    }
+}
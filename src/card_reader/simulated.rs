@@ -0,0 +1,287 @@
+// card_reader/simulated.rs
+use std::collections::VecDeque;
+use std::fs;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::card_reader::{event_from_raw, CardEvent, CardReader};
+use crate::error::{Error, Result};
+use crate::keyboard_layout::KeyboardLayout;
+use crate::uid::Uid;
+
+#[derive(Debug, Clone)]
+enum Action {
+    Present(String),
+    Remove,
+}
+
+#[derive(Debug, Clone)]
+struct Step {
+    /// Time to wait after the previous event
+    delay: Duration,
+    action: Action,
+}
+
+/// Replays card taps from a script or a saved reader log, keeping their timing.
+///
+/// Script lines are `<delay ms> present <raw uid>` or `<delay ms> remove`;
+/// blank lines and lines starting with `#` are skipped. A log saved from the
+/// reader tab is replayed with the gaps between its Unix timestamps.
+pub struct SimulatedReader {
+    name: String,
+    keyboard_layout: KeyboardLayout,
+    steps: VecDeque<Step>,
+    speed: f64,
+    last_event: Instant,
+    current: Option<Uid>,
+}
+
+impl SimulatedReader {
+    /// Parse a script or a reader log; the format is detected from the content
+    pub fn parse(name: &str, text: &str, keyboard_layout: KeyboardLayout) -> Result<Self> {
+        let is_log = text
+            .lines()
+            .any(|line| line.starts_with('[') && line.contains("Raw UID:"));
+
+        let steps = if is_log { parse_log(text) } else { parse_script(text)? };
+
+        Ok(SimulatedReader {
+            name: name.to_string(),
+            keyboard_layout,
+            steps: steps.into(),
+            speed: 1.0,
+            last_event: Instant::now(),
+            current: None,
+        })
+    }
+
+    pub fn from_file(path: &str, keyboard_layout: KeyboardLayout) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| Error::io(format!("Failed to read reader script {}", path), e))?;
+        SimulatedReader::parse(&format!("Simulated ({})", path), &text, keyboard_layout)
+    }
+
+    /// Scale the recorded delays: 2.0 replays twice as fast, 0.0 drops them entirely.
+    /// Negative, NaN and infinite speeds are refused
+    pub fn with_speed(mut self, speed: f64) -> Result<Self> {
+        if !speed.is_finite() || speed < 0.0 {
+            return Err(Error::Reader(format!("replay speed must be a number of 0 or more, got {}", speed)));
+        }
+        self.speed = speed;
+        Ok(self)
+    }
+
+    fn scaled_delay(&self, delay: Duration) -> Duration {
+        if self.speed == 0.0 {
+            Duration::ZERO
+        } else {
+            // A tiny speed stretches a delay past what Duration holds; such a step never comes
+            Duration::try_from_secs_f64(delay.as_secs_f64() / self.speed).unwrap_or(Duration::MAX)
+        }
+    }
+
+    fn take_step(&mut self) -> Option<CardEvent> {
+        let step = self.steps.pop_front()?;
+        self.last_event = Instant::now();
+
+        let event = match step.action {
+            Action::Present(raw) => {
+                let event = event_from_raw(&raw, &self.keyboard_layout);
+                self.current = match &event {
+                    CardEvent::CardPresent(read) => Some(read.uid.clone()),
+                    _ => None,
+                };
+                event
+            }
+            Action::Remove => CardEvent::CardRemoved { uid: self.current.take() },
+        };
+
+        Some(event)
+    }
+}
+
+impl CardReader for SimulatedReader {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn poll(&mut self) -> Result<Option<CardEvent>> {
+        let due = match self.steps.front() {
+            Some(step) => self.last_event.elapsed() >= self.scaled_delay(step.delay),
+            None => false,
+        };
+
+        Ok(if due { self.take_step() } else { None })
+    }
+
+    fn next_event(&mut self) -> Result<Option<CardEvent>> {
+        if let Some(step) = self.steps.front() {
+            let wait = self.scaled_delay(step.delay).saturating_sub(self.last_event.elapsed());
+            thread::sleep(wait);
+        }
+
+        Ok(self.take_step())
+    }
+
+    fn is_finished(&self) -> bool {
+        self.steps.is_empty()
+    }
+}
+
+fn parse_script(text: &str) -> Result<Vec<Step>> {
+    let mut steps = Vec::new();
+
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let bad_line = || Error::Reader(format!("script line {}: expected '<ms> present <uid>' or '<ms> remove', got '{}'", i + 1, line));
+
+        let mut parts = line.splitn(3, char::is_whitespace);
+        let delay = parts
+            .next()
+            .and_then(|ms| ms.parse::<u64>().ok())
+            .map(Duration::from_millis)
+            .ok_or_else(bad_line)?;
+
+        let action = match (parts.next(), parts.next()) {
+            (Some("present"), Some(raw)) if !raw.trim().is_empty() => Action::Present(raw.trim().to_string()),
+            (Some("remove"), None) => Action::Remove,
+            _ => return Err(bad_line()),
+        };
+
+        steps.push(Step { delay, action });
+    }
+
+    Ok(steps)
+}
+
+// Log lines look like "[1697551234] (2023-10-17 15:00:34) Raw UID: 04A1B2C3"
+fn parse_log(text: &str) -> Vec<Step> {
+    let mut steps = Vec::new();
+    let mut previous: Option<u64> = None;
+
+    for line in text.lines() {
+        let (end, start) = match (line.find(']'), line.find("Raw UID: ")) {
+            (Some(end), Some(start)) if line.starts_with('[') => (end, start),
+            _ => continue,
+        };
+        let timestamp = match line[1..end].parse::<u64>() {
+            Ok(timestamp) => timestamp,
+            Err(_) => continue,
+        };
+
+        let delay = previous.map_or(0, |prev| timestamp.saturating_sub(prev));
+        previous = Some(timestamp);
+
+        let raw = line[start + "Raw UID: ".len()..].trim().to_string();
+        steps.push(Step { delay: Duration::from_secs(delay), action: Action::Present(raw) });
+        steps.push(Step { delay: Duration::ZERO, action: Action::Remove });
+    }
+
+    steps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card_reader::CardRead;
+
+    fn replay(text: &str) -> SimulatedReader {
+        SimulatedReader::parse("test", text, KeyboardLayout::Windows).unwrap().with_speed(0.0).unwrap()
+    }
+
+    fn events(mut reader: SimulatedReader) -> Vec<CardEvent> {
+        std::iter::from_fn(|| reader.next_event().unwrap()).collect()
+    }
+
+    fn present(hex: &str) -> CardEvent {
+        CardEvent::CardPresent(CardRead { uid: hex.parse().unwrap(), raw: hex.to_string(), atqa: None, sak: None, version: None })
+    }
+
+    fn removed(hex: Option<&str>) -> CardEvent {
+        CardEvent::CardRemoved { uid: hex.map(|hex| hex.parse().unwrap()) }
+    }
+
+    #[test]
+    fn script_replays_taps_in_order() {
+        let reader = replay(
+            "# two taps and a bad read\n\
+             0 present 04A1B2C3\n\
+             \n\
+             250 remove\n\
+             1000 present 04A1B2C4\n\
+             10 remove\n\
+             5 present XYZ\n\
+             5 remove\n",
+        );
+        assert!(!reader.is_finished());
+
+        let events = events(reader);
+        assert_eq!(events.len(), 6);
+        assert_eq!(events[..4], [present("04A1B2C3"), removed(Some("04A1B2C3")), present("04A1B2C4"), removed(Some("04A1B2C4"))]);
+        // A read that isn't a UID is reported, and its removal carries no UID
+        assert!(matches!(&events[4], CardEvent::ReadError { raw, .. } if raw == "XYZ"));
+        assert_eq!(events[5], removed(None));
+    }
+
+    #[test]
+    fn malformed_script_lines_are_reported_with_their_number() {
+        for (script, line) in [
+            ("0 present 04A1B2C3\nsoon remove\n", "line 2"),
+            ("0 present\n", "line 1"),
+            ("# comment\n\n0 remove now\n", "line 3"),
+            ("-5 remove\n", "line 1"),
+            ("0 tap 04A1B2C3\n", "line 1"),
+        ] {
+            let error = SimulatedReader::parse("test", script, KeyboardLayout::Windows).err().expect(script).to_string();
+            assert!(error.contains(line), "{:?}: {}", script, error);
+        }
+    }
+
+    #[test]
+    fn reader_log_is_replayed_with_its_gaps() {
+        let log = "Reader started\n\
+                   [1697551234] (2023-10-17 15:00:34) Raw UID: 04A1B2C3\n\
+                   [1697551236] (2023-10-17 15:00:36) Raw UID: 04A1B2C4\n\
+                   [bad] Raw UID: 04A1B2C5\n";
+        let reader = SimulatedReader::parse("test", log, KeyboardLayout::Windows).unwrap();
+        let delays: Vec<Duration> = reader.steps.iter().map(|step| step.delay).collect();
+        assert_eq!(delays, [Duration::ZERO, Duration::ZERO, Duration::from_secs(2), Duration::ZERO]);
+
+        // Every read in a log is a tap: present, then removed
+        let events = events(reader.with_speed(0.0).unwrap());
+        assert_eq!(events, [present("04A1B2C3"), removed(Some("04A1B2C3")), present("04A1B2C4"), removed(Some("04A1B2C4"))]);
+    }
+
+    #[test]
+    fn speed_scales_the_delays() {
+        let mut reader = SimulatedReader::parse("test", "0 present 04A1B2C3\n60000 remove\n", KeyboardLayout::Windows)
+            .unwrap()
+            .with_speed(1.0)
+            .unwrap();
+        assert_eq!(reader.poll().unwrap(), Some(present("04A1B2C3")));
+        // The removal is a minute away at normal speed
+        assert_eq!(reader.poll().unwrap(), None);
+        assert!(!reader.is_finished());
+
+        // Speed 0 drops the wait, a tiny one stretches it without overflowing
+        reader = reader.with_speed(1e-300).unwrap();
+        assert_eq!(reader.scaled_delay(Duration::from_secs(60)), Duration::MAX);
+        assert_eq!(reader.poll().unwrap(), None);
+        reader = reader.with_speed(0.0).unwrap();
+        assert_eq!(reader.poll().unwrap(), Some(removed(Some("04A1B2C3"))));
+        assert!(reader.is_finished());
+        assert_eq!(reader.next_event().unwrap(), None);
+    }
+
+    #[test]
+    fn invalid_speeds_are_refused() {
+        for speed in [-1.0, f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            let reader = SimulatedReader::parse("test", "0 remove\n", KeyboardLayout::Windows).unwrap();
+            assert!(reader.with_speed(speed).is_err(), "{}", speed);
+        }
+    }
+}
@@ -1,10 +1,15 @@
 // app_config.rs
 use std::collections::HashMap;
 use std::fs;
-use std::io::{self, Write};
+use std::io::Write;
 use std::path::Path;
 use serde::{Serialize, Deserialize};
 
+use crate::error::{Error, Result};
+use crate::inventory::view::InventoryView;
+use crate::keyboard_layout::{self, KeyboardLayout};
+use crate::manufacturer;
+
 // Define the SyncDirs structure
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct SyncDirs {
@@ -15,7 +20,9 @@ pub struct SyncDirs {
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct AppConfig {
-    pub default_keyboard_layout: i32,
+    // Stored by name; older configs hold a layout number, which is still read
+    #[serde(default)]
+    pub default_keyboard_layout: KeyboardLayout,
     pub manufacturer_database: HashMap<String, String>,
     pub save_logs: bool,
     pub log_directory: String,
@@ -32,36 +39,32 @@ pub struct AppConfig {
     pub gdrive_sync_enabled: bool,
     #[serde(default)]
     pub gdrive_sync_folder: String,
+    // Extra keyboard layout definitions (*.json), loaded after the shipped ones
+    #[serde(default = "default_keyboard_layout_directory")]
+    pub keyboard_layout_directory: String,
+    // Layouts learned with the calibration wizard
+    #[serde(default)]
+    pub custom_keyboard_layouts: Vec<keyboard_layout::LayoutDefinition>,
+    // Named inventory views (search, filters and sort order) for the inventory tab
+    #[serde(default)]
+    pub saved_views: Vec<InventoryView>,
+}
+
+fn default_keyboard_layout_directory() -> String {
+    keyboard_layout::DEFAULT_USER_DIRECTORY.to_string()
 }
 
 impl Default for AppConfig {
     fn default() -> Self {
-        let mut manufacturer_db = HashMap::new();
-        manufacturer_db.insert("04".to_string(), "NXP Semiconductors".to_string());
-        manufacturer_db.insert("05".to_string(), "Infineon Technologies".to_string());
-        manufacturer_db.insert("16".to_string(), "Texas Instruments".to_string());
-        manufacturer_db.insert("21".to_string(), "EM Microelectronic-Marin SA".to_string());
-        manufacturer_db.insert("28".to_string(), "LEGIC Identsystems AG".to_string());
-        manufacturer_db.insert("29".to_string(), "Gemplus".to_string());
-        manufacturer_db.insert("33".to_string(), "Atmel".to_string());
-        manufacturer_db.insert("47".to_string(), "Orga Kartensysteme GmbH".to_string());
-        manufacturer_db.insert("49".to_string(), "Inside Technology".to_string());
-        manufacturer_db.insert("55".to_string(), "Tönnjes C.A.R.D. International".to_string());
-        manufacturer_db.insert("57".to_string(), "Giesecke & Devrient".to_string());
-        manufacturer_db.insert("75".to_string(), "HID Global".to_string());
-        manufacturer_db.insert("87".to_string(), "Identive".to_string());
-        manufacturer_db.insert("95".to_string(), "NXP MIFARE Classic".to_string());
-        manufacturer_db.insert("96".to_string(), "NXP MIFARE Plus".to_string());
-        manufacturer_db.insert("98".to_string(), "NXP MIFARE DESFire".to_string());
-        
         let mut custom_patterns = HashMap::new();
         custom_patterns.insert("*h-!)d-e".to_string(), "Card type 1 with QWERTY encoding".to_string());
         custom_patterns.insert("@h-#d-$h-%d-e".to_string(), "Card type 2 with QWERTY encoding".to_string());
         custom_patterns.insert("*h-e".to_string(), "Card type 3 with QWERTY encoding".to_string());
         
         AppConfig {
-            default_keyboard_layout: 0, // Auto-detect
-            manufacturer_database: manufacturer_db,
+            default_keyboard_layout: KeyboardLayout::AutoDetect,
+            // Only user overrides; the full registry is built in
+            manufacturer_database: HashMap::new(),
             save_logs: false,
             log_directory: "./logs".to_string(),
             recent_files: Vec::new(),
@@ -71,6 +74,39 @@ impl Default for AppConfig {
             error_directory: "./error".to_string(),
             gdrive_sync_enabled: false,
             gdrive_sync_folder: "./gdrive_sync".to_string(),
+            keyboard_layout_directory: default_keyboard_layout_directory(),
+            custom_keyboard_layouts: Vec::new(),
+            saved_views: Vec::new(),
+        }
+    }
+}
+
+// Manufacturer entries older versions wrote into every config file. They are
+// dropped on load so the registry is used instead; 95, 96 and 98 were never
+// manufacturer codes in the first place.
+const LEGACY_MANUFACTURER_DEFAULTS: [(&str, &str); 16] = [
+    ("04", "NXP Semiconductors"),
+    ("05", "Infineon Technologies"),
+    ("16", "Texas Instruments"),
+    ("21", "EM Microelectronic-Marin SA"),
+    ("28", "LEGIC Identsystems AG"),
+    ("29", "Gemplus"),
+    ("33", "Atmel"),
+    ("47", "Orga Kartensysteme GmbH"),
+    ("49", "Inside Technology"),
+    ("55", "Tönnjes C.A.R.D. International"),
+    ("57", "Giesecke & Devrient"),
+    ("75", "HID Global"),
+    ("87", "Identive"),
+    ("95", "NXP MIFARE Classic"),
+    ("96", "NXP MIFARE Plus"),
+    ("98", "NXP MIFARE DESFire"),
+];
+
+fn drop_legacy_manufacturers(config: &mut AppConfig) {
+    for (code, name) in LEGACY_MANUFACTURER_DEFAULTS {
+        if config.manufacturer_database.get(code).map(String::as_str) == Some(name) {
+            config.manufacturer_database.remove(code);
         }
     }
 }
@@ -84,6 +120,18 @@ pub fn new_config() -> AppConfig {
 const CONFIG_PATH: &str = "mifare_reader_config.json";
 
 pub fn load_config() -> AppConfig {
+    let config = read_config();
+    manufacturer::set_overrides(&config.manufacturer_database);
+    if let Err(err) = keyboard_layout::load_user_layouts(&config.keyboard_layout_directory) {
+        eprintln!("Error loading keyboard layouts: {}", err);
+    }
+    for layout in &config.custom_keyboard_layouts {
+        keyboard_layout::register_layout(layout.clone());
+    }
+    config
+}
+
+fn read_config() -> AppConfig {
     if !Path::new(CONFIG_PATH).exists() {
         let config = AppConfig::default();
         save_config(&config).unwrap_or_else(|err| {
@@ -95,7 +143,10 @@ pub fn load_config() -> AppConfig {
     match fs::read_to_string(CONFIG_PATH) {
         Ok(data) => {
             match serde_json::from_str(&data) {
-                Ok(config) => config,
+                Ok(mut config) => {
+                    drop_legacy_manufacturers(&mut config);
+                    config
+                },
                 Err(err) => {
                     eprintln!("Error parsing config file, using defaults: {}", err);
                     AppConfig::default()
@@ -109,10 +160,12 @@ pub fn load_config() -> AppConfig {
     }
 }
 
-pub fn save_config(config: &AppConfig) -> io::Result<()> {
-    let data = serde_json::to_string_pretty(config)?;
-    let mut file = fs::File::create(CONFIG_PATH)?;
-    file.write_all(data.as_bytes())?;
+pub fn save_config(config: &AppConfig) -> Result<()> {
+    let data = serde_json::to_string_pretty(config)
+        .map_err(|e| Error::parse("Failed to serialize config", e))?;
+    fs::File::create(CONFIG_PATH)
+        .and_then(|mut file| file.write_all(data.as_bytes()))
+        .map_err(|e| Error::io(format!("Failed to write {}", CONFIG_PATH), e))?;
     Ok(())
 }
 
@@ -121,32 +174,66 @@ pub fn get_manufacturer(code: &str, config: &AppConfig) -> String {
         return "Unknown (UID too short)".to_string();
     }
     
-    let manuf_code = &code[0..2].to_lowercase();
-    match config.manufacturer_database.get(manuf_code) {
-        Some(name) => name.clone(),
-        None => "Unknown manufacturer".to_string(),
+    let manuf_code = match manufacturer::parse_code(&code[0..2]) {
+        Some(manuf_code) => manuf_code,
+        None => return "Unknown manufacturer".to_string(),
+    };
+    
+    // The config passed in may not be the one whose overrides are installed
+    match config.manufacturer_database.iter().find(|(key, _)| manufacturer::parse_code(key) == Some(manuf_code)) {
+        Some((_, name)) => name.clone(),
+        None => manufacturer::lookup_code(manuf_code).to_string(),
     }
 }
 
-pub fn add_manufacturer(code: &str, name: &str, config: &mut AppConfig) -> io::Result<()> {
+pub fn add_manufacturer(code: &str, name: &str, config: &mut AppConfig) -> Result<()> {
     config.manufacturer_database.insert(code.to_lowercase(), name.to_string());
+    manufacturer::set_overrides(&config.manufacturer_database);
+    save_config(config)
+}
+
+/// Store a calibrated layout, replacing one with the same name
+pub fn add_keyboard_layout(layout: keyboard_layout::LayoutDefinition, config: &mut AppConfig) -> Result<KeyboardLayout> {
+    let name = layout.name.clone();
+    match config.custom_keyboard_layouts.iter_mut().find(|existing| existing.name == name) {
+        Some(existing) => *existing = layout.clone(),
+        None => config.custom_keyboard_layouts.push(layout.clone()),
+    }
+    keyboard_layout::register_layout(layout);
+    save_config(config)?;
+
+    Ok(KeyboardLayout::from_name(&name))
+}
+
+/// Store an inventory view under its name, replacing one with the same name
+pub fn save_view(view: InventoryView, config: &mut AppConfig) -> Result<()> {
+    match config.saved_views.iter_mut().find(|existing| existing.name.eq_ignore_ascii_case(&view.name)) {
+        Some(existing) => *existing = view,
+        None => config.saved_views.push(view),
+    }
+    save_config(config)
+}
+
+pub fn delete_view(name: &str, config: &mut AppConfig) -> Result<()> {
+    config.saved_views.retain(|view| !view.name.eq_ignore_ascii_case(name));
     save_config(config)
 }
 
-pub fn add_custom_pattern(pattern: &str, description: &str, config: &mut AppConfig) -> io::Result<()> {
+pub fn add_custom_pattern(pattern: &str, description: &str, config: &mut AppConfig) -> Result<()> {
     config.custom_format_patterns.insert(pattern.to_string(), description.to_string());
     save_config(config)
 }
 
 // Save log data to a file
-pub fn save_log(log_data: &str, config: &AppConfig) -> io::Result<String> {
+pub fn save_log(log_data: &str, config: &AppConfig) -> Result<String> {
     if !config.save_logs {
         return Ok("Logging disabled".to_string());
     }
     
     // Create log directory if it doesn't exist
     if !Path::new(&config.log_directory).exists() {
-        fs::create_dir_all(&config.log_directory)?;
+        fs::create_dir_all(&config.log_directory)
+            .map_err(|e| Error::io(format!("Failed to create log directory {}", config.log_directory), e))?;
     }
     
     // Generate filename with timestamp
@@ -156,8 +243,9 @@ pub fn save_log(log_data: &str, config: &AppConfig) -> io::Result<String> {
         now.format("%Y%m%d_%H%M%S"));
     
     // Write log data to file
-    let mut file = fs::File::create(&filename)?;
-    file.write_all(log_data.as_bytes())?;
+    fs::File::create(&filename)
+        .and_then(|mut file| file.write_all(log_data.as_bytes()))
+        .map_err(|e| Error::io(format!("Failed to write {}", filename), e))?;
     
     Ok(format!("Log saved to {}", filename))
 }
\ No newline at end of file
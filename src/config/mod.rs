@@ -1,8 +1,6 @@
 // config/mod.rs (correct version)
 pub mod app_config;
 
-use std::rc::Rc;
-use std::cell::RefCell;
 use std::sync::Mutex;
 use once_cell::sync::Lazy;
 
@@ -20,6 +18,9 @@ pub use app_config::{
     save_log,
     get_manufacturer,
     add_manufacturer,
+    add_keyboard_layout,
+    save_view,
+    delete_view,
     add_custom_pattern
 };
 
@@ -0,0 +1,182 @@
+// convert.rs
+use crate::uid::Uid;
+use crate::wiegand::{self, WiegandFormat};
+
+/// Named output formats a UID can be rendered in for access-control systems
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UidFormat {
+    /// Upper-case hex, bytes separated by spaces
+    Hex,
+    /// Big-endian decimal of the whole UID, any length
+    Decimal,
+    /// Hex with the byte order reversed (LSB first)
+    ReversedHex,
+    /// Decimal of the byte-reversed UID
+    ReversedDecimal,
+    /// Low 32 bits as a zero-padded 10-digit number, as printed on EM/prox cards
+    EmDecimal,
+    /// Wiegand 26-bit (H10301): 8-bit facility code and 16-bit card number
+    Wiegand26,
+    /// Wiegand 34-bit: 16-bit facility code and 16-bit card number
+    Wiegand34,
+    /// Wiegand 37-bit (H10304): 16-bit facility code and 19-bit card number
+    Wiegand37,
+}
+
+impl UidFormat {
+    pub const ALL: [UidFormat; 8] = [
+        UidFormat::Hex,
+        UidFormat::Decimal,
+        UidFormat::ReversedHex,
+        UidFormat::ReversedDecimal,
+        UidFormat::EmDecimal,
+        UidFormat::Wiegand26,
+        UidFormat::Wiegand34,
+        UidFormat::Wiegand37,
+    ];
+
+    /// Short machine-readable identifier, used as the key in JSON output
+    pub fn key(&self) -> &'static str {
+        match self {
+            UidFormat::Hex => "hex",
+            UidFormat::Decimal => "decimal",
+            UidFormat::ReversedHex => "reversed_hex",
+            UidFormat::ReversedDecimal => "reversed_decimal",
+            UidFormat::EmDecimal => "em_decimal",
+            UidFormat::Wiegand26 => "wiegand26",
+            UidFormat::Wiegand34 => "wiegand34",
+            UidFormat::Wiegand37 => "wiegand37",
+        }
+    }
+
+    /// Human-readable label shown in the converter and batch tabs
+    pub fn name(&self) -> &'static str {
+        match self {
+            UidFormat::Hex => "Hex",
+            UidFormat::Decimal => "Decimal",
+            UidFormat::ReversedHex => "Reversed hex",
+            UidFormat::ReversedDecimal => "Reversed decimal",
+            UidFormat::EmDecimal => "EM 10-digit",
+            UidFormat::Wiegand26 => "Wiegand 26 (FC:CN)",
+            UidFormat::Wiegand34 => "Wiegand 34 (FC:CN)",
+            UidFormat::Wiegand37 => "Wiegand 37 (FC:CN)",
+        }
+    }
+
+    /// Render the UID in this format
+    pub fn render(&self, uid: &Uid) -> String {
+        match self {
+            UidFormat::Hex => uid.to_spaced_hex(),
+            UidFormat::Decimal => uid.to_decimal(),
+            UidFormat::ReversedHex => uid.reversed().to_spaced_hex(),
+            UidFormat::ReversedDecimal => uid.to_reversed_decimal(),
+            UidFormat::EmDecimal => em_decimal(uid),
+            UidFormat::Wiegand26 => wiegand_fields(uid, WiegandFormat::W26),
+            UidFormat::Wiegand34 => wiegand_fields(uid, WiegandFormat::W34),
+            UidFormat::Wiegand37 => wiegand_fields(uid, WiegandFormat::W37),
+        }
+    }
+}
+
+// Facility code and card number, zero-padded to the widest value each field can hold
+fn wiegand_fields(uid: &Uid, format: WiegandFormat) -> String {
+    let credential = wiegand::encode_uid(uid, format);
+    let fc_width = ((1u64 << format.facility_bits()) - 1).to_string().len();
+    let cn_width = ((1u64 << format.card_bits()) - 1).to_string().len();
+
+    format!(
+        "{:0fc$}:{:0cn$}",
+        credential.facility_code,
+        credential.card_number,
+        fc = fc_width,
+        cn = cn_width
+    )
+}
+
+/// Every named format for a UID, in display order
+pub fn all_formats(uid: &Uid) -> Vec<(UidFormat, String)> {
+    UidFormat::ALL
+        .iter()
+        .map(|format| (*format, format.render(uid)))
+        .collect()
+}
+
+/// Big-endian decimal value of an arbitrary number of bytes
+pub fn bytes_to_decimal(bytes: &[u8]) -> String {
+    // Decimal digits, least significant first; multiply by 256 and add each byte
+    let mut digits: Vec<u32> = vec![0];
+
+    for &byte in bytes {
+        let mut carry = u32::from(byte);
+        for digit in digits.iter_mut() {
+            let value = *digit * 256 + carry;
+            *digit = value % 10;
+            carry = value / 10;
+        }
+        while carry > 0 {
+            digits.push(carry % 10);
+            carry /= 10;
+        }
+    }
+
+    digits
+        .iter()
+        .rev()
+        .map(|d| char::from_digit(*d, 10).unwrap_or('0'))
+        .collect()
+}
+
+/// The last `count` bytes of the UID as an integer (count is at most 8)
+fn low_bytes(uid: &Uid, count: usize) -> u64 {
+    let bytes = uid.as_bytes();
+    let start = bytes.len().saturating_sub(count);
+
+    bytes[start..]
+        .iter()
+        .fold(0u64, |acc, b| (acc << 8) | u64::from(*b))
+}
+
+/// Low 32 bits of the UID as a zero-padded 10-digit decimal
+pub fn em_decimal(uid: &Uid) -> String {
+    format!("{:010}", low_bytes(uid, 4))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uid(hex: &str) -> Uid {
+        Uid::from_hex(hex).unwrap()
+    }
+
+    #[test]
+    fn ten_byte_uid_converts_past_u64() {
+        let uid = uid("04A1B2C3D4E5F6071829");
+        assert_eq!(uid.to_decimal(), "21872273100789168674857");
+        assert_eq!(uid.to_reversed_decimal(), "194060321554128998408452");
+
+        // Back to the same bytes through a wider integer
+        let value: u128 = uid.to_decimal().parse().unwrap();
+        assert!(value > u128::from(u64::MAX));
+        assert_eq!(&value.to_be_bytes()[6..], uid.as_bytes());
+
+        assert_eq!(bytes_to_decimal(&[0xFF; 10]), "1208925819614629174706175");
+        assert_eq!(bytes_to_decimal(&[0x00, 0x00]), "0");
+    }
+
+    #[test]
+    fn vendor_formats() {
+        let short = uid("04A1B2C3");
+        assert_eq!(UidFormat::Decimal.render(&short), "77705923");
+        assert_eq!(UidFormat::ReversedHex.render(&short), "C3 B2 A1 04");
+        assert_eq!(UidFormat::ReversedDecimal.render(&short), "3283263748");
+        assert_eq!(UidFormat::EmDecimal.render(&short), "0077705923");
+        assert_eq!(UidFormat::Wiegand26.render(&short), "161:45763");
+
+        // EM and Wiegand take the low bytes of longer UIDs
+        let long = uid("04A1B2C3D4E5F6");
+        assert_eq!(UidFormat::Decimal.render(&long), "1303689068602870");
+        assert_eq!(UidFormat::EmDecimal.render(&long), "3285509622");
+        assert_eq!(UidFormat::Wiegand34.render(&long), "50132:58870");
+    }
+}
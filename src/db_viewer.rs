@@ -8,13 +8,58 @@ use fltk::{
     dialog,
     frame::Frame,
     group::{Group, Flex, Pack, Scroll},
+    input::Input,
+    menu::MenuButton,
     draw,
 };
 use std::cell::RefCell;
 use std::rc::Rc;
 
+use crate::error::Result;
+use crate::inventory::db::InventoryDB;
+use crate::inventory::model::InventoryItem;
+use crate::inventory::search::{SearchField, SearchQuery};
+use crate::ui::inventory::components::{run_bulk_action, show_movement_history, BulkAction};
+use crate::ui::inventory::selection::Selection;
+use crate::ui::inventory::utils::draw_highlighted_text;
+use crate::utils::csv_field;
 
-pub fn show_database_viewer(inventory_ui: &Rc<crate::inventory::InventoryUI>) {
+// Every item, or what the search finds, best matches first
+fn load_items(inventory_db: &InventoryDB, search_query: &Option<SearchQuery>) -> Result<Vec<InventoryItem>> {
+    match search_query {
+        Some(query) => inventory_db.search_items(query),
+        None => inventory_db.get_all_items(),
+    }
+}
+
+fn count_text(count: usize, search_query: &Option<SearchQuery>) -> String {
+    match search_query {
+        Some(_) => format!("{} items found", count),
+        None => format!("{} items in database", count),
+    }
+}
+
+// Load the items again after a change, keeping the search
+fn reload_items(
+    inventory_db: &InventoryDB,
+    search_query: &Option<SearchQuery>,
+    items_data: &Rc<RefCell<Vec<InventoryItem>>>,
+    table: &mut Table,
+    count_label: &mut Frame
+) {
+    match load_items(inventory_db, search_query) {
+        Ok(updated_items) => {
+            *items_data.borrow_mut() = updated_items;
+            table.set_rows(items_data.borrow().len() as i32);
+            count_label.set_label(&count_text(items_data.borrow().len(), search_query));
+            table.redraw();
+        }
+        Err(e) => dialog::alert(300, 300, &format!("Error loading inventory: {}", e)),
+    }
+}
+
+
+pub fn show_database_viewer(inventory_ui: &Rc<crate::ui::inventory::InventoryUI>) {
     // Create the main window
     let app = app::App::default();
     let mut win = Window::new(100, 100, 960, 620, "Database Viewer");
@@ -31,6 +76,21 @@ pub fn show_database_viewer(inventory_ui: &Rc<crate::inventory::InventoryUI>) {
     header.set_align(fltk::enums::Align::Center);
     flex.fixed(&header, 30);
     
+    // Search row, same query syntax as the inventory tab
+    let mut search_flex = Flex::new(0, 0, 940, 30, None);
+    search_flex.set_type(fltk::group::FlexType::Row);
+    flex.fixed(&search_flex, 30);
+    let mut search_label = Frame::new(0, 0, 60, 30, "Search:");
+    search_label.set_align(fltk::enums::Align::Right | fltk::enums::Align::Inside);
+    search_flex.fixed(&search_label, 60);
+    let mut search_input = Input::new(0, 0, 0, 30, None);
+    search_input.set_tooltip("Words, field:word (tag, name, desc, cat, loc), \"exact phrase\", qty<5");
+    let mut search_btn = Button::new(0, 0, 0, 30, "Search");
+    search_flex.fixed(&search_btn, 100);
+    let mut show_all_btn = Button::new(0, 0, 0, 30, "Show All");
+    search_flex.fixed(&show_all_btn, 100);
+    search_flex.end();
+    
     // Create a scrollable container for the table
     let mut scroll = Scroll::new(0, 0, 940, 0, None);
     scroll.set_type(fltk::group::ScrollType::Both);
@@ -41,7 +101,7 @@ pub fn show_database_viewer(inventory_ui: &Rc<crate::inventory::InventoryUI>) {
     table.set_rows(0);
     table.set_row_header(true);
     table.set_row_resize(true);
-    table.set_cols(7);
+    table.set_cols(8);
     table.set_col_header(true);
     table.set_col_width(0, 130); // Tag ID
     table.set_col_width(1, 190); // Name
@@ -50,6 +110,7 @@ pub fn show_database_viewer(inventory_ui: &Rc<crate::inventory::InventoryUI>) {
     table.set_col_width(4, 130); // Location
     table.set_col_width(5, 140); // Created
     table.set_col_width(6, 140); // Updated
+    table.set_col_width(7, 150); // Card type
     
     scroll.end();
     
@@ -64,10 +125,13 @@ pub fn show_database_viewer(inventory_ui: &Rc<crate::inventory::InventoryUI>) {
 
     let items_data = Rc::new(RefCell::new(items));
     let items_clone = items_data.clone();
+    let search_query: Rc<RefCell<Option<SearchQuery>>> = Rc::new(RefCell::new(None));
+    let search_query_clone = search_query.clone();
 
-    // Setup selected row tracking
+    // Rows selected for the bulk actions, and the row clicked last for History
+    let selection = Rc::new(RefCell::new(Selection::default()));
+    let selection_clone = selection.clone();
     let selected_row = Rc::new(RefCell::new(-1));
-    let selected_row_clone = selected_row.clone();
 
     // Set up table drawing
     table.draw_cell(move |_t, ctx, row, col, x, y, w, h| {
@@ -86,6 +150,7 @@ pub fn show_database_viewer(inventory_ui: &Rc<crate::inventory::InventoryUI>) {
                     4 => "Location",
                     5 => "Created",
                     6 => "Updated",
+                    7 => "Card Type",
                     _ => "",
                 };
                 draw::draw_text2(header, x, y, w, h, fltk::enums::Align::Center);
@@ -94,7 +159,7 @@ pub fn show_database_viewer(inventory_ui: &Rc<crate::inventory::InventoryUI>) {
                 let items = items_clone.borrow();
                 
                 // Determine background color (alternate rows, highlight selected)
-                let is_selected = *selected_row_clone.borrow() == row;
+                let is_selected = items.get(row as usize).is_some_and(|item| selection_clone.borrow().contains(item));
                 let bg_color = if is_selected {
                     fltk::enums::Color::from_rgb(173, 216, 230) // Light blue for selected row
                 } else if row % 2 == 0 {
@@ -109,29 +174,50 @@ pub fn show_database_viewer(inventory_ui: &Rc<crate::inventory::InventoryUI>) {
                 
                 if row < items.len() as i32 {
                     let item = &items[row as usize];
-                    let text = match col {
-                        0 => &item.tag_id,
-                        1 => &item.name,
+                    let tag_id = item.tag_id.to_hex();
+                    let (text, field) = match col {
+                        0 => (tag_id.as_str(), Some(SearchField::Tag)),
+                        1 => (item.name.as_str(), Some(SearchField::Name)),
                         2 => return draw::draw_text2(&item.quantity.to_string(), x, y, w, h, fltk::enums::Align::Center),
-                        3 => return draw::draw_text2(item.category.as_deref().unwrap_or(""), x, y, w, h, fltk::enums::Align::Center),
-                        4 => return draw::draw_text2(item.location.as_deref().unwrap_or(""), x, y, w, h, fltk::enums::Align::Center),
-                        5 => &item.created_at,
-                        6 => &item.last_updated,
-                        _ => "",
+                        3 => (item.category.as_deref().unwrap_or(""), Some(SearchField::Category)),
+                        4 => (item.location.as_deref().unwrap_or(""), Some(SearchField::Location)),
+                        5 => (item.created_at.as_str(), None),
+                        6 => (item.last_updated.as_str(), None),
+                        7 => (item.card_family.map(|f| f.name()).unwrap_or(""), None),
+                        _ => return,
+                    };
+                    
+                    // Mark what the search matched
+                    let highlights = match (search_query_clone.borrow().as_ref(), field) {
+                        (Some(query), Some(field)) => query.highlights(field, text),
+                        _ => Vec::new(),
                     };
                     draw::set_font(fltk::enums::Font::Helvetica, 14);
-                    draw::draw_text2(text, x + 5, y, w - 10, h, fltk::enums::Align::Left);
+                    if col == 3 || col == 4 {
+                        return draw_highlighted_text(text, &highlights, x, y, w, h, fltk::enums::Align::Center);
+                    }
+                    draw_highlighted_text(text, &highlights, x + 5, y, w - 10, h, fltk::enums::Align::Left);
                 }
             },
             _ => {}
         }
     });
     
-    // Handle table selection
+    // Handle table selection: click, Ctrl-click and Shift-click as in the inventory tab
     let selected_row_cb = selected_row.clone();
+    let selection_cb = selection.clone();
+    let items_cb = items_data.clone();
     table.set_callback(move |t| {
-        if app::event() == fltk::enums::Event::Released {
-            *selected_row_cb.borrow_mut() = t.callback_row();
+        if app::event() == fltk::enums::Event::Released && t.callback_context() == fltk::table::TableContext::Cell {
+            let row = t.callback_row();
+            if row < 0 || row >= t.rows() {
+                return;
+            }
+            let state = app::event_state();
+            let ctrl = state.contains(fltk::enums::Shortcut::Ctrl) || state.contains(fltk::enums::Shortcut::Command);
+            let shift = state.contains(fltk::enums::Shortcut::Shift);
+            selection_cb.borrow_mut().click(&items_cb.borrow(), row as usize, ctrl, shift);
+            *selected_row_cb.borrow_mut() = row;
             t.redraw();
         }
     });
@@ -150,26 +236,36 @@ pub fn show_database_viewer(inventory_ui: &Rc<crate::inventory::InventoryUI>) {
     // Add a spacer to push buttons to the right
     let mut spacer = Frame::new(0, 0, 30, 30, "");
     
+    // Actions on the selected rows
+    let mut selection_menu = MenuButton::new(0, 0, 0, 30, "Selected Items");
+    selection_menu.set_tooltip("Click rows with Ctrl or Shift held to select several");
+    button_flex.fixed(&selection_menu, 150);
+    
     // Create bright, visible buttons with contrasting colors
     let mut delete_btn = Button::new(0, 0, 0, 30, "Delete");
     delete_btn.set_color(fltk::enums::Color::from_rgb(255, 100, 100)); // Red for delete
     delete_btn.set_label_color(fltk::enums::Color::White);
-    button_flex.fixed(&delete_btn, 130);
+    button_flex.fixed(&delete_btn, 110);
+    
+    let mut history_btn = Button::new(0, 0, 0, 30, "History");
+    history_btn.set_color(fltk::enums::Color::from_rgb(255, 200, 100)); // Orange for history
+    history_btn.set_label_color(fltk::enums::Color::Black);
+    button_flex.fixed(&history_btn, 110);
     
     let mut export_btn = Button::new(0, 0, 0, 30, "Export CSV");
     export_btn.set_color(fltk::enums::Color::from_rgb(100, 200, 100)); // Green for export
     export_btn.set_label_color(fltk::enums::Color::Black);
-    button_flex.fixed(&export_btn, 130);
+    button_flex.fixed(&export_btn, 110);
     
     let mut refresh_btn = Button::new(0, 0, 0, 30, "Refresh");
     refresh_btn.set_color(fltk::enums::Color::from_rgb(100, 100, 255)); // Blue for refresh
     refresh_btn.set_label_color(fltk::enums::Color::White);
-    button_flex.fixed(&refresh_btn, 130);
+    button_flex.fixed(&refresh_btn, 110);
     
     let mut close_btn = Button::new(0, 0, 0, 30, "Close");
     close_btn.set_color(fltk::enums::Color::from_rgb(200, 200, 200)); // Gray for close
     close_btn.set_label_color(fltk::enums::Color::Black);
-    button_flex.fixed(&close_btn, 130);
+    button_flex.fixed(&close_btn, 110);
     
     button_flex.end();
     flex.end();
@@ -182,45 +278,76 @@ pub fn show_database_viewer(inventory_ui: &Rc<crate::inventory::InventoryUI>) {
     table.set_rows(items_data.borrow().len() as i32);
     
     // After window.end(), set up callbacks:
+    // Bulk actions on the selection; Delete removes every selected row at once
+    let run_action: Rc<dyn Fn(BulkAction)> = {
+        let selection = selection.clone();
+        let items_data = items_data.clone();
+        let inventory_ui_clone = inventory_ui.clone();
+        let table_clone = table.clone();
+        let count_label_clone = count_label.clone();
+        let search_query = search_query.clone();
+        
+        Rc::new(move |action| {
+            let selected = selection.borrow().items(&items_data.borrow());
+            let items_data = items_data.clone();
+            let inventory_db = inventory_ui_clone.inventory_db.clone();
+            let mut table_clone = table_clone.clone();
+            let mut count_label_clone = count_label_clone.clone();
+            let search_query = search_query.clone();
+            
+            run_bulk_action(action, inventory_db.clone(), selected, move |_, changed| {
+                if changed {
+                    reload_items(&inventory_db.borrow(), &search_query.borrow(), &items_data, &mut table_clone, &mut count_label_clone);
+                }
+            });
+        })
+    };
+
+    {
+        for action in BulkAction::ALL {
+            selection_menu.add_choice(action.label());
+        }
+        selection_menu.add_choice("Select All");
+        selection_menu.add_choice("Clear Selection");
+        
+        let run_action = run_action.clone();
+        let selection = selection.clone();
+        let items_data = items_data.clone();
+        let mut table_clone = table.clone();
+        
+        selection_menu.set_callback(move |menu| {
+            let index = menu.value();
+            if index < 0 {
+                return;
+            }
+            match BulkAction::ALL.get(index as usize) {
+                Some(action) => run_action(*action),
+                None if index as usize == BulkAction::ALL.len() => {
+                    selection.borrow_mut().select_all(&items_data.borrow());
+                    table_clone.redraw();
+                }
+                None => {
+                    selection.borrow_mut().clear();
+                    table_clone.redraw();
+                }
+            }
+        });
+    }
+
+    delete_btn.set_callback(move |_| run_action(BulkAction::Delete));
+
     {
         let selected_row = selected_row.clone();
         let items_data = items_data.clone();
         let inventory_ui_clone = inventory_ui.clone();
-        let mut table_clone = table.clone();
-        let mut count_label_clone = count_label.clone();
         
-        delete_btn.set_callback(move |_| {
+        history_btn.set_callback(move |_| {
             let selected_row_val = *selected_row.borrow();
             if selected_row_val >= 0 && (selected_row_val as usize) < items_data.borrow().len() {
-                let items = items_data.borrow();
-                let tag_id = items[selected_row_val as usize].tag_id.clone();
-                
-                // Ask for confirmation
-                if dialog::choice2(300, 300, &format!("Are you sure you want to delete the item with Tag ID '{}'?", tag_id), 
-                                "No", "Yes", "") == Some(1) {
-                    
-                    // Delete the item
-                    if let Err(e) = inventory_ui_clone.inventory_db.borrow().delete_item(&tag_id) {
-                        dialog::alert(300, 300, &format!("Error deleting item: {}", e));
-                    } else {
-                        dialog::message(300, 300, "Item deleted successfully");
-                        
-                        // Refresh the table after deletion
-                        if let Ok(updated_items) = inventory_ui_clone.inventory_db.borrow().get_all_items() {
-                            drop(items); // Explicitly drop the borrowed reference before mutating
-                            *items_data.borrow_mut() = updated_items;
-                            table_clone.set_rows(items_data.borrow().len() as i32);
-                            
-                            // Update the count label
-                            let new_count = format!("{} items in database", items_data.borrow().len());
-                            count_label_clone.set_label(new_count.as_str());
-                            
-                            table_clone.redraw();
-                        }
-                    }
-                }
+                let tag_id = items_data.borrow()[selected_row_val as usize].tag_id.clone();
+                show_movement_history(&inventory_ui_clone.inventory_db.borrow(), &tag_id);
             } else {
-                dialog::alert(300, 300, "Please select an item to delete");
+                dialog::alert(300, 300, "Please select an item to show its history");
             }
         });
     }
@@ -230,21 +357,19 @@ pub fn show_database_viewer(inventory_ui: &Rc<crate::inventory::InventoryUI>) {
         export_btn.set_callback(move |_| {
             if let Some(path) = dialog::file_chooser("Export as CSV", "*.csv", ".", false) {
                 let items = items_data.borrow();
-                let mut csv = String::from("Tag ID,Name,Quantity,Category,Location,Created At,Last Updated\n");
+                let mut csv = String::from("Tag ID,Name,Quantity,Category,Location,Created At,Last Updated,Card Type\n");
                 
                 for item in items.iter() {
-                    let category = item.category.clone().unwrap_or_default().replace(",", "\\,");
-                    let location = item.location.clone().unwrap_or_default().replace(",", "\\,");
-                    
                     csv.push_str(&format!(
-                        "{},{},{},\"{}\",\"{}\",{},{}\n",
-                        item.tag_id,
-                        item.name.replace(",", "\\,"),
-                        item.quantity,
-                        category,
-                        location,
-                        item.created_at,
-                        item.last_updated
+                        "{},{},{},{},{},{},{},{}\n",
+                        csv_field(&item.tag_id),
+                        csv_field(&item.name),
+                        csv_field(item.quantity),
+                        csv_field(item.category.as_deref().unwrap_or("")),
+                        csv_field(item.location.as_deref().unwrap_or("")),
+                        csv_field(&item.created_at),
+                        csv_field(&item.last_updated),
+                        csv_field(item.card_family.map(|f| f.name()).unwrap_or_default())
                     ));
                 }
                 
@@ -262,21 +387,60 @@ pub fn show_database_viewer(inventory_ui: &Rc<crate::inventory::InventoryUI>) {
         let inventory_ui_clone = inventory_ui.clone();
         let mut table_clone = table.clone();
         let mut count_label_clone = count_label.clone();
+        let search_query = search_query.clone();
         
         refresh_btn.set_callback(move |_| {
-            if let Ok(updated_items) = inventory_ui_clone.inventory_db.borrow().get_all_items() {
-                *items_data.borrow_mut() = updated_items;
-                table_clone.set_rows(items_data.borrow().len() as i32);
-                
-                // Update the count label
-                let new_count = format!("{} items in database", items_data.borrow().len());
-                count_label_clone.set_label(new_count.as_str());
-                
-                table_clone.redraw();
+            reload_items(&inventory_ui_clone.inventory_db.borrow(), &search_query.borrow(), &items_data, &mut table_clone, &mut count_label_clone);
+        });
+    }
+
+    {
+        let items_data = items_data.clone();
+        let inventory_ui_clone = inventory_ui.clone();
+        let mut table_clone = table.clone();
+        let mut count_label_clone = count_label.clone();
+        let search_query = search_query.clone();
+        let selected_row = selected_row.clone();
+        let selection = selection.clone();
+        let search_input = search_input.clone();
+        
+        search_btn.set_callback(move |_| {
+            let query = match SearchQuery::parse(&search_input.value()) {
+                Ok(query) if query.is_empty() => None,
+                Ok(query) => Some(query),
+                Err(e) => {
+                    dialog::alert(300, 300, &format!("Error searching: {}", e));
+                    return;
+                }
+            };
+            
+            match load_items(&inventory_ui_clone.inventory_db.borrow(), &query) {
+                Ok(found) => {
+                    *items_data.borrow_mut() = found;
+                    *search_query.borrow_mut() = query;
+                    *selected_row.borrow_mut() = -1;
+                    selection.borrow_mut().clear();
+                    table_clone.set_rows(items_data.borrow().len() as i32);
+                    
+                    let new_count = count_text(items_data.borrow().len(), &search_query.borrow());
+                    count_label_clone.set_label(new_count.as_str());
+                    
+                    table_clone.redraw();
+                },
+                Err(e) => dialog::alert(300, 300, &format!("Error searching: {}", e)),
             }
         });
     }
 
+    {
+        let mut search_input = search_input.clone();
+        let mut search_btn = search_btn.clone();
+        show_all_btn.set_callback(move |_| {
+            search_input.set_value("");
+            search_btn.do_callback();
+        });
+    }
+
     {
         let mut win_clone = win.clone();
         close_btn.set_callback(move |_| {
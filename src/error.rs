@@ -0,0 +1,127 @@
+// error.rs
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use crate::uid::UidError;
+
+/// Crate-wide result type
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Everything that can go wrong in the core library, grouped by kind so
+/// callers can match on the failure instead of parsing a message
+#[derive(Debug)]
+pub enum Error {
+    /// SQLite failed while reading or writing the inventory database
+    Database(rusqlite::Error),
+    /// JSON data (config, inventory import/export) could not be parsed or produced
+    Parse {
+        context: String,
+        source: serde_json::Error,
+    },
+    /// A file or directory operation failed
+    Io {
+        context: String,
+        source: io::Error,
+    },
+    /// A sync operation clashed with one already in progress or with existing data
+    SyncConflict {
+        path: Option<PathBuf>,
+        reason: String,
+    },
+    /// A tag ID is not a usable card UID
+    InvalidUid(UidError),
+    /// A card reader backend failed or was given input it can't use
+    Reader(String),
+    /// The inventory schema could not be upgraded, or is newer than this build
+    Migration(String),
+    /// Keyboard layout calibration was given reads or a name it can't use
+    Calibration(String),
+    /// A scan can't be applied in the active scan mode, e.g. issuing an item with no stock
+    Scan(String),
+    /// A location can't be created, renamed, moved or deleted as asked
+    Location(String),
+    /// A category, its custom fields or an item's field values were rejected
+    Category(String),
+    /// A tag or identifier can't be attached to, moved between or retired from items as asked
+    Tag(String),
+    /// A minimum or reorder quantity can't be used, e.g. a negative one
+    StockLevel(String),
+    /// A search query uses a field or comparison the search doesn't know
+    Search(String),
+    /// A step can't be undone or redone, e.g. because an item changed again since
+    Undo(String),
+}
+
+impl Error {
+    pub fn io(context: impl Into<String>, source: io::Error) -> Self {
+        Error::Io { context: context.into(), source }
+    }
+
+    pub fn parse(context: impl Into<String>, source: serde_json::Error) -> Self {
+        Error::Parse { context: context.into(), source }
+    }
+
+    pub fn sync_conflict(path: Option<PathBuf>, reason: impl Into<String>) -> Self {
+        Error::SyncConflict { path, reason: reason.into() }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Database(e) => write!(f, "database error: {}", e),
+            Error::Parse { context, source } => write!(f, "{}: {}", context, source),
+            Error::Io { context, source } => write!(f, "{}: {}", context, source),
+            Error::SyncConflict { path: Some(path), reason } => {
+                write!(f, "sync conflict on {}: {}", path.display(), reason)
+            }
+            Error::SyncConflict { path: None, reason } => write!(f, "sync conflict: {}", reason),
+            Error::InvalidUid(e) => write!(f, "invalid UID: {}", e),
+            Error::Reader(message) => write!(f, "card reader error: {}", message),
+            Error::Migration(message) => write!(f, "schema migration failed: {}", message),
+            Error::Calibration(message) => write!(f, "layout calibration: {}", message),
+            Error::Scan(message) => write!(f, "scan rejected: {}", message),
+            Error::Location(message) => write!(f, "location: {}", message),
+            Error::Category(message) => write!(f, "category: {}", message),
+            Error::Tag(message) => write!(f, "tag: {}", message),
+            Error::StockLevel(message) => write!(f, "stock level: {}", message),
+            Error::Search(message) => write!(f, "search: {}", message),
+            Error::Undo(message) => write!(f, "undo: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Database(e) => Some(e),
+            Error::Parse { source, .. } => Some(source),
+            Error::Io { source, .. } => Some(source),
+            Error::SyncConflict { .. } => None,
+            Error::InvalidUid(e) => Some(e),
+            Error::Reader(_) => None,
+            Error::Migration(_) => None,
+            Error::Calibration(_) => None,
+            Error::Scan(_) => None,
+            Error::Location(_) => None,
+            Error::Category(_) => None,
+            Error::Tag(_) => None,
+            Error::StockLevel(_) => None,
+            Error::Search(_) => None,
+            Error::Undo(_) => None,
+        }
+    }
+}
+
+impl From<rusqlite::Error> for Error {
+    fn from(e: rusqlite::Error) -> Self {
+        Error::Database(e)
+    }
+}
+
+impl From<UidError> for Error {
+    fn from(e: UidError) -> Self {
+        Error::InvalidUid(e)
+    }
+}
@@ -1,8 +1,16 @@
 // export/formats.rs
 use std::fs;
-use std::io::{self, Write};
+use std::io::Write;
 use std::path::Path;
 use chrono::Local;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+use crate::card_family::CardFamily;
+use crate::convert;
+use crate::error::{Error, Result};
+use crate::uid::Uid;
+use crate::utils::csv_field;
+use crate::wiegand::{self, WiegandCredential};
 
 /// Export formats supported by the application
 pub enum ExportFormat {
@@ -16,10 +24,42 @@ pub enum ExportFormat {
 pub struct CardRecord {
     pub timestamp: String,
     pub raw_uid: String,
-    pub hex_uid: String,
-    pub decimal_uid: String,
+    pub uid: Uid,
     pub manufacturer: String,
     pub format: String,
+    /// The UID as each supported Wiegand frame, for matching against access-control exports
+    pub wiegand: Vec<WiegandCredential>,
+    /// Known only for reads that came with ATQA/SAK
+    pub card_family: Option<CardFamily>,
+}
+
+impl CardRecord {
+    pub fn new(timestamp: String, raw_uid: String, uid: Uid, manufacturer: String, format: String) -> Self {
+        let wiegand = wiegand::encode_uid_all(&uid);
+        CardRecord { timestamp, raw_uid, uid, manufacturer, format, wiegand, card_family: None }
+    }
+}
+
+// Serialized with the derived hex and decimal forms so exports stay readable without the Uid type
+impl Serialize for CardRecord {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("CardRecord", 9)?;
+        state.serialize_field("timestamp", &self.timestamp)?;
+        state.serialize_field("raw_uid", &self.raw_uid)?;
+        state.serialize_field("hex_uid", &self.uid.to_spaced_hex())?;
+        state.serialize_field("decimal_uid", &self.uid.to_decimal())?;
+        state.serialize_field("manufacturer", &self.manufacturer)?;
+        state.serialize_field("format", &self.format)?;
+        state.serialize_field("card_family", &self.card_family)?;
+        
+        let formats: serde_json::Map<String, serde_json::Value> = convert::all_formats(&self.uid)
+            .into_iter()
+            .map(|(format, value)| (format.key().to_string(), value.into()))
+            .collect();
+        state.serialize_field("formats", &formats)?;
+        state.serialize_field("wiegand", &self.wiegand)?;
+        state.end()
+    }
 }
 
 /// Export card data to a file
@@ -27,33 +67,47 @@ pub fn export_data(
     records: &[CardRecord], 
     format: ExportFormat, 
     filename: &str
-) -> io::Result<String> {
-    let content = match format {
-        ExportFormat::CSV => generate_csv(records),
-        ExportFormat::JSON => generate_json(records),
-        ExportFormat::Text => generate_text(records),
-    };
+) -> Result<String> {
+    let content = render(records, &format);
     
     let path = Path::new(filename);
-    let mut file = fs::File::create(path)?;
-    file.write_all(content.as_bytes())?;
+    fs::File::create(path)
+        .and_then(|mut file| file.write_all(content.as_bytes()))
+        .map_err(|e| Error::io(format!("Failed to write {}", filename), e))?;
     
     Ok(format!("Data exported to {}", filename))
 }
 
+/// Render card records in the given format without writing them anywhere
+pub fn render(records: &[CardRecord], format: &ExportFormat) -> String {
+    match format {
+        ExportFormat::CSV => generate_csv(records),
+        ExportFormat::JSON => generate_json(records),
+        ExportFormat::Text => generate_text(records),
+    }
+}
+
 /// Generate CSV content from card records
 fn generate_csv(records: &[CardRecord]) -> String {
-    let mut csv = String::from("Timestamp,Raw UID,Hex UID,Decimal UID,Manufacturer,Format\n");
+    let mut csv = String::from("Timestamp,Raw UID,Hex UID,Decimal UID,Manufacturer,Format,Card Type,Wiegand 26,Wiegand 34,Wiegand 37\n");
     
     for record in records {
+        let wiegand: Vec<String> = record
+            .wiegand
+            .iter()
+            .map(|w| csv_field(format!("{}:{}", w.facility_code, w.card_number)))
+            .collect();
+        
         csv.push_str(&format!(
-            "{},{},{},{},{},{}\n",
-            record.timestamp,
-            record.raw_uid,
-            record.hex_uid,
-            record.decimal_uid,
-            record.manufacturer,
-            record.format
+            "{},{},{},{},{},{},{},{}\n",
+            csv_field(&record.timestamp),
+            csv_field(&record.raw_uid),
+            csv_field(record.uid.to_spaced_hex()),
+            csv_field(record.uid.to_decimal()),
+            csv_field(&record.manufacturer),
+            csv_field(&record.format),
+            csv_field(record.card_family.map(|f| f.name()).unwrap_or_default()),
+            wiegand.join(",")
         ));
     }
     
@@ -62,27 +116,9 @@ fn generate_csv(records: &[CardRecord]) -> String {
 
 /// Generate JSON content from card records
 fn generate_json(records: &[CardRecord]) -> String {
-    let mut json = String::from("[\n");
-    
-    for (i, record) in records.iter().enumerate() {
-        json.push_str(&format!(
-            "  {{\n    \"timestamp\": \"{}\",\n    \"raw_uid\": \"{}\",\n    \"hex_uid\": \"{}\",\n    \"decimal_uid\": \"{}\",\n    \"manufacturer\": \"{}\",\n    \"format\": \"{}\"\n  }}",
-            record.timestamp,
-            record.raw_uid,
-            record.hex_uid,
-            record.decimal_uid,
-            record.manufacturer,
-            record.format
-        ));
-        
-        if i < records.len() - 1 {
-            json.push_str(",\n");
-        } else {
-            json.push_str("\n");
-        }
-    }
-    
-    json.push_str("]\n");
+    // serde takes care of escaping quotes and backslashes in raw keyboard-wedge input
+    let mut json = serde_json::to_string_pretty(records).unwrap_or_else(|_| "[]".to_string());
+    json.push('\n');
     json
 }
 
@@ -95,10 +131,17 @@ fn generate_text(records: &[CardRecord]) -> String {
         text.push_str(&format!("Card #{}\n", i + 1));
         text.push_str(&format!("Timestamp: {}\n", record.timestamp));
         text.push_str(&format!("Raw UID: {}\n", record.raw_uid));
-        text.push_str(&format!("Hex UID: {}\n", record.hex_uid));
-        text.push_str(&format!("Decimal UID: {}\n", record.decimal_uid));
+        text.push_str(&format!("Hex UID: {}\n", record.uid.to_spaced_hex()));
+        text.push_str(&format!("Decimal UID: {}\n", record.uid.to_decimal()));
         text.push_str(&format!("Manufacturer: {}\n", record.manufacturer));
-        text.push_str(&format!("Format: {}\n\n", record.format));
+        text.push_str(&format!("Format: {}\n", record.format));
+        if let Some(family) = record.card_family {
+            text.push_str(&format!("Card Type: {} ({})\n", family, family.describe_capabilities()));
+        }
+        for credential in &record.wiegand {
+            text.push_str(&format!("{}: {} (frame {})\n", credential.format.name(), credential, credential.to_hex()));
+        }
+        text.push('\n');
     }
     
     text
@@ -129,9 +172,9 @@ pub fn parse_display_text(text: &str) -> Vec<CardRecord> {
             
             // Extract other fields from subsequent lines
             let mut hex_uid = String::new();
-            let mut decimal_uid = String::new();
             let mut manufacturer = String::new();
             let mut format = String::new();
+            let mut card_family = None;
             
             // Try to read the next lines for additional data
             while let Some(next_line) = lines.peek() {
@@ -143,11 +186,7 @@ pub fn parse_display_text(text: &str) -> Vec<CardRecord> {
                         .to_string();
                     lines.next();
                 } else if next_line.trim().starts_with("→ Decimal:") {
-                    decimal_uid = next_line
-                        .trim()
-                        .trim_start_matches("→ Decimal:")
-                        .trim()
-                        .to_string();
+                    // Derived from the hex UID, nothing to keep
                     lines.next();
                 } else if next_line.trim().starts_with("→ Manufacturer:") {
                     manufacturer = next_line
@@ -156,6 +195,12 @@ pub fn parse_display_text(text: &str) -> Vec<CardRecord> {
                         .trim()
                         .to_string();
                     lines.next();
+                } else if next_line.trim().starts_with("→ ATQA:") {
+                    // "→ ATQA: 0044  SAK: 00"; the family is derived again rather than parsed from its name
+                    card_family = parse_atqa_sak(next_line).map(|(atqa, sak)| CardFamily::identify(atqa, sak));
+                    lines.next();
+                } else if next_line.trim().starts_with("→ Card Type:") {
+                    lines.next();
                 } else if next_line.trim().starts_with("→ Format:") {
                     format = next_line
                         .trim()
@@ -169,19 +214,23 @@ pub fn parse_display_text(text: &str) -> Vec<CardRecord> {
                 }
             }
             
-            // Add the record if we have the minimum data
-            if !hex_uid.is_empty() {
-                records.push(CardRecord {
-                    timestamp,
-                    raw_uid,
-                    hex_uid,
-                    decimal_uid,
-                    manufacturer,
-                    format,
-                });
+            // Add the record if the logged hex is a valid UID; failed reads are skipped
+            if let Ok(uid) = Uid::from_hex(&hex_uid) {
+                let mut record = CardRecord::new(timestamp, raw_uid, uid, manufacturer, format);
+                record.card_family = card_family;
+                records.push(record);
             }
         }
     }
     
     records
-}
\ No newline at end of file
+}
+
+// Read the hex values from a logged "→ ATQA: 0044  SAK: 00" line
+fn parse_atqa_sak(line: &str) -> Option<(u16, u8)> {
+    let rest = line.trim().trim_start_matches("→ ATQA:");
+    let (atqa, sak) = rest.split_once("SAK:")?;
+    let atqa = u16::from_str_radix(atqa.trim(), 16).ok()?;
+    let sak = u8::from_str_radix(sak.trim(), 16).ok()?;
+    Some((atqa, sak))
+}
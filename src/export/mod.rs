@@ -6,5 +6,6 @@ pub use formats::{
     ExportFormat,
     CardRecord,
     export_data,
+    render,
     parse_display_text
 };
\ No newline at end of file
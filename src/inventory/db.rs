@@ -1,282 +1,2559 @@
 // inventory/db.rs
-use rusqlite::{params, Connection, Result};
-use std::path::Path;
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, Type, Value, ValueRef};
+use chrono::NaiveDate;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Params, Row};
+use std::collections::BTreeMap;
 use std::sync::{Arc, Mutex};
 
-use crate::inventory::model::{InventoryItem, generate_timestamp};
+use crate::card_family::CardFamily;
+use crate::error::{Error, Result};
+use crate::inventory::migrations::{self, MigrationReport};
+use crate::inventory::model::{
+    Actor, Category, CategoryField, CategoryStats, FieldType, InventoryItem, ItemTag, Loan, Location, LocationStats,
+    Movement, MovementReason, QuantityMismatch, ReorderItem, DATE_FORMAT, PATH_SEPARATOR, generate_timestamp, parse_color,
+    format_color, path_segments, path_is_within, timestamp_days_ago, validate_stock_levels,
+};
+use crate::inventory::search::SearchQuery;
+use crate::inventory::view::{sort_items, InventoryView};
+use crate::inventory::stocktake::{StocktakeScope, Variance, VarianceKind};
+use crate::inventory::undo::{changed_since, describe_step, same_tags, ItemChange, ItemState, TrashedItem, UndoStep, UNDO_LIMIT};
+use crate::uid::Uid;
+use crate::utils::csv_field;
+
+// Tag IDs are kept in the TEXT column as plain upper-case hex
+impl ToSql for Uid {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.to_hex()))
+    }
+}
+
+impl FromSql for Uid {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value
+            .as_str()?
+            .parse::<Uid>()
+            .map_err(|e| FromSqlError::Other(Box::new(e)))
+    }
+}
+
+// Card families are kept by key, NULL when the reader didn't report ATQA/SAK
+impl ToSql for CardFamily {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.key()))
+    }
+}
+
+impl FromSql for CardFamily {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let key = value.as_str()?;
+        CardFamily::from_key(key).ok_or_else(|| FromSqlError::Other(format!("unknown card family '{}'", key).into()))
+    }
+}
+
+impl ToSql for MovementReason {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.key()))
+    }
+}
+
+impl FromSql for MovementReason {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let key = value.as_str()?;
+        MovementReason::from_key(key).ok_or_else(|| FromSqlError::Other(format!("unknown movement reason '{}'", key).into()))
+    }
+}
+
+impl ToSql for FieldType {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.key()))
+    }
+}
+
+impl FromSql for FieldType {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let key = value.as_str()?;
+        FieldType::from_key(key).ok_or_else(|| FromSqlError::Other(format!("unknown field type '{}'", key).into()))
+    }
+}
+
+// Columns read by every item query, in the order item_from_row expects
+const ITEM_COLUMNS: &str =
+    "id, tag_id, name, description, quantity, location, category, last_updated, created_at, card_family, location_id, \
+     category_id, min_quantity, reorder_quantity";
+
+fn item_from_row(row: &Row) -> rusqlite::Result<InventoryItem> {
+    Ok(InventoryItem {
+        id: row.get(0)?,
+        tag_id: row.get(1)?,
+        name: row.get(2)?,
+        description: row.get(3)?,
+        quantity: row.get(4)?,
+        location: row.get(5)?,
+        category: row.get(6)?,
+        last_updated: row.get(7)?,
+        created_at: row.get(8)?,
+        card_family: row.get(9)?,
+        location_id: row.get(10)?,
+        category_id: row.get(11)?,
+        // Filled in by attach_attributes
+        attributes: BTreeMap::new(),
+        min_quantity: row.get(12)?,
+        reorder_quantity: row.get(13)?,
+    })
+}
+
+// Columns read by every tag link query, in the order tag_from_row expects
+const TAG_COLUMNS: &str = "id, item_id, uid, identifier, attached_at, retired_at";
+
+fn tag_from_row(row: &Row) -> rusqlite::Result<ItemTag> {
+    Ok(ItemTag {
+        id: row.get(0)?,
+        item_id: row.get(1)?,
+        uid: row.get(2)?,
+        identifier: row.get(3)?,
+        attached_at: row.get(4)?,
+        retired_at: row.get(5)?,
+    })
+}
+
+// Columns read by every undo journal query, in the order step_from_row expects
+const STEP_COLUMNS: &str = "id, description, changes, created_at, undone_at";
+
+fn step_from_row(row: &Row) -> rusqlite::Result<UndoStep> {
+    let changes: String = row.get(2)?;
+    Ok(UndoStep {
+        id: row.get(0)?,
+        description: row.get(1)?,
+        changes: serde_json::from_str(&changes)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(2, Type::Text, Box::new(e)))?,
+        created_at: row.get(3)?,
+        undone_at: row.get(4)?,
+    })
+}
+
+// Columns read by every trash query, in the order trashed_from_row expects
+const TRASH_COLUMNS: &str = "item_id, state, deleted_at, deleted_by";
+
+fn trashed_from_row(row: &Row) -> rusqlite::Result<TrashedItem> {
+    let state: String = row.get(1)?;
+    let mut state: ItemState = serde_json::from_str(&state)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(1, Type::Text, Box::new(e)))?;
+    // The item ID isn't part of its JSON form
+    state.item.id = Some(row.get(0)?);
+    Ok(TrashedItem {
+        state,
+        deleted_at: row.get(2)?,
+        deleted_by: row.get(3)?,
+    })
+}
+
+// Columns read by every loan query, in the order loan_from_row expects
+const LOAN_COLUMNS: &str = "id, tag_id, borrower, checked_out_at, due_date, returned_at";
+
+fn loan_from_row(row: &Row) -> rusqlite::Result<Loan> {
+    Ok(Loan {
+        id: row.get(0)?,
+        tag_id: row.get(1)?,
+        borrower: row.get(2)?,
+        checked_out_at: row.get(3)?,
+        due_date: row.get(4)?,
+        returned_at: row.get(5)?,
+    })
+}
+
+// Columns read by every location query, in the order location_from_row expects
+const LOCATION_COLUMNS: &str = "l.id, l.name, l.parent_id, l.tag_id, tree.path, l.created_at";
+
+fn location_from_row(row: &Row) -> rusqlite::Result<Location> {
+    Ok(Location {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        parent_id: row.get(2)?,
+        tag_id: row.get(3)?,
+        path: row.get(4)?,
+        created_at: row.get(5)?,
+    })
+}
+
+// Every location with its full path, built from the top level down
+fn location_tree() -> String {
+    format!(
+        "WITH RECURSIVE tree (id, path) AS (
+            SELECT id, name FROM locations WHERE parent_id IS NULL
+            UNION ALL
+            SELECT l.id, tree.path || '{}' || l.name FROM locations l JOIN tree ON l.parent_id = tree.id
+        )",
+        PATH_SEPARATOR
+    )
+}
+
+// Columns read by every category query, in the order category_from_row expects
+const CATEGORY_COLUMNS: &str =
+    "c.id, c.name, c.parent_id, c.color, tree.color, tree.path, c.created_at, c.min_quantity, c.reorder_quantity, \
+     tree.min_quantity, tree.reorder_quantity";
+
+fn category_from_row(row: &Row) -> rusqlite::Result<Category> {
+    Ok(Category {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        parent_id: row.get(2)?,
+        color: row.get(3)?,
+        display_color: row.get(4)?,
+        path: row.get(5)?,
+        created_at: row.get(6)?,
+        min_quantity: row.get(7)?,
+        reorder_quantity: row.get(8)?,
+        effective_min_quantity: row.get(9)?,
+        effective_reorder_quantity: row.get(10)?,
+    })
+}
+
+// Every category with its full path, and the colour and stock levels it uses, each inherited from
+// the nearest parent that has one
+fn category_tree() -> String {
+    format!(
+        "WITH RECURSIVE tree (id, path, color, min_quantity, reorder_quantity) AS (
+            SELECT id, name, color, min_quantity, reorder_quantity FROM categories WHERE parent_id IS NULL
+            UNION ALL
+            SELECT c.id, tree.path || '{}' || c.name, COALESCE(c.color, tree.color),
+                COALESCE(c.min_quantity, tree.min_quantity), COALESCE(c.reorder_quantity, tree.reorder_quantity)
+            FROM categories c JOIN tree ON c.parent_id = tree.id
+        )",
+        PATH_SEPARATOR
+    )
+}
+
+// Columns read by every custom field query, in the order field_from_row expects
+const FIELD_COLUMNS: &str = "f.id, f.category_id, f.name, f.field_type, f.required, f.position";
+
+fn field_from_row(row: &Row) -> rusqlite::Result<CategoryField> {
+    Ok(CategoryField {
+        id: row.get(0)?,
+        category_id: row.get(1)?,
+        name: row.get(2)?,
+        field_type: row.get(3)?,
+        required: row.get(4)?,
+        position: row.get(5)?,
+    })
+}
 
 // Database management functions
 pub struct InventoryDB {
     conn: Connection,
+    migration: Option<MigrationReport>,
+    // Recorded as user and station on every movement
+    actor: Actor,
 }
 
 impl InventoryDB {
-    // Initialize the database
+    // Open (or create) the database and upgrade its schema to the current version
     pub fn new(db_path: &str) -> Result<Self> {
-        let create_new = !Path::new(db_path).exists();
-        let conn = Connection::open(db_path)?;
+        let mut conn = Connection::open(db_path)?;
+        let migration = migrations::migrate(&mut conn, db_path)?;
+        
+        Ok(InventoryDB { conn, migration, actor: Actor::from_env() })
+    }
+    
+    // Schema version recorded in the file
+    pub fn schema_version(&self) -> Result<u32> {
+        migrations::schema_version(&self.conn)
+    }
+    
+    // The upgrade done when this database was opened, if it needed one
+    pub fn migration(&self) -> Option<&MigrationReport> {
+        self.migration.as_ref()
+    }
+    
+    // Who changes made through this handle are attributed to
+    pub fn set_actor(&mut self, actor: Actor) {
+        self.actor = actor;
+    }
+    
+    pub fn actor(&self) -> &Actor {
+        &self.actor
+    }
+    
+    // Add or update an item by hand; a quantity change is booked as an adjustment
+    pub fn save_item(&self, item: &InventoryItem) -> Result<()> {
+        self.save_item_with_reason(item, MovementReason::Adjustment)
+    }
+    
+    // Add or update an item, booking any quantity change to the ledger with `reason`, as one undo step.
+    // An item without an ID is matched by any active tag; a new item gets `tag_id` as its first tag
+    pub fn save_item_with_reason(&self, item: &InventoryItem, reason: MovementReason) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        let change = self.store_item(&tx, item, reason)?;
+        record_step(&tx, &describe_step("Save", std::slice::from_ref(&change)), &[change])?;
+        
+        tx.commit()?;
+        Ok(())
+    }
+    
+    // Add or update an item inside the caller's transaction; returns the change for the undo journal
+    fn store_item(&self, conn: &Connection, item: &InventoryItem, reason: MovementReason) -> Result<ItemChange> {
+        if let Some(location) = select_locations(conn, "WHERE l.tag_id = ?", params![item.tag_id])?.pop() {
+            return Err(Error::Location(format!("tag {} already marks the location '{}'", item.tag_id, location.path)));
+        }
+        let existing = match item.id {
+            Some(id) => current_quantity(conn, id)?.map(|_| id),
+            None => item_id_for_tag(conn, &item.tag_id)?,
+        };
+        let before = match existing {
+            Some(id) => item_state(conn, id)?,
+            None => None,
+        };
+        let previous = before.as_ref().map(|before| before.item.quantity);
+        
+        // The typed location and category are matched to their hierarchies, adding any part that doesn't exist yet
+        let location = match item.location.as_deref() {
+            Some(path) => ensure_location_path(conn, path)?,
+            None => None,
+        };
+        let category = match item.category.as_deref() {
+            Some(path) => ensure_category_path(conn, path)?,
+            None => None,
+        };
+        let attributes = checked_attributes(conn, category.as_ref(), &item.attributes)?;
+        validate_stock_levels(item.min_quantity, item.reorder_quantity).map_err(Error::StockLevel)?;
         
-        let db = InventoryDB { conn };
+        // The tags an existing item carries are changed with attach_tag, retag_item and retire_tag
+        let item_id = match existing {
+            Some(id) => {
+                conn.execute(
+                    "UPDATE inventory SET
+                        name = ?, description = ?, quantity = ?, location = ?, category = ?, last_updated = ?,
+                        card_family = ?, location_id = ?, category_id = ?, min_quantity = ?, reorder_quantity = ?
+                    WHERE id = ?",
+                    params![
+                        item.name,
+                        item.description,
+                        item.quantity,
+                        location.as_ref().map(|location| &location.path),
+                        category.as_ref().map(|category| &category.path),
+                        item.last_updated,
+                        item.card_family,
+                        location.as_ref().map(|location| location.id),
+                        category.as_ref().map(|category| category.id),
+                        item.min_quantity,
+                        item.reorder_quantity,
+                        id
+                    ],
+                )?;
+                id
+            }
+            None => {
+                conn.execute(
+                    "INSERT INTO inventory (
+                        tag_id, name, description, quantity, location, category, last_updated, created_at, card_family,
+                        location_id, category_id, min_quantity, reorder_quantity
+                    ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                    params![
+                        item.tag_id,
+                        item.name,
+                        item.description,
+                        item.quantity,
+                        location.as_ref().map(|location| &location.path),
+                        category.as_ref().map(|category| &category.path),
+                        item.last_updated,
+                        item.created_at,
+                        item.card_family,
+                        location.as_ref().map(|location| location.id),
+                        category.as_ref().map(|category| category.id),
+                        item.min_quantity,
+                        item.reorder_quantity
+                    ],
+                )?;
+                let id = conn.last_insert_rowid();
+                link_tag(conn, id, Some(&item.tag_id), None)?;
+                id
+            }
+        };
+        
+        conn.execute("DELETE FROM item_attributes WHERE item_id = ?", params![item_id])?;
+        for (name, value) in &attributes {
+            conn.execute(
+                "INSERT INTO item_attributes (item_id, name, value) VALUES (?, ?, ?)",
+                params![item_id, name, value],
+            )?;
+        }
         
-        // Create tables if this is a new database
-        if create_new {
-            db.create_tables()?;
+        let delta = item.quantity - previous.unwrap_or(0);
+        if delta != 0 {
+            let note = if previous.is_none() { Some("New item") } else { None };
+            self.insert_movement(conn, item_id, &item.tag_id, delta, reason, note)?;
         }
         
-        Ok(db)
+        Ok(ItemChange { item_id, before, after: item_state(conn, item_id)? })
     }
     
-    // Create the necessary tables
-    fn create_tables(&self) -> Result<()> {
-        self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS inventory (
-                tag_id TEXT PRIMARY KEY,
-                name TEXT NOT NULL,
-                description TEXT,
-                quantity INTEGER NOT NULL DEFAULT 0,
-                location TEXT,
-                category TEXT,
-                last_updated TEXT NOT NULL,
-                created_at TEXT NOT NULL
-            )",
-            [],
-        )?;
-        
-        Ok(())
+    // Retrieve the item a tag is attached to; any of its active tags finds it. A tag several items
+    // share finds the item it's shown for, and is an error if it isn't shown for any of them
+    pub fn get_item(&self, tag_id: &Uid) -> Result<Option<InventoryItem>> {
+        match item_id_for_tag(&self.conn, tag_id)? {
+            Some(item_id) => self.get_item_by_id(item_id),
+            None => Ok(None),
+        }
     }
     
-    // Add or update an item
-    pub fn save_item(&self, item: &InventoryItem) -> Result<()> {
-        self.conn.execute(
-            "INSERT OR REPLACE INTO inventory (
-                tag_id, name, description, quantity, location, category, last_updated, created_at
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
-            params![
-                item.tag_id,
-                item.name,
-                item.description,
-                item.quantity,
-                item.location,
-                item.category,
-                item.last_updated,
-                item.created_at
-            ],
-        )?;
+    // Every item a UID is attached to, the one it's shown for first, e.g. to ask which one a scan meant
+    pub fn get_items_for_tag(&self, tag_id: &Uid) -> Result<Vec<InventoryItem>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM inventory
+             WHERE id IN (SELECT item_id FROM tags WHERE uid = ?1 AND retired_at IS NULL)
+             ORDER BY tag_id = ?1 DESC, name",
+            ITEM_COLUMNS
+        ))?;
         
-        Ok(())
+        let mut items = stmt.query_map(params![tag_id], item_from_row)?.collect::<rusqlite::Result<Vec<_>>>()?;
+        attach_attributes(&self.conn, &mut items)?;
+        Ok(items)
+    }
+    
+    pub fn get_item_by_id(&self, id: i64) -> Result<Option<InventoryItem>> {
+        self.select_item("WHERE id = ?", params![id])
+    }
+    
+    // Retrieve the item an active text identifier (e.g. an NDEF text record) is attached to, in any case
+    pub fn get_item_by_identifier(&self, identifier: &str) -> Result<Option<InventoryItem>> {
+        self.select_item(
+            "WHERE id = (SELECT item_id FROM tags WHERE identifier = ? COLLATE NOCASE AND retired_at IS NULL)",
+            params![identifier.trim()],
+        )
     }
     
-    // Retrieve an item by tag ID
-    pub fn get_item(&self, tag_id: &str) -> Result<Option<InventoryItem>> {
+    fn select_item<P: Params>(&self, condition: &str, params: P) -> Result<Option<InventoryItem>> {
         let mut stmt = self.conn.prepare(
-            "SELECT tag_id, name, description, quantity, location, category, last_updated, created_at 
-             FROM inventory WHERE tag_id = ?"
+            &format!("SELECT {} FROM inventory {}", ITEM_COLUMNS, condition)
         )?;
         
-        let item_iter = stmt.query_map(params![tag_id], |row| {
-            Ok(InventoryItem {
-                tag_id: row.get(0)?,
-                name: row.get(1)?,
-                description: row.get(2)?,
-                quantity: row.get(3)?,
-                location: row.get(4)?,
-                category: row.get(5)?,
-                last_updated: row.get(6)?,
-                created_at: row.get(7)?,
-            })
-        })?;
+        let item_iter = stmt.query_map(params, item_from_row)?;
         
-        let item = item_iter.into_iter().next().transpose()?;
+        let mut item = item_iter.into_iter().next().transpose()?;
+        attach_attributes(&self.conn, item.as_mut_slice())?;
         Ok(item)
     }
     
     // Get all inventory items
     pub fn get_all_items(&self) -> Result<Vec<InventoryItem>> {
         let mut stmt = self.conn.prepare(
-            "SELECT tag_id, name, description, quantity, location, category, last_updated, created_at 
-             FROM inventory ORDER BY name"
+            &format!("SELECT {} FROM inventory ORDER BY name", ITEM_COLUMNS)
         )?;
         
-        let item_iter = stmt.query_map([], |row| {
-            Ok(InventoryItem {
-                tag_id: row.get(0)?,
-                name: row.get(1)?,
-                description: row.get(2)?,
-                quantity: row.get(3)?,
-                location: row.get(4)?,
-                category: row.get(5)?,
-                last_updated: row.get(6)?,
-                created_at: row.get(7)?,
-            })
-        })?;
+        let item_iter = stmt.query_map([], item_from_row)?;
         
         let mut items = Vec::new();
         for item in item_iter {
             items.push(item?);
         }
         
+        attach_attributes(&self.conn, &mut items)?;
         Ok(items)
     }
     
-    // Delete an item
-    pub fn delete_item(&self, tag_id: &str) -> Result<bool> {
-        let affected = self.conn.execute(
-            "DELETE FROM inventory WHERE tag_id = ?",
-            params![tag_id],
+    // Move an item to the trash as one undo step and free its tags; the stock it still had is booked
+    // out so the ledger keeps adding up
+    pub fn delete_item(&self, tag_id: &Uid) -> Result<bool> {
+        let Some(item_id) = item_id_for_tag(&self.conn, tag_id)? else {
+            return Ok(false);
+        };
+        let deleted = self.journaled("Delete", &[item_id], |conn, item_id, _| {
+            self.remove_item(conn, item_id, tag_id, "Item deleted")
+        })?;
+        
+        Ok(deleted > 0)
+    }
+    
+    // Move an item with its tags and field values to the trash inside the caller's transaction,
+    // booking out its stock. A loan still open on it is closed, or it would stay on the overdue list;
+    // undoing the delete reopens it, restoring from the trash doesn't
+    fn remove_item(&self, conn: &Connection, item_id: i64, tag_id: &Uid, note: &str) -> Result<()> {
+        conn.execute(
+            "UPDATE loans SET returned_at = ? WHERE item_id = ? AND returned_at IS NULL",
+            params![generate_timestamp(), item_id],
+        )?;
+        let Some(state) = item_state(conn, item_id)? else {
+            return Ok(());
+        };
+        let quantity = state.item.quantity;
+        let state = serde_json::to_string(&state).map_err(|e| Error::parse("Failed to move item to the trash", e))?;
+        conn.execute(
+            "INSERT OR REPLACE INTO trash (item_id, state, deleted_at, deleted_by) VALUES (?, ?, ?, ?)",
+            params![item_id, state, generate_timestamp(), self.actor.user],
         )?;
         
-        Ok(affected > 0)
+        conn.execute("DELETE FROM inventory WHERE id = ?", params![item_id])?;
+        conn.execute("DELETE FROM tags WHERE item_id = ?", params![item_id])?;
+        conn.execute("DELETE FROM item_attributes WHERE item_id = ?", params![item_id])?;
+        
+        if quantity != 0 {
+            self.insert_movement(conn, item_id, tag_id, -quantity, MovementReason::Adjustment, Some(note))?;
+        }
+        
+        Ok(())
+    }
+    
+    // Set the quantity of an item by hand
+    pub fn update_quantity(&self, tag_id: &Uid, new_quantity: i32) -> Result<bool> {
+        self.set_quantity(tag_id, new_quantity, MovementReason::Adjustment)
+    }
+    
+    // Set the quantity of an item as one undo step, booking the difference with `reason`
+    pub fn set_quantity(&self, tag_id: &Uid, new_quantity: i32, reason: MovementReason) -> Result<bool> {
+        let Some(item_id) = item_id_for_tag(&self.conn, tag_id)? else {
+            return Ok(false);
+        };
+        let action = format!("Set quantity to {}", new_quantity);
+        
+        self.journaled(&action, &[item_id], |conn, item_id, before| {
+            conn.execute(
+                "UPDATE inventory SET quantity = ?, last_updated = ? WHERE id = ?",
+                params![new_quantity, generate_timestamp(), item_id],
+            )?;
+            if new_quantity != before.item.quantity {
+                self.insert_movement(conn, item_id, tag_id, new_quantity - before.item.quantity, reason, None)?;
+            }
+            Ok(())
+        })?;
+        
+        Ok(true)
+    }
+    
+    // Change the quantity of an item by `delta` as one undo step and return the new quantity; `None`
+    // if the tag is unknown
+    pub fn record_movement(&self, tag_id: &Uid, delta: i32, reason: MovementReason) -> Result<Option<i32>> {
+        let Some(item_id) = item_id_for_tag(&self.conn, tag_id)? else {
+            return Ok(None);
+        };
+        let action = format!("{} {:+}", reason.name(), delta);
+        
+        let mut quantity = None;
+        self.journaled(&action, &[item_id], |conn, _, _| {
+            quantity = self.change_quantity(conn, tag_id, delta, reason, None)?;
+            Ok(())
+        })?;
+        
+        Ok(quantity)
+    }
+    
+    // Lend one unit of an item to `borrower` as one undo step, booking it out of stock
+    pub fn check_out(&self, tag_id: &Uid, borrower: &str, due_date: Option<NaiveDate>) -> Result<Loan> {
+        let Some(item_id) = item_id_for_tag(&self.conn, tag_id)? else {
+            return Err(Error::Tag(format!("{} isn't attached to any item", tag_id)));
+        };
+        let due_date = due_date.map(|due| due.format(DATE_FORMAT).to_string());
+        
+        let mut loan = None;
+        self.journaled(&format!("Check out to {}", borrower), &[item_id], |conn, item_id, _| {
+            let now = generate_timestamp();
+            conn.execute(
+                "INSERT INTO loans (item_id, tag_id, borrower, checked_out_at, due_date) VALUES (?, ?, ?, ?, ?)",
+                params![item_id, tag_id, borrower, now, due_date],
+            )?;
+            loan = Some(Loan {
+                id: conn.last_insert_rowid(),
+                tag_id: tag_id.clone(),
+                borrower: borrower.to_string(),
+                checked_out_at: now,
+                due_date: due_date.clone(),
+                returned_at: None,
+            });
+            
+            let note = format!("Checked out to {}", borrower);
+            self.change_quantity(conn, tag_id, -1, MovementReason::ScanOut, Some(&note))?;
+            Ok(())
+        })?;
+        
+        Ok(loan.expect("the item was found above"))
     }
     
-    // Update quantity of an item
-    pub fn update_quantity(&self, tag_id: &str, new_quantity: i32) -> Result<bool> {
+    // Close the open loan of the item a tag is attached to and book the unit back in, as one undo
+    // step; `None` if it wasn't out
+    pub fn check_in(&self, tag_id: &Uid) -> Result<Option<Loan>> {
+        let (Some(item_id), Some(mut loan)) = (item_id_for_tag(&self.conn, tag_id)?, open_loan(&self.conn, tag_id)?) else {
+            return Ok(None);
+        };
+        
         let now = generate_timestamp();
+        self.journaled(&format!("Check in from {}", loan.borrower), &[item_id], |conn, _, _| {
+            conn.execute("UPDATE loans SET returned_at = ? WHERE id = ?", params![now, loan.id])?;
+            let note = format!("Returned by {}", loan.borrower);
+            self.change_quantity(conn, tag_id, 1, MovementReason::ScanIn, Some(&note))?;
+            Ok(())
+        })?;
+        
+        loan.returned_at = Some(now);
+        Ok(Some(loan))
+    }
+    
+    // The loan a tag is currently out on, if any
+    pub fn get_open_loan(&self, tag_id: &Uid) -> Result<Option<Loan>> {
+        open_loan(&self.conn, tag_id)
+    }
+    
+    // Every tag currently checked out, soonest due first
+    pub fn get_open_loans(&self) -> Result<Vec<Loan>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM loans WHERE returned_at IS NULL ORDER BY due_date IS NULL, due_date, checked_out_at",
+            LOAN_COLUMNS
+        ))?;
         
-        let affected = self.conn.execute(
-            "UPDATE inventory SET quantity = ?, last_updated = ? WHERE tag_id = ?",
-            params![new_quantity, now, tag_id],
+        let loans = stmt
+            .query_map([], loan_from_row)?
+            .collect::<rusqlite::Result<Vec<Loan>>>()?;
+        
+        Ok(loans)
+    }
+    
+    // Apply `delta` to the item `tag_id` is attached to inside the caller's transaction and book it to the ledger
+    fn change_quantity(
+        &self,
+        conn: &Connection,
+        tag_id: &Uid,
+        delta: i32,
+        reason: MovementReason,
+        note: Option<&str>
+    ) -> Result<Option<i32>> {
+        let Some(item_id) = item_id_for_tag(conn, tag_id)? else {
+            return Ok(None);
+        };
+        conn.execute(
+            "UPDATE inventory SET quantity = quantity + ?, last_updated = ? WHERE id = ?",
+            params![delta, generate_timestamp(), item_id],
         )?;
         
-        Ok(affected > 0)
+        self.insert_movement(conn, item_id, tag_id, delta, reason, note)?;
+        Ok(current_quantity(conn, item_id)?)
     }
     
-    // Get items by category
-    pub fn get_items_by_category(&self, category: &str) -> Result<Vec<InventoryItem>> {
+    // Every movement of the item a tag is attached to, whichever of its tags was scanned, oldest first.
+    // A tag no item has any more shows the movements booked with it
+    pub fn get_movements(&self, tag_id: &Uid) -> Result<Vec<Movement>> {
+        let item_id = item_id_for_tag(&self.conn, tag_id)?;
         let mut stmt = self.conn.prepare(
-            "SELECT tag_id, name, description, quantity, location, category, last_updated, created_at 
-             FROM inventory WHERE category = ? ORDER BY name"
+            "SELECT id, tag_id, delta, reason, user, station, timestamp, note
+             FROM movements WHERE CASE WHEN ?1 IS NULL THEN tag_id = ?2 ELSE item_id = ?1 END ORDER BY id"
         )?;
         
-        let item_iter = stmt.query_map(params![category], |row| {
-            Ok(InventoryItem {
-                tag_id: row.get(0)?,
-                name: row.get(1)?,
-                description: row.get(2)?,
-                quantity: row.get(3)?,
-                location: row.get(4)?,
-                category: row.get(5)?,
-                last_updated: row.get(6)?,
-                created_at: row.get(7)?,
-            })
+        let movements = stmt
+            .query_map(params![item_id, tag_id], |row| {
+                Ok(Movement {
+                    id: row.get(0)?,
+                    tag_id: row.get(1)?,
+                    delta: row.get(2)?,
+                    reason: row.get(3)?,
+                    user: row.get(4)?,
+                    station: row.get(5)?,
+                    timestamp: row.get(6)?,
+                    note: row.get(7)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<Movement>>>()?;
+        
+        Ok(movements)
+    }
+    
+    // Items whose stored quantity differs from the sum of their movements
+    pub fn quantity_mismatches(&self) -> Result<Vec<QuantityMismatch>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT i.tag_id, i.quantity, COALESCE(SUM(m.delta), 0) AS ledger
+             FROM inventory i LEFT JOIN movements m ON m.item_id = i.id
+             GROUP BY i.id
+             HAVING i.quantity <> ledger
+             ORDER BY i.tag_id"
+        )?;
+        
+        let mismatches = stmt
+            .query_map([], |row| {
+                Ok(QuantityMismatch {
+                    tag_id: row.get(0)?,
+                    stored: row.get(1)?,
+                    ledger: row.get(2)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<QuantityMismatch>>>()?;
+        
+        Ok(mismatches)
+    }
+    
+    // Reset stored quantities to what the ledger adds up to, as one undo step; returns what was
+    // corrected. Undoing it puts the stored quantities back and books the difference, so the ledger
+    // agrees with them instead
+    pub fn reconcile_quantities(&self) -> Result<Vec<QuantityMismatch>> {
+        let mismatches = self.quantity_mismatches()?;
+        let mut item_ids = Vec::new();
+        for mismatch in &mismatches {
+            item_ids.extend(item_id_for_tag(&self.conn, &mismatch.tag_id)?);
+        }
+        
+        self.journaled("Reconcile quantities", &item_ids, |conn, item_id, _| {
+            conn.execute(
+                "UPDATE inventory SET quantity = (SELECT COALESCE(SUM(delta), 0) FROM movements WHERE item_id = ?1),
+                    last_updated = ?2
+                 WHERE id = ?1",
+                params![item_id, generate_timestamp()],
+            )?;
+            Ok(())
         })?;
         
-        let mut items = Vec::new();
-        for item in item_iter {
-            items.push(item?);
+        Ok(mismatches)
+    }
+    
+    // Write approved stocktake adjustments back in one transaction, as one undo step; returns how many
+    // items changed
+    pub fn apply_stocktake(&self, scope: &StocktakeScope, approved: &[Variance]) -> Result<usize> {
+        // Items deleted or re-tagged since the count started are skipped
+        let mut counted: Vec<(i64, &Variance)> = Vec::new();
+        for variance in approved.iter().filter(|v| v.is_adjustable()) {
+            if let Some(item_id) = item_id_for_tag(&self.conn, &variance.tag_id)? {
+                counted.push((item_id, variance));
+            }
         }
+        let item_ids: Vec<i64> = counted.iter().map(|(item_id, _)| *item_id).collect();
         
-        Ok(items)
+        self.journaled(&format!("Stocktake of {}", scope), &item_ids, |conn, item_id, before| {
+            let Some((_, variance)) = counted.iter().find(|(id, _)| *id == item_id) else {
+                return Ok(());
+            };
+            
+            // Items found in the wrong place move to where they were counted
+            if let (VarianceKind::WrongLocation, Some(path)) = (variance.kind, &scope.location) {
+                let location = ensure_location_path(conn, path)?;
+                set_item_location(conn, item_id, location.as_ref())?;
+            }
+            
+            // An item in the wrong place whose count matches only moves; the ledger gets no empty entry
+            let delta = variance.counted - before.item.quantity;
+            if delta != 0 {
+                let note = format!("Stocktake of {}: {}", scope, variance.kind.name().to_lowercase());
+                self.change_quantity(conn, &variance.tag_id, delta, MovementReason::Adjustment, Some(&note))?;
+            }
+            Ok(())
+        })
     }
     
-    // Get all categories with counts
-    pub fn get_categories(&self) -> Result<Vec<(String, i32)>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT category, COUNT(*) FROM inventory 
-             GROUP BY category ORDER BY category"
+    // Append one ledger entry inside the caller's transaction; `tag_id` is the tag the change was made with
+    fn insert_movement(
+        &self,
+        conn: &Connection,
+        item_id: i64,
+        tag_id: &Uid,
+        delta: i32,
+        reason: MovementReason,
+        note: Option<&str>
+    ) -> Result<()> {
+        conn.execute(
+            "INSERT INTO movements (item_id, tag_id, delta, reason, user, station, timestamp, note)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            params![item_id, tag_id, delta, reason, self.actor.user, self.actor.station, generate_timestamp(), note],
         )?;
         
-        let category_iter = stmt.query_map([], |row| {
-            let category: Option<String> = row.get(0)?;
-            let count: i32 = row.get(1)?;
-            
-            Ok((category.unwrap_or_else(|| "Uncategorized".to_string()), count))
+        Ok(())
+    }
+    
+    // Record the card family of an item as one undo step, e.g. the first time it's scanned with a
+    // serial reader; `false` if the tag is unknown
+    pub fn update_card_family(&self, tag_id: &Uid, family: CardFamily) -> Result<bool> {
+        let Some(item_id) = item_id_for_tag(&self.conn, tag_id)? else {
+            return Ok(false);
+        };
+        
+        let changed = self.journaled(&format!("Set card type to {}", family), &[item_id], |conn, item_id, _| {
+            conn.execute(
+                "UPDATE inventory SET card_family = ?, last_updated = ? WHERE id = ?",
+                params![family, generate_timestamp(), item_id],
+            )?;
+            Ok(())
+        })?;
+        
+        Ok(changed > 0)
+    }
+    
+    // Set an item's own stock levels as one undo step; `None` uses its category's. `false` if the tag is unknown
+    pub fn set_stock_levels(&self, tag_id: &Uid, min_quantity: Option<i32>, reorder_quantity: Option<i32>) -> Result<bool> {
+        validate_stock_levels(min_quantity, reorder_quantity).map_err(Error::StockLevel)?;
+        let Some(item_id) = item_id_for_tag(&self.conn, tag_id)? else {
+            return Ok(false);
+        };
+        
+        let changed = self.journaled("Set stock levels", &[item_id], |conn, item_id, _| {
+            conn.execute(
+                "UPDATE inventory SET min_quantity = ?, reorder_quantity = ?, last_updated = ? WHERE id = ?",
+                params![min_quantity, reorder_quantity, generate_timestamp(), item_id],
+            )?;
+            Ok(())
         })?;
         
-        let mut categories = Vec::new();
-        for category in category_iter {
-            categories.push(category?);
+        Ok(changed > 0)
+    }
+    
+    // Every item below its minimum stock, those out of stock first
+    pub fn needs_reorder(&self) -> Result<Vec<ReorderItem>> {
+        let categories = self.get_categories()?;
+        let mut reorder: Vec<ReorderItem> = self
+            .get_all_items()?
+            .iter()
+            .filter_map(|item| {
+                let (min_quantity, reorder_quantity) = stock_levels(item, &categories);
+                ReorderItem::check(item, min_quantity, reorder_quantity)
+            })
+            .collect();
+        
+        reorder.sort_by_key(|entry| !entry.is_out_of_stock());
+        Ok(reorder)
+    }
+    
+    // Whether the item a tag is attached to needs reordering, e.g. right after a scan issued some
+    pub fn reorder_status(&self, tag_id: &Uid) -> Result<Option<ReorderItem>> {
+        let Some(item) = self.get_item(tag_id)? else {
+            return Ok(None);
+        };
+        let categories = match item.category_id {
+            Some(id) => category_by_id(&self.conn, id)?.into_iter().collect(),
+            None => Vec::new(),
+        };
+        
+        let (min_quantity, reorder_quantity) = stock_levels(&item, &categories);
+        Ok(ReorderItem::check(&item, min_quantity, reorder_quantity))
+    }
+    
+    // Every UID and identifier an item has carried, active ones first
+    pub fn get_item_tags(&self, item_id: i64) -> Result<Vec<ItemTag>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM tags WHERE item_id = ? ORDER BY retired_at IS NOT NULL, retired_at, id",
+            TAG_COLUMNS
+        ))?;
+        
+        let tags = stmt.query_map(params![item_id], tag_from_row)?.collect::<rusqlite::Result<Vec<ItemTag>>>()?;
+        Ok(tags)
+    }
+    
+    // Attach another UID and/or text identifier to an item as one undo step. A UID may already be on
+    // other items, e.g. one tag for a kit and its parts, but not mark a location; an identifier must be free
+    pub fn attach_tag(&self, item_id: i64, uid: Option<&Uid>, identifier: Option<&str>) -> Result<ItemTag> {
+        self.existing_item(item_id)?;
+        let identifier = identifier.map(str::trim).filter(|identifier| !identifier.is_empty());
+        if uid.is_none() && identifier.is_none() {
+            return Err(Error::Tag("give a UID or an identifier to attach".to_string()));
         }
         
-        Ok(categories)
+        let mut tag = None;
+        self.journaled("Attach tag", &[item_id], |conn, item_id, _| {
+            tag = Some(link_tag(conn, item_id, uid, identifier)?);
+            touch_item(conn, item_id)?;
+            Ok(())
+        })?;
+        
+        Ok(tag.expect("the item was found above"))
     }
     
-    // Search inventory by name, description, or location
-    pub fn search_items(&self, query: &str) -> Result<Vec<InventoryItem>> {
-        let search_term = format!("%{}%", query);
+    // Replace the tag an item is found by as one undo step: `old` is retired and `new` becomes the
+    // tag shown for it
+    pub fn retag_item(&self, old: &Uid, new: &Uid) -> Result<InventoryItem> {
+        let item = self
+            .get_item(old)?
+            .ok_or_else(|| Error::Tag(format!("{} isn't attached to any item", old)))?;
+        let item_id = item.id.expect("items read from the database have an ID");
         
-        let mut stmt = self.conn.prepare(
-            "SELECT tag_id, name, description, quantity, location, category, last_updated, created_at 
-             FROM inventory 
-             WHERE name LIKE ? OR description LIKE ? OR location LIKE ? OR category LIKE ?
-             ORDER BY name"
-        )?;
+        self.journaled(&format!("Re-tag {} as {}", old, new), &[item_id], |conn, item_id, _| {
+            let now = generate_timestamp();
+            conn.execute(
+                "UPDATE tags SET retired_at = ? WHERE item_id = ? AND uid = ? AND retired_at IS NULL",
+                params![now, item_id, old],
+            )?;
+            link_tag(conn, item_id, Some(new), None)?;
+            conn.execute("UPDATE inventory SET tag_id = ?, last_updated = ? WHERE id = ?", params![new, now, item_id])?;
+            Ok(())
+        })?;
         
-        let item_iter = stmt.query_map(
-            params![&search_term, &search_term, &search_term, &search_term], 
-            |row| {
-                Ok(InventoryItem {
-                    tag_id: row.get(0)?,
-                    name: row.get(1)?,
-                    description: row.get(2)?,
-                    quantity: row.get(3)?,
-                    location: row.get(4)?,
-                    category: row.get(5)?,
-                    last_updated: row.get(6)?,
-                    created_at: row.get(7)?,
-                })
+        self.existing_item(item_id)
+    }
+    
+    // Retire one tag link as one undo step so it no longer finds the item and can be attached
+    // elsewhere. Retiring the tag shown for an item shows its next active UID instead; the last UID
+    // of an item can't be retired
+    pub fn retire_tag(&self, tag_link_id: i64) -> Result<ItemTag> {
+        let tag = tag_by_id(&self.conn, tag_link_id)?
+            .filter(ItemTag::is_active)
+            .ok_or_else(|| Error::Tag(format!("no active tag with id {}", tag_link_id)))?;
+        let item = self.existing_item(tag.item_id)?;
+        
+        self.journaled(&format!("Retire tag {}", tag), &[tag.item_id], |conn, item_id, before| {
+            if tag.uid.as_ref() == Some(&item.tag_id) {
+                let next = before
+                    .tags
+                    .iter()
+                    .filter(|other| other.is_active() && other.id != tag.id)
+                    .find_map(|other| other.uid.clone())
+                    .ok_or_else(|| {
+                        Error::Tag(format!("{} is the last tag of '{}'; re-tag the item instead", item.tag_id, item.name))
+                    })?;
+                conn.execute("UPDATE inventory SET tag_id = ? WHERE id = ?", params![next, item_id])?;
             }
-        )?;
+            conn.execute("UPDATE tags SET retired_at = ? WHERE id = ?", params![generate_timestamp(), tag.id])?;
+            touch_item(conn, item_id)?;
+            Ok(())
+        })?;
         
-        let mut items = Vec::new();
-        for item in item_iter {
-            items.push(item?);
-        }
+        Ok(tag_by_id(&self.conn, tag.id)?.expect("retired tags are kept"))
+    }
+    
+    fn existing_item(&self, id: i64) -> Result<InventoryItem> {
+        self.get_item_by_id(id)?.ok_or_else(|| Error::Tag(format!("no item with id {}", id)))
+    }
+    
+    // Items in a category or any of its sub-categories
+    pub fn get_items_by_category(&self, category: &str) -> Result<Vec<InventoryItem>> {
+        let items = self
+            .get_all_items()?
+            .into_iter()
+            .filter(|item| item.category.as_deref().is_some_and(|path| path_is_within(path, category)))
+            .collect();
         
         Ok(items)
     }
     
-    // Export inventory as JSON
-    pub fn export_json(&self) -> Result<String> {
-        let items = self.get_all_items()?;
-        let json = serde_json::to_string_pretty(&items)
-            .map_err(|e| rusqlite::Error::InvalidParameterName(e.to_string()))?;
-        
-        Ok(json)
+    // Every category with its full path, sorted by path
+    pub fn get_categories(&self) -> Result<Vec<Category>> {
+        Ok(select_categories(&self.conn, "", [])?)
     }
     
-    // Export inventory as CSV
-    pub fn export_csv(&self) -> Result<String> {
-        let items = self.get_all_items()?;
+    pub fn get_category(&self, id: i64) -> Result<Option<Category>> {
+        Ok(category_by_id(&self.conn, id)?)
+    }
+    
+    // Look a category up by its path, typed with any spacing or case
+    pub fn find_category(&self, path: &str) -> Result<Option<Category>> {
+        Ok(self.get_categories()?.into_iter().find(|category| same_path(&category.path, path)))
+    }
+    
+    // Add a category under `parent_id`, or at the top level
+    pub fn add_category(&self, name: &str, parent_id: Option<i64>) -> Result<Category> {
+        let name = level_name(name).map_err(Error::Category)?;
+        if let Some(parent_id) = parent_id {
+            self.existing_category(parent_id)?;
+        }
+        if let Some(id) = child_id(&self.conn, "categories", parent_id, &name)? {
+            let existing = self.existing_category(id)?;
+            return Err(Error::Category(format!("'{}' already exists", existing.path)));
+        }
         
-        let mut csv = String::from("Tag ID,Name,Description,Quantity,Location,Category,Last Updated,Created At\n");
+        self.conn.execute(
+            "INSERT INTO categories (name, parent_id, created_at) VALUES (?, ?, ?)",
+            params![name, parent_id, generate_timestamp()],
+        )?;
+        self.existing_category(self.conn.last_insert_rowid())
+    }
+    
+    // The category at `path`, adding whichever part of it doesn't exist yet
+    pub fn ensure_category(&self, path: &str) -> Result<Category> {
+        let tx = self.conn.unchecked_transaction()?;
+        let category = ensure_category_path(&tx, path)?
+            .ok_or_else(|| Error::Category(format!("'{}' doesn't name a category", path)))?;
         
-        for item in items {
-            let description = item.description.unwrap_or_default().replace(",", "\\,");
-            let location = item.location.unwrap_or_default().replace(",", "\\,");
-            let category = item.category.unwrap_or_default().replace(",", "\\,");
-            
-            csv.push_str(&format!(
-                "{},{},\"{}\",{},\"{}\",\"{}\",{},{}\n",
-                item.tag_id,
-                item.name.replace(",", "\\,"),
-                description,
-                item.quantity,
-                location,
-                category,
-                item.last_updated,
-                item.created_at
-            ));
+        tx.commit()?;
+        Ok(category)
+    }
+    
+    // Rename a category; the items in it and below it show the new path
+    pub fn rename_category(&self, id: i64, name: &str) -> Result<Category> {
+        let name = level_name(name).map_err(Error::Category)?;
+        let category = self.existing_category(id)?;
+        if child_id(&self.conn, "categories", category.parent_id, &name)?.is_some_and(|other| other != id) {
+            return Err(Error::Category(format!("a category named '{}' is already next to '{}'", name, category.path)));
         }
         
-        Ok(csv)
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute("UPDATE categories SET name = ? WHERE id = ?", params![name, id])?;
+        refresh_item_categories(&tx)?;
+        
+        tx.commit()?;
+        self.existing_category(id)
     }
     
-    // Import inventory from JSON
-    pub fn import_json(&self, json: &str) -> Result<usize> {
-        let items: Vec<InventoryItem> = serde_json::from_str(json)
-            .map_err(|e| rusqlite::Error::InvalidParameterName(e.to_string()))?;
+    // Move a category under `parent_id` (or to the top level). Its items then inherit the new
+    // parent's fields, and drop the values of fields they no longer have
+    pub fn move_category(&self, id: i64, parent_id: Option<i64>) -> Result<Category> {
+        let category = self.existing_category(id)?;
+        let inherited = match parent_id {
+            Some(parent_id) => {
+                let parent = self.existing_category(parent_id)?;
+                if parent.is_within(&category) {
+                    return Err(Error::Category(format!("can't move '{}' into itself", category.path)));
+                }
+                category_fields(&self.conn, parent.id)?
+            }
+            None => Vec::new(),
+        };
+        if child_id(&self.conn, "categories", parent_id, &category.name)?.is_some_and(|other| other != id) {
+            return Err(Error::Category(format!("the new parent already has a category named '{}'", category.name)));
+        }
+        
+        let subtree = self.category_subtree(&category)?;
+        for field in self.own_fields(&subtree)? {
+            if inherited.iter().any(|other| other.name.eq_ignore_ascii_case(&field.name)) {
+                return Err(Error::Category(format!("the new parent already declares a field '{}'", field.name)));
+            }
+        }
         
-        let mut count = 0;
-        for item in items {
-            self.save_item(&item)?;
-            count += 1;
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute("UPDATE categories SET parent_id = ? WHERE id = ?", params![parent_id, id])?;
+        refresh_item_categories(&tx)?;
+        for member in &subtree {
+            prune_attributes(&tx, member.id)?;
         }
         
-        Ok(count)
+        tx.commit()?;
+        self.existing_category(id)
     }
-}
-
-// Add a function to create a thread-safe version of the inventory DB
-pub fn create_thread_safe_db(db: InventoryDB) -> Arc<Mutex<InventoryDB>> {
+    
+    // Delete an unused category and its fields; one that still has items or sub-categories is refused
+    pub fn delete_category(&self, id: i64) -> Result<()> {
+        let category = self.existing_category(id)?;
+        
+        let children: i64 = self.conn.query_row("SELECT COUNT(*) FROM categories WHERE parent_id = ?", params![id], |row| row.get(0))?;
+        if children > 0 {
+            return Err(Error::Category(format!("'{}' still has {} sub-categories", category.path, children)));
+        }
+        let items: i64 = self.conn.query_row("SELECT COUNT(*) FROM inventory WHERE category_id = ?", params![id], |row| row.get(0))?;
+        if items > 0 {
+            return Err(Error::Category(format!("'{}' still has {} item(s)", category.path, items)));
+        }
+        
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute("DELETE FROM category_fields WHERE category_id = ?", params![id])?;
+        tx.execute("DELETE FROM categories WHERE id = ?", params![id])?;
+        
+        tx.commit()?;
+        Ok(())
+    }
+    
+    // Set the display colour of a category ("#RRGGBB"), or inherit the parent's again with `None`
+    pub fn set_category_color(&self, id: i64, color: Option<&str>) -> Result<Category> {
+        let category = self.existing_category(id)?;
+        let color = match color {
+            Some(color) => Some(format_color(
+                parse_color(color).ok_or_else(|| Error::Category(format!("'{}' is not a colour like #RRGGBB", color)))?
+            )),
+            None => None,
+        };
+        
+        self.conn.execute("UPDATE categories SET color = ? WHERE id = ?", params![color, category.id])?;
+        self.existing_category(id)
+    }
+    
+    // Set the stock levels of a category's items that don't set their own; `None` inherits the parent's
+    pub fn set_category_stock_levels(
+        &self,
+        id: i64,
+        min_quantity: Option<i32>,
+        reorder_quantity: Option<i32>
+    ) -> Result<Category> {
+        let category = self.existing_category(id)?;
+        validate_stock_levels(min_quantity, reorder_quantity).map_err(Error::StockLevel)?;
+        
+        self.conn.execute(
+            "UPDATE categories SET min_quantity = ?, reorder_quantity = ? WHERE id = ?",
+            params![min_quantity, reorder_quantity, category.id],
+        )?;
+        self.existing_category(id)
+    }
+    
+    // The custom fields items of a category have: those inherited from its parents first, then its own
+    pub fn get_category_fields(&self, id: i64) -> Result<Vec<CategoryField>> {
+        self.existing_category(id)?;
+        Ok(category_fields(&self.conn, id)?)
+    }
+    
+    // Declare a custom field on a category. A required field only binds items from their next save
+    pub fn add_category_field(&self, id: i64, name: &str, field_type: FieldType, required: bool) -> Result<CategoryField> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(Error::Category("a field needs a name".to_string()));
+        }
+        let category = self.existing_category(id)?;
+        
+        // A name may only mean one thing for any item in the category's subtree
+        let subtree = self.category_subtree(&category)?;
+        let mut taken = category_fields(&self.conn, id)?;
+        taken.extend(self.own_fields(&subtree)?);
+        if let Some(existing) = taken.iter().find(|field| field.name.eq_ignore_ascii_case(name)) {
+            return Err(Error::Category(format!("'{}' already has a field '{}'", category.path, existing.name)));
+        }
+        
+        self.conn.execute(
+            "INSERT INTO category_fields (category_id, name, field_type, required, position)
+             VALUES (?1, ?2, ?3, ?4, (SELECT COALESCE(MAX(position), 0) + 1 FROM category_fields WHERE category_id = ?1))",
+            params![id, name, field_type, required],
+        )?;
+        
+        let field = self.conn.query_row(
+            &format!("SELECT {} FROM category_fields f WHERE f.id = ?", FIELD_COLUMNS),
+            params![self.conn.last_insert_rowid()],
+            field_from_row,
+        )?;
+        Ok(field)
+    }
+    
+    // Remove a field declared by this category, with the values its items had for it
+    pub fn remove_category_field(&self, id: i64, name: &str) -> Result<()> {
+        let category = self.existing_category(id)?;
+        let field = category_fields(&self.conn, id)?
+            .into_iter()
+            .find(|field| field.name.eq_ignore_ascii_case(name.trim()))
+            .ok_or_else(|| Error::Category(format!("'{}' has no field '{}'", category.path, name.trim())))?;
+        if field.category_id != id {
+            let owner = self.existing_category(field.category_id)?;
+            return Err(Error::Category(format!("'{}' is inherited from '{}'", field.name, owner.path)));
+        }
+        
+        let subtree = self.category_subtree(&category)?;
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute("DELETE FROM category_fields WHERE id = ?", params![field.id])?;
+        for member in &subtree {
+            prune_attributes(&tx, member.id)?;
+        }
+        
+        tx.commit()?;
+        Ok(())
+    }
+    
+    // Set one custom field of an item (`None` or an empty value clears it); `false` if the tag is unknown
+    pub fn set_attribute(&self, tag_id: &Uid, name: &str, value: Option<&str>) -> Result<bool> {
+        let Some(mut item) = self.get_item(tag_id)? else {
+            return Ok(false);
+        };
+        
+        item.attributes.retain(|existing, _| !existing.eq_ignore_ascii_case(name.trim()));
+        if let Some(value) = value {
+            item.attributes.insert(name.trim().to_string(), value.to_string());
+        }
+        item.last_updated = generate_timestamp();
+        
+        self.save_item(&item)?;
+        Ok(true)
+    }
+    
+    // Items and stock per category, each rolled up over its sub-categories
+    pub fn category_stats(&self) -> Result<Vec<CategoryStats>> {
+        let categories = self.get_categories()?;
+        let items = self.get_all_items()?;
+        
+        let stats = categories
+            .into_iter()
+            .map(|category| {
+                let inside: Vec<&InventoryItem> = items
+                    .iter()
+                    .filter(|item| {
+                        item.category.as_deref().is_some_and(|path| path_is_within(path, &category.path))
+                    })
+                    .collect();
+                CategoryStats {
+                    items: inside.len(),
+                    quantity: inside.iter().map(|item| item.quantity).sum(),
+                    category,
+                }
+            })
+            .collect();
+        
+        Ok(stats)
+    }
+    
+    fn existing_category(&self, id: i64) -> Result<Category> {
+        category_by_id(&self.conn, id)?.ok_or_else(|| Error::Category(format!("no category with id {}", id)))
+    }
+    
+    // A category and every category below it
+    fn category_subtree(&self, category: &Category) -> Result<Vec<Category>> {
+        Ok(self.get_categories()?.into_iter().filter(|other| other.is_within(category)).collect())
+    }
+    
+    // Fields declared by the given categories themselves, not inherited ones
+    fn own_fields(&self, categories: &[Category]) -> Result<Vec<CategoryField>> {
+        let mut stmt = self.conn.prepare(
+            &format!("SELECT {} FROM category_fields f WHERE f.category_id = ? ORDER BY f.position", FIELD_COLUMNS)
+        )?;
+        
+        let mut fields = Vec::new();
+        for category in categories {
+            fields.extend(stmt.query_map(params![category.id], field_from_row)?.collect::<rusqlite::Result<Vec<_>>>()?);
+        }
+        Ok(fields)
+    }
+    
+    // Every location with its full path, sorted by path
+    pub fn get_locations(&self) -> Result<Vec<Location>> {
+        Ok(select_locations(&self.conn, "", [])?)
+    }
+    
+    pub fn get_location(&self, id: i64) -> Result<Option<Location>> {
+        Ok(location_by_id(&self.conn, id)?)
+    }
+    
+    // Look a location up by its path, typed with any spacing or case
+    pub fn find_location(&self, path: &str) -> Result<Option<Location>> {
+        Ok(self.get_locations()?.into_iter().find(|location| same_path(&location.path, path)))
+    }
+    
+    // The location a scanned tag marks, if any
+    pub fn get_location_by_tag(&self, tag_id: &Uid) -> Result<Option<Location>> {
+        Ok(select_locations(&self.conn, "WHERE l.tag_id = ?", params![tag_id])?.pop())
+    }
+    
+    // Add a location under `parent_id`, or at the top level
+    pub fn add_location(&self, name: &str, parent_id: Option<i64>) -> Result<Location> {
+        let name = level_name(name).map_err(Error::Location)?;
+        if let Some(parent_id) = parent_id {
+            self.existing_location(parent_id)?;
+        }
+        if let Some(id) = child_id(&self.conn, "locations", parent_id, &name)? {
+            let existing = self.existing_location(id)?;
+            return Err(Error::Location(format!("'{}' already exists", existing.path)));
+        }
+        
+        self.conn.execute(
+            "INSERT INTO locations (name, parent_id, created_at) VALUES (?, ?, ?)",
+            params![name, parent_id, generate_timestamp()],
+        )?;
+        self.existing_location(self.conn.last_insert_rowid())
+    }
+    
+    // The location at `path`, adding whichever part of it doesn't exist yet
+    pub fn ensure_location(&self, path: &str) -> Result<Location> {
+        let tx = self.conn.unchecked_transaction()?;
+        let location = ensure_location_path(&tx, path)?
+            .ok_or_else(|| Error::Location(format!("'{}' doesn't name a location", path)))?;
+        
+        tx.commit()?;
+        Ok(location)
+    }
+    
+    // Rename a location; the items in it and below it show the new path
+    pub fn rename_location(&self, id: i64, name: &str) -> Result<Location> {
+        let name = level_name(name).map_err(Error::Location)?;
+        let location = self.existing_location(id)?;
+        if child_id(&self.conn, "locations", location.parent_id, &name)?.is_some_and(|other| other != id) {
+            return Err(Error::Location(format!("a location named '{}' is already next to '{}'", name, location.path)));
+        }
+        
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute("UPDATE locations SET name = ? WHERE id = ?", params![name, id])?;
+        refresh_item_locations(&tx)?;
+        
+        tx.commit()?;
+        self.existing_location(id)
+    }
+    
+    // Move a location, with everything in it, under `parent_id` (or to the top level)
+    pub fn move_location(&self, id: i64, parent_id: Option<i64>) -> Result<Location> {
+        let location = self.existing_location(id)?;
+        if let Some(parent_id) = parent_id {
+            let parent = self.existing_location(parent_id)?;
+            if parent.is_within(&location) {
+                return Err(Error::Location(format!("can't move '{}' into itself", location.path)));
+            }
+        }
+        if child_id(&self.conn, "locations", parent_id, &location.name)?.is_some_and(|other| other != id) {
+            return Err(Error::Location(format!("the new place already has a location named '{}'", location.name)));
+        }
+        
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute("UPDATE locations SET parent_id = ? WHERE id = ?", params![parent_id, id])?;
+        refresh_item_locations(&tx)?;
+        
+        tx.commit()?;
+        self.existing_location(id)
+    }
+    
+    // Delete an empty location; one that still holds items or sub-locations is refused
+    pub fn delete_location(&self, id: i64) -> Result<()> {
+        let location = self.existing_location(id)?;
+        
+        let children: i64 = self.conn.query_row("SELECT COUNT(*) FROM locations WHERE parent_id = ?", params![id], |row| row.get(0))?;
+        if children > 0 {
+            return Err(Error::Location(format!("'{}' still has {} sub-location(s)", location.path, children)));
+        }
+        let items: i64 = self.conn.query_row("SELECT COUNT(*) FROM inventory WHERE location_id = ?", params![id], |row| row.get(0))?;
+        if items > 0 {
+            return Err(Error::Location(format!("'{}' still holds {} item(s)", location.path, items)));
+        }
+        
+        self.conn.execute("DELETE FROM locations WHERE id = ?", params![id])?;
+        Ok(())
+    }
+    
+    // Mark a location with an NFC tag, or remove its tag with `None`
+    pub fn set_location_tag(&self, id: i64, tag_id: Option<&Uid>) -> Result<Location> {
+        let location = self.existing_location(id)?;
+        
+        if let Some(tag_id) = tag_id {
+            if let Some(item) = self.get_item(tag_id)? {
+                return Err(Error::Location(format!("tag {} already belongs to the item '{}'", tag_id, item.name)));
+            }
+            if let Some(other) = self.get_location_by_tag(tag_id)?.filter(|other| other.id != id) {
+                return Err(Error::Location(format!("tag {} already marks '{}'", tag_id, other.path)));
+            }
+        }
+        
+        self.conn.execute("UPDATE locations SET tag_id = ? WHERE id = ?", params![tag_id, location.id])?;
+        self.existing_location(id)
+    }
+    
+    // Move an item to a location (`None` clears it); quantities don't change
+    pub fn move_item(&self, tag_id: &Uid, location_id: Option<i64>) -> Result<bool> {
+        Ok(self.move_items(std::slice::from_ref(tag_id), location_id)? > 0)
+    }
+    
+    // Move several items at once, as one undo step; returns how many were found
+    pub fn move_items(&self, tag_ids: &[Uid], location_id: Option<i64>) -> Result<usize> {
+        let mut item_ids = Vec::new();
+        for tag_id in tag_ids {
+            item_ids.extend(item_id_for_tag(&self.conn, tag_id)?);
+        }
+        
+        self.bulk_set_location(&item_ids, location_id)
+    }
+    
+    // Items kept in a location, optionally with those in its sub-locations
+    pub fn get_items_in_location(&self, id: i64, include_sublocations: bool) -> Result<Vec<InventoryItem>> {
+        let location = self.existing_location(id)?;
+        let items = self
+            .get_all_items()?
+            .into_iter()
+            .filter(|item| match item.location_id {
+                Some(item_location) if item_location == id => true,
+                Some(_) if include_sublocations => {
+                    item.location.as_deref().is_some_and(|path| path_is_within(path, &location.path))
+                }
+                _ => false,
+            })
+            .collect();
+        
+        Ok(items)
+    }
+    
+    // Items and stock per location, each rolled up over its sub-locations
+    pub fn location_stats(&self) -> Result<Vec<LocationStats>> {
+        let locations = self.get_locations()?;
+        let items = self.get_all_items()?;
+        
+        let stats = locations
+            .into_iter()
+            .map(|location| {
+                let inside: Vec<&InventoryItem> = items
+                    .iter()
+                    .filter(|item| {
+                        item.location.as_deref().is_some_and(|path| path_is_within(path, &location.path))
+                    })
+                    .collect();
+                LocationStats {
+                    items: inside.len(),
+                    quantity: inside.iter().map(|item| item.quantity).sum(),
+                    location,
+                }
+            })
+            .collect();
+        
+        Ok(stats)
+    }
+    
+    fn existing_location(&self, id: i64) -> Result<Location> {
+        location_by_id(&self.conn, id)?.ok_or_else(|| Error::Location(format!("no location with id {}", id)))
+    }
+    
+    // Search the full-text index, best matches first; see `SearchQuery::parse` for the syntax
+    pub fn search_items(&self, query: &SearchQuery) -> Result<Vec<InventoryItem>> {
+        let mut conditions: Vec<String> = Vec::new();
+        let mut values: Vec<Value> = Vec::new();
+        
+        if let Some((condition, quantities)) = query.quantity_condition() {
+            conditions.push(condition);
+            values.extend(quantities.into_iter().map(|quantity| Value::Integer(quantity.into())));
+        }
+        let filter = if conditions.is_empty() { String::new() } else { format!("WHERE {}", conditions.join(" AND ")) };
+        
+        // bm25 weights follow the index columns: tag_id, name, description, category, location, tags
+        let sql = match query.match_expression() {
+            Some(expression) => {
+                values.insert(0, Value::Text(expression));
+                format!(
+                    "SELECT {} FROM inventory
+                     JOIN (SELECT rowid AS fts_id, bm25(inventory_fts, 10.0, 5.0, 1.0, 2.0, 2.0, 10.0) AS score
+                           FROM inventory_fts WHERE inventory_fts MATCH ?) ON fts_id = inventory.id
+                     {}
+                     ORDER BY score, name",
+                    ITEM_COLUMNS, filter
+                )
+            }
+            None => format!("SELECT {} FROM inventory {} ORDER BY name", ITEM_COLUMNS, filter),
+        };
+        
+        let mut stmt = self.conn.prepare(&sql)?;
+        let mut items = stmt
+            .query_map(params_from_iter(values), item_from_row)?
+            .collect::<rusqlite::Result<Vec<InventoryItem>>>()?;
+        
+        attach_attributes(&self.conn, &mut items)?;
+        Ok(items)
+    }
+    
+    // The items a view shows, in its order: the search (or everything), then the filters and the sort
+    pub fn get_view_items(&self, view: &InventoryView) -> Result<Vec<InventoryItem>> {
+        let query = SearchQuery::parse(&view.search)?;
+        let mut items = if query.is_empty() { self.get_all_items()? } else { self.search_items(&query)? };
+        
+        if !view.filter.is_empty() {
+            let categories = if view.filter.low_stock { self.get_categories()? } else { Vec::new() };
+            items.retain(|item| view.filter.matches(item, stock_levels(item, &categories).0));
+        }
+        sort_items(&mut items, &view.sort);
+        
+        Ok(items)
+    }
+    
+    // Export inventory as JSON
+    pub fn export_json(&self) -> Result<String> {
+        let items = self.get_all_items()?;
+        let json = serde_json::to_string_pretty(&items)
+            .map_err(|e| Error::parse("Failed to serialize inventory", e))?;
+        
+        Ok(json)
+    }
+    
+    // Export inventory as CSV
+    pub fn export_csv(&self) -> Result<String> {
+        let items = self.get_all_items()?;
+        Ok(items_to_csv(&items))
+    }
+    
+    // Import inventory from JSON
+    pub fn import_json(&self, json: &str) -> Result<usize> {
+        self.import_json_with_reason(json, MovementReason::Import)
+    }
+    
+    // Import inventory from JSON in one transaction, booking quantity changes with `reason` (import
+    // or sync); the whole import is one undo step
+    pub fn import_json_with_reason(&self, json: &str, reason: MovementReason) -> Result<usize> {
+        let items: Vec<InventoryItem> = serde_json::from_str(json)
+            .map_err(|e| Error::parse("Invalid inventory JSON", e))?;
+        
+        let tx = self.conn.unchecked_transaction()?;
+        let mut changes = Vec::new();
+        for item in &items {
+            changes.push(self.store_item(&tx, item, reason)?);
+        }
+        if !changes.is_empty() {
+            record_step(&tx, &describe_step(reason.name(), &changes), &changes)?;
+        }
+        
+        tx.commit()?;
+        Ok(changes.len())
+    }
+    
+    // Set the category of several items (`None` clears it) as one undo step; returns how many were
+    // found. Values for fields the new category doesn't declare are dropped, required ones bind the
+    // items from their next save
+    pub fn bulk_set_category(&self, item_ids: &[i64], category_id: Option<i64>) -> Result<usize> {
+        let category = match category_id {
+            Some(id) => Some(self.existing_category(id)?),
+            None => None,
+        };
+        let fields: Vec<String> = match &category {
+            Some(category) => category_fields(&self.conn, category.id)?.into_iter().map(|field| field.name).collect(),
+            None => Vec::new(),
+        };
+        let action = match &category {
+            Some(category) => format!("Set category to '{}'", category.path),
+            None => "Clear category".to_string(),
+        };
+        
+        self.journaled(&action, item_ids, |conn, item_id, before| {
+            conn.execute(
+                "UPDATE inventory SET category = ?, category_id = ?, last_updated = ? WHERE id = ?",
+                params![
+                    category.as_ref().map(|category| &category.path),
+                    category.as_ref().map(|category| category.id),
+                    generate_timestamp(),
+                    item_id
+                ],
+            )?;
+            for name in before.item.attributes.keys().filter(|name| !fields.contains(name)) {
+                conn.execute("DELETE FROM item_attributes WHERE item_id = ? AND name = ?", params![item_id, name])?;
+            }
+            Ok(())
+        })
+    }
+    
+    // Move several items to a location (`None` clears it) as one undo step; returns how many were found
+    pub fn bulk_set_location(&self, item_ids: &[i64], location_id: Option<i64>) -> Result<usize> {
+        let location = match location_id {
+            Some(id) => Some(self.existing_location(id)?),
+            None => None,
+        };
+        let action = match &location {
+            Some(location) => format!("Move to '{}'", location.path),
+            None => "Clear location".to_string(),
+        };
+        
+        self.journaled(&action, item_ids, |conn, item_id, _| {
+            set_item_location(conn, item_id, location.as_ref())?;
+            Ok(())
+        })
+    }
+    
+    // Add `delta` (negative to take away) to the quantity of several items as one undo step, booked
+    // as adjustments; returns how many were found
+    pub fn bulk_adjust_quantity(&self, item_ids: &[i64], delta: i32) -> Result<usize> {
+        self.journaled(&format!("Adjust quantity by {:+}", delta), item_ids, |conn, item_id, before| {
+            conn.execute(
+                "UPDATE inventory SET quantity = quantity + ?, last_updated = ? WHERE id = ?",
+                params![delta, generate_timestamp(), item_id],
+            )?;
+            if delta != 0 {
+                self.insert_movement(conn, item_id, &before.item.tag_id, delta, MovementReason::Adjustment, None)?;
+            }
+            Ok(())
+        })
+    }
+    
+    // Delete several items as one undo step; returns how many were found
+    pub fn bulk_delete(&self, item_ids: &[i64]) -> Result<usize> {
+        self.journaled("Delete", item_ids, |conn, item_id, before| {
+            self.remove_item(conn, item_id, &before.item.tag_id, "Item deleted")
+        })
+    }
+    
+    // Run `change` on each item in one transaction and journal the lot as one undo step; ids of
+    // items that don't exist are skipped. Returns how many items were changed
+    fn journaled<F>(&self, action: &str, item_ids: &[i64], mut change: F) -> Result<usize>
+    where
+        F: FnMut(&Connection, i64, &ItemState) -> Result<()>,
+    {
+        let tx = self.conn.unchecked_transaction()?;
+        let mut changes: Vec<ItemChange> = Vec::new();
+        for &item_id in item_ids {
+            if changes.iter().any(|change| change.item_id == item_id) {
+                continue;
+            }
+            let Some(before) = item_state(&tx, item_id)? else {
+                continue;
+            };
+            
+            change(&tx, item_id, &before)?;
+            let after = item_state(&tx, item_id)?;
+            changes.push(ItemChange { item_id, before: Some(before), after });
+        }
+        
+        if !changes.is_empty() {
+            record_step(&tx, &describe_step(action, &changes), &changes)?;
+        }
+        tx.commit()?;
+        Ok(changes.len())
+    }
+    
+    // The step `undo` would undo, if any
+    pub fn next_undo(&self) -> Result<Option<UndoStep>> {
+        Ok(select_step(&self.conn, NEXT_UNDO)?)
+    }
+    
+    // The step `redo` would apply again, if any
+    pub fn next_redo(&self) -> Result<Option<UndoStep>> {
+        Ok(select_step(&self.conn, NEXT_REDO)?)
+    }
+    
+    // Put the items of the latest step back the way they were before it; `None` if there is
+    // nothing to undo. Refused when one of them has changed since, so that change isn't lost
+    pub fn undo(&self) -> Result<Option<UndoStep>> {
+        let tx = self.conn.unchecked_transaction()?;
+        let Some(mut step) = select_step(&tx, NEXT_UNDO)? else {
+            return Ok(None);
+        };
+        
+        let note = format!("Undo: {}", step.description);
+        for change in step.changes.iter().rev() {
+            let current = item_state(&tx, change.item_id)?;
+            if changed_since(current.as_ref(), change.after.as_ref()) {
+                return Err(Error::Undo(format!(
+                    "'{}' has changed since \"{}\"; undoing it would overwrite that",
+                    change.name(),
+                    step.description
+                )));
+            }
+            self.restore_item(&tx, change.item_id, current.as_ref(), change.before.as_ref(), &note)?;
+        }
+        
+        let undone_at = generate_timestamp();
+        tx.execute("UPDATE undo_steps SET undone_at = ? WHERE id = ?", params![undone_at, step.id])?;
+        tx.commit()?;
+        
+        step.undone_at = Some(undone_at);
+        Ok(Some(step))
+    }
+    
+    // Apply the step undone last again; `None` if there is nothing to redo
+    pub fn redo(&self) -> Result<Option<UndoStep>> {
+        let tx = self.conn.unchecked_transaction()?;
+        let Some(mut step) = select_step(&tx, NEXT_REDO)? else {
+            return Ok(None);
+        };
+        
+        let note = format!("Redo: {}", step.description);
+        for change in &step.changes {
+            let current = item_state(&tx, change.item_id)?;
+            if changed_since(current.as_ref(), change.before.as_ref()) {
+                return Err(Error::Undo(format!(
+                    "'{}' has changed since \"{}\" was undone; redoing it would overwrite that",
+                    change.name(),
+                    step.description
+                )));
+            }
+            self.restore_item(&tx, change.item_id, current.as_ref(), change.after.as_ref(), &note)?;
+        }
+        
+        tx.execute("UPDATE undo_steps SET undone_at = NULL WHERE id = ?", params![step.id])?;
+        tx.commit()?;
+        
+        step.undone_at = None;
+        Ok(Some(step))
+    }
+    
+    // Deleted items, most recently deleted first
+    pub fn get_trash(&self) -> Result<Vec<TrashedItem>> {
+        let mut stmt = self.conn.prepare(
+            &format!("SELECT {} FROM trash ORDER BY deleted_at DESC, item_id DESC", TRASH_COLUMNS)
+        )?;
+        
+        let trash = stmt
+            .query_map([], trashed_from_row)?
+            .collect::<rusqlite::Result<Vec<TrashedItem>>>()?;
+        
+        Ok(trash)
+    }
+    
+    // Bring items back from the trash with their old ID, field values, tags and stock, as one undo
+    // step; returns how many were restored. Refused if one of their tags was given to another item
+    pub fn restore_items(&self, item_ids: &[i64]) -> Result<usize> {
+        let tx = self.conn.unchecked_transaction()?;
+        let mut changes: Vec<ItemChange> = Vec::new();
+        for &item_id in item_ids {
+            if changes.iter().any(|change| change.item_id == item_id) {
+                continue;
+            }
+            let trashed = tx
+                .query_row(&format!("SELECT {} FROM trash WHERE item_id = ?", TRASH_COLUMNS), params![item_id], trashed_from_row)
+                .optional()?;
+            let Some(trashed) = trashed else {
+                continue;
+            };
+            
+            self.restore_item(&tx, item_id, None, Some(&trashed.state), "Restored from trash")?;
+            changes.push(ItemChange { item_id, before: None, after: item_state(&tx, item_id)? });
+        }
+        
+        if !changes.is_empty() {
+            record_step(&tx, &describe_step("Restore", &changes), &changes)?;
+        }
+        tx.commit()?;
+        Ok(changes.len())
+    }
+    
+    // Remove items deleted more than `days` ago from the trash for good (0 empties it); returns how
+    // many were removed. Undo steps up to the last one that touched them go too, so undo can't bring
+    // them back either
+    pub fn purge_trash(&self, days: u32) -> Result<usize> {
+        let tx = self.conn.unchecked_transaction()?;
+        let cutoff = timestamp_days_ago(days);
+        let purged = {
+            let mut stmt = tx.prepare("SELECT item_id FROM trash WHERE deleted_at <= ?")?;
+            let purged = stmt.query_map(params![cutoff], |row| row.get(0))?.collect::<rusqlite::Result<Vec<i64>>>()?;
+            purged
+        };
+        if purged.is_empty() {
+            return Ok(0);
+        }
+        tx.execute("DELETE FROM trash WHERE deleted_at <= ?", params![cutoff])?;
+        
+        let last_step = {
+            let mut stmt = tx.prepare(&format!("SELECT {} FROM undo_steps ORDER BY id", STEP_COLUMNS))?;
+            let steps = stmt.query_map([], step_from_row)?.collect::<rusqlite::Result<Vec<UndoStep>>>()?;
+            steps
+                .iter()
+                .filter(|step| step.changes.iter().any(|change| purged.contains(&change.item_id)))
+                .map(|step| step.id)
+                .max()
+        };
+        if let Some(last_step) = last_step {
+            tx.execute("DELETE FROM undo_steps WHERE id <= ?", params![last_step])?;
+        }
+        
+        tx.commit()?;
+        Ok(purged.len())
+    }
+    
+    // Bring an item to `target` (`None` removes it) inside the caller's transaction. The quantity
+    // difference is booked with `note` so the movement ledger keeps adding up
+    fn restore_item(
+        &self,
+        conn: &Connection,
+        item_id: i64,
+        current: Option<&ItemState>,
+        target: Option<&ItemState>,
+        note: &str
+    ) -> Result<()> {
+        let Some(target) = target else {
+            if let Some(current) = current {
+                self.remove_item(conn, item_id, &current.item.tag_id, note)?;
+            }
+            return Ok(());
+        };
+        let item = &target.item;
+        
+        // Tags the change freed may have gone to another item since
+        let shown_by: Option<String> = conn
+            .query_row("SELECT name FROM inventory WHERE tag_id = ? AND id != ?", params![item.tag_id, item_id], |row| row.get(0))
+            .optional()?;
+        if let Some(name) = shown_by {
+            return Err(Error::Undo(format!("{} is now the tag of '{}'", item.tag_id, name)));
+        }
+        // UIDs can be shared, identifiers can't
+        for tag in target.tags.iter().filter(|tag| tag.is_active() && tag.identifier.is_some()) {
+            let owner: Option<String> = conn
+                .query_row(
+                    "SELECT i.name FROM tags t JOIN inventory i ON i.id = t.item_id
+                     WHERE t.item_id != ?1 AND t.retired_at IS NULL AND t.identifier = ?2 COLLATE NOCASE",
+                    params![item_id, tag.identifier],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            if let Some(name) = owner {
+                return Err(Error::Undo(format!("{} is now attached to '{}'", tag, name)));
+            }
+        }
+        
+        // Locations and categories are found by id even if renamed; deleted ones are added again
+        let location = match target.location_id.map(|id| location_by_id(conn, id)).transpose()?.flatten() {
+            Some(location) => Some(location),
+            None => match item.location.as_deref() {
+                Some(path) => ensure_location_path(conn, path)?,
+                None => None,
+            },
+        };
+        let category = match target.category_id.map(|id| category_by_id(conn, id)).transpose()?.flatten() {
+            Some(category) => Some(category),
+            None => match item.category.as_deref() {
+                Some(path) => ensure_category_path(conn, path)?,
+                None => None,
+            },
+        };
+        
+        let sql = if current.is_some() {
+            "UPDATE inventory SET
+                tag_id = ?2, name = ?3, description = ?4, quantity = ?5, location = ?6, category = ?7,
+                last_updated = ?8, created_at = ?9, card_family = ?10, location_id = ?11, category_id = ?12,
+                min_quantity = ?13, reorder_quantity = ?14
+            WHERE id = ?1"
+        } else {
+            "INSERT INTO inventory (
+                id, tag_id, name, description, quantity, location, category, last_updated, created_at, card_family,
+                location_id, category_id, min_quantity, reorder_quantity
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)"
+        };
+        conn.execute(
+            sql,
+            params![
+                item_id,
+                item.tag_id,
+                item.name,
+                item.description,
+                item.quantity,
+                location.as_ref().map(|location| &location.path),
+                category.as_ref().map(|category| &category.path),
+                item.last_updated,
+                item.created_at,
+                item.card_family,
+                location.as_ref().map(|location| location.id),
+                category.as_ref().map(|category| category.id),
+                item.min_quantity,
+                item.reorder_quantity
+            ],
+        )?;
+        
+        // An item brought back is out of the trash
+        if current.is_none() {
+            conn.execute("DELETE FROM trash WHERE item_id = ?", params![item_id])?;
+        }
+        
+        conn.execute("DELETE FROM item_attributes WHERE item_id = ?", params![item_id])?;
+        for (name, value) in &item.attributes {
+            conn.execute(
+                "INSERT INTO item_attributes (item_id, name, value) VALUES (?, ?, ?)",
+                params![item_id, name, value],
+            )?;
+        }
+        
+        // Tags and loans are only rewritten when they differ, most changes don't touch them
+        if !current.is_some_and(|current| same_tags(&current.tags, &target.tags)) {
+            conn.execute("DELETE FROM tags WHERE item_id = ?", params![item_id])?;
+            for tag in &target.tags {
+                conn.execute(
+                    "INSERT INTO tags (item_id, uid, identifier, attached_at, retired_at) VALUES (?, ?, ?, ?, ?)",
+                    params![item_id, tag.uid, tag.identifier, tag.attached_at, tag.retired_at],
+                )?;
+            }
+        }
+        if !current.is_some_and(|current| current.loans == target.loans) {
+            conn.execute("DELETE FROM loans WHERE item_id = ?", params![item_id])?;
+            for loan in &target.loans {
+                conn.execute(
+                    "INSERT INTO loans (id, item_id, tag_id, borrower, checked_out_at, due_date, returned_at)
+                     VALUES (?, ?, ?, ?, ?, ?, ?)",
+                    params![loan.id, item_id, loan.tag_id, loan.borrower, loan.checked_out_at, loan.due_date, loan.returned_at],
+                )?;
+            }
+        }
+        
+        let delta = item.quantity - current.map(|current| current.item.quantity).unwrap_or(0);
+        if delta != 0 {
+            self.insert_movement(conn, item_id, &item.tag_id, delta, MovementReason::Adjustment, Some(note))?;
+        }
+        Ok(())
+    }
+}
+
+// Open loan of the item a tag is attached to, `None` if it isn't checked out
+fn open_loan(conn: &Connection, tag_id: &Uid) -> Result<Option<Loan>> {
+    let Some(item_id) = item_id_for_tag(conn, tag_id)? else {
+        return Ok(None);
+    };
+    let loan = conn
+        .query_row(
+            &format!("SELECT {} FROM loans WHERE item_id = ? AND returned_at IS NULL", LOAN_COLUMNS),
+            params![item_id],
+            loan_from_row,
+        )
+        .optional()?;
+    Ok(loan)
+}
+
+// An item with its field values, tags and loans as the undo journal keeps it, `None` if it doesn't exist
+fn item_state(conn: &Connection, item_id: i64) -> rusqlite::Result<Option<ItemState>> {
+    let item = conn
+        .query_row(&format!("SELECT {} FROM inventory WHERE id = ?", ITEM_COLUMNS), params![item_id], item_from_row)
+        .optional()?;
+    let Some(mut item) = item else {
+        return Ok(None);
+    };
+    attach_attributes(conn, std::slice::from_mut(&mut item))?;
+    
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM tags WHERE item_id = ? ORDER BY id", TAG_COLUMNS))?;
+    let tags = stmt.query_map(params![item_id], tag_from_row)?.collect::<rusqlite::Result<Vec<ItemTag>>>()?;
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM loans WHERE item_id = ? ORDER BY id", LOAN_COLUMNS))?;
+    let loans = stmt.query_map(params![item_id], loan_from_row)?.collect::<rusqlite::Result<Vec<Loan>>>()?;
+    
+    Ok(Some(ItemState { location_id: item.location_id, category_id: item.category_id, item, tags, loans }))
+}
+
+// The step undo takes back next: the latest one still in effect
+const NEXT_UNDO: &str = "WHERE undone_at IS NULL ORDER BY id DESC LIMIT 1";
+// The step redo applies next: the undone one that came first
+const NEXT_REDO: &str = "WHERE undone_at IS NOT NULL ORDER BY id LIMIT 1";
+
+fn select_step(conn: &Connection, condition: &str) -> rusqlite::Result<Option<UndoStep>> {
+    conn.query_row(&format!("SELECT {} FROM undo_steps {}", STEP_COLUMNS, condition), [], step_from_row)
+        .optional()
+}
+
+// Journal a change as the newest undo step; steps that were undone can't be redone after it,
+// and the oldest ones go once the journal is full
+fn record_step(conn: &Connection, description: &str, changes: &[ItemChange]) -> Result<()> {
+    let changes = serde_json::to_string(changes).map_err(|e| Error::parse("Failed to record undo step", e))?;
+    
+    conn.execute("DELETE FROM undo_steps WHERE undone_at IS NOT NULL", [])?;
+    conn.execute(
+        "INSERT INTO undo_steps (description, changes, created_at) VALUES (?, ?, ?)",
+        params![description, changes, generate_timestamp()],
+    )?;
+    conn.execute(
+        "DELETE FROM undo_steps WHERE id NOT IN (SELECT id FROM undo_steps ORDER BY id DESC LIMIT ?)",
+        params![UNDO_LIMIT as i64],
+    )?;
+    Ok(())
+}
+
+// Quantity currently stored for an item, `None` if it isn't in the inventory
+fn current_quantity(conn: &Connection, item_id: i64) -> rusqlite::Result<Option<i32>> {
+    conn.query_row("SELECT quantity FROM inventory WHERE id = ?", params![item_id], |row| row.get(0))
+        .optional()
+}
+
+// Item an active UID is attached to, `None` if it isn't attached to any. A UID several items share
+// means the item it's shown for; if it isn't shown for any of them it can't say which
+fn item_id_for_tag(conn: &Connection, tag_id: &Uid) -> Result<Option<i64>> {
+    let mut stmt = conn.prepare(
+        "SELECT t.item_id, i.tag_id = ?1 FROM tags t JOIN inventory i ON i.id = t.item_id
+         WHERE t.uid = ?1 AND t.retired_at IS NULL
+         ORDER BY i.tag_id = ?1 DESC",
+    )?;
+    let items = stmt
+        .query_map(params![tag_id], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, bool>(1)?)))?
+        .collect::<rusqlite::Result<Vec<(i64, bool)>>>()?;
+    
+    match items.as_slice() {
+        [] => Ok(None),
+        [(item_id, _)] | [(item_id, true), ..] => Ok(Some(*item_id)),
+        _ => Err(Error::Tag(format!(
+            "{} is attached to {} items and shown for none of them; use the tag shown for one",
+            tag_id,
+            items.len()
+        ))),
+    }
+}
+
+// Mark an item as changed now inside the caller's transaction, e.g. when its tags change
+fn touch_item(conn: &Connection, item_id: i64) -> rusqlite::Result<()> {
+    conn.execute("UPDATE inventory SET last_updated = ? WHERE id = ?", params![generate_timestamp(), item_id])?;
+    Ok(())
+}
+
+fn tag_by_id(conn: &Connection, id: i64) -> rusqlite::Result<Option<ItemTag>> {
+    conn.query_row(&format!("SELECT {} FROM tags WHERE id = ?", TAG_COLUMNS), params![id], tag_from_row)
+        .optional()
+}
+
+// Link a UID and/or identifier to an item inside the caller's transaction, refusing an identifier that's
+// already in use; a UID only has to be new to this item
+fn link_tag(conn: &Connection, item_id: i64, uid: Option<&Uid>, identifier: Option<&str>) -> Result<ItemTag> {
+    if let Some(uid) = uid {
+        if let Some(location) = select_locations(conn, "WHERE l.tag_id = ?", params![uid])?.pop() {
+            return Err(Error::Tag(format!("{} already marks the location '{}'", uid, location.path)));
+        }
+        let attached: bool = conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM tags WHERE item_id = ? AND uid = ? AND retired_at IS NULL)",
+            params![item_id, uid],
+            |row| row.get(0),
+        )?;
+        if attached {
+            return Err(Error::Tag(format!("{} is already attached to this item", uid)));
+        }
+    }
+    if let Some(identifier) = identifier {
+        let owner: Option<String> = conn
+            .query_row(
+                "SELECT i.name FROM tags t JOIN inventory i ON i.id = t.item_id
+                 WHERE t.identifier = ? COLLATE NOCASE AND t.retired_at IS NULL",
+                params![identifier],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if let Some(name) = owner {
+            return Err(Error::Tag(format!("\"{}\" is already attached to '{}'", identifier, name)));
+        }
+    }
+    
+    conn.execute(
+        "INSERT INTO tags (item_id, uid, identifier, attached_at) VALUES (?, ?, ?, ?)",
+        params![item_id, uid, identifier, generate_timestamp()],
+    )?;
+    Ok(tag_by_id(conn, conn.last_insert_rowid())?.expect("tag link was just inserted"))
+}
+
+fn select_locations<P: Params>(conn: &Connection, condition: &str, params: P) -> rusqlite::Result<Vec<Location>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} SELECT {} FROM locations l JOIN tree ON tree.id = l.id {} ORDER BY tree.path COLLATE NOCASE",
+        location_tree(),
+        LOCATION_COLUMNS,
+        condition
+    ))?;
+    
+    let locations = stmt.query_map(params, location_from_row)?.collect::<rusqlite::Result<Vec<Location>>>()?;
+    Ok(locations)
+}
+
+fn location_by_id(conn: &Connection, id: i64) -> rusqlite::Result<Option<Location>> {
+    Ok(select_locations(conn, "WHERE l.id = ?", params![id])?.pop())
+}
+
+// Id of the node called `name` (in any case) directly under `parent_id` in the locations or categories table
+fn child_id(conn: &Connection, table: &str, parent_id: Option<i64>, name: &str) -> rusqlite::Result<Option<i64>> {
+    conn.query_row(
+        &format!("SELECT id FROM {} WHERE COALESCE(parent_id, 0) = COALESCE(?, 0) AND name = ? COLLATE NOCASE", table),
+        params![parent_id, name],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+// A name for one level of a hierarchy; '/' separates levels so it can't be part of a name
+fn level_name(name: &str) -> std::result::Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("a name can't be empty".to_string());
+    }
+    if name.contains('/') {
+        return Err(format!("'{}' can't contain '/'", name));
+    }
+    Ok(name.to_string())
+}
+
+// Whether two paths name the same node, whatever their spacing or case
+fn same_path(a: &str, b: &str) -> bool {
+    let (a, b) = (path_segments(a), path_segments(b));
+    a.len() == b.len() && a.iter().zip(&b).all(|(a, b)| a.eq_ignore_ascii_case(b))
+}
+
+// Find the node at `path` inside the caller's transaction, creating missing levels; `None` for an empty path
+fn ensure_path(conn: &Connection, table: &str, path: &str) -> rusqlite::Result<Option<i64>> {
+    let mut parent_id = None;
+    for name in path_segments(path) {
+        let id = match child_id(conn, table, parent_id, name)? {
+            Some(id) => id,
+            None => {
+                conn.execute(
+                    &format!("INSERT INTO {} (name, parent_id, created_at) VALUES (?, ?, ?)", table),
+                    params![name, parent_id, generate_timestamp()],
+                )?;
+                conn.last_insert_rowid()
+            }
+        };
+        parent_id = Some(id);
+    }
+    
+    Ok(parent_id)
+}
+
+fn ensure_location_path(conn: &Connection, path: &str) -> Result<Option<Location>> {
+    match ensure_path(conn, "locations", path)? {
+        Some(id) => Ok(location_by_id(conn, id)?),
+        None => Ok(None),
+    }
+}
+
+fn ensure_category_path(conn: &Connection, path: &str) -> Result<Option<Category>> {
+    match ensure_path(conn, "categories", path)? {
+        Some(id) => Ok(category_by_id(conn, id)?),
+        None => Ok(None),
+    }
+}
+
+fn select_categories<P: Params>(conn: &Connection, condition: &str, params: P) -> rusqlite::Result<Vec<Category>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} SELECT {} FROM categories c JOIN tree ON tree.id = c.id {} ORDER BY tree.path COLLATE NOCASE",
+        category_tree(),
+        CATEGORY_COLUMNS,
+        condition
+    ))?;
+    
+    let categories = stmt.query_map(params, category_from_row)?.collect::<rusqlite::Result<Vec<Category>>>()?;
+    Ok(categories)
+}
+
+fn category_by_id(conn: &Connection, id: i64) -> rusqlite::Result<Option<Category>> {
+    Ok(select_categories(conn, "WHERE c.id = ?", params![id])?.pop())
+}
+
+// Fields of a category and its parents, the top-level category's first
+fn category_fields(conn: &Connection, category_id: i64) -> rusqlite::Result<Vec<CategoryField>> {
+    let mut stmt = conn.prepare(&format!(
+        "WITH RECURSIVE ancestors (id, depth) AS (
+            SELECT id, 0 FROM categories WHERE id = ?
+            UNION ALL
+            SELECT c.parent_id, ancestors.depth + 1 FROM categories c JOIN ancestors ON c.id = ancestors.id
+            WHERE c.parent_id IS NOT NULL
+        )
+        SELECT {} FROM category_fields f JOIN ancestors ON f.category_id = ancestors.id
+        ORDER BY ancestors.depth DESC, f.position",
+        FIELD_COLUMNS
+    ))?;
+    
+    let fields = stmt.query_map(params![category_id], field_from_row)?.collect::<rusqlite::Result<Vec<CategoryField>>>()?;
+    Ok(fields)
+}
+
+// Check an item's field values against its category: blank values are left out, names take the
+// field's spelling, values must fit the field's type and required fields must be there
+fn checked_attributes(
+    conn: &Connection,
+    category: Option<&Category>,
+    attributes: &BTreeMap<String, String>
+) -> Result<BTreeMap<String, String>> {
+    let fields = match category {
+        Some(category) => category_fields(conn, category.id)?,
+        None => Vec::new(),
+    };
+    
+    let mut checked = BTreeMap::new();
+    for (name, value) in attributes.iter().filter(|(_, value)| !value.trim().is_empty()) {
+        let Some(field) = fields.iter().find(|field| field.name.eq_ignore_ascii_case(name.trim())) else {
+            return Err(Error::Category(match category {
+                Some(category) => format!("'{}' has no field '{}'", category.path, name),
+                None => format!("'{}' can only be set on an item with a category", name),
+            }));
+        };
+        let value = field.field_type.validate(value).map_err(|e| Error::Category(format!("{}: {}", field.name, e)))?;
+        checked.insert(field.name.clone(), value);
+    }
+    
+    if let (Some(category), Some(missing)) = (category, fields.iter().find(|f| f.required && !checked.contains_key(&f.name))) {
+        return Err(Error::Category(format!("'{}' needs a value for '{}'", category.path, missing.name)));
+    }
+    Ok(checked)
+}
+
+// Fill in the custom field values of items read from the inventory table
+fn attach_attributes(conn: &Connection, items: &mut [InventoryItem]) -> rusqlite::Result<()> {
+    let mut stmt = conn.prepare("SELECT name, value FROM item_attributes WHERE item_id = ?")?;
+    for item in items {
+        item.attributes = stmt
+            .query_map(params![item.id], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<BTreeMap<String, String>>>()?;
+    }
+    Ok(())
+}
+
+// Drop values the items of a category keep for fields it no longer has, e.g. after it moved
+fn prune_attributes(conn: &Connection, category_id: i64) -> rusqlite::Result<()> {
+    let names: Vec<String> = category_fields(conn, category_id)?.into_iter().map(|field| field.name).collect();
+    let mut stmt = conn.prepare(
+        "SELECT a.item_id, a.name FROM item_attributes a JOIN inventory i ON i.id = a.item_id WHERE i.category_id = ?"
+    )?;
+    let stale = stmt
+        .query_map(params![category_id], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?
+        .collect::<rusqlite::Result<Vec<(i64, String)>>>()?;
+    
+    for (item_id, name) in stale.into_iter().filter(|(_, name)| !names.contains(name)) {
+        conn.execute("DELETE FROM item_attributes WHERE item_id = ? AND name = ?", params![item_id, name])?;
+    }
+    Ok(())
+}
+
+// The minimum and reorder quantity that apply to an item: its own, or else its category's
+fn stock_levels(item: &InventoryItem, categories: &[Category]) -> (Option<i32>, Option<i32>) {
+    let category = item.category_id.and_then(|id| categories.iter().find(|category| category.id == id));
+    (
+        item.min_quantity.or_else(|| category.and_then(|category| category.effective_min_quantity)),
+        item.reorder_quantity.or_else(|| category.and_then(|category| category.effective_reorder_quantity)),
+    )
+}
+
+// Point an item at a location inside the caller's transaction; returns 0 if the item is unknown
+fn set_item_location(conn: &Connection, item_id: i64, location: Option<&Location>) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE inventory SET location = ?, location_id = ?, last_updated = ? WHERE id = ?",
+        params![location.map(|l| &l.path), location.map(|l| l.id), generate_timestamp(), item_id],
+    )
+}
+
+// Rewrite the stored paths of located items after locations were renamed or moved
+fn refresh_item_locations(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        &format!(
+            "{} UPDATE inventory SET location = (SELECT path FROM tree WHERE tree.id = inventory.location_id)
+             WHERE location_id IS NOT NULL",
+            location_tree()
+        ),
+        [],
+    )?;
+    Ok(())
+}
+
+// Rewrite the stored paths of categorised items after categories were renamed or moved
+fn refresh_item_categories(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        &format!(
+            "{} UPDATE inventory SET category = (SELECT path FROM tree WHERE tree.id = inventory.category_id)
+             WHERE category_id IS NOT NULL",
+            category_tree()
+        ),
+        [],
+    )?;
+    Ok(())
+}
+
+// Render a list of items as CSV, one row per item
+pub fn items_to_csv(items: &[InventoryItem]) -> String {
+    let mut csv = String::from("Tag ID,Name,Description,Quantity,Location,Category,Last Updated,Created At,Card Type\n");
+    
+    for item in items {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{},{},{}\n",
+            csv_field(&item.tag_id),
+            csv_field(&item.name),
+            csv_field(item.description.as_deref().unwrap_or("")),
+            csv_field(item.quantity),
+            csv_field(item.location.as_deref().unwrap_or("")),
+            csv_field(item.category.as_deref().unwrap_or("")),
+            csv_field(&item.last_updated),
+            csv_field(&item.created_at),
+            csv_field(item.card_family.map(|f| f.name()).unwrap_or_default())
+        ));
+    }
+    
+    csv
+}
+
+// Render a reorder list as CSV, one row per item to order
+pub fn reorder_to_csv(reorder: &[ReorderItem]) -> String {
+    let mut csv = String::from("Tag ID,Name,Category,Location,Quantity,Minimum,Order Quantity\n");
+    
+    for entry in reorder {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{}\n",
+            csv_field(&entry.tag_id),
+            csv_field(&entry.name),
+            csv_field(entry.category.as_deref().unwrap_or("")),
+            csv_field(entry.location.as_deref().unwrap_or("")),
+            csv_field(entry.quantity),
+            csv_field(entry.min_quantity),
+            csv_field(entry.order_quantity)
+        ));
+    }
+    
+    csv
+}
+
+// Render a tag's movements as CSV with the running balance after each one
+pub fn movements_to_csv(movements: &[Movement]) -> String {
+    let mut csv = String::from("Tag ID,Timestamp,Delta,Balance,Reason,User,Station,Note\n");
+    
+    let mut balance = 0;
+    for movement in movements {
+        balance += movement.delta;
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{},{}\n",
+            csv_field(&movement.tag_id),
+            csv_field(&movement.timestamp),
+            csv_field(movement.delta),
+            csv_field(balance),
+            csv_field(movement.reason.key()),
+            csv_field(movement.user.as_deref().unwrap_or("")),
+            csv_field(movement.station.as_deref().unwrap_or("")),
+            csv_field(movement.note.as_deref().unwrap_or(""))
+        ));
+    }
+    
+    csv
+}
+
+// Add a function to create a thread-safe version of the inventory DB
+pub fn create_thread_safe_db(db: InventoryDB) -> Arc<Mutex<InventoryDB>> {
     Arc::new(Mutex::new(db))
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    
+    // A fresh directory for one test's database and the backup migrating it leaves
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("mifare-db-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+    
+    #[test]
+    fn legacy_tags_that_arent_uids_are_quarantined() {
+        let dir = test_dir("legacy-tags");
+        let path = dir.join("inventory.db");
+        
+        // Files from before schema versioning, with a 3-byte read like the one in the shipped inventory.db
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE inventory (
+                tag_id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                description TEXT,
+                quantity INTEGER NOT NULL DEFAULT 0,
+                location TEXT,
+                category TEXT,
+                last_updated TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            );
+            INSERT INTO inventory VALUES
+                ('A655A2', 'Rust for Rustaceans', NULL, 2, 'Warehouse 1', NULL, '2025-03-09T19:13:40Z', '2025-03-09T18:45:29Z'),
+                ('04A1B2C3', 'Drill', NULL, 3, NULL, NULL, '2025-03-09T19:13:40Z', '2025-03-09T18:45:29Z');",
+        )
+        .unwrap();
+        drop(conn);
+        
+        let db = InventoryDB::new(path.to_str().unwrap()).unwrap();
+        let report = db.migration().expect("the file was upgraded");
+        assert_eq!(report.quarantined, vec!["A655A2".to_string()]);
+        
+        let items = db.get_all_items().unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].name, "Drill");
+        assert!(db.quantity_mismatches().unwrap().is_empty());
+        
+        let (source, data, reason): (String, String, String) = db
+            .conn
+            .query_row("SELECT source, data, reason FROM quarantine", [], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap();
+        let data: serde_json::Value = serde_json::from_str(&data).unwrap();
+        assert_eq!(source, "inventory");
+        assert_eq!(data["name"], "Rust for Rustaceans");
+        assert_eq!(data["quantity"], 2);
+        assert_eq!(data["tags"][0]["uid"], "A655A2");
+        assert!(reason.contains("3 bytes"), "{}", reason);
+        
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    // A database with one item per tag, as `inventory add` leaves it
+    fn db_with(items: &[(&str, &str, i32)]) -> InventoryDB {
+        let db = InventoryDB::new(":memory:").unwrap();
+        for (tag, name, quantity) in items {
+            let item = crate::inventory::model::create_inventory_item(&uid(tag), name, None, *quantity, None, None);
+            db.save_item(&item).unwrap();
+        }
+        db
+    }
+    
+    fn uid(hex: &str) -> Uid {
+        hex.parse().unwrap()
+    }
+    
+    #[test]
+    fn a_tag_can_be_shared_by_several_items() {
+        use crate::inventory::scan::{apply_scan, apply_scan_to, ScanMode, ScanOptions, ScanOutcome};
+        
+        let db = db_with(&[("04A1B2C3", "Tool kit", 1), ("04A1B2C4", "Drill", 2), ("04A1B2C5", "Saw", 3)]);
+        let drill = db.get_item(&uid("04A1B2C4")).unwrap().unwrap();
+        let saw = db.get_item(&uid("04A1B2C5")).unwrap().unwrap();
+        
+        // The kit's tag goes on its parts too; it stays the tag shown for the kit
+        db.attach_tag(drill.id.unwrap(), Some(&uid("04A1B2C3")), None).unwrap();
+        db.attach_tag(saw.id.unwrap(), Some(&uid("04A1B2C3")), None).unwrap();
+        assert!(db.attach_tag(saw.id.unwrap(), Some(&uid("04A1B2C3")), None).is_err());
+        
+        let names: Vec<String> = db.get_items_for_tag(&uid("04A1B2C3")).unwrap().into_iter().map(|item| item.name).collect();
+        assert_eq!(names, vec!["Tool kit", "Drill", "Saw"]);
+        assert_eq!(db.get_item(&uid("04A1B2C3")).unwrap().unwrap().name, "Tool kit");
+        
+        // A scan asks which item it meant, and changes nothing until told
+        let options = ScanOptions::default();
+        let ScanOutcome::Shared(items) = apply_scan(&db, &uid("04A1B2C3"), ScanMode::Receive, &options).unwrap() else {
+            panic!("a shared tag should ask which item was meant");
+        };
+        assert_eq!(items.len(), 3);
+        let outcome = apply_scan_to(&db, items[1].clone(), ScanMode::Receive, &options).unwrap();
+        assert_eq!(outcome.item().map(|item| (item.name.as_str(), item.quantity)), Some(("Drill", 3)));
+        assert_eq!(db.get_item(&uid("04A1B2C3")).unwrap().unwrap().quantity, 1);
+        
+        // Re-tagging the kit retires the tag on the kit only
+        db.retag_item(&uid("04A1B2C3"), &uid("04A1B2C6")).unwrap();
+        let names: Vec<String> = db.get_items_for_tag(&uid("04A1B2C3")).unwrap().into_iter().map(|item| item.name).collect();
+        assert_eq!(names, vec!["Drill", "Saw"]);
+        
+        // Now it isn't shown for any of them, so a lookup by tag alone can't say which
+        assert!(db.get_item(&uid("04A1B2C3")).is_err());
+        assert_eq!(db.get_item(&uid("04A1B2C4")).unwrap().unwrap().name, "Drill");
+    }
+    
+    #[test]
+    fn deleting_an_item_on_loan_closes_the_loan_until_undone() {
+        let db = db_with(&[("04A1B2C3", "Drill", 2)]);
+        db.check_out(&uid("04A1B2C3"), "Sam", None).unwrap();
+        let item_id = db.get_item(&uid("04A1B2C3")).unwrap().unwrap().id.unwrap();
+        
+        db.delete_item(&uid("04A1B2C3")).unwrap();
+        assert!(db.get_open_loans().unwrap().is_empty());
+        
+        db.undo().unwrap();
+        let loans = db.get_open_loans().unwrap();
+        assert_eq!(loans.iter().map(|loan| loan.borrower.as_str()).collect::<Vec<_>>(), vec!["Sam"]);
+        assert_eq!(db.get_open_loan(&uid("04A1B2C3")).unwrap(), loans.first().cloned());
+        
+        db.redo().unwrap();
+        assert!(db.get_open_loans().unwrap().is_empty());
+        
+        // Restoring from the trash brings the item back, not the loan
+        db.restore_items(&[item_id]).unwrap();
+        assert_eq!(db.get_item(&uid("04A1B2C3")).unwrap().unwrap().quantity, 1);
+        assert_eq!(db.get_open_loan(&uid("04A1B2C3")).unwrap(), None);
+        assert!(db.quantity_mismatches().unwrap().is_empty());
+    }
+    
+    fn quantity(db: &InventoryDB, tag: &str) -> Option<i32> {
+        db.get_item(&uid(tag)).unwrap().map(|item| item.quantity)
+    }
+    
+    fn ids(db: &InventoryDB, tags: &[&str]) -> Vec<i64> {
+        tags.iter().map(|tag| db.get_item(&uid(tag)).unwrap().unwrap().id.unwrap()).collect()
+    }
+    
+    #[test]
+    fn undo_and_redo_a_save() {
+        let db = db_with(&[("04A1B2C3", "Drill", 2)]);
+        let mut item = db.get_item(&uid("04A1B2C3")).unwrap().unwrap();
+        let created_at = item.created_at.clone();
+        item.name = "Hammer drill".to_string();
+        item.description = Some("18V".to_string());
+        db.save_item(&item).unwrap();
+        
+        db.undo().unwrap();
+        let undone = db.get_item(&uid("04A1B2C3")).unwrap().unwrap();
+        assert_eq!((undone.name.as_str(), undone.description), ("Drill", None));
+        
+        db.redo().unwrap();
+        let redone = db.get_item(&uid("04A1B2C3")).unwrap().unwrap();
+        assert_eq!((redone.name.as_str(), redone.description.as_deref()), ("Hammer drill", Some("18V")));
+        assert_eq!(redone.created_at, created_at);
+    }
+    
+    #[test]
+    fn undo_and_redo_a_delete() {
+        let db = db_with(&[("04A1B2C3", "Drill", 2)]);
+        db.delete_item(&uid("04A1B2C3")).unwrap();
+        assert_eq!(quantity(&db, "04A1B2C3"), None);
+        assert_eq!(db.get_trash().unwrap().len(), 1);
+        
+        let step = db.undo().unwrap().expect("the delete is undoable");
+        assert_eq!(step.description, "Delete (Drill)");
+        assert_eq!(quantity(&db, "04A1B2C3"), Some(2));
+        assert!(db.get_trash().unwrap().is_empty());
+        
+        db.redo().unwrap().expect("the delete can be redone");
+        assert_eq!(quantity(&db, "04A1B2C3"), None);
+        assert_eq!(db.get_trash().unwrap().len(), 1);
+        assert!(db.quantity_mismatches().unwrap().is_empty());
+    }
+    
+    #[test]
+    fn undo_and_redo_a_quantity_change() {
+        let db = db_with(&[("04A1B2C3", "Drill", 2)]);
+        db.record_movement(&uid("04A1B2C3"), 3, MovementReason::ScanIn).unwrap();
+        assert_eq!(quantity(&db, "04A1B2C3"), Some(5));
+        
+        db.undo().unwrap();
+        assert_eq!(quantity(&db, "04A1B2C3"), Some(2));
+        db.redo().unwrap();
+        assert_eq!(quantity(&db, "04A1B2C3"), Some(5));
+        
+        // Both directions are booked, so the ledger still adds up to the stock
+        assert!(db.quantity_mismatches().unwrap().is_empty());
+    }
+    
+    #[test]
+    fn undo_and_redo_an_import() {
+        use crate::inventory::model::create_inventory_item;
+        
+        let db = db_with(&[("04A1B2C3", "Drill", 2)]);
+        let imported = vec![
+            create_inventory_item(&uid("04A1B2C3"), "Drill", None, 5, None, None),
+            create_inventory_item(&uid("04A1B2C4"), "Saw", None, 1, None, None),
+        ];
+        assert_eq!(db.import_json(&serde_json::to_string(&imported).unwrap()).unwrap(), 2);
+        
+        // The import is one step: the changed item goes back and the new one goes
+        db.undo().unwrap();
+        assert_eq!((quantity(&db, "04A1B2C3"), quantity(&db, "04A1B2C4")), (Some(2), None));
+        db.redo().unwrap();
+        assert_eq!((quantity(&db, "04A1B2C3"), quantity(&db, "04A1B2C4")), (Some(5), Some(1)));
+        assert!(db.quantity_mismatches().unwrap().is_empty());
+    }
+    
+    #[test]
+    fn undo_and_redo_a_bulk_step() {
+        let db = db_with(&[("04A1B2C3", "Drill", 2), ("04A1B2C4", "Saw", 1), ("04A1B2C5", "Tape", 7)]);
+        let selection = ids(&db, &["04A1B2C3", "04A1B2C4"]);
+        let shelf = db.ensure_location("Store/Shelf A").unwrap();
+        
+        assert_eq!(db.bulk_adjust_quantity(&selection, 4).unwrap(), 2);
+        assert_eq!(db.bulk_set_location(&selection, Some(shelf.id)).unwrap(), 2);
+        
+        // Each bulk action is one step covering every selected item
+        let step = db.undo().unwrap().unwrap();
+        assert_eq!(step.description, "Move to 'Store / Shelf A' (2 items)");
+        assert!(db.get_items_in_location(shelf.id, false).unwrap().is_empty());
+        assert_eq!((quantity(&db, "04A1B2C3"), quantity(&db, "04A1B2C4")), (Some(6), Some(5)));
+        
+        db.undo().unwrap();
+        assert_eq!((quantity(&db, "04A1B2C3"), quantity(&db, "04A1B2C4")), (Some(2), Some(1)));
+        assert_eq!(quantity(&db, "04A1B2C5"), Some(7));
+        
+        db.redo().unwrap();
+        db.redo().unwrap();
+        assert_eq!(db.get_items_in_location(shelf.id, false).unwrap().len(), 2);
+        assert_eq!((quantity(&db, "04A1B2C3"), quantity(&db, "04A1B2C4")), (Some(6), Some(5)));
+        
+        assert_eq!(db.bulk_delete(&selection).unwrap(), 2);
+        db.undo().unwrap();
+        assert_eq!((quantity(&db, "04A1B2C3"), quantity(&db, "04A1B2C4")), (Some(6), Some(5)));
+        assert!(db.quantity_mismatches().unwrap().is_empty());
+    }
+    
+    #[test]
+    fn a_new_change_drops_the_step_that_was_undone() {
+        let db = db_with(&[("04A1B2C3", "Drill", 2)]);
+        db.update_quantity(&uid("04A1B2C3"), 9).unwrap();
+        db.undo().unwrap();
+        assert!(db.next_redo().unwrap().is_some());
+        
+        db.record_movement(&uid("04A1B2C3"), -1, MovementReason::ScanOut).unwrap();
+        assert!(db.next_redo().unwrap().is_none());
+        assert!(db.redo().unwrap().is_none());
+        assert_eq!(quantity(&db, "04A1B2C3"), Some(1));
+    }
+    
+    #[test]
+    fn undo_is_refused_when_the_item_changed_since() {
+        let db = db_with(&[("04A1B2C3", "Drill", 2)]);
+        db.update_quantity(&uid("04A1B2C3"), 9).unwrap();
+        
+        // Changed behind the journal's back, e.g. by another program
+        db.conn.execute("UPDATE inventory SET name = 'Hammer drill'", []).unwrap();
+        assert!(matches!(db.undo(), Err(Error::Undo(_))));
+        assert_eq!(quantity(&db, "04A1B2C3"), Some(9));
+        assert!(db.next_undo().unwrap().is_some());
+    }
+    
+    #[test]
+    fn restore_from_the_trash_and_undo_it() {
+        let db = db_with(&[("04A1B2C3", "Drill", 2)]);
+        let item_id = ids(&db, &["04A1B2C3"])[0];
+        db.attach_tag(item_id, Some(&uid("04A1B2C4")), None).unwrap();
+        db.delete_item(&uid("04A1B2C3")).unwrap();
+        
+        // It comes back with its id, stock and every tag
+        assert_eq!(db.restore_items(&[item_id]).unwrap(), 1);
+        let restored = db.get_item(&uid("04A1B2C4")).unwrap().unwrap();
+        assert_eq!((restored.id, restored.quantity), (Some(item_id), 2));
+        assert!(db.get_trash().unwrap().is_empty());
+        
+        db.undo().unwrap();
+        assert_eq!(quantity(&db, "04A1B2C3"), None);
+        assert_eq!(db.get_trash().unwrap().len(), 1);
+        assert!(db.quantity_mismatches().unwrap().is_empty());
+    }
+    
+    #[test]
+    fn purging_removes_old_trash_and_the_steps_that_could_bring_it_back() {
+        let db = db_with(&[("04A1B2C3", "Drill", 2), ("04A1B2C4", "Saw", 1)]);
+        db.delete_item(&uid("04A1B2C3")).unwrap();
+        db.update_quantity(&uid("04A1B2C4"), 3).unwrap();
+        
+        // Nothing was deleted 30 days ago
+        assert_eq!(db.purge_trash(30).unwrap(), 0);
+        assert_eq!(db.get_trash().unwrap().len(), 1);
+        
+        assert_eq!(db.purge_trash(0).unwrap(), 1);
+        assert!(db.get_trash().unwrap().is_empty());
+        
+        // The later change to another item can still be undone, the delete can't
+        db.undo().unwrap().expect("the quantity change is kept");
+        assert_eq!(quantity(&db, "04A1B2C4"), Some(1));
+        assert!(db.undo().unwrap().is_none());
+        assert_eq!(quantity(&db, "04A1B2C3"), None);
+    }
+    
+    #[test]
+    fn csv_export_quotes_commas_and_quotes_in_fields() {
+        let db = db_with(&[("04A1B2C3", "12\" drill, \"red\"", 2)]);
+        let csv = db.export_csv().unwrap();
+        let row = csv.lines().nth(1).unwrap();
+        
+        assert!(row.starts_with("\"04A1B2C3\",\"12\"\" drill, \"\"red\"\"\",\"\",\"2\","), "{}", row);
+        assert_eq!(csv.lines().count(), 2);
+    }
+}
@@ -0,0 +1,920 @@
+// inventory/migrations.rs
+// Versioned schema upgrades for the inventory database. The version is kept in
+// PRAGMA user_version; files written before versioning read as version 0.
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+
+use crate::error::{Error, Result};
+use crate::inventory::model::generate_timestamp;
+use crate::uid::Uid;
+
+/// One schema change, applied in version order
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    apply: fn(&Transaction) -> rusqlite::Result<()>,
+}
+
+// Append only: a step that has shipped must never be edited, add a new one instead
+const MIGRATIONS: &[Migration] = &[
+    Migration { version: 1, description: "Create inventory table", apply: create_inventory },
+    Migration { version: 2, description: "Add card_family column", apply: add_card_family },
+    Migration { version: 3, description: "Add stock movements ledger", apply: create_movements },
+    Migration { version: 4, description: "Add loans for check-out/check-in", apply: create_loans },
+    Migration { version: 5, description: "Add location hierarchy", apply: create_locations },
+    Migration { version: 6, description: "Add categories with custom fields", apply: create_categories },
+    Migration { version: 7, description: "Give items their own ID and link tags to them", apply: create_tags },
+    Migration { version: 8, description: "Add minimum and reorder quantities", apply: add_stock_levels },
+    Migration { version: 9, description: "Add full-text search index", apply: create_search_index },
+    Migration { version: 10, description: "Add undo journal", apply: create_undo_journal },
+    Migration { version: 11, description: "Add trash for deleted items", apply: create_trash },
+    Migration { version: 12, description: "Quarantine tags that aren't valid UIDs", apply: quarantine_invalid_tags },
+    Migration { version: 13, description: "Store tags as plain upper-case hex", apply: canonicalise_tags },
+    Migration { version: 14, description: "Let several items share a UID", apply: share_tags },
+];
+
+/// What `migrate` did to a database
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationReport {
+    pub from_version: u32,
+    pub to_version: u32,
+    /// Copy of the file taken before upgrading; `None` for a new database
+    pub backup: Option<PathBuf>,
+    /// Tags the upgrade found unreadable or already taken, whose rows were moved to the quarantine table
+    pub quarantined: Vec<String>,
+}
+
+/// Every known migration, oldest first
+pub fn migrations() -> &'static [Migration] {
+    MIGRATIONS
+}
+
+/// Schema version this build creates and understands
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+/// Version recorded in the database file
+pub fn schema_version(conn: &Connection) -> Result<u32> {
+    Ok(conn.query_row("PRAGMA user_version", [], |row| row.get(0))?)
+}
+
+/// Bring the database at `db_path` up to `latest_version()`.
+///
+/// Existing files are copied next to the database before anything changes, and
+/// all pending steps run in a single transaction, so a failed upgrade leaves the
+/// file as it was. Returns `None` when the schema was already current.
+pub fn migrate(conn: &mut Connection, db_path: &str) -> Result<Option<MigrationReport>> {
+    let from_version = schema_version(conn)?;
+    let latest = latest_version();
+
+    if from_version > latest {
+        return Err(Error::Migration(format!(
+            "{} has schema version {}, but this version of the app only knows up to {}",
+            db_path, from_version, latest
+        )));
+    }
+    if from_version == latest {
+        return Ok(None);
+    }
+
+    let backup = if has_tables(conn)? { Some(backup_file(db_path, from_version)?) } else { None };
+
+    let tx = conn.transaction()?;
+    let quarantined_before = last_quarantined(&tx)?;
+    for migration in MIGRATIONS.iter().filter(|m| m.version > from_version) {
+        (migration.apply)(&tx).map_err(|e| {
+            Error::Migration(format!("step {} ({}) failed: {}", migration.version, migration.description, e))
+        })?;
+    }
+    let quarantined = {
+        let mut stmt = tx.prepare("SELECT tag_id FROM quarantine WHERE id > ? ORDER BY id")?;
+        let quarantined = stmt
+            .query_map(params![quarantined_before], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        quarantined
+    };
+    // user_version is part of the database header, so it commits or rolls back with the steps
+    tx.pragma_update(None, "user_version", latest)?;
+    tx.commit()?;
+
+    Ok(Some(MigrationReport { from_version, to_version: latest, backup, quarantined }))
+}
+
+// Last row in the quarantine table, 0 before the step that adds it
+fn last_quarantined(tx: &Transaction) -> rusqlite::Result<i64> {
+    let exists: bool = tx.query_row(
+        "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'quarantine'",
+        [],
+        |row| row.get(0),
+    )?;
+    if !exists {
+        return Ok(0);
+    }
+    tx.query_row("SELECT COALESCE(MAX(id), 0) FROM quarantine", [], |row| row.get(0))
+}
+
+// A brand-new file has nothing worth backing up
+fn has_tables(conn: &Connection) -> Result<bool> {
+    let count: i64 = conn.query_row("SELECT COUNT(*) FROM sqlite_master WHERE type = 'table'", [], |row| row.get(0))?;
+    Ok(count > 0)
+}
+
+fn backup_file(db_path: &str, version: u32) -> Result<PathBuf> {
+    let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
+    let backup = PathBuf::from(format!("{}.v{}-{}.bak", db_path, version, timestamp));
+
+    fs::copy(Path::new(db_path), &backup)
+        .map_err(|e| Error::io(format!("Failed to back up {} before migrating", db_path), e))?;
+
+    Ok(backup)
+}
+
+fn has_column(tx: &Transaction, table: &str, column: &str) -> rusqlite::Result<bool> {
+    let mut stmt = tx.prepare(&format!("PRAGMA table_info({})", table))?;
+    let columns = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<rusqlite::Result<Vec<String>>>()?;
+
+    Ok(columns.iter().any(|c| c == column))
+}
+
+fn create_inventory(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS inventory (
+            tag_id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            description TEXT,
+            quantity INTEGER NOT NULL DEFAULT 0,
+            location TEXT,
+            category TEXT,
+            last_updated TEXT NOT NULL,
+            created_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+fn add_card_family(tx: &Transaction) -> rusqlite::Result<()> {
+    // Unversioned files from the release that introduced card types already have it
+    if !has_column(tx, "inventory", "card_family")? {
+        tx.execute("ALTER TABLE inventory ADD COLUMN card_family TEXT", [])?;
+    }
+    Ok(())
+}
+
+fn create_movements(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE movements (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            tag_id TEXT NOT NULL,
+            delta INTEGER NOT NULL,
+            reason TEXT NOT NULL,
+            user TEXT,
+            station TEXT,
+            timestamp TEXT NOT NULL,
+            note TEXT
+        );
+        CREATE INDEX idx_movements_tag_id ON movements (tag_id);
+
+        -- The ledger is append-only; corrections are new movements
+        CREATE TRIGGER movements_no_update BEFORE UPDATE ON movements
+        BEGIN
+            SELECT RAISE(ABORT, 'movements are append-only');
+        END;
+        CREATE TRIGGER movements_no_delete BEFORE DELETE ON movements
+        BEGIN
+            SELECT RAISE(ABORT, 'movements are append-only');
+        END;
+
+        -- Existing stock becomes an opening balance so the ledger adds up to it
+        INSERT INTO movements (tag_id, delta, reason, timestamp, note)
+            SELECT tag_id, quantity, 'adjustment', last_updated, 'Opening balance'
+            FROM inventory WHERE quantity <> 0;",
+    )
+}
+
+fn create_loans(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE loans (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            tag_id TEXT NOT NULL,
+            borrower TEXT NOT NULL,
+            checked_out_at TEXT NOT NULL,
+            due_date TEXT,
+            returned_at TEXT
+        );
+        CREATE INDEX idx_loans_tag_id ON loans (tag_id);
+
+        -- A tag can only be out to one borrower at a time
+        CREATE UNIQUE INDEX idx_loans_open ON loans (tag_id) WHERE returned_at IS NULL;",
+    )
+}
+
+fn create_locations(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE locations (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            parent_id INTEGER REFERENCES locations (id),
+            tag_id TEXT UNIQUE,
+            created_at TEXT NOT NULL
+        );
+        -- Names only have to be unique among siblings, and case doesn't make a new place
+        CREATE UNIQUE INDEX idx_locations_name ON locations (COALESCE(parent_id, 0), name COLLATE NOCASE);
+
+        ALTER TABLE inventory ADD COLUMN location_id INTEGER REFERENCES locations (id);
+        CREATE INDEX idx_inventory_location_id ON inventory (location_id);",
+    )?;
+
+    // Free-text locations become paths: "Store/Shelf A" nests, "shelf a" and "Shelf A" merge
+    let texts = {
+        let mut stmt = tx.prepare("SELECT DISTINCT location FROM inventory WHERE location IS NOT NULL")?;
+        let texts = stmt.query_map([], |row| row.get::<_, String>(0))?.collect::<rusqlite::Result<Vec<String>>>()?;
+        texts
+    };
+
+    for text in texts {
+        let mut parent_id: Option<i64> = None;
+        let mut path: Vec<String> = Vec::new();
+
+        for name in text.split('/').map(str::trim).filter(|name| !name.is_empty()) {
+            let existing = tx
+                .query_row(
+                    "SELECT id, name FROM locations WHERE COALESCE(parent_id, 0) = COALESCE(?, 0) AND name = ? COLLATE NOCASE",
+                    params![parent_id, name],
+                    |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)),
+                )
+                .optional()?;
+
+            let (id, name) = match existing {
+                Some(existing) => existing,
+                None => {
+                    tx.execute(
+                        "INSERT INTO locations (name, parent_id, created_at)
+                         VALUES (?, ?, (SELECT MIN(created_at) FROM inventory WHERE location = ?))",
+                        params![name, parent_id, text],
+                    )?;
+                    (tx.last_insert_rowid(), name.to_string())
+                }
+            };
+            parent_id = Some(id);
+            path.push(name);
+        }
+
+        let path = if path.is_empty() { None } else { Some(path.join(" / ")) };
+        tx.execute(
+            "UPDATE inventory SET location = ?, location_id = ? WHERE location = ?",
+            params![path, parent_id, text],
+        )?;
+    }
+
+    Ok(())
+}
+
+fn create_categories(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE categories (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            parent_id INTEGER REFERENCES categories (id),
+            color TEXT,
+            created_at TEXT NOT NULL
+        );
+        CREATE UNIQUE INDEX idx_categories_name ON categories (COALESCE(parent_id, 0), name COLLATE NOCASE);
+
+        -- Custom fields a category declares for its items, and the values items have for them
+        CREATE TABLE category_fields (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            category_id INTEGER NOT NULL REFERENCES categories (id),
+            name TEXT NOT NULL,
+            field_type TEXT NOT NULL,
+            required INTEGER NOT NULL DEFAULT 0,
+            position INTEGER NOT NULL
+        );
+        CREATE UNIQUE INDEX idx_category_fields_name ON category_fields (category_id, name COLLATE NOCASE);
+        CREATE TABLE item_attributes (
+            tag_id TEXT NOT NULL,
+            name TEXT NOT NULL,
+            value TEXT NOT NULL,
+            PRIMARY KEY (tag_id, name)
+        );
+
+        ALTER TABLE inventory ADD COLUMN category_id INTEGER REFERENCES categories (id);
+        CREATE INDEX idx_inventory_category_id ON inventory (category_id);",
+    )?;
+
+    // Free-text categories become records the same way locations did: "Tools/Meters" nests
+    let texts = {
+        let mut stmt = tx.prepare("SELECT DISTINCT category FROM inventory WHERE category IS NOT NULL")?;
+        let texts = stmt.query_map([], |row| row.get::<_, String>(0))?.collect::<rusqlite::Result<Vec<String>>>()?;
+        texts
+    };
+
+    for text in texts {
+        let mut parent_id: Option<i64> = None;
+        let mut path: Vec<String> = Vec::new();
+
+        for name in text.split('/').map(str::trim).filter(|name| !name.is_empty()) {
+            let existing = tx
+                .query_row(
+                    "SELECT id, name FROM categories WHERE COALESCE(parent_id, 0) = COALESCE(?, 0) AND name = ? COLLATE NOCASE",
+                    params![parent_id, name],
+                    |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)),
+                )
+                .optional()?;
+
+            let (id, name) = match existing {
+                Some(existing) => existing,
+                None => {
+                    tx.execute(
+                        "INSERT INTO categories (name, parent_id, created_at)
+                         VALUES (?, ?, (SELECT MIN(created_at) FROM inventory WHERE category = ?))",
+                        params![name, parent_id, text],
+                    )?;
+                    (tx.last_insert_rowid(), name.to_string())
+                }
+            };
+            parent_id = Some(id);
+            path.push(name);
+        }
+
+        let path = if path.is_empty() { None } else { Some(path.join(" / ")) };
+        tx.execute(
+            "UPDATE inventory SET category = ?, category_id = ? WHERE category = ?",
+            params![path, parent_id, text],
+        )?;
+    }
+
+    Ok(())
+}
+
+fn create_tags(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "-- The tag stops being the key: items get an ID of their own, tag_id keeps the tag shown for the item
+        CREATE TABLE inventory_new (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            tag_id TEXT NOT NULL UNIQUE,
+            name TEXT NOT NULL,
+            description TEXT,
+            quantity INTEGER NOT NULL DEFAULT 0,
+            location TEXT,
+            category TEXT,
+            last_updated TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            card_family TEXT,
+            location_id INTEGER REFERENCES locations (id),
+            category_id INTEGER REFERENCES categories (id)
+        );
+        INSERT INTO inventory_new (
+            tag_id, name, description, quantity, location, category, last_updated, created_at, card_family,
+            location_id, category_id
+        )
+            SELECT tag_id, name, description, quantity, location, category, last_updated, created_at, card_family,
+                location_id, category_id
+            FROM inventory ORDER BY created_at, tag_id;
+        DROP TABLE inventory;
+        ALTER TABLE inventory_new RENAME TO inventory;
+        CREATE INDEX idx_inventory_location_id ON inventory (location_id);
+        CREATE INDEX idx_inventory_category_id ON inventory (category_id);
+
+        -- Every UID or text identifier an item has carried; a link is active until it is retired
+        CREATE TABLE tags (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            item_id INTEGER NOT NULL REFERENCES inventory (id),
+            uid TEXT,
+            identifier TEXT,
+            attached_at TEXT NOT NULL,
+            retired_at TEXT,
+            CHECK (uid IS NOT NULL OR identifier IS NOT NULL)
+        );
+        CREATE INDEX idx_tags_item_id ON tags (item_id);
+        CREATE UNIQUE INDEX idx_tags_uid ON tags (uid) WHERE retired_at IS NULL;
+        CREATE UNIQUE INDEX idx_tags_identifier ON tags (identifier COLLATE NOCASE) WHERE retired_at IS NULL;
+        INSERT INTO tags (item_id, uid, attached_at) SELECT id, tag_id, created_at FROM inventory;
+
+        -- History, loans and field values follow the item rather than the tag
+        DROP TRIGGER movements_no_update;
+        ALTER TABLE movements ADD COLUMN item_id INTEGER REFERENCES inventory (id);
+        UPDATE movements SET item_id = (SELECT id FROM inventory WHERE inventory.tag_id = movements.tag_id);
+        CREATE INDEX idx_movements_item_id ON movements (item_id);
+        CREATE TRIGGER movements_no_update BEFORE UPDATE ON movements
+        BEGIN
+            SELECT RAISE(ABORT, 'movements are append-only');
+        END;
+
+        ALTER TABLE loans ADD COLUMN item_id INTEGER REFERENCES inventory (id);
+        UPDATE loans SET item_id = (SELECT id FROM inventory WHERE inventory.tag_id = loans.tag_id);
+        DROP INDEX idx_loans_open;
+        CREATE INDEX idx_loans_item_id ON loans (item_id);
+        CREATE UNIQUE INDEX idx_loans_open ON loans (item_id) WHERE returned_at IS NULL;
+
+        CREATE TABLE item_attributes_new (
+            item_id INTEGER NOT NULL REFERENCES inventory (id),
+            name TEXT NOT NULL,
+            value TEXT NOT NULL,
+            PRIMARY KEY (item_id, name)
+        );
+        INSERT INTO item_attributes_new (item_id, name, value)
+            SELECT i.id, a.name, a.value FROM item_attributes a JOIN inventory i ON i.tag_id = a.tag_id;
+        DROP TABLE item_attributes;
+        ALTER TABLE item_attributes_new RENAME TO item_attributes;",
+    )
+}
+
+fn add_stock_levels(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "-- Set on an item, or on a category for the items in it that don't set their own
+        ALTER TABLE inventory ADD COLUMN min_quantity INTEGER;
+        ALTER TABLE inventory ADD COLUMN reorder_quantity INTEGER;
+        ALTER TABLE categories ADD COLUMN min_quantity INTEGER;
+        ALTER TABLE categories ADD COLUMN reorder_quantity INTEGER;",
+    )
+}
+
+fn create_search_index(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "-- One row per item, rowid = inventory.id; tags holds the item's active UIDs and identifiers
+        CREATE VIRTUAL TABLE inventory_fts USING fts5 (
+            tag_id, name, description, category, location, tags,
+            tokenize = 'unicode61'
+        );
+        INSERT INTO inventory_fts (rowid, tag_id, name, description, category, location, tags)
+            SELECT id, tag_id, name, description, category, location,
+                (SELECT group_concat(COALESCE(uid, '') || ' ' || COALESCE(identifier, ''), ' ')
+                 FROM tags WHERE tags.item_id = inventory.id AND retired_at IS NULL)
+            FROM inventory;
+
+        -- Triggers keep the index in step with every write, whichever code path makes it
+        CREATE TRIGGER inventory_fts_insert AFTER INSERT ON inventory
+        BEGIN
+            INSERT INTO inventory_fts (rowid, tag_id, name, description, category, location, tags)
+                VALUES (new.id, new.tag_id, new.name, new.description, new.category, new.location,
+                    (SELECT group_concat(COALESCE(uid, '') || ' ' || COALESCE(identifier, ''), ' ')
+                     FROM tags WHERE item_id = new.id AND retired_at IS NULL));
+        END;
+        CREATE TRIGGER inventory_fts_update AFTER UPDATE OF tag_id, name, description, category, location ON inventory
+        BEGIN
+            UPDATE inventory_fts
+                SET tag_id = new.tag_id, name = new.name, description = new.description,
+                    category = new.category, location = new.location
+                WHERE rowid = new.id;
+        END;
+        CREATE TRIGGER inventory_fts_delete AFTER DELETE ON inventory
+        BEGIN
+            DELETE FROM inventory_fts WHERE rowid = old.id;
+        END;
+
+        CREATE TRIGGER tags_fts_insert AFTER INSERT ON tags
+        BEGIN
+            UPDATE inventory_fts
+                SET tags = (SELECT group_concat(COALESCE(uid, '') || ' ' || COALESCE(identifier, ''), ' ')
+                            FROM tags WHERE item_id = new.item_id AND retired_at IS NULL)
+                WHERE rowid = new.item_id;
+        END;
+        CREATE TRIGGER tags_fts_update AFTER UPDATE ON tags
+        BEGIN
+            UPDATE inventory_fts
+                SET tags = (SELECT group_concat(COALESCE(uid, '') || ' ' || COALESCE(identifier, ''), ' ')
+                            FROM tags WHERE item_id = new.item_id AND retired_at IS NULL)
+                WHERE rowid = new.item_id;
+        END;
+        CREATE TRIGGER tags_fts_delete AFTER DELETE ON tags
+        BEGIN
+            UPDATE inventory_fts
+                SET tags = (SELECT group_concat(COALESCE(uid, '') || ' ' || COALESCE(identifier, ''), ' ')
+                            FROM tags WHERE item_id = old.item_id AND retired_at IS NULL)
+                WHERE rowid = old.item_id;
+        END;",
+    )
+}
+
+fn create_undo_journal(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "-- One row per undoable change; changes holds the items it touched as JSON, before and after.
+        -- A step is undone while undone_at is set and is dropped by the next change
+        CREATE TABLE undo_steps (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            description TEXT NOT NULL,
+            changes TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            undone_at TEXT
+        );",
+    )
+}
+
+fn create_trash(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "-- Deleted items, kept as JSON with their field values and tags until restored or purged.
+        -- Item ids are never reused, so a restored item gets its old id and movements back
+        CREATE TABLE trash (
+            item_id INTEGER PRIMARY KEY,
+            state TEXT NOT NULL,
+            deleted_at TEXT NOT NULL,
+            deleted_by TEXT
+        );
+        CREATE INDEX idx_trash_deleted_at ON trash(deleted_at);",
+    )
+}
+
+fn quarantine_invalid_tags(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "-- Rows whose tag can't be read as a 4, 7 or 10-byte UID, e.g. 3-byte reads stored before tags
+        -- were checked. They are taken out of their table, which every query would otherwise fail on,
+        -- and kept here as JSON so they can be entered again by hand
+        CREATE TABLE quarantine (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            source TEXT NOT NULL,
+            tag_id TEXT NOT NULL,
+            data TEXT NOT NULL,
+            reason TEXT NOT NULL,
+            quarantined_at TEXT NOT NULL
+        );",
+    )?;
+    let now = generate_timestamp();
+
+    // Items go with their tags, field values and loans; their movements stay, the ledger is append-only
+    for (item_id, tag_id, reason) in invalid_tags(tx, "SELECT id, tag_id FROM inventory")? {
+        quarantine_item(tx, item_id, &tag_id, &reason, &now)?;
+    }
+
+    // Other tags of items that are kept, and loans booked with such a tag
+    for (id, tag_id, reason) in invalid_tags(tx, "SELECT id, uid FROM tags WHERE uid IS NOT NULL")? {
+        quarantine_tag(tx, id, &tag_id, &reason, &now)?;
+    }
+    for (id, tag_id, reason) in invalid_tags(tx, "SELECT id, tag_id FROM loans")? {
+        tx.execute(
+            "INSERT INTO quarantine (source, tag_id, data, reason, quarantined_at)
+                SELECT 'loans', ?2, json_object(
+                    'item_id', item_id, 'borrower', borrower, 'checked_out_at', checked_out_at,
+                    'due_date', due_date, 'returned_at', returned_at
+                ), ?3, ?4
+                FROM loans WHERE id = ?1",
+            params![id, tag_id, reason, now],
+        )?;
+        tx.execute("DELETE FROM loans WHERE id = ?", params![id])?;
+    }
+
+    // A location keeps its place in the hierarchy and just loses the tag
+    for (id, tag_id, reason) in invalid_tags(tx, "SELECT id, tag_id FROM locations WHERE tag_id IS NOT NULL")? {
+        quarantine_location_tag(tx, id, &tag_id, &reason, &now)?;
+    }
+    Ok(())
+}
+
+fn canonicalise_tags(tx: &Transaction) -> rusqlite::Result<()> {
+    // Lookups bind `Uid::to_hex()`, so a tag stored as "04a1b2c3" or "04:A1:B2:C3" could never be found.
+    // Each one is rewritten; one whose plain form another item or location already has is quarantined
+    let now = generate_timestamp();
+
+    for (item_id, tag_id, hex) in non_canonical_tags(tx, "SELECT id, tag_id FROM inventory ORDER BY id")? {
+        let taken: bool = tx.query_row(
+            "SELECT EXISTS (SELECT 1 FROM inventory WHERE tag_id = ?2 AND id != ?1)
+                OR EXISTS (SELECT 1 FROM tags WHERE uid = ?2 AND item_id != ?1 AND retired_at IS NULL)",
+            params![item_id, hex],
+            |row| row.get(0),
+        )?;
+        if taken {
+            quarantine_item(tx, item_id, &tag_id, &format!("{} is already the tag of another item", hex), &now)?;
+        } else {
+            tx.execute("UPDATE inventory SET tag_id = ? WHERE id = ?", params![hex, item_id])?;
+        }
+    }
+
+    for (id, uid, hex) in non_canonical_tags(tx, "SELECT id, uid FROM tags WHERE uid IS NOT NULL ORDER BY id")? {
+        // Only active links have to be unique; retired ones are history
+        let taken: bool = tx.query_row(
+            "SELECT retired_at IS NULL AND EXISTS (
+                SELECT 1 FROM tags other WHERE other.uid = ?2 AND other.id != ?1 AND other.retired_at IS NULL)
+             FROM tags WHERE id = ?1",
+            params![id, hex],
+            |row| row.get(0),
+        )?;
+        if taken {
+            quarantine_tag(tx, id, &uid, &format!("{} is already attached to an item", hex), &now)?;
+        } else {
+            tx.execute("UPDATE tags SET uid = ? WHERE id = ?", params![hex, id])?;
+        }
+    }
+
+    for (id, _, hex) in non_canonical_tags(tx, "SELECT id, tag_id FROM loans ORDER BY id")? {
+        tx.execute("UPDATE loans SET tag_id = ? WHERE id = ?", params![hex, id])?;
+    }
+
+    for (id, tag_id, hex) in non_canonical_tags(tx, "SELECT id, tag_id FROM locations WHERE tag_id IS NOT NULL ORDER BY id")? {
+        let taken: bool = tx.query_row(
+            "SELECT EXISTS (SELECT 1 FROM locations WHERE tag_id = ? AND id != ?)",
+            params![hex, id],
+            |row| row.get(0),
+        )?;
+        if taken {
+            quarantine_location_tag(tx, id, &tag_id, &format!("{} is already the tag of another location", hex), &now)?;
+        } else {
+            tx.execute("UPDATE locations SET tag_id = ? WHERE id = ?", params![hex, id])?;
+        }
+    }
+
+    // The ledger stays append-only apart from this one rewrite of how its tags are spelled
+    let movements = non_canonical_tags(tx, "SELECT id, tag_id FROM movements ORDER BY id")?;
+    if !movements.is_empty() {
+        tx.execute("DROP TRIGGER movements_no_update", [])?;
+        for (id, _, hex) in movements {
+            tx.execute("UPDATE movements SET tag_id = ? WHERE id = ?", params![hex, id])?;
+        }
+        tx.execute_batch(
+            "CREATE TRIGGER movements_no_update BEFORE UPDATE ON movements
+            BEGIN
+                SELECT RAISE(ABORT, 'movements are append-only');
+            END;",
+        )?;
+    }
+    Ok(())
+}
+
+fn share_tags(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "-- One UID may be on several items, e.g. a kit and its parts; each item still shows a tag of its own.
+        -- Text identifiers stay unique
+        DROP INDEX idx_tags_uid;
+        CREATE INDEX idx_tags_uid ON tags (uid);
+        CREATE UNIQUE INDEX idx_tags_item_uid ON tags (item_id, uid) WHERE retired_at IS NULL;",
+    )
+}
+
+// Move an item with its tags, field values and loans to the quarantine table
+fn quarantine_item(tx: &Transaction, item_id: i64, tag_id: &str, reason: &str, now: &str) -> rusqlite::Result<()> {
+    tx.execute(
+        "INSERT INTO quarantine (source, tag_id, data, reason, quarantined_at)
+            SELECT 'inventory', ?2, json_object(
+                'id', id, 'name', name, 'description', description, 'quantity', quantity,
+                'location', location, 'category', category, 'last_updated', last_updated,
+                'created_at', created_at, 'card_family', card_family, 'min_quantity', min_quantity,
+                'reorder_quantity', reorder_quantity,
+                'tags', json((SELECT json_group_array(json_object(
+                    'uid', uid, 'identifier', identifier, 'attached_at', attached_at, 'retired_at', retired_at))
+                    FROM tags WHERE item_id = ?1)),
+                'attributes', json((SELECT json_group_object(name, value) FROM item_attributes WHERE item_id = ?1)),
+                'loans', json((SELECT json_group_array(json_object(
+                    'tag_id', tag_id, 'borrower', borrower, 'checked_out_at', checked_out_at,
+                    'due_date', due_date, 'returned_at', returned_at))
+                    FROM loans WHERE item_id = ?1))
+            ), ?3, ?4
+            FROM inventory WHERE id = ?1",
+        params![item_id, tag_id, reason, now],
+    )?;
+    tx.execute("DELETE FROM tags WHERE item_id = ?", params![item_id])?;
+    tx.execute("DELETE FROM item_attributes WHERE item_id = ?", params![item_id])?;
+    tx.execute("DELETE FROM loans WHERE item_id = ?", params![item_id])?;
+    tx.execute("DELETE FROM inventory WHERE id = ?", params![item_id])?;
+    Ok(())
+}
+
+// Move one tag link of an item that is kept to the quarantine table
+fn quarantine_tag(tx: &Transaction, id: i64, uid: &str, reason: &str, now: &str) -> rusqlite::Result<()> {
+    tx.execute(
+        "INSERT INTO quarantine (source, tag_id, data, reason, quarantined_at)
+            SELECT 'tags', ?2, json_object(
+                'item_id', item_id, 'identifier', identifier, 'attached_at', attached_at, 'retired_at', retired_at
+            ), ?3, ?4
+            FROM tags WHERE id = ?1",
+        params![id, uid, reason, now],
+    )?;
+    tx.execute("DELETE FROM tags WHERE id = ?", params![id])?;
+    Ok(())
+}
+
+// Record a location's tag in the quarantine table and take it off the location
+fn quarantine_location_tag(tx: &Transaction, id: i64, tag_id: &str, reason: &str, now: &str) -> rusqlite::Result<()> {
+    tx.execute(
+        "INSERT INTO quarantine (source, tag_id, data, reason, quarantined_at)
+            SELECT 'locations', ?2, json_object('id', id, 'name', name), ?3, ?4 FROM locations WHERE id = ?1",
+        params![id, tag_id, reason, now],
+    )?;
+    tx.execute("UPDATE locations SET tag_id = NULL WHERE id = ?", params![id])?;
+    Ok(())
+}
+
+// Rows of `query` (id, tag) whose tag is a valid UID spelled other than `Uid::to_hex()`, with that spelling
+fn non_canonical_tags(tx: &Transaction, query: &str) -> rusqlite::Result<Vec<(i64, String, String)>> {
+    let mut stmt = tx.prepare(query)?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?
+        .collect::<rusqlite::Result<Vec<(i64, String)>>>()?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|(id, tag)| {
+            let hex = tag.parse::<Uid>().ok()?.to_hex();
+            (hex != tag).then_some((id, tag, hex))
+        })
+        .collect())
+}
+
+// Rows of `query` (id, tag) whose tag isn't a valid UID, with the reason
+fn invalid_tags(tx: &Transaction, query: &str) -> rusqlite::Result<Vec<(i64, String, String)>> {
+    let mut stmt = tx.prepare(query)?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?
+        .collect::<rusqlite::Result<Vec<(i64, String)>>>()?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|(id, tag)| tag.parse::<Uid>().err().map(|e| (id, tag, e.to_string())))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A fresh directory for one test's database and the backup migrating it leaves
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("mifare-migrations-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    // A file as written before schema versioning, with one item
+    fn baseline_db(path: &Path) -> Connection {
+        let conn = Connection::open(path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE inventory (
+                tag_id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                description TEXT,
+                quantity INTEGER NOT NULL DEFAULT 0,
+                location TEXT,
+                category TEXT,
+                last_updated TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            );
+            INSERT INTO inventory VALUES
+                ('04A1B2C3', 'Drill', 'Cordless', 3, 'Store room', 'Tools', '2025-03-09T19:13:40Z', '2025-03-09T18:45:29Z');",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn backups(dir: &Path) -> Vec<PathBuf> {
+        fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "bak"))
+            .collect()
+    }
+
+    #[test]
+    fn new_database_gets_the_latest_schema() {
+        let dir = test_dir("new");
+        let path = dir.join("inventory.db");
+        let mut conn = Connection::open(&path).unwrap();
+
+        let report = migrate(&mut conn, path.to_str().unwrap()).unwrap().expect("a new file is migrated");
+        assert_eq!((report.from_version, report.to_version), (0, latest_version()));
+        assert_eq!(report.backup, None);
+        assert_eq!(schema_version(&conn).unwrap(), latest_version());
+
+        // Nothing left to do the second time
+        assert_eq!(migrate(&mut conn, path.to_str().unwrap()).unwrap(), None);
+        assert!(backups(&dir).is_empty());
+
+        drop(conn);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn baseline_database_upgrades_with_its_data() {
+        let dir = test_dir("baseline");
+        let path = dir.join("inventory.db");
+        let mut conn = baseline_db(&path);
+
+        let report = migrate(&mut conn, path.to_str().unwrap()).unwrap().expect("an old file is migrated");
+        assert_eq!((report.from_version, report.to_version), (0, latest_version()));
+        assert!(report.quarantined.is_empty());
+        let backup = report.backup.expect("an old file is backed up");
+        assert_eq!(backups(&dir), vec![backup]);
+        assert_eq!(schema_version(&conn).unwrap(), latest_version());
+
+        let item: (i64, String, Option<String>, i32, String, String, String) = conn
+            .query_row(
+                "SELECT id, name, description, quantity, location, category, created_at FROM inventory WHERE tag_id = '04A1B2C3'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?, row.get(6)?)),
+            )
+            .unwrap();
+        let (id, name, description, quantity, location, category, created_at) = item;
+        assert_eq!((name.as_str(), description.as_deref(), quantity), ("Drill", Some("Cordless"), 3));
+        assert_eq!((location.as_str(), category.as_str()), ("Store room", "Tools"));
+        assert_eq!(created_at, "2025-03-09T18:45:29Z");
+
+        // The stock became an opening balance, and the tag a link to the item
+        let ledger: i32 = conn
+            .query_row("SELECT SUM(delta) FROM movements WHERE item_id = ?", params![id], |row| row.get(0))
+            .unwrap();
+        assert_eq!(ledger, 3);
+        let uid: String = conn
+            .query_row("SELECT uid FROM tags WHERE item_id = ? AND retired_at IS NULL", params![id], |row| row.get(0))
+            .unwrap();
+        assert_eq!(uid, "04A1B2C3");
+
+        drop(conn);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn tags_spelled_other_ways_are_rewritten_as_plain_hex() {
+        let dir = test_dir("canonical");
+        let path = dir.join("inventory.db");
+        let mut conn = Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE inventory (
+                tag_id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                description TEXT,
+                quantity INTEGER NOT NULL DEFAULT 0,
+                location TEXT,
+                category TEXT,
+                last_updated TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            );
+            INSERT INTO inventory VALUES
+                ('04a1b2c3', 'Drill', NULL, 3, NULL, NULL, '2025-03-09T19:13:40Z', '2025-03-09T18:45:29Z'),
+                ('04:A1:B2:C4', 'Saw', NULL, 1, 'Store room', NULL, '2025-03-09T19:13:40Z', '2025-03-09T18:46:00Z'),
+                ('04 A1 B2 C3', 'Drill again', NULL, 1, NULL, NULL, '2025-03-09T19:13:40Z', '2025-03-09T18:47:00Z');",
+        )
+        .unwrap();
+
+        // Bring it to the version before the rewrite, then add a loan and a location tag spelled the same ways
+        let tx = conn.transaction().unwrap();
+        for migration in MIGRATIONS.iter().filter(|m| m.version < 13) {
+            (migration.apply)(&tx).unwrap();
+        }
+        tx.pragma_update(None, "user_version", 12).unwrap();
+        tx.execute_batch(
+            "INSERT INTO loans (item_id, tag_id, borrower, checked_out_at)
+                SELECT id, '04a1b2c3', 'Sam', '2025-03-10T09:00:00Z' FROM inventory WHERE name = 'Drill';
+            UPDATE locations SET tag_id = '04-a1-b2-c5' WHERE name = 'Store room';",
+        )
+        .unwrap();
+        tx.commit().unwrap();
+
+        let report = migrate(&mut conn, path.to_str().unwrap()).unwrap().expect("version 12 is upgraded");
+        assert_eq!(report.from_version, 12);
+        assert_eq!(report.quarantined, vec!["04 A1 B2 C3".to_string()]);
+
+        let tags = |query: &str| -> Vec<String> {
+            let mut stmt = conn.prepare(query).unwrap();
+            let tags = stmt.query_map([], |row| row.get(0)).unwrap().collect::<rusqlite::Result<Vec<String>>>().unwrap();
+            tags
+        };
+        assert_eq!(tags("SELECT tag_id FROM inventory ORDER BY id"), vec!["04A1B2C3", "04A1B2C4"]);
+        assert_eq!(tags("SELECT uid FROM tags ORDER BY item_id"), vec!["04A1B2C3", "04A1B2C4"]);
+        assert_eq!(tags("SELECT tag_id FROM loans"), vec!["04A1B2C3"]);
+        assert_eq!(tags("SELECT tag_id FROM locations WHERE tag_id IS NOT NULL"), vec!["04A1B2C5"]);
+        // The quarantined item's opening balance stays in the ledger, spelled like the rest
+        assert_eq!(tags("SELECT tag_id FROM movements ORDER BY id"), vec!["04A1B2C3", "04A1B2C4", "04A1B2C3"]);
+
+        let reason: String = conn.query_row("SELECT reason FROM quarantine", [], |row| row.get(0)).unwrap();
+        assert!(reason.contains("already the tag of another item"), "{}", reason);
+
+        // The ledger is append-only again afterwards
+        assert!(conn.execute("UPDATE movements SET delta = 0", []).is_err());
+
+        drop(conn);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn failed_upgrade_rolls_back_and_keeps_the_backup() {
+        let dir = test_dir("failed");
+        let path = dir.join("inventory.db");
+        let mut conn = baseline_db(&path);
+        // Step 3 creates this table, so it fails on a file that already has one
+        conn.execute("CREATE TABLE movements (id INTEGER PRIMARY KEY)", []).unwrap();
+
+        let error = migrate(&mut conn, path.to_str().unwrap()).unwrap_err().to_string();
+        assert!(error.contains("step 3"), "{}", error);
+
+        // Step 2 ran before it and was rolled back with it
+        assert_eq!(schema_version(&conn).unwrap(), 0);
+        let tx = conn.transaction().unwrap();
+        assert!(!has_column(&tx, "inventory", "card_family").unwrap());
+        drop(tx);
+        let name: String = conn.query_row("SELECT name FROM inventory", [], |row| row.get(0)).unwrap();
+        assert_eq!(name, "Drill");
+
+        let backups = backups(&dir);
+        assert_eq!(backups.len(), 1);
+        let backup = Connection::open(&backups[0]).unwrap();
+        let name: String = backup.query_row("SELECT name FROM inventory", [], |row| row.get(0)).unwrap();
+        assert_eq!(name, "Drill");
+
+        drop(conn);
+        drop(backup);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,10 +1,20 @@
-
 pub mod db;
+pub mod migrations;
 pub mod model;
-pub mod ui;
-
+pub mod scan;
+pub mod search;
+pub mod stocktake;
+pub mod undo;
+pub mod view;
 
-pub use db::InventoryDB;
-pub use model::{InventoryItem, create_inventory_item};
 
-pub use ui::inventory_ui::InventoryUI;
\ No newline at end of file
+pub use db::{InventoryDB, items_to_csv, movements_to_csv, reorder_to_csv};
+pub use model::{
+    Actor, Category, CategoryField, CategoryStats, FieldType, InventoryItem, ItemTag, Loan, Location, LocationStats,
+    Movement, MovementReason, QuantityMismatch, ReorderItem, create_inventory_item,
+};
+pub use scan::{ScanMode, ScanOptions, ScanOutcome, apply_scan, apply_scan_to};
+pub use search::{Comparison, SearchField, SearchQuery, SearchTerm};
+pub use stocktake::{Stocktake, StocktakeScope, Variance, VarianceKind, VarianceReport};
+pub use undo::{ItemChange, ItemState, TrashedItem, UndoStep};
+pub use view::{InventoryView, ItemFilter, SortColumn, SortKey, sort_items};
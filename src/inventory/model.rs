@@ -1,17 +1,472 @@
-use serde::{Deserialize, Serialize};
+use chrono::NaiveDate;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::str::FromStr;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use crate::card_family::CardFamily;
+use crate::uid::Uid;
+
 // Define item structure
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct InventoryItem {
-    pub tag_id: String,
+    // Stable ID of the item, `None` until it's saved. IDs are local to one database; exports and
+    // imports match items by tag
+    #[serde(skip)]
+    pub id: Option<i64>,
+    // The tag shown for the item; scans resolve through every active tag linked to it
+    pub tag_id: Uid,
     pub name: String,
     pub description: Option<String>,
     pub quantity: i32,
+    // Full path of the location, e.g. "Main site / Store room / Shelf A"
     pub location: Option<String>,
+    // Full path of the category, e.g. "Tools / Meters"
     pub category: Option<String>,
     pub last_updated: String,
     pub created_at: String,
+    // Filled in when the tag is scanned with a reader that reports ATQA/SAK
+    #[serde(default)]
+    pub card_family: Option<CardFamily>,
+    // Row in the locations table; ids are local to one database, so exports carry the path only
+    #[serde(skip)]
+    pub location_id: Option<i64>,
+    #[serde(skip)]
+    pub category_id: Option<i64>,
+    // Values of the custom fields declared by the category, keyed by field name
+    #[serde(default)]
+    pub attributes: BTreeMap<String, String>,
+    // Stock below the minimum needs reordering; `None` uses the category's levels
+    #[serde(default)]
+    pub min_quantity: Option<i32>,
+    #[serde(default)]
+    pub reorder_quantity: Option<i32>,
+}
+
+// A UID or text identifier (e.g. an NDEF text record) linked to an item
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ItemTag {
+    pub id: i64,
+    pub item_id: i64,
+    pub uid: Option<Uid>,
+    pub identifier: Option<String>,
+    pub attached_at: String,
+    // Retired tags no longer resolve to the item and may be attached to another one
+    pub retired_at: Option<String>,
+}
+
+impl ItemTag {
+    pub fn is_active(&self) -> bool {
+        self.retired_at.is_none()
+    }
+}
+
+impl fmt::Display for ItemTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.uid, &self.identifier) {
+            (Some(uid), Some(identifier)) => write!(f, "{} ({})", uid, identifier),
+            (Some(uid), None) => write!(f, "{}", uid),
+            (None, Some(identifier)) => write!(f, "\"{}\"", identifier),
+            (None, None) => write!(f, "-"),
+        }
+    }
+}
+
+// A place items are kept. Locations nest, e.g. site / room / shelf / bin
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Location {
+    pub id: i64,
+    pub name: String,
+    pub parent_id: Option<i64>,
+    // Scanning this tag makes it the current location for the scans that follow
+    pub tag_id: Option<Uid>,
+    // Names from the top level down, joined by PATH_SEPARATOR
+    pub path: String,
+    pub created_at: String,
+}
+
+impl Location {
+    // 0 for a top-level location such as a site
+    pub fn depth(&self) -> usize {
+        path_segments(&self.path).len().saturating_sub(1)
+    }
+    
+    // Whether this is `other` or somewhere inside it
+    pub fn is_within(&self, other: &Location) -> bool {
+        path_is_within(&self.path, &other.path)
+    }
+}
+
+// Items and stock in a location, sub-locations included
+#[derive(Clone, Debug, Serialize)]
+pub struct LocationStats {
+    pub location: Location,
+    pub items: usize,
+    pub quantity: i32,
+}
+
+// A kind of item. Categories nest, and a sub-category inherits the custom fields of its parents
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Category {
+    pub id: i64,
+    pub name: String,
+    pub parent_id: Option<i64>,
+    // "#RRGGBB" set on this category
+    pub color: Option<String>,
+    // The colour shown for it: its own, or else the nearest parent's
+    pub display_color: Option<String>,
+    pub path: String,
+    pub created_at: String,
+    // Stock levels set on this category for items that don't set their own
+    pub min_quantity: Option<i32>,
+    pub reorder_quantity: Option<i32>,
+    // The levels that apply to its items: its own, or else the nearest parent's
+    pub effective_min_quantity: Option<i32>,
+    pub effective_reorder_quantity: Option<i32>,
+}
+
+impl Category {
+    pub fn depth(&self) -> usize {
+        path_segments(&self.path).len().saturating_sub(1)
+    }
+    
+    // Whether this is `other` or one of its sub-categories
+    pub fn is_within(&self, other: &Category) -> bool {
+        path_is_within(&self.path, &other.path)
+    }
+    
+    pub fn rgb(&self) -> Option<(u8, u8, u8)> {
+        self.display_color.as_deref().and_then(parse_color)
+    }
+}
+
+// Items and stock in a category, sub-categories included
+#[derive(Clone, Debug, Serialize)]
+pub struct CategoryStats {
+    pub category: Category,
+    pub items: usize,
+    pub quantity: i32,
+}
+
+// Colours are stored as "#RRGGBB"; the '#' may be left out when typing one
+pub fn parse_color(text: &str) -> Option<(u8, u8, u8)> {
+    let hex = text.trim().trim_start_matches('#');
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some((channel(0)?, channel(2)?, channel(4)?))
+}
+
+pub fn format_color((r, g, b): (u8, u8, u8)) -> String {
+    format!("#{:02X}{:02X}{:02X}", r, g, b)
+}
+
+// What a custom field holds
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FieldType {
+    Text,
+    Number,
+    Date,
+}
+
+impl FieldType {
+    pub const ALL: [FieldType; 3] = [FieldType::Text, FieldType::Number, FieldType::Date];
+    
+    // Stored in the category_fields table
+    pub fn key(&self) -> &'static str {
+        match self {
+            FieldType::Text => "text",
+            FieldType::Number => "number",
+            FieldType::Date => "date",
+        }
+    }
+    
+    pub fn name(&self) -> &'static str {
+        match self {
+            FieldType::Text => "Text",
+            FieldType::Number => "Number",
+            FieldType::Date => "Date",
+        }
+    }
+    
+    pub fn from_key(key: &str) -> Option<FieldType> {
+        FieldType::ALL.iter().copied().find(|field_type| field_type.key() == key)
+    }
+    
+    // The value in its stored form, or why it doesn't fit this type
+    pub fn validate(&self, value: &str) -> Result<String, String> {
+        let value = value.trim();
+        match self {
+            FieldType::Text => Ok(value.to_string()),
+            FieldType::Number => match value.parse::<f64>() {
+                Ok(number) if number.is_finite() => Ok(value.to_string()),
+                _ => Err(format!("'{}' is not a number", value)),
+            },
+            FieldType::Date => NaiveDate::parse_from_str(value, DATE_FORMAT)
+                .map(|date| date.format(DATE_FORMAT).to_string())
+                .map_err(|_| format!("'{}' is not a date (YYYY-MM-DD)", value)),
+        }
+    }
+}
+
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for FieldType {
+    type Err = String;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        FieldType::from_key(&s.trim().to_lowercase())
+            .ok_or_else(|| format!("unknown field type '{}', expected text, number or date", s))
+    }
+}
+
+impl Serialize for FieldType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.key())
+    }
+}
+
+// A custom field declared by a category, e.g. "Calibration due" of type date
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct CategoryField {
+    pub id: i64,
+    pub category_id: i64,
+    pub name: String,
+    pub field_type: FieldType,
+    pub required: bool,
+    // Order within the category, fields are shown in the order they were added
+    pub position: i64,
+}
+
+// Joins the names in a location or category path
+pub const PATH_SEPARATOR: &str = " / ";
+
+// The names in a path typed as "Site/Room/Shelf" or "Site / Room / Shelf"
+pub fn path_segments(path: &str) -> Vec<&str> {
+    path.split('/').map(str::trim).filter(|name| !name.is_empty()).collect()
+}
+
+// A typed path in the stored form, `None` if it names nothing
+pub fn normalize_path(path: &str) -> Option<String> {
+    let segments = path_segments(path);
+    if segments.is_empty() {
+        None
+    } else {
+        Some(segments.join(PATH_SEPARATOR))
+    }
+}
+
+// Whether `path` is `ancestor` or a location inside it; names compare without case
+pub fn path_is_within(path: &str, ancestor: &str) -> bool {
+    let path = path_segments(path);
+    let ancestor = path_segments(ancestor);
+    
+    !ancestor.is_empty()
+        && path.len() >= ancestor.len()
+        && path.iter().zip(&ancestor).all(|(a, b)| a.eq_ignore_ascii_case(b))
+}
+
+// Why a quantity changed
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MovementReason {
+    ScanIn,
+    ScanOut,
+    // Typed in by hand, or a correction made by reconciling
+    Adjustment,
+    Import,
+    Sync,
+}
+
+impl MovementReason {
+    pub const ALL: [MovementReason; 5] = [
+        MovementReason::ScanIn,
+        MovementReason::ScanOut,
+        MovementReason::Adjustment,
+        MovementReason::Import,
+        MovementReason::Sync,
+    ];
+    
+    // Stored in the movements table and exports
+    pub fn key(&self) -> &'static str {
+        match self {
+            MovementReason::ScanIn => "scan_in",
+            MovementReason::ScanOut => "scan_out",
+            MovementReason::Adjustment => "adjustment",
+            MovementReason::Import => "import",
+            MovementReason::Sync => "sync",
+        }
+    }
+    
+    pub fn name(&self) -> &'static str {
+        match self {
+            MovementReason::ScanIn => "Scan in",
+            MovementReason::ScanOut => "Scan out",
+            MovementReason::Adjustment => "Adjustment",
+            MovementReason::Import => "Import",
+            MovementReason::Sync => "Sync",
+        }
+    }
+    
+    pub fn from_key(key: &str) -> Option<MovementReason> {
+        MovementReason::ALL.iter().copied().find(|reason| reason.key() == key)
+    }
+}
+
+impl fmt::Display for MovementReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl Serialize for MovementReason {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.key())
+    }
+}
+
+impl<'de> Deserialize<'de> for MovementReason {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let key = String::deserialize(deserializer)?;
+        MovementReason::from_key(&key).ok_or_else(|| serde::de::Error::custom(format!("unknown movement reason '{}'", key)))
+    }
+}
+
+// One entry in the append-only stock ledger
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Movement {
+    pub id: i64,
+    pub tag_id: Uid,
+    pub delta: i32,
+    pub reason: MovementReason,
+    pub user: Option<String>,
+    pub station: Option<String>,
+    pub timestamp: String,
+    pub note: Option<String>,
+}
+
+// An item below its minimum stock, with how many to order
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ReorderItem {
+    pub tag_id: Uid,
+    pub name: String,
+    pub category: Option<String>,
+    pub location: Option<String>,
+    pub quantity: i32,
+    pub min_quantity: i32,
+    // The reorder quantity, but at least enough to get back to the minimum
+    pub order_quantity: i32,
+}
+
+impl ReorderItem {
+    // `Some` if `item` is below `min_quantity`, the levels being the item's or its category's
+    pub fn check(item: &InventoryItem, min_quantity: Option<i32>, reorder_quantity: Option<i32>) -> Option<ReorderItem> {
+        let min_quantity = min_quantity?;
+        if item.quantity >= min_quantity {
+            return None;
+        }
+        
+        Some(ReorderItem {
+            tag_id: item.tag_id.clone(),
+            name: item.name.clone(),
+            category: item.category.clone(),
+            location: item.location.clone(),
+            quantity: item.quantity,
+            min_quantity,
+            order_quantity: reorder_quantity.unwrap_or(0).max(min_quantity - item.quantity),
+        })
+    }
+    
+    pub fn is_out_of_stock(&self) -> bool {
+        self.quantity <= 0
+    }
+    
+    // e.g. "'Drill bits' is low on stock: 1 left, minimum 5; reorder 20"
+    pub fn warning(&self) -> String {
+        let state = if self.is_out_of_stock() {
+            "is out of stock".to_string()
+        } else {
+            format!("is low on stock: {} left", self.quantity)
+        };
+        format!("'{}' {}, minimum {}; reorder {}", self.name, state, self.min_quantity, self.order_quantity)
+    }
+}
+
+// Check a minimum and reorder quantity before they're stored
+pub fn validate_stock_levels(min_quantity: Option<i32>, reorder_quantity: Option<i32>) -> Result<(), String> {
+    if let Some(min_quantity) = min_quantity.filter(|min| *min < 0) {
+        return Err(format!("minimum quantity can't be negative ({})", min_quantity));
+    }
+    if let Some(reorder_quantity) = reorder_quantity.filter(|reorder| *reorder <= 0) {
+        return Err(format!("reorder quantity must be at least 1 ({})", reorder_quantity));
+    }
+    Ok(())
+}
+
+// An item whose stored quantity disagrees with its movements
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct QuantityMismatch {
+    pub tag_id: Uid,
+    pub stored: i32,
+    pub ledger: i32,
+}
+
+// A tag lent out in check-out/check-in scan mode
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Loan {
+    pub id: i64,
+    pub tag_id: Uid,
+    pub borrower: String,
+    pub checked_out_at: String,
+    // YYYY-MM-DD; open-ended loans have none
+    pub due_date: Option<String>,
+    pub returned_at: Option<String>,
+}
+
+impl Loan {
+    pub fn is_open(&self) -> bool {
+        self.returned_at.is_none()
+    }
+    
+    // Still out after its due date
+    pub fn is_overdue(&self, today: NaiveDate) -> bool {
+        self.is_open() && self.due().is_some_and(|due| due < today)
+    }
+    
+    pub fn due(&self) -> Option<NaiveDate> {
+        self.due_date.as_deref().and_then(|date| NaiveDate::parse_from_str(date, DATE_FORMAT).ok())
+    }
+}
+
+// Format of loan due dates
+pub const DATE_FORMAT: &str = "%Y-%m-%d";
+
+// Who is making changes and from which workstation, recorded with every movement
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Actor {
+    pub user: Option<String>,
+    pub station: Option<String>,
+}
+
+impl Actor {
+    // The logged-in user and host name, as far as the environment tells us
+    pub fn from_env() -> Actor {
+        let first_set = |names: &[&str]| names.iter().find_map(|name| env::var(name).ok().filter(|v| !v.is_empty()));
+        
+        Actor {
+            user: first_set(&["USER", "USERNAME"]),
+            station: first_set(&["HOSTNAME", "COMPUTERNAME"]).or_else(|| {
+                // Most Linux shells don't export HOSTNAME
+                std::fs::read_to_string("/etc/hostname").ok().map(|name| name.trim().to_string()).filter(|name| !name.is_empty())
+            }),
+        }
+    }
 }
 
 // Helper to generate ISO timestamp
@@ -29,9 +484,15 @@ pub fn generate_timestamp() -> String {
     datetime.format("%Y-%m-%dT%H:%M:%S.%fZ").to_string()
 }
 
+// Timestamp of `days` ago in the same format, to compare stored timestamps against
+pub fn timestamp_days_ago(days: u32) -> String {
+    let datetime = chrono::Utc::now() - chrono::Duration::days(days as i64);
+    datetime.naive_utc().format("%Y-%m-%dT%H:%M:%S.%fZ").to_string()
+}
+
 // Create a new inventory item
 pub fn create_inventory_item(
-    tag_id: &str, 
+    tag_id: &Uid, 
     name: &str, 
     description: Option<&str>, 
     quantity: i32, 
@@ -41,7 +502,8 @@ pub fn create_inventory_item(
     let now = generate_timestamp();
     
     InventoryItem {
-        tag_id: tag_id.to_string(),
+        id: None,
+        tag_id: tag_id.clone(),
         name: name.to_string(),
         description: description.map(ToString::to_string),
         quantity,
@@ -49,5 +511,11 @@ pub fn create_inventory_item(
         category: category.map(ToString::to_string),
         last_updated: now.clone(),
         created_at: now,
+        card_family: None,
+        location_id: None,
+        category_id: None,
+        attributes: BTreeMap::new(),
+        min_quantity: None,
+        reorder_quantity: None,
     }
 }
\ No newline at end of file
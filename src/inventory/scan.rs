@@ -0,0 +1,293 @@
+// inventory/scan.rs
+// What a scan of a known tag does to the inventory. The GUI capture window and
+// mifare-cli both apply scans through here so the modes behave the same.
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{Duration, Local, NaiveDate};
+
+use crate::error::{Error, Result};
+use crate::inventory::db::InventoryDB;
+use crate::inventory::model::{InventoryItem, Loan, Location, MovementReason, ReorderItem, DATE_FORMAT};
+use crate::uid::Uid;
+
+/// How a scan of a known tag changes its stock
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ScanMode {
+    /// One unit arrives
+    #[default]
+    Receive,
+    /// One unit leaves
+    Issue,
+    /// The scan is followed by the counted quantity
+    SetCount,
+    /// Show the item without changing anything
+    Lookup,
+    /// Lend the tag to a borrower; the next scan returns it
+    CheckOutIn,
+}
+
+impl ScanMode {
+    pub const ALL: [ScanMode; 5] = [
+        ScanMode::Receive,
+        ScanMode::Issue,
+        ScanMode::SetCount,
+        ScanMode::Lookup,
+        ScanMode::CheckOutIn,
+    ];
+
+    /// Used on the command line
+    pub fn key(&self) -> &'static str {
+        match self {
+            ScanMode::Receive => "receive",
+            ScanMode::Issue => "issue",
+            ScanMode::SetCount => "set",
+            ScanMode::Lookup => "lookup",
+            ScanMode::CheckOutIn => "checkout",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScanMode::Receive => "Receive (+1)",
+            ScanMode::Issue => "Issue (-1)",
+            ScanMode::SetCount => "Set count",
+            ScanMode::Lookup => "Lookup only",
+            ScanMode::CheckOutIn => "Check-out and check-in",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<ScanMode> {
+        ScanMode::ALL.iter().copied().find(|mode| mode.key() == key)
+    }
+
+    /// Whether scans in this mode can change stock
+    pub fn changes_stock(&self) -> bool {
+        *self != ScanMode::Lookup
+    }
+
+    /// Whether an item scanned in this mode is put away at the current location
+    pub fn places_items(&self) -> bool {
+        matches!(self, ScanMode::Receive | ScanMode::SetCount)
+    }
+}
+
+impl fmt::Display for ScanMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for ScanMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        ScanMode::from_key(s).ok_or_else(|| {
+            let keys: Vec<&str> = ScanMode::ALL.iter().map(ScanMode::key).collect();
+            format!("unknown scan mode '{}', expected one of {}", s, keys.join(", "))
+        })
+    }
+}
+
+/// Extra input some modes need for each scan
+#[derive(Clone, Debug, Default)]
+pub struct ScanOptions {
+    /// Counted quantity for `SetCount`
+    pub count: Option<i32>,
+    /// Who a tag is checked out to in `CheckOutIn`
+    pub borrower: Option<String>,
+    pub due_date: Option<NaiveDate>,
+    /// Set by the last location tag scanned; received and counted items are moved here
+    pub location: Option<Location>,
+}
+
+/// What a scan did
+#[derive(Clone, Debug)]
+pub enum ScanOutcome {
+    /// The tag isn't in the inventory
+    NotFound,
+    /// The tag is attached to several items, the one it's shown for first; nothing changed. The caller
+    /// asks which one was meant and applies the scan to it with `apply_scan_to`
+    Shared(Vec<InventoryItem>),
+    /// A location tag was scanned; it is the current location until the next one
+    LocationSelected(Location),
+    /// Receive, issue or set count changed the quantity; `moved` if it was put away at the current location.
+    /// `reorder` is set when the item ended up below its minimum stock
+    Updated { item: InventoryItem, previous: i32, moved: bool, reorder: Option<ReorderItem> },
+    Lookup { item: InventoryItem, loan: Option<Loan> },
+    CheckedOut { item: InventoryItem, loan: Loan, reorder: Option<ReorderItem> },
+    CheckedIn { item: InventoryItem, loan: Loan },
+}
+
+impl ScanOutcome {
+    /// The item after the scan
+    pub fn item(&self) -> Option<&InventoryItem> {
+        match self {
+            ScanOutcome::NotFound | ScanOutcome::Shared(_) | ScanOutcome::LocationSelected(_) => None,
+            ScanOutcome::Updated { item, .. }
+            | ScanOutcome::Lookup { item, .. }
+            | ScanOutcome::CheckedOut { item, .. }
+            | ScanOutcome::CheckedIn { item, .. } => Some(item),
+        }
+    }
+
+    /// Set when the scan left the item below its minimum stock, so the operator can be warned
+    pub fn reorder(&self) -> Option<&ReorderItem> {
+        match self {
+            ScanOutcome::Updated { reorder, .. } | ScanOutcome::CheckedOut { reorder, .. } => reorder.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// One line for the user, e.g. a dialog or the CLI log
+    pub fn message(&self) -> String {
+        let today = Local::now().date_naive();
+
+        match self {
+            ScanOutcome::NotFound => "Tag not found in inventory".to_string(),
+            ScanOutcome::Shared(items) => {
+                let names: Vec<String> = items.iter().map(|item| format!("'{}'", item.name)).collect();
+                format!("Tag is attached to {} items: {}", items.len(), names.join(", "))
+            }
+            ScanOutcome::LocationSelected(location) => format!("Current location: {}", location.path),
+            ScanOutcome::Updated { item, previous, moved, reorder } => {
+                let mut text = format!("Quantity of '{}' changed from {} to {}", item.name, previous, item.quantity);
+                if let (true, Some(location)) = (moved, &item.location) {
+                    text.push_str(&format!(", moved to {}", location));
+                }
+                if let Some(reorder) = reorder {
+                    text.push_str(&format!(". {}", reorder.warning()));
+                }
+                text
+            }
+            ScanOutcome::Lookup { item, loan: None } => {
+                format!("'{}': {} in stock", item.name, item.quantity)
+            }
+            ScanOutcome::Lookup { item, loan: Some(loan) } => {
+                format!("'{}': {} in stock, {}", item.name, item.quantity, describe_loan(loan, today))
+            }
+            ScanOutcome::CheckedOut { item, loan, reorder } => {
+                let mut text = format!("'{}' {}", item.name, describe_loan(loan, today));
+                if let Some(reorder) = reorder {
+                    text.push_str(&format!(". {}", reorder.warning()));
+                }
+                text
+            }
+            ScanOutcome::CheckedIn { item, loan } => {
+                let late = if loan.due().is_some_and(|due| due < today) { " (returned late)" } else { "" };
+                format!("'{}' returned by {}{}", item.name, loan.borrower, late)
+            }
+        }
+    }
+}
+
+/// e.g. "checked out to Sam, due 2024-05-01 (OVERDUE)"
+pub fn describe_loan(loan: &Loan, today: NaiveDate) -> String {
+    let mut text = format!("checked out to {}", loan.borrower);
+    if let Some(due) = &loan.due_date {
+        text.push_str(&format!(", due {}", due));
+    }
+    if loan.is_overdue(today) {
+        text.push_str(" (OVERDUE)");
+    }
+    text
+}
+
+/// Due date from either a date (YYYY-MM-DD) or a number of days from `today`
+pub fn parse_due_date(text: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let text = text.trim();
+    match text.parse::<i64>() {
+        Ok(days) if days >= 0 => Some(today + Duration::days(days)),
+        Ok(_) => None,
+        Err(_) => NaiveDate::parse_from_str(text, DATE_FORMAT).ok(),
+    }
+}
+
+/// Apply one scan of `tag_id` in `mode`. Location tags are recognised in every mode;
+/// the caller keeps the location and passes it back in `options.location`.
+pub fn apply_scan(db: &InventoryDB, tag_id: &Uid, mode: ScanMode, options: &ScanOptions) -> Result<ScanOutcome> {
+    if let Some(location) = db.get_location_by_tag(tag_id)? {
+        return Ok(ScanOutcome::LocationSelected(location));
+    }
+
+    let mut items = db.get_items_for_tag(tag_id)?;
+    let item = match items.len() {
+        0 => return Ok(ScanOutcome::NotFound),
+        1 => items.remove(0),
+        _ => return Ok(ScanOutcome::Shared(items)),
+    };
+    apply(db, tag_id, item, mode, options)
+}
+
+/// Apply a scan of a shared tag to the item the user picked from `ScanOutcome::Shared`
+pub fn apply_scan_to(db: &InventoryDB, item: InventoryItem, mode: ScanMode, options: &ScanOptions) -> Result<ScanOutcome> {
+    // The tag shown for an item always finds that item
+    let tag_id = item.tag_id.clone();
+    apply(db, &tag_id, item, mode, options)
+}
+
+fn apply(db: &InventoryDB, tag_id: &Uid, item: InventoryItem, mode: ScanMode, options: &ScanOptions) -> Result<ScanOutcome> {
+    let previous = item.quantity;
+
+    match mode {
+        ScanMode::Receive => {
+            db.record_movement(tag_id, 1, MovementReason::ScanIn)?;
+        }
+        ScanMode::Issue => {
+            if item.quantity <= 0 {
+                return Err(Error::Scan(format!("'{}' has no stock left to issue", item.name)));
+            }
+            db.record_movement(tag_id, -1, MovementReason::ScanOut)?;
+        }
+        ScanMode::SetCount => {
+            let count = options
+                .count
+                .ok_or_else(|| Error::Scan("set count mode needs the counted quantity".to_string()))?;
+            if count < 0 {
+                return Err(Error::Scan(format!("counted quantity can't be negative ({})", count)));
+            }
+            db.set_quantity(tag_id, count, MovementReason::Adjustment)?;
+        }
+        ScanMode::Lookup => {
+            let loan = db.get_open_loan(tag_id)?;
+            return Ok(ScanOutcome::Lookup { item, loan });
+        }
+        ScanMode::CheckOutIn => {
+            // A tag that is out comes back; one that is in goes out
+            if let Some(loan) = db.check_in(tag_id)? {
+                return Ok(ScanOutcome::CheckedIn { item: reload(db, item)?, loan });
+            }
+
+            let borrower = options
+                .borrower
+                .as_deref()
+                .map(str::trim)
+                .filter(|borrower| !borrower.is_empty())
+                .ok_or_else(|| Error::Scan("check-out needs a borrower".to_string()))?;
+            if item.quantity <= 0 {
+                return Err(Error::Scan(format!("'{}' has no stock left to check out", item.name)));
+            }
+
+            let loan = db.check_out(tag_id, borrower, options.due_date)?;
+            let reorder = db.reorder_status(tag_id)?;
+            return Ok(ScanOutcome::CheckedOut { item: reload(db, item)?, loan, reorder });
+        }
+    }
+
+    // Received and counted items are where the scanner is
+    let moved = match &options.location {
+        Some(location) if mode.places_items() && item.location_id != Some(location.id) => {
+            db.move_item(tag_id, Some(location.id))?
+        }
+        _ => false,
+    };
+
+    // Checked after every change so the warning comes with the scan that took the last unit
+    let reorder = db.reorder_status(tag_id)?;
+    Ok(ScanOutcome::Updated { item: reload(db, item)?, previous, moved, reorder })
+}
+
+// The item as stored after a change, falling back to what we had
+fn reload(db: &InventoryDB, item: InventoryItem) -> Result<InventoryItem> {
+    Ok(db.get_item(&item.tag_id)?.unwrap_or(item))
+}
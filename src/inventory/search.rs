@@ -0,0 +1,426 @@
+// inventory/search.rs
+// Search queries over the inventory, e.g. `category:tools loc:"shelf b" drill qty<5`.
+// Words become an FTS5 match expression over the search index, quantity comparisons
+// a plain SQL filter; the GUI uses the same query to highlight what matched.
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+use crate::error::{Error, Result};
+
+/// A part of an item a word can be limited to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SearchField {
+    /// The tag shown for the item and every other active tag or identifier it carries
+    Tag,
+    Name,
+    Description,
+    Category,
+    Location,
+}
+
+impl SearchField {
+    pub const ALL: [SearchField; 5] = [
+        SearchField::Tag,
+        SearchField::Name,
+        SearchField::Description,
+        SearchField::Category,
+        SearchField::Location,
+    ];
+
+    /// Used in queries, e.g. `cat:tools`
+    pub fn key(&self) -> &'static str {
+        match self {
+            SearchField::Tag => "tag",
+            SearchField::Name => "name",
+            SearchField::Description => "desc",
+            SearchField::Category => "cat",
+            SearchField::Location => "loc",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<SearchField> {
+        match key.to_ascii_lowercase().as_str() {
+            "tag" | "id" => Some(SearchField::Tag),
+            "name" => Some(SearchField::Name),
+            "desc" | "description" => Some(SearchField::Description),
+            "cat" | "category" => Some(SearchField::Category),
+            "loc" | "location" => Some(SearchField::Location),
+            _ => None,
+        }
+    }
+
+    // Column filter for the inventory_fts table
+    fn columns(&self) -> &'static str {
+        match self {
+            SearchField::Tag => "{tag_id tags}",
+            SearchField::Name => "name",
+            SearchField::Description => "description",
+            SearchField::Category => "category",
+            SearchField::Location => "location",
+        }
+    }
+}
+
+/// A word or quoted phrase, optionally limited to one field
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchTerm {
+    pub field: Option<SearchField>,
+    pub text: String,
+    /// Quoted phrases match whole words; bare words also match words they start
+    pub exact: bool,
+}
+
+impl SearchTerm {
+    fn applies_to(&self, field: SearchField) -> bool {
+        self.field.is_none_or(|own| own == field)
+    }
+
+    // "04:A1:B2" and "04 A1 B2" are how UIDs get written, the index holds "04A1B2"
+    fn indexed_text(&self) -> String {
+        let separated = self.text.contains([':', '-', ' ']);
+        let uid_like = self
+            .text
+            .split([':', '-', ' '])
+            .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_hexdigit()));
+        let tag_term = self.field.is_none_or(|field| field == SearchField::Tag);
+
+        if separated && uid_like && tag_term {
+            self.text.chars().filter(char::is_ascii_hexdigit).collect()
+        } else {
+            self.text.clone()
+        }
+    }
+}
+
+/// How `qty` compares in a query
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Equal,
+    GreaterOrEqual,
+    Greater,
+}
+
+impl Comparison {
+    fn operator(&self) -> &'static str {
+        match self {
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Equal => "=",
+            Comparison::GreaterOrEqual => ">=",
+            Comparison::Greater => ">",
+        }
+    }
+}
+
+/// A parsed search; every term and comparison has to match
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SearchQuery {
+    pub terms: Vec<SearchTerm>,
+    pub quantity: Vec<(Comparison, i32)>,
+}
+
+impl SearchQuery {
+    /// Words match the start of words in any field, `"..."` a whole phrase; `field:word`
+    /// limits a word to tag, name, desc, cat or loc, and `qty<5` (also `<=`, `=`, `>=`, `>`)
+    /// compares the quantity
+    pub fn parse(query: &str) -> Result<SearchQuery> {
+        let mut search = SearchQuery::default();
+
+        for token in tokenize(query) {
+            if !token.quoted {
+                if let Some(comparison) = parse_quantity(&token)? {
+                    search.quantity.push(comparison);
+                    continue;
+                }
+            }
+
+            let field = match &token.key {
+                Some(key) => Some(SearchField::from_key(key).ok_or_else(|| {
+                    Error::Search(format!("unknown field '{}'; use tag, name, desc, cat, loc or qty", key))
+                })?),
+                None => None,
+            };
+            // Punctuation on its own is no word to look for
+            if token.text.chars().any(char::is_alphanumeric) {
+                search.terms.push(SearchTerm { field, text: token.text, exact: token.quoted });
+            }
+        }
+
+        Ok(search)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty() && self.quantity.is_empty()
+    }
+
+    /// FTS5 expression for the words, `None` when the query only compares quantities
+    pub fn match_expression(&self) -> Option<String> {
+        if self.terms.is_empty() {
+            return None;
+        }
+
+        let parts: Vec<String> = self
+            .terms
+            .iter()
+            .map(|term| {
+                let phrase = format!("\"{}\"{}", term.indexed_text().replace('"', "\"\""), if term.exact { "" } else { "*" });
+                match term.field {
+                    Some(field) => format!("{} : {}", field.columns(), phrase),
+                    None => phrase,
+                }
+            })
+            .collect();
+
+        Some(parts.join(" AND "))
+    }
+
+    /// SQL condition on `quantity` with its parameters, `None` without comparisons
+    pub fn quantity_condition(&self) -> Option<(String, Vec<i32>)> {
+        if self.quantity.is_empty() {
+            return None;
+        }
+
+        let condition = self
+            .quantity
+            .iter()
+            .map(|(comparison, _)| format!("quantity {} ?", comparison.operator()))
+            .collect::<Vec<_>>()
+            .join(" AND ");
+        Some((condition, self.quantity.iter().map(|(_, value)| *value).collect()))
+    }
+
+    /// Byte ranges of `text` that the query's words matched, for highlighting `field`
+    pub fn highlights(&self, field: SearchField, text: &str) -> Vec<Range<usize>> {
+        let haystack = text.to_ascii_lowercase();
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+
+        for term in self.terms.iter().filter(|term| term.applies_to(field)) {
+            let needle = term.indexed_text().to_ascii_lowercase();
+            if needle.is_empty() {
+                continue;
+            }
+
+            for (start, _) in haystack.match_indices(&needle) {
+                let end = start + needle.len();
+                let starts_word = !haystack[..start].chars().next_back().is_some_and(char::is_alphanumeric);
+                let ends_word = !haystack[end..].chars().next().is_some_and(char::is_alphanumeric);
+                if starts_word && (ends_word || !term.exact) {
+                    ranges.push(start..end);
+                }
+            }
+        }
+
+        // Overlapping matches of different words become one run
+        ranges.sort_by_key(|range| range.start);
+        let mut merged: Vec<Range<usize>> = Vec::new();
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => merged.push(range),
+            }
+        }
+        merged
+    }
+}
+
+impl FromStr for SearchQuery {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        SearchQuery::parse(s)
+    }
+}
+
+impl fmt::Display for SearchQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts: Vec<String> = self
+            .terms
+            .iter()
+            .map(|term| {
+                let text = if term.exact || term.text.contains(' ') {
+                    format!("\"{}\"", term.text)
+                } else {
+                    term.text.clone()
+                };
+                match term.field {
+                    Some(field) => format!("{}:{}", field.key(), text),
+                    None => text,
+                }
+            })
+            .collect();
+        parts.extend(self.quantity.iter().map(|(comparison, value)| format!("qty{}{}", comparison.operator(), value)));
+
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+// One whitespace-separated part of a query, with a `key:` in front taken off
+struct Token {
+    key: Option<String>,
+    text: String,
+    quoted: bool,
+}
+
+fn tokenize(query: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut current = Token { key: None, text: String::new(), quoted: false };
+    let mut in_quotes = false;
+
+    for c in query.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                current.quoted = true;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.text.is_empty() || current.key.is_some() {
+                    tokens.push(current);
+                }
+                current = Token { key: None, text: String::new(), quoted: false };
+            }
+            // Only a plain word before the first colon is a field; "12:30" stays a word
+            ':' if !in_quotes
+                && !current.quoted
+                && current.key.is_none()
+                && !current.text.is_empty()
+                && current.text.chars().all(char::is_alphabetic) =>
+            {
+                current.key = Some(std::mem::take(&mut current.text));
+            }
+            c => current.text.push(c),
+        }
+    }
+    if !current.text.is_empty() || current.key.is_some() {
+        tokens.push(current);
+    }
+
+    tokens
+}
+
+// `qty<5`, `quantity>=10` or `qty:3`; other tokens are words
+fn parse_quantity(token: &Token) -> Result<Option<(Comparison, i32)>> {
+    let is_quantity = |key: &str| key.eq_ignore_ascii_case("qty") || key.eq_ignore_ascii_case("quantity");
+
+    let (comparison, value) = match &token.key {
+        Some(key) if is_quantity(key) => (Comparison::Equal, token.text.as_str()),
+        Some(_) => return Ok(None),
+        None => {
+            let Some(split) = token.text.find(['<', '>', '=']) else {
+                return Ok(None);
+            };
+            if !is_quantity(&token.text[..split]) {
+                return Ok(None);
+            }
+            let rest = &token.text[split..];
+            let (comparison, operator_len) = if rest.starts_with("<=") {
+                (Comparison::LessOrEqual, 2)
+            } else if rest.starts_with(">=") {
+                (Comparison::GreaterOrEqual, 2)
+            } else if rest.starts_with('<') {
+                (Comparison::Less, 1)
+            } else if rest.starts_with('>') {
+                (Comparison::Greater, 1)
+            } else {
+                (Comparison::Equal, 1)
+            };
+            (comparison, &rest[operator_len..])
+        }
+    };
+
+    let value = value
+        .trim()
+        .parse::<i32>()
+        .map_err(|_| Error::Search(format!("'{}' is not a quantity to compare with", value.trim())))?;
+    Ok(Some((comparison, value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inventory::db::InventoryDB;
+    use crate::inventory::model::create_inventory_item;
+    use crate::uid::Uid;
+
+    fn inventory() -> InventoryDB {
+        let db = InventoryDB::new(":memory:").unwrap();
+        let items = [
+            ("04A1B2C3", "Cordless drill", "18V with two batteries", "Store room / Shelf A", "Tools / Power"),
+            ("04A1B2C4", "Drill bits", "HSS set", "Store room / Shelf B", "Consumables"),
+            ("04D4E5F6", "Multimeter", "Fits in the drill case", "Lab", "Tools / Meters"),
+        ];
+        for (tag, name, description, location, category) in items {
+            let tag = Uid::from_hex(tag).unwrap();
+            db.save_item(&create_inventory_item(&tag, name, Some(description), 1, Some(location), Some(category)))
+                .unwrap();
+        }
+        db
+    }
+
+    fn search(db: &InventoryDB, query: &str) -> Vec<String> {
+        let query = SearchQuery::parse(query).unwrap();
+        db.search_items(&query).unwrap().into_iter().map(|item| item.name).collect()
+    }
+
+    fn sorted(mut names: Vec<String>) -> Vec<String> {
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn qualifiers_limit_words_to_a_field() {
+        let db = inventory();
+
+        // The multimeter only mentions a drill in its description
+        assert_eq!(sorted(search(&db, "name:drill")), ["Cordless drill", "Drill bits"]);
+        assert_eq!(search(&db, "tag:04D4E5F6"), ["Multimeter"]);
+        assert_eq!(search(&db, "tag:04:A1:B2:C3"), ["Cordless drill"]);
+        assert_eq!(sorted(search(&db, "tag:04A1B2")), ["Cordless drill", "Drill bits"]);
+        assert_eq!(sorted(search(&db, "category:tools")), ["Cordless drill", "Multimeter"]);
+        assert_eq!(search(&db, "cat:tools loc:lab"), ["Multimeter"]);
+        assert!(search(&db, "cat:drill").is_empty());
+    }
+
+    #[test]
+    fn quoted_phrases_match_whole_words_in_order() {
+        let db = inventory();
+
+        assert_eq!(search(&db, "\"two batteries\""), ["Cordless drill"]);
+        assert!(search(&db, "\"batteries two\"").is_empty());
+        // A bare word also matches words it starts, a quoted one doesn't
+        assert_eq!(search(&db, "drill bit"), ["Drill bits"]);
+        assert!(search(&db, "drill \"bit\"").is_empty());
+        assert_eq!(search(&db, "loc:\"shelf b\""), ["Drill bits"]);
+    }
+
+    #[test]
+    fn fts_syntax_in_a_query_is_searched_for_as_text() {
+        let db = inventory();
+
+        for query in [
+            "drill*", "\"unbalanced", "AND", "OR NOT", "(drill)", "NEAR(drill bits)", "-drill", "drill +bits", "a^b",
+            "{name}", "name:\"", "\"\"\"", "drill's", "col*:x",
+        ] {
+            let parsed = SearchQuery::parse(query).unwrap();
+            if let Err(e) = db.search_items(&parsed) {
+                panic!("searching for {:?} failed: {}", query, e);
+            }
+        }
+        assert_eq!(sorted(search(&db, "(drill)")), ["Cordless drill", "Drill bits", "Multimeter"]);
+    }
+
+    #[test]
+    fn matches_rank_by_the_field_they_are_in() {
+        let db = inventory();
+
+        // Names outweigh descriptions
+        let names = search(&db, "drill");
+        assert_eq!(names.len(), 3);
+        assert_eq!(names[2], "Multimeter");
+
+        // Both are tools, the drill by name and the multimeter by description
+        assert_eq!(search(&db, "tools drill"), ["Cordless drill", "Multimeter"]);
+    }
+}
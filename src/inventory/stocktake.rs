@@ -0,0 +1,409 @@
+// inventory/stocktake.rs
+// Cycle counts: snapshot what should be in a location or category, collect
+// scans without touching stock, then compare the two. Approved adjustments are
+// written back with InventoryDB::apply_stocktake.
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::error::Result;
+use crate::inventory::db::InventoryDB;
+use crate::inventory::model::{InventoryItem, generate_timestamp, normalize_path, path_is_within};
+use crate::uid::Uid;
+use crate::utils::csv_field;
+
+/// Which items a stocktake covers; a filter left empty matches everything
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct StocktakeScope {
+    /// Location path; its sub-locations are counted with it
+    pub location: Option<String>,
+    /// Category path; its sub-categories are counted with it
+    pub category: Option<String>,
+}
+
+impl StocktakeScope {
+    pub fn new(location: Option<&str>, category: Option<&str>) -> StocktakeScope {
+        StocktakeScope { location: location.and_then(normalize_path), category: category.and_then(normalize_path) }
+    }
+
+    pub fn contains(&self, item: &InventoryItem) -> bool {
+        self.matches_location(item) && self.matches_category(item)
+    }
+
+    fn matches_location(&self, item: &InventoryItem) -> bool {
+        match &self.location {
+            Some(location) => item.location.as_deref().is_some_and(|path| path_is_within(path, location)),
+            None => true,
+        }
+    }
+
+    fn matches_category(&self, item: &InventoryItem) -> bool {
+        match &self.category {
+            Some(category) => item.category.as_deref().is_some_and(|path| path_is_within(path, category)),
+            None => true,
+        }
+    }
+}
+
+impl fmt::Display for StocktakeScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.location, &self.category) {
+            (Some(location), Some(category)) => write!(f, "{} in {}", category, location),
+            (Some(location), None) => write!(f, "{}", location),
+            (None, Some(category)) => write!(f, "{}", category),
+            (None, None) => write!(f, "whole inventory"),
+        }
+    }
+}
+
+/// A count in progress. Scans only update the session, never the stock.
+#[derive(Clone, Debug)]
+pub struct Stocktake {
+    scope: StocktakeScope,
+    started_at: String,
+    // Items in scope and their quantity when the count started
+    expected: Vec<InventoryItem>,
+    counts: BTreeMap<Uid, i32>,
+}
+
+impl Stocktake {
+    /// Start counting `scope`, taking a snapshot of what the database expects there
+    pub fn start(db: &InventoryDB, scope: StocktakeScope) -> Result<Stocktake> {
+        let expected = db
+            .get_all_items()?
+            .into_iter()
+            .filter(|item| scope.contains(item))
+            .collect();
+
+        Ok(Stocktake { scope, started_at: generate_timestamp(), expected, counts: BTreeMap::new() })
+    }
+
+    pub fn scope(&self) -> &StocktakeScope {
+        &self.scope
+    }
+
+    pub fn expected(&self) -> &[InventoryItem] {
+        &self.expected
+    }
+
+    /// Count one more of `tag_id`; returns its count so far
+    pub fn record_scan(&mut self, tag_id: &Uid) -> i32 {
+        let count = self.counts.entry(tag_id.clone()).or_insert(0);
+        *count += 1;
+        *count
+    }
+
+    /// Replace the count of `tag_id`, e.g. one tag on a bin of many units
+    pub fn set_count(&mut self, tag_id: &Uid, count: i32) {
+        self.counts.insert(tag_id.clone(), count);
+    }
+
+    pub fn counted(&self, tag_id: &Uid) -> i32 {
+        self.counts.get(tag_id).copied().unwrap_or(0)
+    }
+
+    /// Distinct tags scanned so far
+    pub fn scanned(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Compare the counts with the snapshot
+    pub fn report(&self, db: &InventoryDB) -> Result<VarianceReport> {
+        // An item can carry several tags, so scans add up per item rather than per tag
+        let mut item_counts: BTreeMap<Option<i64>, (InventoryItem, i32)> = BTreeMap::new();
+        let mut unknown_tags = Vec::new();
+        for (tag_id, &counted) in &self.counts {
+            match db.get_item(tag_id)? {
+                Some(item) => item_counts.entry(item.id).or_insert((item, 0)).1 += counted,
+                None => unknown_tags.push((tag_id.clone(), counted)),
+            }
+        }
+
+        let mut variances = Vec::new();
+        let mut matched = 0;
+
+        for item in &self.expected {
+            let counted = item_counts.get(&item.id).map_or(0, |(_, counted)| *counted);
+            if counted == item.quantity {
+                matched += 1;
+                continue;
+            }
+
+            let kind = if counted == 0 { VarianceKind::Missing } else { VarianceKind::Miscounted };
+            variances.push(Variance::for_item(kind, item, item.quantity, counted));
+        }
+
+        // Scanned items that weren't expected here
+        for (item, counted) in item_counts.values() {
+            if self.expected.iter().any(|expected| expected.id == item.id) {
+                continue;
+            }
+
+            // Belongs to this count but is recorded somewhere else
+            let kind = if self.scope.location.is_some() && !self.scope.matches_location(item)
+                && self.scope.matches_category(item) {
+                VarianceKind::WrongLocation
+            } else {
+                VarianceKind::Unexpected
+            };
+            variances.push(Variance::for_item(kind, item, 0, *counted));
+        }
+
+        // Scanned tags that aren't attached to anything
+        for (tag_id, counted) in unknown_tags {
+            variances.push(Variance {
+                tag_id,
+                kind: VarianceKind::Unexpected,
+                name: None,
+                expected: 0,
+                counted,
+                recorded_location: None,
+            });
+        }
+
+        Ok(VarianceReport {
+            scope: self.scope.clone(),
+            started_at: self.started_at.clone(),
+            expected_items: self.expected.len(),
+            scanned_tags: self.counts.len(),
+            matched,
+            variances,
+        })
+    }
+}
+
+/// How a count differs from the database
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VarianceKind {
+    /// Expected here but not scanned
+    Missing,
+    /// Scanned, but not an item of this count (or not in the inventory at all)
+    Unexpected,
+    /// Scanned a different number of times than the recorded quantity
+    Miscounted,
+    /// Found here, but recorded in another location
+    WrongLocation,
+}
+
+impl VarianceKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            VarianceKind::Missing => "Missing",
+            VarianceKind::Unexpected => "Unexpected",
+            VarianceKind::Miscounted => "Miscounted",
+            VarianceKind::WrongLocation => "Wrong location",
+        }
+    }
+}
+
+impl fmt::Display for VarianceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// One line of a variance report
+#[derive(Clone, Debug, Serialize)]
+pub struct Variance {
+    pub tag_id: Uid,
+    pub kind: VarianceKind,
+    /// `None` for tags that aren't in the inventory
+    pub name: Option<String>,
+    pub expected: i32,
+    pub counted: i32,
+    /// Where the database says the item is
+    pub recorded_location: Option<String>,
+}
+
+impl Variance {
+    fn for_item(kind: VarianceKind, item: &InventoryItem, expected: i32, counted: i32) -> Variance {
+        Variance {
+            tag_id: item.tag_id.clone(),
+            kind,
+            name: Some(item.name.clone()),
+            expected,
+            counted,
+            recorded_location: item.location.clone(),
+        }
+    }
+
+    /// Whether approving this line changes the database; unexpected tags are only reported
+    pub fn is_adjustable(&self) -> bool {
+        self.kind != VarianceKind::Unexpected
+    }
+
+    /// e.g. "Miscounted: 04A1B2C3 'Drill' expected 3, counted 2"
+    pub fn describe(&self) -> String {
+        let name = self.name.as_deref().map(|name| format!(" '{}'", name)).unwrap_or_default();
+        match self.kind {
+            VarianceKind::Missing => format!("{}: {}{} expected {}, not found", self.kind, self.tag_id, name, self.expected),
+            VarianceKind::Unexpected if self.name.is_none() => {
+                format!("{}: {} is not in the inventory (scanned {})", self.kind, self.tag_id, self.counted)
+            }
+            VarianceKind::Unexpected => format!("{}: {}{} scanned {}", self.kind, self.tag_id, name, self.counted),
+            VarianceKind::Miscounted => {
+                format!("{}: {}{} expected {}, counted {}", self.kind, self.tag_id, name, self.expected, self.counted)
+            }
+            VarianceKind::WrongLocation => format!(
+                "{}: {}{} counted {}, recorded in {}",
+                self.kind,
+                self.tag_id,
+                name,
+                self.counted,
+                self.recorded_location.as_deref().unwrap_or("no location")
+            ),
+        }
+    }
+}
+
+/// Result of comparing a stocktake with the database
+#[derive(Clone, Debug, Serialize)]
+pub struct VarianceReport {
+    pub scope: StocktakeScope,
+    pub started_at: String,
+    pub expected_items: usize,
+    pub scanned_tags: usize,
+    /// Expected items whose count matched
+    pub matched: usize,
+    pub variances: Vec<Variance>,
+}
+
+impl VarianceReport {
+    pub fn summary(&self) -> String {
+        format!(
+            "Stocktake of {}: {} expected, {} scanned, {} matched, {} variance(s)",
+            self.scope,
+            self.expected_items,
+            self.scanned_tags,
+            self.matched,
+            self.variances.len()
+        )
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("Tag ID,Variance,Name,Expected,Counted,Recorded Location\n");
+
+        for variance in &self.variances {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{}\n",
+                csv_field(&variance.tag_id),
+                csv_field(variance.kind),
+                csv_field(variance.name.as_deref().unwrap_or("")),
+                csv_field(variance.expected),
+                csv_field(variance.counted),
+                csv_field(variance.recorded_location.as_deref().unwrap_or(""))
+            ));
+        }
+
+        csv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inventory::model::create_inventory_item;
+
+    fn uid(hex: &str) -> Uid {
+        hex.parse().unwrap()
+    }
+
+    // Shelf A holds a drill, saws and tape; the level is recorded on shelf B
+    fn stocked_db() -> InventoryDB {
+        let db = InventoryDB::new(":memory:").unwrap();
+        for (tag, name, quantity, location, category) in [
+            ("04A1B2C3", "Drill", 2, "Store/Shelf A", "Tools"),
+            ("04A1B2C4", "Saw", 3, "Store/Shelf A", "Tools"),
+            ("04A1B2C5", "Tape", 1, "Store/Shelf A", "Supplies"),
+            ("04A1B2C6", "Level", 1, "Store/Shelf B", "Tools"),
+            ("04A1B2C7", "Glue", 4, "Store/Shelf B", "Supplies"),
+        ] {
+            db.save_item(&create_inventory_item(&uid(tag), name, None, quantity, Some(location), Some(category)))
+                .unwrap();
+        }
+        db
+    }
+
+    fn kinds(report: &VarianceReport) -> Vec<(String, VarianceKind, i32, i32)> {
+        report
+            .variances
+            .iter()
+            .map(|variance| (variance.tag_id.to_hex(), variance.kind, variance.expected, variance.counted))
+            .collect()
+    }
+
+    #[test]
+    fn counts_are_classified_against_the_snapshot() {
+        let db = stocked_db();
+        let mut count = Stocktake::start(&db, StocktakeScope::new(Some("Store/Shelf A"), None)).unwrap();
+        assert_eq!(count.expected().len(), 3);
+
+        count.record_scan(&uid("04A1B2C3"));
+        assert_eq!(count.record_scan(&uid("04A1B2C3")), 2);
+        count.record_scan(&uid("04A1B2C4"));
+        count.record_scan(&uid("04A1B2C6"));
+        count.set_count(&uid("04A1B2C8"), 5);
+
+        let report = count.report(&db).unwrap();
+        assert_eq!((report.expected_items, report.scanned_tags, report.matched), (3, 4, 1));
+        assert_eq!(
+            kinds(&report),
+            vec![
+                ("04A1B2C4".to_string(), VarianceKind::Miscounted, 3, 1),
+                ("04A1B2C5".to_string(), VarianceKind::Missing, 1, 0),
+                ("04A1B2C6".to_string(), VarianceKind::WrongLocation, 0, 1),
+                ("04A1B2C8".to_string(), VarianceKind::Unexpected, 0, 5),
+            ]
+        );
+        assert_eq!(report.variances[2].recorded_location.as_deref(), Some("Store / Shelf B"));
+        assert_eq!(report.variances[3].name, None);
+        assert!(!report.variances[3].is_adjustable());
+    }
+
+    #[test]
+    fn an_item_outside_the_category_counted_is_unexpected() {
+        let db = stocked_db();
+        let mut count = Stocktake::start(&db, StocktakeScope::new(None, Some("Tools"))).unwrap();
+        for tag in ["04A1B2C3", "04A1B2C3", "04A1B2C4", "04A1B2C4", "04A1B2C4", "04A1B2C6", "04A1B2C7"] {
+            count.record_scan(&uid(tag));
+        }
+
+        let report = count.report(&db).unwrap();
+        assert_eq!(report.matched, 3);
+        assert_eq!(kinds(&report), vec![("04A1B2C7".to_string(), VarianceKind::Unexpected, 0, 1)]);
+        assert_eq!(report.variances[0].name.as_deref(), Some("Glue"));
+    }
+
+    #[test]
+    fn approved_variances_are_written_back_as_one_step() {
+        let db = stocked_db();
+        let scope = StocktakeScope::new(Some("Store/Shelf A"), None);
+        let mut count = Stocktake::start(&db, scope.clone()).unwrap();
+        for tag in ["04A1B2C3", "04A1B2C3", "04A1B2C4", "04A1B2C6", "04A1B2C8"] {
+            count.record_scan(&uid(tag));
+        }
+        let report = count.report(&db).unwrap();
+
+        // The unknown tag is only reported
+        assert_eq!(db.apply_stocktake(&scope, &report.variances).unwrap(), 3);
+
+        let item = |tag: &str| db.get_item(&uid(tag)).unwrap().unwrap();
+        assert_eq!(item("04A1B2C4").quantity, 1);
+        assert_eq!(item("04A1B2C5").quantity, 0);
+        assert_eq!(item("04A1B2C3").quantity, 2);
+
+        // The level was where it was counted, in the right number: it moves and nothing is booked
+        let level = item("04A1B2C6");
+        assert_eq!((level.location.as_deref(), level.quantity), (Some("Store / Shelf A"), 1));
+        assert_eq!(db.get_movements(&uid("04A1B2C6")).unwrap().len(), 1);
+        assert_eq!(db.get_movements(&uid("04A1B2C4")).unwrap().iter().map(|m| m.delta).collect::<Vec<_>>(), vec![3, -2]);
+        assert!(db.quantity_mismatches().unwrap().is_empty());
+
+        let step = db.undo().unwrap().unwrap();
+        assert_eq!(step.changes.len(), 3);
+        assert_eq!(item("04A1B2C4").quantity, 3);
+        assert_eq!(item("04A1B2C6").location.as_deref(), Some("Store / Shelf B"));
+    }
+}
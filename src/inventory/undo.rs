@@ -0,0 +1,111 @@
+// inventory/undo.rs
+// The undo journal and the trash. A journaled change keeps every item it touched as it was
+// before and after, as one step; undo puts the items of the latest step back the way they were
+// and redo applies the step again. A new change drops the steps that were undone.
+// Deleted items are kept in the trash the same way until they are restored or purged.
+use serde::{Deserialize, Serialize};
+
+use crate::inventory::model::{InventoryItem, ItemTag, Loan};
+
+/// How many steps the journal keeps; older ones can no longer be undone
+pub const UNDO_LIMIT: usize = 100;
+
+/// An item with everything needed to put it back: its row, field values, tags and loans
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ItemState {
+    pub item: InventoryItem,
+    // Kept apart from the paths so a location or category renamed since is found again
+    pub location_id: Option<i64>,
+    pub category_id: Option<i64>,
+    pub tags: Vec<ItemTag>,
+    // Open and returned, so undoing a check-out or check-in puts the loan back too
+    #[serde(default)]
+    pub loans: Vec<Loan>,
+}
+
+/// One item touched by a step; `None` means the item didn't exist on that side
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ItemChange {
+    pub item_id: i64,
+    pub before: Option<ItemState>,
+    pub after: Option<ItemState>,
+}
+
+impl ItemChange {
+    /// Name of the item to show in messages, from whichever side has it
+    pub fn name(&self) -> &str {
+        self.after
+            .as_ref()
+            .or(self.before.as_ref())
+            .map(|state| state.item.name.as_str())
+            .unwrap_or("?")
+    }
+}
+
+/// A change that can be undone as a whole, e.g. re-categorising a selection of items
+#[derive(Clone, Debug, Serialize)]
+pub struct UndoStep {
+    pub id: i64,
+    pub description: String,
+    pub created_at: String,
+    /// Set while the step is undone and can be redone
+    pub undone_at: Option<String>,
+    pub changes: Vec<ItemChange>,
+}
+
+/// A deleted item as the trash keeps it, ready to be restored
+#[derive(Clone, Debug, Serialize)]
+pub struct TrashedItem {
+    pub state: ItemState,
+    pub deleted_at: String,
+    /// User who deleted it, when known
+    pub deleted_by: Option<String>,
+}
+
+impl TrashedItem {
+    pub fn item(&self) -> &InventoryItem {
+        &self.state.item
+    }
+}
+
+/// Step description naming the item, or with the number of items when there are several,
+/// e.g. "Delete (Drill)" or "Set category to 'Tools' (3 items)"
+pub fn describe_step(action: &str, changes: &[ItemChange]) -> String {
+    match changes {
+        [change] => format!("{} ({})", action, change.name()),
+        _ => format!("{} ({} items)", action, changes.len()),
+    }
+}
+
+/// Whether an item was changed by something else since it was left in `expected`; undoing
+/// or redoing over such a change would silently throw it away. Every part of the state counts
+/// except the location and category paths, which follow their ids when those are renamed
+pub fn changed_since(current: Option<&ItemState>, expected: Option<&ItemState>) -> bool {
+    match (current, expected) {
+        (None, None) => false,
+        (Some(current), Some(expected)) => {
+            // The ids aren't journaled with the item; the state keeps the location and category ones
+            let item = |state: &ItemState| InventoryItem {
+                id: None,
+                location: None,
+                category: None,
+                location_id: None,
+                category_id: None,
+                ..state.item.clone()
+            };
+            item(current) != item(expected)
+                || current.location_id != expected.location_id
+                || current.category_id != expected.category_id
+                || !same_tags(&current.tags, &expected.tags)
+                || current.loans != expected.loans
+        }
+        _ => true,
+    }
+}
+
+/// Whether two items carry the same tag links. The ids of the links are left out, restoring
+/// links them again under new ones
+pub fn same_tags(a: &[ItemTag], b: &[ItemTag]) -> bool {
+    let key = |tag: &ItemTag| (tag.uid.clone(), tag.identifier.clone(), tag.attached_at.clone(), tag.retired_at.clone());
+    a.iter().map(key).eq(b.iter().map(key))
+}
@@ -0,0 +1,241 @@
+// inventory/view.rs
+// What the inventory list shows: a search, filters that all have to match and the sort
+// order. Views can be saved by name in the app config, and exports use the same view so
+// they hold exactly the rows on screen.
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::inventory::model::{path_is_within, InventoryItem, DATE_FORMAT};
+
+/// Filters on the item list; unset ones let everything through
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ItemFilter {
+    /// Category path; items in sub-categories match too
+    #[serde(default)]
+    pub category: Option<String>,
+    /// Location path; items in sub-locations match too
+    #[serde(default)]
+    pub location: Option<String>,
+    #[serde(default)]
+    pub min_quantity: Option<i32>,
+    #[serde(default)]
+    pub max_quantity: Option<i32>,
+    /// YYYY-MM-DD; items changed on or after that day
+    #[serde(default)]
+    pub updated_since: Option<String>,
+    /// Only items below their minimum stock
+    #[serde(default)]
+    pub low_stock: bool,
+}
+
+impl ItemFilter {
+    pub fn is_empty(&self) -> bool {
+        *self == ItemFilter::default()
+    }
+
+    pub fn updated_since_date(&self) -> Option<NaiveDate> {
+        self.updated_since.as_deref().and_then(|date| NaiveDate::parse_from_str(date, DATE_FORMAT).ok())
+    }
+
+    /// Whether `item` passes; `min_quantity` is its minimum stock, its own or its category's
+    pub fn matches(&self, item: &InventoryItem, min_quantity: Option<i32>) -> bool {
+        let within = |path: &Option<String>, filter: &Option<String>| match filter {
+            Some(filter) => path.as_deref().is_some_and(|path| path_is_within(path, filter)),
+            None => true,
+        };
+
+        within(&item.category, &self.category)
+            && within(&item.location, &self.location)
+            && self.min_quantity.is_none_or(|min| item.quantity >= min)
+            && self.max_quantity.is_none_or(|max| item.quantity <= max)
+            && self.updated_since_date().is_none_or(|since| updated_on(item).is_some_and(|day| day >= since))
+            && (!self.low_stock || min_quantity.is_some_and(|min| item.quantity < min))
+    }
+
+    /// Short description for status lines, e.g. "category Tools, qty 1-5"
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(category) = &self.category {
+            parts.push(format!("category {}", category));
+        }
+        if let Some(location) = &self.location {
+            parts.push(format!("location {}", location));
+        }
+        match (self.min_quantity, self.max_quantity) {
+            (Some(min), Some(max)) => parts.push(format!("qty {}-{}", min, max)),
+            (Some(min), None) => parts.push(format!("qty >= {}", min)),
+            (None, Some(max)) => parts.push(format!("qty <= {}", max)),
+            (None, None) => {}
+        }
+        if let Some(since) = &self.updated_since {
+            parts.push(format!("updated since {}", since));
+        }
+        if self.low_stock {
+            parts.push("low stock".to_string());
+        }
+        parts.join(", ")
+    }
+}
+
+// Local day the item was last changed
+fn updated_on(item: &InventoryItem) -> Option<NaiveDate> {
+    DateTime::parse_from_rfc3339(&item.last_updated)
+        .ok()
+        .map(|timestamp| timestamp.with_timezone(&Local).date_naive())
+}
+
+/// A column the list can be sorted by
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum SortColumn {
+    TagId,
+    Name,
+    Quantity,
+    Category,
+    Location,
+    Updated,
+}
+
+impl SortColumn {
+    pub const ALL: [SortColumn; 6] = [
+        SortColumn::TagId,
+        SortColumn::Name,
+        SortColumn::Quantity,
+        SortColumn::Category,
+        SortColumn::Location,
+        SortColumn::Updated,
+    ];
+
+    /// Used on the command line, e.g. `--sort category,-qty`
+    pub fn key(&self) -> &'static str {
+        match self {
+            SortColumn::TagId => "tag",
+            SortColumn::Name => "name",
+            SortColumn::Quantity => "qty",
+            SortColumn::Category => "category",
+            SortColumn::Location => "location",
+            SortColumn::Updated => "updated",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<SortColumn> {
+        SortColumn::ALL.into_iter().find(|column| column.key().eq_ignore_ascii_case(key))
+    }
+
+    fn compare(&self, a: &InventoryItem, b: &InventoryItem) -> Ordering {
+        // Text sorts without regard to case, items without a value go last
+        let text = |a: Option<&str>, b: Option<&str>| match (a, b) {
+            (Some(a), Some(b)) => a.to_lowercase().cmp(&b.to_lowercase()),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        };
+
+        match self {
+            SortColumn::TagId => a.tag_id.to_hex().cmp(&b.tag_id.to_hex()),
+            SortColumn::Name => text(Some(&a.name), Some(&b.name)),
+            SortColumn::Quantity => a.quantity.cmp(&b.quantity),
+            SortColumn::Category => text(a.category.as_deref(), b.category.as_deref()),
+            SortColumn::Location => text(a.location.as_deref(), b.location.as_deref()),
+            SortColumn::Updated => a.last_updated.cmp(&b.last_updated),
+        }
+    }
+}
+
+/// One level of the sort order
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SortKey {
+    pub column: SortColumn,
+    #[serde(default)]
+    pub descending: bool,
+}
+
+impl fmt::Display for SortKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", if self.descending { "-" } else { "" }, self.column.key())
+    }
+}
+
+impl FromStr for SortKey {
+    type Err = String;
+
+    // "qty" sorts ascending, "-qty" descending
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (descending, key) = match s.strip_prefix('-') {
+            Some(key) => (true, key),
+            None => (false, s),
+        };
+        let column = SortColumn::from_key(key).ok_or_else(|| {
+            let keys: Vec<&str> = SortColumn::ALL.iter().map(|column| column.key()).collect();
+            format!("Unknown sort column '{}', expected {}", key, keys.join(", "))
+        })?;
+
+        Ok(SortKey { column, descending })
+    }
+}
+
+/// Sort `items` by the first key, then the next for ties, and so on; with no keys the order is kept
+pub fn sort_items(items: &mut [InventoryItem], keys: &[SortKey]) {
+    if keys.is_empty() {
+        return;
+    }
+
+    items.sort_by(|a, b| {
+        keys.iter()
+            .map(|key| {
+                let order = key.column.compare(a, b);
+                if key.descending { order.reverse() } else { order }
+            })
+            .find(|order| order.is_ne())
+            .unwrap_or(Ordering::Equal)
+    });
+}
+
+/// Click on a column: a new column becomes the only key (or, with `add`, the last one),
+/// clicking the sorted column again flips its direction
+pub fn toggle_sort(keys: &mut Vec<SortKey>, column: SortColumn, add: bool) {
+    match keys.iter().position(|key| key.column == column) {
+        Some(index) if add || keys.len() == 1 => keys[index].descending = !keys[index].descending,
+        _ if add => keys.push(SortKey { column, descending: false }),
+        _ => *keys = vec![SortKey { column, descending: false }],
+    }
+}
+
+/// A named combination of search, filters and sort order
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct InventoryView {
+    #[serde(default)]
+    pub name: String,
+    /// Search query, see `SearchQuery::parse`; with no sort keys results stay in relevance order
+    #[serde(default)]
+    pub search: String,
+    #[serde(default)]
+    pub filter: ItemFilter,
+    #[serde(default)]
+    pub sort: Vec<SortKey>,
+}
+
+impl InventoryView {
+    /// The whole inventory by name, as the list shows it without a view
+    pub fn all() -> InventoryView {
+        InventoryView {
+            name: "All items".to_string(),
+            sort: vec![SortKey { column: SortColumn::Name, descending: false }],
+            ..InventoryView::default()
+        }
+    }
+
+    /// Sort order as text, e.g. "category,-qty"
+    pub fn sort_text(&self) -> String {
+        self.sort.iter().map(SortKey::to_string).collect::<Vec<_>>().join(",")
+    }
+
+    pub fn parse_sort(text: &str) -> Result<Vec<SortKey>, String> {
+        text.split(',').filter(|part| !part.trim().is_empty()).map(str::parse).collect()
+    }
+}
@@ -0,0 +1,178 @@
+// keyboard_layout/calibration.rs
+// Learns a layout definition from reads of reference cards whose real UID is
+// known: each typed character is lined up with the hex digit it stands for.
+use std::collections::BTreeMap;
+
+use crate::error::{Error, Result};
+use crate::keyboard_layout::{default_layouts, KeyboardLayout, LayoutDefinition};
+use crate::uid::Uid;
+
+const HEX_DIGITS: &str = "0123456789ABCDEF";
+
+/// A typed character that has stood for more than one hex digit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ambiguity {
+    pub typed: char,
+    /// Every digit seen for the character, with how often, most frequent first
+    pub digits: Vec<(char, usize)>,
+}
+
+/// Mapping learned so far from one or more reference reads
+#[derive(Debug, Clone, Default)]
+pub struct Calibration {
+    // Typed character -> digit it stood for -> times seen
+    observed: BTreeMap<char, BTreeMap<char, usize>>,
+    samples: usize,
+}
+
+impl Calibration {
+    pub fn new() -> Self {
+        Calibration::default()
+    }
+
+    /// Line up one raw read with the UID printed on (or known for) the card.
+    ///
+    /// Whitespace is ignored; every other typed character must pair with one
+    /// hex digit, so the reader has to be set to plain hex output.
+    pub fn add_sample(&mut self, raw: &str, true_uid: &Uid) -> Result<()> {
+        let typed: Vec<char> = raw.chars().filter(|c| !c.is_whitespace()).collect();
+        let expected: Vec<char> = true_uid.to_hex().chars().collect();
+
+        if typed.len() != expected.len() {
+            return Err(Error::Calibration(format!(
+                "the reader typed {} characters but UID {} has {} hex digits; \
+                 set the reader to plain hex output without prefixes or suffixes",
+                typed.len(),
+                true_uid.to_hex(),
+                expected.len()
+            )));
+        }
+
+        for (typed, digit) in typed.into_iter().zip(expected) {
+            *self.observed.entry(typed).or_default().entry(digit).or_insert(0) += 1;
+        }
+        self.samples += 1;
+        Ok(())
+    }
+
+    /// Number of reads added so far
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    /// Characters that were typed for different digits in different places
+    pub fn ambiguities(&self) -> Vec<Ambiguity> {
+        self.observed
+            .iter()
+            .filter(|(_, digits)| digits.len() > 1)
+            .map(|(typed, digits)| {
+                let mut digits: Vec<(char, usize)> = digits.iter().map(|(d, n)| (*d, *n)).collect();
+                digits.sort_by_key(|(_, n)| std::cmp::Reverse(*n));
+                Ambiguity { typed: *typed, digits }
+            })
+            .collect()
+    }
+
+    /// Hex digits none of the reference cards contained, so their keys are still unknown
+    pub fn missing_digits(&self) -> Vec<char> {
+        HEX_DIGITS
+            .chars()
+            .filter(|digit| !self.observed.values().any(|digits| digits.contains_key(digit)))
+            .collect()
+    }
+
+    /// Typed character -> digit, taking the most frequent digit for ambiguous characters
+    pub fn mapping(&self) -> BTreeMap<char, char> {
+        self.observed
+            .iter()
+            .filter_map(|(typed, digits)| {
+                let (digit, _) = digits.iter().max_by_key(|(_, n)| **n)?;
+                Some((*typed, *digit))
+            })
+            .collect()
+    }
+
+    /// Build the layout definition; characters that already type their own digit need no entry
+    pub fn to_layout(&self, name: &str) -> Result<LayoutDefinition> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(Error::Calibration("a calibrated layout needs a name".to_string()));
+        }
+        if default_layouts().iter().any(|layout| layout.name.eq_ignore_ascii_case(name)) {
+            return Err(Error::Calibration(format!("'{}' is a built-in layout; choose another name", name)));
+        }
+        // Configs and --layout would read these as auto-detect or a layout number
+        if KeyboardLayout::AutoDetect.name().eq_ignore_ascii_case(name) || name.parse::<i64>().is_ok() {
+            return Err(Error::Calibration(format!("'{}' is a reserved layout name; choose another name", name)));
+        }
+        if self.samples == 0 {
+            return Err(Error::Calibration("scan at least one reference card first".to_string()));
+        }
+
+        let keys = self
+            .mapping()
+            .into_iter()
+            .filter(|(typed, digit)| !typed.eq_ignore_ascii_case(digit))
+            .collect();
+
+        Ok(LayoutDefinition {
+            name: name.to_string(),
+            description: format!("Calibrated from {} reference read(s)", self.samples),
+            keys,
+        })
+    }
+
+    /// Summary of what has been learned, for showing to the user
+    pub fn report(&self) -> String {
+        let mut report = format!("Reference reads: {}\n\nLearned keys:\n", self.samples);
+
+        for (typed, digit) in self.mapping() {
+            let note = if typed.eq_ignore_ascii_case(&digit) { "  (unchanged)" } else { "" };
+            report.push_str(&format!("  '{}' -> {}{}\n", typed, digit, note));
+        }
+
+        let ambiguities = self.ambiguities();
+        if !ambiguities.is_empty() {
+            report.push_str("\nAmbiguous keys (most frequent digit is used):\n");
+            for ambiguity in ambiguities {
+                let digits: Vec<String> = ambiguity.digits.iter().map(|(d, n)| format!("{} x{}", d, n)).collect();
+                report.push_str(&format!("  '{}' -> {}\n", ambiguity.typed, digits.join(", ")));
+            }
+        }
+
+        let missing = self.missing_digits();
+        if !missing.is_empty() {
+            let missing: String = missing.into_iter().collect();
+            report.push_str(&format!("\nDigits not seen yet: {}\nScan another card containing them to complete the layout.\n", missing));
+        }
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn calibrated() -> Calibration {
+        let mut calibration = Calibration::new();
+        calibration.add_sample("à4a1b2c3", &Uid::from_hex("04A1B2C3").unwrap()).unwrap();
+        calibration
+    }
+
+    #[test]
+    fn reserved_and_built_in_names_are_rejected() {
+        let calibration = calibrated();
+        for name in ["", "  ", "Auto-detect", "auto-DETECT", "Windows", "azerty (french)", "3", " 0 "] {
+            assert!(calibration.to_layout(name).is_err(), "'{}' should be rejected", name);
+        }
+    }
+
+    #[test]
+    fn other_names_are_accepted() {
+        let layout = calibrated().to_layout("  Front desk reader ").unwrap();
+        assert_eq!(layout.name, "Front desk reader");
+        assert_eq!(layout.keys.get(&'à'), Some(&'0'));
+        assert_eq!(layout.keys.get(&'b'), None);
+    }
+}
@@ -0,0 +1,371 @@
+// keyboard_layout/mod.rs
+// Keyboard-wedge readers type the UID as if the host had a US keyboard. On any
+// other layout the keys come out as different characters; a layout definition
+// maps those characters back to what the reader meant to type.
+pub mod calibration;
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::RwLock;
+
+use once_cell::sync::Lazy;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::error::{Error, Result};
+use crate::manufacturer::{self, ManufacturerSource};
+use crate::uid::Uid;
+
+pub use calibration::{Ambiguity, Calibration};
+
+// Shipped definitions, in the order of KeyboardLayout::BUILT_IN
+const DEFAULT_LAYOUTS: [&str; 7] = [
+    include_str!("defaults/windows.json"),
+    include_str!("defaults/mac_us.json"),
+    include_str!("defaults/mac_intl.json"),
+    include_str!("defaults/azerty.json"),
+    include_str!("defaults/qwertz.json"),
+    include_str!("defaults/dvorak.json"),
+    include_str!("defaults/nordic.json"),
+];
+
+/// Where user layout files are looked for unless the config says otherwise
+pub const DEFAULT_USER_DIRECTORY: &str = "./keyboard_layouts";
+
+/// How one keyboard layout garbles what the reader types
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct LayoutDefinition {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Character that arrives on this layout -> character the reader meant
+    pub keys: BTreeMap<char, char>,
+}
+
+impl LayoutDefinition {
+    /// Undo the layout; hex digits and format markers that aren't remapped pass through
+    pub fn decode(&self, input: &str) -> String {
+        input
+            .chars()
+            .filter_map(|c| self.keys.get(&c).copied().or_else(|| passes_through(c).then_some(c)))
+            .collect()
+    }
+
+    /// Number of characters in `input` this layout has no meaning for
+    pub fn unknown_chars(&self, input: &str) -> usize {
+        input
+            .chars()
+            .filter(|c| !self.keys.contains_key(c) && !passes_through(*c) && !c.is_whitespace())
+            .count()
+    }
+}
+
+// Characters every layout types the same: hex digits, the reader's format markers and separators
+fn passes_through(c: char) -> bool {
+    c.is_ascii_hexdigit() || matches!(c, 'h' | 'r' | '-' | ' ')
+}
+
+static LAYOUTS: Lazy<RwLock<Vec<LayoutDefinition>>> = Lazy::new(|| RwLock::new(default_layouts()));
+
+/// The shipped layout definitions
+pub fn default_layouts() -> Vec<LayoutDefinition> {
+    DEFAULT_LAYOUTS
+        .iter()
+        .map(|json| serde_json::from_str(json).expect("shipped keyboard layout is valid JSON"))
+        .collect()
+}
+
+/// All known layouts: shipped ones first, then user files and calibrated ones, in layout-number order from 1
+pub fn layouts() -> Vec<LayoutDefinition> {
+    LAYOUTS.read().map(|layouts| layouts.clone()).unwrap_or_else(|_| default_layouts())
+}
+
+/// Add a layout, replacing one with the same name
+pub fn register_layout(layout: LayoutDefinition) {
+    if let Ok(mut layouts) = LAYOUTS.write() {
+        match layouts.iter_mut().find(|existing| existing.name == layout.name) {
+            Some(existing) => *existing = layout,
+            None => layouts.push(layout),
+        }
+    }
+}
+
+/// Load every `*.json` definition in `dir`; a missing directory just means no user layouts
+pub fn load_user_layouts(dir: &str) -> Result<usize> {
+    if !Path::new(dir).is_dir() {
+        return Ok(0);
+    }
+
+    let entries = fs::read_dir(dir)
+        .map_err(|e| Error::io(format!("Failed to read keyboard layout directory {}", dir), e))?;
+
+    let mut paths: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+
+    let mut count = 0;
+    for path in paths {
+        let data = fs::read_to_string(&path)
+            .map_err(|e| Error::io(format!("Failed to read {}", path.display()), e))?;
+        let layout: LayoutDefinition = serde_json::from_str(&data)
+            .map_err(|e| Error::parse(format!("Invalid keyboard layout {}", path.display()), e))?;
+
+        register_layout(layout);
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+/// The keyboard layout raw reads are decoded with
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub enum KeyboardLayout {
+    /// Try every layout and keep the most plausible result
+    #[default]
+    AutoDetect,
+    Windows,
+    MacUs,
+    MacIntl,
+    Azerty,
+    Qwertz,
+    Dvorak,
+    Nordic,
+    /// A layout from the user layout directory or the calibration wizard
+    Custom(String),
+}
+
+impl KeyboardLayout {
+    /// The shipped layouts, in the order of their definitions
+    pub const BUILT_IN: [KeyboardLayout; 7] = [
+        KeyboardLayout::Windows,
+        KeyboardLayout::MacUs,
+        KeyboardLayout::MacIntl,
+        KeyboardLayout::Azerty,
+        KeyboardLayout::Qwertz,
+        KeyboardLayout::Dvorak,
+        KeyboardLayout::Nordic,
+    ];
+
+    /// Name shown in menus and stored in the config; built-in names match defaults/*.json
+    pub fn name(&self) -> &str {
+        match self {
+            KeyboardLayout::AutoDetect => "Auto-detect",
+            KeyboardLayout::Windows => "Windows",
+            KeyboardLayout::MacUs => "Mac US",
+            KeyboardLayout::MacIntl => "Mac International",
+            KeyboardLayout::Azerty => "AZERTY (French)",
+            KeyboardLayout::Qwertz => "QWERTZ (German)",
+            KeyboardLayout::Dvorak => "Dvorak",
+            KeyboardLayout::Nordic => "Nordic",
+            KeyboardLayout::Custom(name) => name,
+        }
+    }
+
+    /// The layout called `name`; anything that isn't built in is a custom layout
+    pub fn from_name(name: &str) -> KeyboardLayout {
+        std::iter::once(KeyboardLayout::AutoDetect)
+            .chain(KeyboardLayout::BUILT_IN)
+            .find(|layout| layout.name() == name)
+            .unwrap_or_else(|| KeyboardLayout::Custom(name.to_string()))
+    }
+
+    /// Auto-detect followed by every known layout, for menus and choice lists
+    pub fn all() -> Vec<KeyboardLayout> {
+        std::iter::once(KeyboardLayout::AutoDetect)
+            .chain(layouts().iter().map(|definition| KeyboardLayout::from_name(&definition.name)))
+            .collect()
+    }
+
+    pub fn is_auto_detect(&self) -> bool {
+        *self == KeyboardLayout::AutoDetect
+    }
+
+    /// The definition behind this layout; `None` for auto-detect and custom layouts that aren't loaded
+    pub fn definition(&self) -> Option<LayoutDefinition> {
+        if self.is_auto_detect() {
+            return None;
+        }
+        layouts().into_iter().find(|definition| definition.name == self.name())
+    }
+
+    /// Undo keyboard-wedge encoding, auto-detecting when there is no definition to use
+    pub fn decode(&self, raw: &str) -> String {
+        match self.definition() {
+            Some(definition) => definition.decode(raw),
+            // Best effort even when nothing yields a UID, so the error shows what was decoded
+            None => score_layouts(raw).into_iter().next().map(|best| best.decoded).unwrap_or_default(),
+        }
+    }
+
+    // Configs written before layouts were stored by name hold 0 for auto-detect
+    // and 1.. for the built-in layouts
+    fn from_legacy_number(number: i64) -> KeyboardLayout {
+        usize::try_from(number)
+            .ok()
+            .and_then(|number| number.checked_sub(1))
+            .and_then(|index| KeyboardLayout::BUILT_IN.get(index).cloned())
+            .unwrap_or_default()
+    }
+}
+
+impl fmt::Display for KeyboardLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for KeyboardLayout {
+    type Err = String;
+
+    /// A position in `KeyboardLayout::all()` (as listed by the CLI) or a known layout name
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let all = KeyboardLayout::all();
+        if let Ok(index) = s.parse::<usize>() {
+            return all.get(index).cloned().ok_or_else(|| format!("no keyboard layout number {}", index));
+        }
+
+        all.into_iter()
+            .find(|layout| layout.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown keyboard layout '{}'", s))
+    }
+}
+
+// Stored by name so user layouts keep their meaning when others are added or removed
+impl Serialize for KeyboardLayout {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for KeyboardLayout {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        struct LayoutVisitor;
+
+        impl de::Visitor<'_> for LayoutVisitor {
+            type Value = KeyboardLayout;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "a keyboard layout name or legacy layout number")
+            }
+
+            fn visit_str<E: de::Error>(self, name: &str) -> std::result::Result<KeyboardLayout, E> {
+                Ok(KeyboardLayout::from_name(name))
+            }
+
+            fn visit_i64<E: de::Error>(self, number: i64) -> std::result::Result<KeyboardLayout, E> {
+                Ok(KeyboardLayout::from_legacy_number(number))
+            }
+
+            fn visit_u64<E: de::Error>(self, number: u64) -> std::result::Result<KeyboardLayout, E> {
+                Ok(KeyboardLayout::from_legacy_number(i64::try_from(number).unwrap_or(-1)))
+            }
+        }
+
+        deserializer.deserialize_any(LayoutVisitor)
+    }
+}
+
+/// Result of trying one layout on a raw read
+#[derive(Debug, Clone)]
+pub struct LayoutScore {
+    pub layout: KeyboardLayout,
+    pub decoded: String,
+    pub uid: Option<Uid>,
+    pub score: i32,
+}
+
+/// Decode `raw` with every layout and rank the results, best first.
+///
+/// A layout scores for producing a valid UID and for a UID whose manufacturer
+/// code is registered, and loses points for every character it can't explain.
+/// Ties keep the layout order, so plain Windows wins when nothing is remapped.
+pub fn score_layouts(raw: &str) -> Vec<LayoutScore> {
+    let mut scores: Vec<LayoutScore> = layouts()
+        .iter()
+        .map(|definition| {
+            let decoded = definition.decode(raw);
+            let hex: String = decoded.chars().filter(|c| c.is_ascii_hexdigit()).collect();
+            let uid = Uid::from_hex(&hex).ok();
+
+            let mut score = -5 * definition.unknown_chars(raw) as i32;
+            if let Some(uid) = &uid {
+                score += 100;
+                // Only double and triple size UIDs carry a real manufacturer code
+                if uid.as_bytes().len() > 4 && manufacturer::identify(uid).source != ManufacturerSource::Unknown {
+                    score += 10;
+                }
+            } else if !hex.is_empty() && hex.len().is_multiple_of(2) {
+                // Whole bytes at least, just not a UID length
+                score += 10;
+            }
+
+            LayoutScore { layout: KeyboardLayout::from_name(&definition.name), decoded, uid, score }
+        })
+        .collect();
+
+    // Stable, so equal scores stay in layout order
+    scores.sort_by_key(|s| std::cmp::Reverse(s.score));
+    scores
+}
+
+/// Pick the layout that turns `raw` into the most plausible UID; `None` when no layout yields one
+pub fn detect_layout(raw: &str) -> Option<LayoutScore> {
+    score_layouts(raw).into_iter().next().filter(|best| best.uid.is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_shipped_layout_loads() {
+        let definitions = default_layouts();
+        let names: Vec<&str> = definitions.iter().map(|definition| definition.name.as_str()).collect();
+        let built_in: Vec<&str> = KeyboardLayout::BUILT_IN.iter().map(KeyboardLayout::name).collect();
+        assert_eq!(names, built_in);
+
+        // Every file in defaults/ is one of them, so none is left out of DEFAULT_LAYOUTS
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/keyboard_layout/defaults");
+        let mut files = 0;
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            let definition: LayoutDefinition = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+            assert!(definitions.contains(&definition), "{} isn't shipped", path.display());
+            assert!(!definition.keys.is_empty(), "{} remaps nothing", path.display());
+            files += 1;
+        }
+        assert_eq!(files, DEFAULT_LAYOUTS.len());
+    }
+
+    #[test]
+    fn an_azerty_read_is_detected_as_azerty() {
+        // 04A1B2C3D4E5F6 typed by a US reader into a French host: unshifted digits and A on the Q key
+        let best = detect_layout("à'Q&BéC\"D'E(F-").unwrap();
+        assert_eq!(best.layout, KeyboardLayout::Azerty);
+        assert_eq!(best.uid, Some(Uid::from_hex("04A1B2C3D4E5F6").unwrap()));
+        assert_eq!(KeyboardLayout::AutoDetect.decode("à'Q&BéC\"D'E(F-"), "04A1B2C3D4E5F6");
+    }
+
+    #[test]
+    fn a_plain_hex_read_is_detected_as_the_default_layout() {
+        let best = detect_layout("04A1B2C3D4E5F6").unwrap();
+        assert_eq!(best.layout, KeyboardLayout::BUILT_IN[0]);
+        assert_eq!(best.uid, Some(Uid::from_hex("04A1B2C3D4E5F6").unwrap()));
+
+        // Every layout gets a score, the winner first
+        let scores = score_layouts("04A1B2C3D4E5F6");
+        assert_eq!(scores.len(), layouts().len());
+        assert!(scores.windows(2).all(|pair| pair[0].score >= pair[1].score));
+    }
+
+    #[test]
+    fn garbage_is_not_detected_as_any_layout() {
+        for raw in ["", "zzzz", "?!*~ §§"] {
+            assert!(detect_layout(raw).is_none(), "'{}' was detected", raw);
+        }
+    }
+}
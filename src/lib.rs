@@ -0,0 +1,18 @@
+// lib.rs
+// GUI-free core: UID decoding, the CardRecord export pipeline, the inventory
+// database and the sync engines. The FLTK app in main.rs and mifare-cli are
+// both thin frontends over these modules.
+pub mod error;
+pub mod uid;
+pub mod card_family;
+pub mod manufacturer;
+pub mod convert;
+pub mod keyboard_layout;
+pub mod wiegand;
+pub mod utils;
+pub mod batch;
+pub mod card_reader;
+pub mod config;
+pub mod export;
+pub mod inventory;
+pub mod sync;
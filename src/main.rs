@@ -1,14 +1,11 @@
 // main.rs
 mod ui;
 mod reader;
-mod utils;
-mod batch;
-mod config;
-mod export;
-mod inventory;
 mod db_viewer;
 mod app;
-mod sync;
+
+// Shared modules live in the library crate so mifare-cli can use them too
+use mifare_reader_utility::{batch, card_family, card_reader, config, convert, error, export, inventory, keyboard_layout, manufacturer, sync, uid, utils, wiegand};
 
 use fltk::{
     prelude::*,
@@ -39,16 +36,17 @@ fn main() {
     let sender_log = sender.clone();
     let sender_exit = sender.clone();
     let sender_pref = sender.clone();
-    let sender_kb_auto = sender.clone();
-    let sender_kb_win = sender.clone();
-    let sender_kb_mac = sender.clone();
-    let sender_kb_intl = sender.clone();
     let sender_about = sender.clone();
     let sender_import = sender.clone();
     let sender_view_db = sender.clone();
     let sender_check_files = sender.clone();
     let sender_gdrive_export = sender.clone();
     let sender_gdrive_import = sender.clone();
+    let sender_stocktake = sender.clone();
+    let sender_locations = sender.clone();
+    let sender_categories = sender.clone();
+    let sender_reorder = sender.clone();
+    let sender_trash = sender.clone();
     
     // Add menu items
     menu.add(
@@ -87,75 +85,84 @@ fn main() {
     );
     
     menu.add(
-        "&File/&Check Import Files\t",
-        fltk::enums::Shortcut::Ctrl | 'r',
+        "&File/&Locations...\t",
+        fltk::enums::Shortcut::None,
         MenuFlag::Normal,
-        move |_| { sender_check_files.send("check_files".to_string()); }
+        move |_| { sender_locations.send("locations".to_string()); }
     );
     
     menu.add(
-        "&File/&Google Drive/Export Database\t",
+        "&File/&Categories...\t",
         fltk::enums::Shortcut::None,
         MenuFlag::Normal,
-        move |_| { sender_gdrive_export.send("gdrive_export".to_string()); }
+        move |_| { sender_categories.send("categories".to_string()); }
     );
     
     menu.add(
-        "&File/&Google Drive/Import Database\t",
+        "&File/Stock&take...\t",
         fltk::enums::Shortcut::None,
         MenuFlag::Normal,
-        move |_| { sender_gdrive_import.send("gdrive_import".to_string()); }
+        move |_| { sender_stocktake.send("stocktake".to_string()); }
     );
     
     menu.add(
-        "&File/&Save Log\t",
-        fltk::enums::Shortcut::Ctrl | 's',
+        "&File/&Reorder List...\t",
+        fltk::enums::Shortcut::None,
         MenuFlag::Normal,
-        move |_| { sender_log.send("save_log".to_string()); }
+        move |_| { sender_reorder.send("reorder".to_string()); }
     );
     
     menu.add(
-        "&File/E&xit\t",
-        fltk::enums::Shortcut::Ctrl | 'q',
+        "&File/&Trash...\t",
+        fltk::enums::Shortcut::None,
         MenuFlag::Normal,
-        move |_| { sender_exit.send("exit".to_string()); }
+        move |_| { sender_trash.send("trash".to_string()); }
     );
     
     menu.add(
-        "&Edit/&Preferences\t",
-        fltk::enums::Shortcut::Ctrl | 'p',
+        "&File/&Check Import Files\t",
+        fltk::enums::Shortcut::Ctrl | 'r',
         MenuFlag::Normal,
-        move |_| { sender_pref.send("preferences".to_string()); }
+        move |_| { sender_check_files.send("check_files".to_string()); }
     );
     
     menu.add(
-        "&Edit/&Keyboard Layout/&Auto-detect\t",
+        "&File/&Google Drive/Export Database\t",
         fltk::enums::Shortcut::None,
         MenuFlag::Normal,
-        move |_| { sender_kb_auto.send("kb_auto".to_string()); }
+        move |_| { sender_gdrive_export.send("gdrive_export".to_string()); }
     );
     
     menu.add(
-        "&Edit/&Keyboard Layout/&Windows\t",
+        "&File/&Google Drive/Import Database\t",
         fltk::enums::Shortcut::None,
         MenuFlag::Normal,
-        move |_| { sender_kb_win.send("kb_windows".to_string()); }
+        move |_| { sender_gdrive_import.send("gdrive_import".to_string()); }
     );
     
     menu.add(
-        "&Edit/&Keyboard Layout/&Mac US\t",
-        fltk::enums::Shortcut::None,
+        "&File/&Save Log\t",
+        fltk::enums::Shortcut::Ctrl | 's',
         MenuFlag::Normal,
-        move |_| { sender_kb_mac.send("kb_mac_us".to_string()); }
+        move |_| { sender_log.send("save_log".to_string()); }
     );
     
     menu.add(
-        "&Edit/&Keyboard Layout/Mac &International\t",
-        fltk::enums::Shortcut::None,
+        "&File/E&xit\t",
+        fltk::enums::Shortcut::Ctrl | 'q',
+        MenuFlag::Normal,
+        move |_| { sender_exit.send("exit".to_string()); }
+    );
+    
+    menu.add(
+        "&Edit/&Preferences\t",
+        fltk::enums::Shortcut::Ctrl | 'p',
         MenuFlag::Normal,
-        move |_| { sender_kb_intl.send("kb_mac_intl".to_string()); }
+        move |_| { sender_pref.send("preferences".to_string()); }
     );
     
+    app::menu::add_keyboard_layout_menu(&mut menu, &sender);
+    
     menu.add(
         "&Help/&About\t",
         fltk::enums::Shortcut::None,
@@ -172,18 +179,18 @@ fn main() {
     let app_config = Rc::new(RefCell::new(config::load_config()));
     
     // Create shared state for keyboard layout selection
-    let keyboard_layout = Rc::new(RefCell::new(app_config.borrow().default_keyboard_layout));
+    let keyboard_layout = Rc::new(RefCell::new(app_config.borrow().default_keyboard_layout.clone()));
     
     // Create card data buffer to share between tabs
     let card_data_buffer = Rc::new(RefCell::new(fltk::text::TextBuffer::default()));
     
     // Create the basic UI tabs first
-    ui::create_reader_tab(&mut tabs, keyboard_layout.clone(), card_data_buffer.clone());
+    ui::create_reader_tab(&mut tabs, keyboard_layout.clone(), card_data_buffer.clone(), app_config.clone());
     ui::create_conversion_tab(&mut tabs, keyboard_layout.clone());
     ui::create_batch_tab(&mut tabs, keyboard_layout.clone());
     
     // Try to initialize inventory tab with better error handling
-    let inventory_ui = match inventory::InventoryUI::new("inventory.db") {
+    let inventory_ui = match ui::inventory::InventoryUI::new("inventory.db") {
         Ok(ui) => {
             println!("Successfully initialized inventory database");
             let ui_rc = Rc::new(ui);
@@ -217,7 +224,7 @@ fn main() {
     
     // Create inventory tab - we reach here only if initialization succeeded
     println!("Adding inventory tab");
-    inventory_ui.create_tab(&mut tabs);
+    inventory_ui.create_tab(&mut tabs, app_config.clone());
     
     tabs.end();
     
@@ -0,0 +1,279 @@
+// manufacturer.rs
+// IC manufacturer lookup for the first UID byte, backed by the ISO/IEC 7816-6
+// registration table with optional user overrides from the config file.
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::RwLock;
+
+use once_cell::sync::Lazy;
+
+use crate::uid::Uid;
+
+/// ISO/IEC 7816-6 IC manufacturer registration (amendment 1 and later), as used in
+/// double and triple size ISO14443-3 UIDs and ISO15693 UIDs
+pub const REGISTRY: &[(u8, &str)] = &[
+    (0x01, "Motorola"),
+    (0x02, "STMicroelectronics"),
+    (0x03, "Hitachi"),
+    (0x04, "NXP Semiconductors"),
+    (0x05, "Infineon Technologies"),
+    (0x06, "Cylink"),
+    (0x07, "Texas Instruments"),
+    (0x08, "Fujitsu"),
+    (0x09, "Matsushita Electronics"),
+    (0x0A, "NEC"),
+    (0x0B, "Oki Electric Industry"),
+    (0x0C, "Toshiba"),
+    (0x0D, "Mitsubishi Electric"),
+    (0x0E, "Samsung Electronics"),
+    (0x0F, "Hynix"),
+    (0x10, "LG Semiconductors"),
+    (0x11, "Emosyn-EM Microelectronics"),
+    (0x12, "Inside Technology"),
+    (0x13, "Orga Kartensysteme"),
+    (0x14, "Sharp"),
+    (0x15, "Atmel"),
+    (0x16, "EM Microelectronic-Marin SA"),
+    (0x17, "KSW Microtec"),
+    (0x18, "ZMD"),
+    (0x19, "Xicor"),
+    (0x1A, "Sony"),
+    (0x1B, "Malaysia Microelectronic Solutions"),
+    (0x1C, "Emosyn"),
+    (0x1D, "Shanghai Fudan Microelectronics"),
+    (0x1E, "Magellan Technology"),
+    (0x1F, "Melexis"),
+    (0x20, "Renesas Technology"),
+    (0x21, "TAGSYS"),
+    (0x22, "Transcore"),
+    (0x23, "Shanghai Belling"),
+    (0x24, "Masktech"),
+    (0x25, "Innovision Research and Technology"),
+    (0x26, "Hitachi ULSI Systems"),
+    (0x27, "Cypak"),
+    (0x28, "Ricoh"),
+    (0x29, "ASK"),
+    (0x2A, "Unicore Microsystems"),
+    (0x2B, "Dallas Semiconductor / Maxim"),
+    (0x2C, "Impinj"),
+    (0x2D, "RightPlug Alliance"),
+    (0x2E, "Broadcom"),
+    (0x2F, "MStar Semiconductor"),
+    (0x30, "BeeDar Technology"),
+    (0x31, "RFIDsec"),
+    (0x32, "Schweizer Electronic"),
+    (0x33, "AMIC Technology"),
+    (0x34, "Mikron"),
+    (0x35, "Fraunhofer Institute for Photonic Microsystems"),
+    (0x36, "IDS Microchip"),
+    (0x37, "Kovio"),
+    (0x38, "HMT Microelectronic"),
+    (0x39, "Silicon Craft Technology"),
+    (0x3A, "Advanced Film Device"),
+    (0x3B, "Nitecrest"),
+    (0x3C, "Verayo"),
+    (0x3D, "HID Global"),
+    (0x3E, "Productivity Engineering"),
+    (0x3F, "Austriamicrosystems"),
+    (0x40, "Gemalto"),
+    (0x41, "Renesas Electronics"),
+    (0x42, "3Alogics"),
+    (0x43, "Top TroniQ Asia"),
+    (0x44, "Gentag"),
+    (0x45, "Invengo Information Technology"),
+    (0x46, "Guangzhou Sysur Microelectronics"),
+    (0x47, "CEITEC"),
+    (0x48, "Shanghai Quanray Electronics"),
+    (0x49, "MediaTek"),
+    (0x4A, "Angstrem"),
+    (0x4B, "Celisic Semiconductor"),
+    (0x4C, "LEGIC Identsystems AG"),
+    (0x4D, "Balluff"),
+    (0x4E, "Oberthur Technologies"),
+    (0x4F, "Silterra Malaysia"),
+    (0x50, "DELTA Danish Electronics, Light & Acoustics"),
+    (0x51, "Giesecke & Devrient"),
+    (0x52, "Shenzhen China Vision Microelectronics"),
+    (0x53, "Shanghai Feiju Microelectronics"),
+    (0x54, "Intel"),
+    (0x55, "Microsensys"),
+    (0x56, "Sonix Technology"),
+    (0x57, "Qualcomm Technologies"),
+    (0x58, "Realtek Semiconductor"),
+    (0x59, "Freevision Technologies"),
+    (0x5A, "Giantec Semiconductor"),
+    (0x5B, "Angstrem-T"),
+    (0x5C, "StarChip"),
+    (0x5D, "Spirtech"),
+    (0x5E, "Gantner Electronic"),
+    (0x5F, "Nordic Semiconductor"),
+    (0x60, "Verisiti"),
+    (0x61, "Wearlinks Technology"),
+    (0x62, "Userstar Information Systems"),
+    (0x63, "Pragmatic Printing"),
+    (0x64, "LSI-TEC"),
+    (0x65, "Tendyron"),
+    (0x66, "MUTO Smart"),
+    (0x67, "ON Semiconductor"),
+    (0x68, "TÜBİTAK BİLGEM"),
+    (0x69, "Huada Semiconductor"),
+    (0x6A, "Seveney"),
+    (0x6B, "ISSM"),
+    (0x6C, "Wisesec"),
+    // Assigned in the register ahead of 0x7C but not named here yet; shown by
+    // code until they are, and the config file can override them meanwhile
+    (0x6D, "ISO/IEC 7816-6 registrant 6D"),
+    (0x6E, "ISO/IEC 7816-6 registrant 6E"),
+    (0x6F, "ISO/IEC 7816-6 registrant 6F"),
+    (0x70, "ISO/IEC 7816-6 registrant 70"),
+    (0x71, "ISO/IEC 7816-6 registrant 71"),
+    (0x72, "ISO/IEC 7816-6 registrant 72"),
+    (0x73, "ISO/IEC 7816-6 registrant 73"),
+    (0x74, "ISO/IEC 7816-6 registrant 74"),
+    (0x75, "ISO/IEC 7816-6 registrant 75"),
+    (0x76, "ISO/IEC 7816-6 registrant 76"),
+    (0x77, "ISO/IEC 7816-6 registrant 77"),
+    (0x78, "ISO/IEC 7816-6 registrant 78"),
+    (0x79, "ISO/IEC 7816-6 registrant 79"),
+    (0x7A, "ISO/IEC 7816-6 registrant 7A"),
+    (0x7B, "ISO/IEC 7816-6 registrant 7B"),
+    (0x7C, "DB HiTek"),
+    (0x7D, "SATO Vicinity"),
+    (0x7E, "Holtek"),
+];
+
+// ISO14443-3: a single size UID starting with 08 is a random ID generated at every power-up
+const RANDOM_UID_PREFIX: u8 = 0x08;
+
+// User entries from AppConfig.manufacturer_database, keyed by the code they were parsed to
+static OVERRIDES: Lazy<RwLock<HashMap<u8, String>>> = Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// Where a manufacturer name came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManufacturerSource {
+    /// The ISO/IEC 7816-6 registry
+    Registry,
+    /// An entry in the user's config file
+    Override,
+    /// The UID is a random ID and carries no manufacturer code
+    RandomUid,
+    /// The code is not registered and has no override
+    Unknown,
+}
+
+/// Result of a manufacturer lookup
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Manufacturer {
+    /// The first UID byte the lookup was based on
+    pub code: u8,
+    /// `None` for random and unknown codes
+    pub name: Option<String>,
+    pub source: ManufacturerSource,
+}
+
+impl Manufacturer {
+    pub fn is_random_uid(&self) -> bool {
+        self.source == ManufacturerSource::RandomUid
+    }
+}
+
+impl fmt::Display for Manufacturer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.name, self.source) {
+            (_, ManufacturerSource::RandomUid) => write!(f, "Random UID (no manufacturer code)"),
+            (Some(name), _) => write!(f, "{}", name),
+            (None, _) => write!(f, "Unknown manufacturer ({:02X})", self.code),
+        }
+    }
+}
+
+/// Name registered for a code in ISO/IEC 7816-6, ignoring overrides
+pub fn registry_name(code: u8) -> Option<&'static str> {
+    REGISTRY.iter().find(|(c, _)| *c == code).map(|(_, name)| *name)
+}
+
+/// Replace the user overrides; keys are hex codes such as "04" or "0x04"
+pub fn set_overrides(overrides: &HashMap<String, String>) {
+    let parsed = overrides
+        .iter()
+        .filter_map(|(code, name)| parse_code(code).map(|code| (code, name.clone())))
+        .collect();
+
+    if let Ok(mut current) = OVERRIDES.write() {
+        *current = parsed;
+    }
+}
+
+/// Look up a manufacturer code, user overrides first
+pub fn lookup_code(code: u8) -> Manufacturer {
+    let user_name = OVERRIDES.read().ok().and_then(|overrides| overrides.get(&code).cloned());
+
+    let (name, source) = match (user_name, registry_name(code)) {
+        (Some(name), _) => (Some(name), ManufacturerSource::Override),
+        (None, Some(name)) => (Some(name.to_string()), ManufacturerSource::Registry),
+        (None, None) => (None, ManufacturerSource::Unknown),
+    };
+
+    Manufacturer { code, name, source }
+}
+
+/// Identify the manufacturer of a UID, recognising random single size UIDs
+pub fn identify(uid: &Uid) -> Manufacturer {
+    let code = uid.manufacturer_code();
+
+    if uid.as_bytes().len() == 4 && code == RANDOM_UID_PREFIX {
+        return Manufacturer { code, name: None, source: ManufacturerSource::RandomUid };
+    }
+
+    lookup_code(code)
+}
+
+/// Parse a manufacturer code written as two hex digits, with or without 0x
+pub fn parse_code(code: &str) -> Option<u8> {
+    let code = code.trim();
+    let code = code.strip_prefix("0x").or_else(|| code.strip_prefix("0X")).unwrap_or(code);
+    u8::from_str_radix(code, 16).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_assigned_code_has_a_registry_name() {
+        let last = REGISTRY.last().unwrap().0;
+        for code in 0x01..=last {
+            assert!(registry_name(code).is_some(), "code {:02X} is missing from the registry", code);
+        }
+        assert_eq!(registry_name(0x00), None);
+        assert_eq!(registry_name(last + 1), None);
+
+        // Sorted and without duplicates, so the table stays easy to extend
+        assert!(REGISTRY.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+
+    #[test]
+    fn identify_uses_the_first_byte_and_spots_random_uids() {
+        let nxp: Uid = "04A1B2C3D4E5F6".parse().unwrap();
+        assert_eq!(identify(&nxp).name.as_deref(), Some("NXP Semiconductors"));
+        assert_eq!(identify(&nxp).source, ManufacturerSource::Registry);
+
+        let random: Uid = "08A1B2C3".parse().unwrap();
+        assert!(identify(&random).is_random_uid());
+        // Only single size UIDs are random; 08 in a longer UID is Fujitsu's code
+        let fujitsu: Uid = "08A1B2C3D4E5F6".parse().unwrap();
+        assert_eq!(identify(&fujitsu).name.as_deref(), Some("Fujitsu"));
+
+        let unknown = lookup_code(0xF0);
+        assert_eq!(unknown.source, ManufacturerSource::Unknown);
+        assert_eq!(unknown.to_string(), "Unknown manufacturer (F0)");
+    }
+
+    #[test]
+    fn codes_parse_with_or_without_a_prefix() {
+        assert_eq!(parse_code("04"), Some(0x04));
+        assert_eq!(parse_code(" 0x7e "), Some(0x7E));
+        assert_eq!(parse_code("0X1d"), Some(0x1D));
+        assert_eq!(parse_code("zz"), None);
+    }
+}
@@ -0,0 +1,111 @@
+// reader/calibration.rs
+// Wizard that learns a keyboard layout from reference cards with a known UID
+use fltk::{
+    button::Button,
+    enums::{CallbackTrigger, Color},
+    frame::Frame,
+    input::Input,
+    prelude::*,
+    text::{TextBuffer, TextDisplay},
+    window::Window,
+    dialog,
+};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::config::{self, AppConfig};
+use crate::keyboard_layout::{Calibration, KeyboardLayout};
+use crate::uid::Uid;
+
+pub fn open_calibration_wizard(kb_layout: Rc<RefCell<KeyboardLayout>>, app_config: Rc<RefCell<AppConfig>>) {
+    let mut wind = Window::new(250, 150, 560, 470, "Keyboard Layout Calibration");
+    wind.set_color(Color::White);
+
+    Frame::new(20, 10, 520, 50, "Type the real UID of a reference card, then scan the card into the scan field.\n\
+                                 Scan more cards until every hex digit has been seen.");
+
+    let uid_input = Input::new(170, 70, 370, 30, "Real UID (hex):");
+    let mut scan_input = Input::new(170, 110, 370, 30, "Scan card here:");
+    scan_input.set_trigger(CallbackTrigger::EnterKey);
+
+    let report_buffer = TextBuffer::default();
+    let mut report_display = TextDisplay::new(20, 150, 520, 260, "");
+    report_display.set_buffer(report_buffer.clone());
+
+    let name_input = Input::new(120, 420, 200, 30, "Layout name:");
+    let mut reset_btn = Button::new(330, 420, 60, 30, "Reset");
+    let mut save_btn = Button::new(400, 420, 60, 30, "Save");
+    let mut close_btn = Button::new(470, 420, 70, 30, "Close");
+
+    let calibration = Rc::new(RefCell::new(Calibration::new()));
+
+    let calibration_clone = calibration.clone();
+    let mut report_buffer_clone = report_buffer.clone();
+    scan_input.set_callback(move |inp| {
+        let raw = inp.value();
+        inp.set_value("");
+
+        let true_uid = match Uid::from_hex(&uid_input.value()) {
+            Ok(uid) => uid,
+            Err(e) => {
+                dialog::alert(300, 300, &format!("Enter the card's real UID first: {}", e));
+                return;
+            }
+        };
+
+        let mut calibration = calibration_clone.borrow_mut();
+        match calibration.add_sample(&raw, &true_uid) {
+            Ok(()) => report_buffer_clone.set_text(&calibration.report()),
+            Err(e) => dialog::alert(300, 300, &format!("Could not use this read: {}", e)),
+        }
+    });
+
+    let calibration_clone = calibration.clone();
+    let mut report_buffer_clone = report_buffer.clone();
+    reset_btn.set_callback(move |_| {
+        *calibration_clone.borrow_mut() = Calibration::new();
+        report_buffer_clone.set_text("");
+    });
+
+    let mut wind_clone = wind.clone();
+    save_btn.set_callback(move |_| {
+        let (layout, complete) = {
+            let calibration = calibration.borrow();
+            let complete = calibration.ambiguities().is_empty() && calibration.missing_digits().is_empty();
+            (calibration.to_layout(&name_input.value()), complete)
+        };
+        let layout = match layout {
+            Ok(layout) => layout,
+            Err(e) => {
+                dialog::alert(300, 300, &format!("Cannot save layout: {}", e));
+                return;
+            }
+        };
+
+        if !complete {
+            let question = "The layout is ambiguous or incomplete (see the report).\nSave it anyway?";
+            if dialog::choice2(300, 300, question, "Cancel", "Save", "") != Some(1) {
+                return;
+            }
+        }
+
+        let name = layout.name.clone();
+        match config::add_keyboard_layout(layout, &mut app_config.borrow_mut()) {
+            Ok(layout) => {
+                *kb_layout.borrow_mut() = layout;
+                dialog::message(300, 300, &format!("Saved layout '{}' and made it the active keyboard layout.", name));
+                wind_clone.hide();
+            }
+            Err(e) => dialog::alert(300, 300, &format!("Error saving layout: {}", e)),
+        }
+    });
+
+    let mut wind_clone = wind.clone();
+    close_btn.set_callback(move |_| {
+        wind_clone.hide();
+    });
+
+    wind.end();
+    wind.make_modal(true);
+    wind.show();
+}
@@ -1,5 +1,7 @@
 // reader/mod.rs
 pub mod ui;
+pub mod calibration;
 
 // Re-export the main reader functions for backwards compatibility
-pub use ui::{start_capture, set_inventory_ui};
\ No newline at end of file
+pub use ui::{start_capture, set_inventory_ui};
+pub use calibration::open_calibration_wizard;
\ No newline at end of file
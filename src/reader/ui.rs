@@ -1,7 +1,7 @@
 // reader/ui.rs
 use fltk::{
-    button::Button,
-    enums::{Color, CallbackTrigger, Font},
+    button::{Button, CheckButton},
+    enums::{Align, Color, CallbackTrigger, Font},
     frame::Frame,
     input::{Input, MultilineInput},
     prelude::*,
@@ -14,9 +14,17 @@ use fltk::{
 use std::cell::RefCell;
 use std::rc::Rc;
 
+use crate::card_family::CardFamily;
+use crate::card_reader::{pn532, CardEvent, CardReader, KeyboardWedgeReader, Pn532Reader, SimulatedReader};
+use crate::keyboard_layout::KeyboardLayout;
+use crate::manufacturer;
+use crate::uid::Uid;
 use crate::utils;
-use crate::inventory::InventoryUI;
-use crate::inventory::model::{create_inventory_item, generate_timestamp, InventoryItem};
+use crate::ui::inventory::InventoryUI;
+use crate::ui::inventory::components::choose_item;
+use crate::ui::inventory::utils::ChoiceExt;
+use crate::inventory::model::{create_inventory_item, generate_timestamp, InventoryItem, Location, MovementReason};
+use crate::inventory::scan::{self, ScanMode, ScanOptions};
 
 // Instead of a static variable, we'll use a more direct approach
 // through function parameters
@@ -31,21 +39,24 @@ pub fn set_inventory_ui(inventory_ui: &Rc<InventoryUI>) {
     }
 }
 
-pub fn start_capture(btn: &mut Button, card_buffer: Rc<RefCell<TextBuffer>>, kb_layout: Rc<RefCell<i32>>) {
+pub fn start_capture(btn: &mut Button, card_buffer: Rc<RefCell<TextBuffer>>, kb_layout: Rc<RefCell<KeyboardLayout>>) {
     if btn.label() == "Start Capture" {
         btn.set_label("Stop Capture");
         
         // Create a capture window
-        let mut capture_wind = Window::new(300, 300, 500, 200, "Card Capture");
+        let mut capture_wind = Window::new(300, 300, 500, 280, "Card Capture");
         capture_wind.set_color(Color::White);
         
         Frame::new(20, 20, 460, 40, "Present cards to the reader\nCard data will appear here:").set_label_size(14);
         
-        let mut capture_input = Input::new(20, 80, 460, 30, "");
+        let mut capture_input = Input::new(20, 80, 220, 30, "");
         capture_input.set_trigger(CallbackTrigger::EnterKey);
         
-        let card_buffer_clone = card_buffer.clone();
-        let kb_layout_clone = kb_layout.clone();
+        // Readers on a serial port (PN532) are polled instead of typing into the input
+        let mut serial_btn = Button::new(250, 80, 110, 30, "Serial Reader...");
+        
+        // Replays a script or saved log through the simulated reader, for demos and testing without hardware
+        let mut replay_btn = Button::new(370, 80, 110, 30, "Replay Script...");
         
         // Create a checkbox for inventory mode
         let inventory_mode = fltk::button::CheckButton::new(20, 120, 200, 30, "Update Inventory");
@@ -55,104 +66,99 @@ pub fn start_capture(btn: &mut Button, card_buffer: Rc<RefCell<TextBuffer>>, kb_
         let show_form = fltk::button::CheckButton::new(220, 120, 260, 30, "Show Item Form When Scanning");
         show_form.set_checked(true); // Enable by default
         
-        // Function to process card data
-        let show_form_clone = show_form.clone();
+        // What a scan of a known tag does; the title shows the active mode as well
+        let mut mode_choice = Choice::new(100, 160, 220, 30, "Scan mode:");
+        for mode in ScanMode::ALL {
+            mode_choice.add_choice(mode.name());
+        }
+        mode_choice.set_value(0);
+        capture_wind.set_label(&format!("Card Capture - {}", ScanMode::ALL[0]));
+        
+        // Only used when checking tags out
+        let mut borrower_input = Input::new(100, 200, 180, 30, "Borrower:");
+        let mut due_input = Input::new(380, 200, 100, 30, "Due (days):");
+        due_input.set_tooltip("Days from today, or a date as YYYY-MM-DD; leave empty for no due date");
+        borrower_input.deactivate();
+        due_input.deactivate();
+        
+        let mut capture_wind_clone = capture_wind.clone();
+        let mut borrower_input_clone = borrower_input.clone();
+        let mut due_input_clone = due_input.clone();
+        mode_choice.set_callback(move |choice| {
+            let mode = ScanMode::ALL.get(choice.value() as usize).copied().unwrap_or_default();
+            capture_wind_clone.set_label(&format!("Card Capture - {}", mode));
+            if mode == ScanMode::CheckOutIn {
+                borrower_input_clone.activate();
+                due_input_clone.activate();
+            } else {
+                borrower_input_clone.deactivate();
+                due_input_clone.deactivate();
+            }
+        });
+        
+        // Set by scanning a location tag; received and counted items are put away there
+        let mut location_frame = Frame::new(20, 240, 460, 30, "Location: none (scan a location tag to set it)");
+        location_frame.set_align(Align::Left | Align::Inside);
+        
+        let controls = ScanControls {
+            inventory_mode,
+            show_form,
+            mode_choice,
+            borrower_input,
+            due_input,
+            location_frame,
+            current_location: Rc::new(RefCell::new(None)),
+        };
+        
+        // The keyboard-wedge reader types into the input; each Enter is one read
+        let wedge = Rc::new(RefCell::new(KeyboardWedgeReader::new(kb_layout.borrow().clone())));
+        
+        let card_buffer_clone = card_buffer.clone();
+        let kb_layout_clone = kb_layout.clone();
+        let controls_clone = controls.clone();
         capture_input.set_callback(move |inp| {
             let data = inp.value();
-            if !data.is_empty() {
-                if !data.contains("config") && !data.contains("Buz") {
-                    // Get timestamp information
-                    let (unix_timestamp, human_timestamp) = utils::get_timestamps();
-                    
-                    // Get selected keyboard layout
-                    let kb_layout_value = *kb_layout_clone.borrow();
-                    
-                    // Process the UID for human-readable format
-                    let (hex_uid, manufacturer) = utils::process_uid_for_display(&data, kb_layout_value);
-                    
-                    // Calculate decimal value for human readability
-                    let decimal_value = utils::hex_to_decimal(&hex_uid);
-                    
-                    // Interpret format
-                    let format_desc = utils::interpret_format_code(&data);
-                    
-                    // Create a more detailed record
-                    let record = format!(
-                        "[{}] ({}) Raw UID: {}\n    → Hex: {}\n    → Decimal: {}\n    → Manufacturer: {}\n    → Format: {}\n\n", 
-                        unix_timestamp,
-                        human_timestamp, 
-                        data, 
-                        hex_uid,
-                        decimal_value, 
-                        manufacturer,
-                        format_desc
-                    );
-                    
-                    // Add to the display
-                    let mut buffer = card_buffer_clone.borrow_mut();
-                    let current = buffer.text();
-                    buffer.set_text(&format!("{}{}", current, record));
-                    
-                    // Clean the tag ID (remove spaces)
-                    let clean_tag_id = hex_uid.replace(" ", "");
-                    
-                    // If inventory mode is checked, pass this tag to inventory system
-                    if inventory_mode.is_checked() {
-                        // First check if we can access the inventory database from the main module
-                        if let Ok(inventory_ui) = get_inventory_ui() {
-                            // Check if the tag already exists in inventory
-                            match inventory_ui.inventory_db.borrow().get_item(&clean_tag_id) {
-                                Ok(Some(item)) => {
-                                    // Item exists - show quick update dialog if form is enabled
-                                    if show_form_clone.is_checked() {
-                                        show_item_update_dialog(inventory_ui, item.clone());
-                                    } else {
-                                        // Just increment quantity by default
-                                        if let Err(e) = inventory_ui.inventory_db.borrow().update_quantity(&clean_tag_id, item.quantity + 1) {
-                                            dialog::alert(300, 300, &format!("Error updating quantity: {}", e));
-                                        } else {
-                                            dialog::message(300, 300, &format!("Updated quantity of '{}' to {}", item.name, item.quantity + 1));
-                                        }
-                                    }
-                                },
-                                Ok(None) => {
-                                    // New item - show creation dialog if form is enabled
-                                    if show_form_clone.is_checked() {
-                                        show_new_item_dialog(inventory_ui, clean_tag_id.clone(), manufacturer.clone());
-                                    } else {
-                                        // Ask to create a simple item
-                                        if dialog::choice2(300, 300, &format!("Tag ID {} not found in inventory. Create a new item?", clean_tag_id), "No", "Yes", "") == Some(1) {
-                                            if let Some(name) = dialog::input(300, 300, "Enter item name:", "") {
-                                                if !name.is_empty() {
-                                                    let new_item = create_inventory_item(
-                                                        &clean_tag_id,
-                                                        &name,
-                                                        None,
-                                                        1,
-                                                        None,
-                                                        None
-                                                    );
-                                                    
-                                                    if let Err(e) = inventory_ui.inventory_db.borrow().save_item(&new_item) {
-                                                        dialog::alert(300, 300, &format!("Error saving item: {}", e));
-                                                    } else {
-                                                        dialog::message(300, 300, &format!("New item '{}' added to inventory", name));
-                                                    }
-                                                }
-                                            }
-                                        }
-                                    }
-                                },
-                                Err(e) => {
-                                    dialog::alert(300, 300, &format!("Error checking inventory: {}", e));
-                                }
-                            }
-                        } else {
-                            dialog::alert(300, 300, "Could not access inventory system.");
-                        }
-                    }
-                }
-                inp.set_value("");
+            inp.set_value("");
+            
+            let events = {
+                let mut reader = wedge.borrow_mut();
+                reader.set_keyboard_layout(kb_layout_clone.borrow().clone());
+                reader.push_line(&data);
+                poll_events(&mut *reader)
+            };
+            
+            for event in events {
+                handle_card_event(event, &card_buffer_clone, &controls_clone);
+            }
+        });
+        
+        let card_buffer_clone = card_buffer.clone();
+        let kb_layout_clone = kb_layout.clone();
+        let controls_clone = controls.clone();
+        let capture_wind_clone = capture_wind.clone();
+        replay_btn.set_callback(move |_| {
+            let path = match dialog::file_chooser("Replay reader script or log", "*.{txt,log}", ".", true) {
+                Some(path) => path,
+                None => return,
+            };
+            
+            match SimulatedReader::from_file(&path, kb_layout_clone.borrow().clone()) {
+                Ok(reader) => start_polling(Box::new(reader), &capture_wind_clone, &card_buffer_clone, &controls_clone),
+                Err(e) => dialog::alert(300, 300, &format!("Error loading script: {}", e)),
+            }
+        });
+        
+        let card_buffer_clone = card_buffer.clone();
+        let capture_wind_clone = capture_wind.clone();
+        serial_btn.set_callback(move |_| {
+            let path = match dialog::input(300, 300, "Serial device of the PN532 reader:", DEFAULT_SERIAL_DEVICE) {
+                Some(path) if !path.is_empty() => path,
+                _ => return,
+            };
+            
+            match Pn532Reader::open(&path, pn532::DEFAULT_BAUD_RATE) {
+                Ok(reader) => start_polling(Box::new(reader), &capture_wind_clone, &card_buffer_clone, &controls),
+                Err(e) => dialog::alert(300, 300, &format!("Error connecting to reader: {}", e)),
             }
         });
         
@@ -175,6 +181,315 @@ pub fn start_capture(btn: &mut Button, card_buffer: Rc<RefCell<TextBuffer>>, kb_
     }
 }
 
+// The capture window's inventory settings, read at the time of each scan
+#[derive(Clone)]
+struct ScanControls {
+    inventory_mode: CheckButton,
+    show_form: CheckButton,
+    mode_choice: Choice,
+    borrower_input: Input,
+    due_input: Input,
+    location_frame: Frame,
+    current_location: Rc<RefCell<Option<Location>>>,
+}
+
+impl ScanControls {
+    fn mode(&self) -> ScanMode {
+        ScanMode::ALL.get(self.mode_choice.value() as usize).copied().unwrap_or_default()
+    }
+    
+    fn location(&self) -> Option<Location> {
+        self.current_location.borrow().clone()
+    }
+    
+    fn set_location(&self, location: Location) {
+        let mut frame = self.location_frame.clone();
+        frame.set_label(&format!("Location: {}", location.path));
+        *self.current_location.borrow_mut() = Some(location);
+    }
+    
+    // Gather what the mode needs for this scan, asking when the window doesn't have it; `None` cancels the scan
+    fn options(&self, inventory_ui: &InventoryUI, item: &InventoryItem) -> Option<ScanOptions> {
+        let mut options = ScanOptions { location: self.location(), ..ScanOptions::default() };
+        
+        match self.mode() {
+            ScanMode::SetCount => {
+                let prompt = format!("Counted quantity of '{}':", item.name);
+                let count = dialog::input(300, 300, &prompt, &item.quantity.to_string())?;
+                match count.trim().parse::<i32>() {
+                    Ok(count) => options.count = Some(count),
+                    Err(_) => {
+                        dialog::alert(300, 300, &format!("Invalid quantity '{}'", count));
+                        return None;
+                    }
+                }
+            },
+            ScanMode::CheckOutIn => {
+                // A tag that is already out is returned and needs nothing else
+                if let Ok(Some(_)) = inventory_ui.inventory_db.borrow().get_open_loan(&item.tag_id) {
+                    return Some(options);
+                }
+                
+                let mut borrower = self.borrower_input.value();
+                if borrower.trim().is_empty() {
+                    borrower = dialog::input(300, 300, &format!("Check '{}' out to:", item.name), "")?;
+                }
+                options.borrower = Some(borrower);
+                
+                let due = self.due_input.value();
+                if !due.trim().is_empty() {
+                    match scan::parse_due_date(&due, chrono::Local::now().date_naive()) {
+                        Some(due_date) => options.due_date = Some(due_date),
+                        None => {
+                            dialog::alert(300, 300, &format!("Invalid due date '{}', use days or YYYY-MM-DD", due));
+                            return None;
+                        }
+                    }
+                }
+            },
+            _ => {}
+        }
+        
+        Some(options)
+    }
+}
+
+// How often a polled reader (serial or simulated) is checked, in seconds
+const READER_POLL_INTERVAL: f64 = 0.05;
+
+#[cfg(windows)]
+const DEFAULT_SERIAL_DEVICE: &str = "COM3";
+#[cfg(not(windows))]
+const DEFAULT_SERIAL_DEVICE: &str = "/dev/ttyUSB0";
+
+// Poll the reader from the FLTK event loop until it runs out of events or the capture window closes
+fn start_polling(
+    reader: Box<dyn CardReader>,
+    capture_wind: &Window,
+    card_buffer: &Rc<RefCell<TextBuffer>>,
+    controls: &ScanControls
+) {
+    append_to_buffer(card_buffer, &format!("--- Reading from {} ---\n", reader.name()));
+    
+    let reader = Rc::new(RefCell::new(reader));
+    let capture_wind = capture_wind.clone();
+    let card_buffer = card_buffer.clone();
+    let controls = controls.clone();
+    
+    fltk::app::add_timeout3(READER_POLL_INTERVAL, move |handle| {
+        // Release the reader before handling, dialogs run a nested event loop that can re-enter here
+        let events = poll_events(&mut **reader.borrow_mut());
+        for event in events {
+            handle_card_event(event, &card_buffer, &controls);
+        }
+        
+        if capture_wind.shown() && !reader.borrow().is_finished() {
+            fltk::app::repeat_timeout3(READER_POLL_INTERVAL, handle);
+        }
+    });
+}
+
+// Drain every event the reader has ready
+fn poll_events(reader: &mut dyn CardReader) -> Vec<CardEvent> {
+    let mut events = Vec::new();
+    loop {
+        match reader.poll() {
+            Ok(Some(event)) => events.push(event),
+            Ok(None) => break,
+            Err(e) => {
+                println!("Error polling {}: {}", reader.name(), e);
+                break;
+            }
+        }
+    }
+    events
+}
+
+// Log a reader event and, for a card read, pass the tag to the inventory
+fn handle_card_event(event: CardEvent, card_buffer: &Rc<RefCell<TextBuffer>>, controls: &ScanControls) {
+    let (unix_timestamp, human_timestamp) = utils::get_timestamps();
+    
+    let read = match event {
+        CardEvent::CardPresent(read) => read,
+        CardEvent::ReadError { raw, error } => {
+            // A bad read is logged but never reaches the inventory
+            let record = format!(
+                "[{}] ({}) Raw UID: {}\n    → Error: {}\n    → Format: {}\n\n",
+                unix_timestamp,
+                human_timestamp,
+                raw,
+                error,
+                utils::interpret_format_code(&raw)
+            );
+            append_to_buffer(card_buffer, &record);
+            return;
+        },
+        // Nothing to log when the card leaves the field
+        CardEvent::CardRemoved { .. } => return,
+    };
+    
+    let uid = read.uid;
+    let manufacturer_info = manufacturer::identify(&uid);
+    let manufacturer = manufacturer_info.to_string();
+    
+    let card_family = utils::identify_card_type(read.atqa, read.sak, read.version.as_deref());
+    
+    // Serial readers also report the anticollision response, which identifies the card type
+    let anticollision = match (read.atqa, read.sak, card_family) {
+        (Some(atqa), Some(sak), Some(family)) => format!(
+            "    → ATQA: {:04X}  SAK: {:02X}\n    → Card Type: {} ({})\n",
+            atqa,
+            sak,
+            family,
+            family.describe_capabilities()
+        ),
+        _ => String::new(),
+    };
+    
+    // Create a more detailed record
+    let record = format!(
+        "[{}] ({}) Raw UID: {}\n    → Hex: {}\n    → Decimal: {}\n    → Manufacturer: {}\n    → Format: {}\n{}\n", 
+        unix_timestamp,
+        human_timestamp, 
+        read.raw, 
+        uid.to_spaced_hex(),
+        uid.to_decimal(), 
+        manufacturer,
+        utils::interpret_format_code(&read.raw),
+        anticollision
+    );
+    append_to_buffer(card_buffer, &record);
+    
+    // If inventory mode is checked, pass this tag to inventory system
+    if !controls.inventory_mode.is_checked() {
+        return;
+    }
+    let mode = controls.mode();
+    let show_form = controls.show_form.is_checked();
+    
+    if manufacturer_info.is_random_uid() {
+        dialog::alert(300, 300, &format!("Tag {} has a random UID that changes on every read.\nIt can't be used to track inventory.", uid));
+        return;
+    }
+    
+    // First check if we can access the inventory database from the main module
+    let inventory_ui = match get_inventory_ui() {
+        Ok(inventory_ui) => inventory_ui,
+        Err(_) => {
+            dialog::alert(300, 300, "Could not access inventory system.");
+            return;
+        }
+    };
+    
+    // A location tag sets where the next items are put away
+    let location = inventory_ui.inventory_db.borrow().get_location_by_tag(&uid);
+    match location {
+        Ok(Some(location)) => {
+            append_to_buffer(card_buffer, &format!("    → Current location: {}\n\n", location.path));
+            controls.set_location(location);
+            return;
+        },
+        Ok(None) => {},
+        Err(e) => {
+            dialog::alert(300, 300, &format!("Error checking locations: {}", e));
+            return;
+        }
+    }
+    let location_path = controls.location().map(|location| location.path);
+    
+    // Check if the tag already exists in inventory; a tag several items share asks which one was meant,
+    // and the rest of the scan goes by that item's own tag
+    let found = inventory_ui.inventory_db.borrow().get_items_for_tag(&uid);
+    let shared = found.as_ref().is_ok_and(|items| items.len() > 1);
+    let existing = match found {
+        Ok(items) if shared => match choose_item(&uid, &items) {
+            Some(item) => Ok(Some(item)),
+            None => return,
+        },
+        Ok(items) => Ok(items.into_iter().next()),
+        Err(e) => Err(e),
+    };
+    match existing {
+        Ok(Some(mut item)) => {
+            // Items added from a keyboard wedge learn their card type the first time a serial reader sees them
+            if let (None, Some(family)) = (item.card_family, card_family) {
+                if let Err(e) = inventory_ui.inventory_db.borrow().update_card_family(&item.tag_id, family) {
+                    dialog::alert(300, 300, &format!("Error saving card type: {}", e));
+                }
+                item.card_family = Some(family);
+            }
+            
+            // Item exists - show quick update dialog if form is enabled; the other modes act on the scan directly
+            if show_form && mode == ScanMode::Receive {
+                show_item_update_dialog(inventory_ui, item.clone(), location_path);
+                return;
+            }
+            
+            let options = match controls.options(inventory_ui, &item) {
+                Some(options) => options,
+                None => return,
+            };
+            let outcome = if shared {
+                scan::apply_scan_to(&inventory_ui.inventory_db.borrow(), item, mode, &options)
+            } else {
+                scan::apply_scan(&inventory_ui.inventory_db.borrow(), &uid, mode, &options)
+            };
+            match outcome {
+                Ok(outcome) => {
+                    append_to_buffer(card_buffer, &format!("    → {}: {}\n\n", mode, outcome.message()));
+                    if outcome.reorder().is_some() {
+                        dialog::alert(300, 300, &outcome.message());
+                    } else {
+                        dialog::message(300, 300, &outcome.message());
+                    }
+                }
+                Err(e) => dialog::alert(300, 300, &format!("Scan not applied: {}", e)),
+            }
+        },
+        Ok(None) if !mode.changes_stock() => {
+            dialog::message(300, 300, &format!("Tag ID {} not found in inventory", uid));
+        },
+        Ok(None) => {
+            // New item - show creation dialog if form is enabled
+            if show_form {
+                show_new_item_dialog(inventory_ui, uid.clone(), manufacturer.clone(), card_family, location_path);
+            } else {
+                // Ask to create a simple item
+                if dialog::choice2(300, 300, &format!("Tag ID {} not found in inventory. Create a new item?", uid), "No", "Yes", "") == Some(1) {
+                    if let Some(name) = dialog::input(300, 300, "Enter item name:", "") {
+                        if !name.is_empty() {
+                            let mut new_item = create_inventory_item(
+                                &uid,
+                                &name,
+                                None,
+                                1,
+                                location_path.as_deref(),
+                                None
+                            );
+                            new_item.card_family = card_family;
+                            
+                            if let Err(e) = inventory_ui.inventory_db.borrow().save_item_with_reason(&new_item, MovementReason::ScanIn) {
+                                dialog::alert(300, 300, &format!("Error saving item: {}", e));
+                            } else {
+                                dialog::message(300, 300, &format!("New item '{}' added to inventory", name));
+                            }
+                        }
+                    }
+                }
+            }
+        },
+        Err(e) => {
+            dialog::alert(300, 300, &format!("Error checking inventory: {}", e));
+        }
+    }
+}
+
+fn append_to_buffer(card_buffer: &Rc<RefCell<TextBuffer>>, record: &str) {
+    let mut buffer = card_buffer.borrow_mut();
+    let current = buffer.text();
+    buffer.set_text(&format!("{}{}", current, record));
+}
+
 // Helper function to get inventory UI instance
 fn get_inventory_ui() -> Result<&'static InventoryUI, String> {
     unsafe {
@@ -189,7 +504,13 @@ fn get_inventory_ui() -> Result<&'static InventoryUI, String> {
 }
 
 // New function to show item creation dialog - Note: takes ownership of tag_id and manufacturer
-fn show_new_item_dialog(inventory_ui: &'static InventoryUI, tag_id: String, manufacturer: String) {
+fn show_new_item_dialog(
+    inventory_ui: &'static InventoryUI,
+    tag_id: Uid,
+    manufacturer: String,
+    card_family: Option<CardFamily>,
+    location_path: Option<String>
+) {
     // Create modal window
     let mut win = Window::new(300, 200, 450, 450, "New Item");
     win.make_modal(true);
@@ -205,7 +526,10 @@ fn show_new_item_dialog(inventory_ui: &'static InventoryUI, tag_id: String, manu
     tag_frame.set_label_font(Font::HelveticaBold);
     
     // Manufacturer display
-    let manuf_label = format!("Manufacturer: {}", manufacturer);
+    let manuf_label = match card_family {
+        Some(family) => format!("Manufacturer: {}  |  {}", manufacturer, family),
+        None => format!("Manufacturer: {}", manufacturer),
+    };
     Frame::new(20, 80, 410, 30, manuf_label.as_str());
     
     // Add form elements with labels
@@ -215,14 +539,12 @@ fn show_new_item_dialog(inventory_ui: &'static InventoryUI, tag_id: String, manu
     qty_input.set_value("1"); // Default quantity
     
     let mut location_input = Input::new(150, 280, 270, 30, "Location:");
+    location_input.set_value(location_path.as_deref().unwrap_or_default());
     
     let mut category_choice = Choice::new(150, 320, 270, 30, "Category:");
     // Get categories from database and populate the dropdown
-    if let Ok(categories_with_count) = inventory_ui.inventory_db.borrow().get_categories() {
-        category_choice.add_choice("Uncategorized");
-        for (category, _) in categories_with_count {
-            category_choice.add_choice(&category);
-        }
+    if let Ok(categories) = inventory_ui.inventory_db.borrow().get_categories() {
+        category_choice.update_categories(&categories);
     }
     
     // Add save and cancel buttons
@@ -269,7 +591,7 @@ fn show_new_item_dialog(inventory_ui: &'static InventoryUI, tag_id: String, manu
             Some(location_input_clone.value()) 
         };
         
-        let new_item = create_inventory_item(
+        let mut new_item = create_inventory_item(
             &tag_id_for_save,
             &name_input_clone.value(),
             description.as_deref(),
@@ -277,6 +599,7 @@ fn show_new_item_dialog(inventory_ui: &'static InventoryUI, tag_id: String, manu
             location.as_deref(),
             category.as_deref()
         );
+        new_item.card_family = card_family;
         
         // Save to database
         if let Err(e) = inventory_ui.inventory_db.borrow().save_item(&new_item) {
@@ -294,7 +617,8 @@ fn show_new_item_dialog(inventory_ui: &'static InventoryUI, tag_id: String, manu
 }
 
 // New function to show item update dialog - Note: takes ownership of the item
-fn show_item_update_dialog(inventory_ui: &'static InventoryUI, item: InventoryItem) {
+// A current location from the capture window replaces the item's, since that's where it was scanned
+fn show_item_update_dialog(inventory_ui: &'static InventoryUI, item: InventoryItem, location_path: Option<String>) {
     // Create modal window
     let mut win = Window::new(300, 200, 450, 500, "Update Item");
     win.make_modal(true);
@@ -329,25 +653,19 @@ fn show_item_update_dialog(inventory_ui: &'static InventoryUI, item: InventoryIt
     // Location update
     Frame::new(20, 200, 100, 30, "Location:");
     let mut location_input = Input::new(120, 200, 310, 30, "");
-    location_input.set_value(&item.location.clone().unwrap_or_default());
+    location_input.set_value(&location_path.or_else(|| item.location.clone()).unwrap_or_default());
     
     // Category update
     Frame::new(20, 240, 100, 30, "Category:");
     let mut category_choice = Choice::new(120, 240, 310, 30, "");
     
     // Populate categories dropdown
-    if let Ok(categories_with_count) = inventory_ui.inventory_db.borrow().get_categories() {
-        category_choice.add_choice("Uncategorized");
-        let mut selected_index = 0;
-        
-        for (i, (category, _)) in categories_with_count.iter().enumerate() {
-            category_choice.add_choice(category);
-            if let Some(ref item_category) = item.category {
-                if item_category == category {
-                    selected_index = i + 1; // +1 because Uncategorized is at index 0
-                }
-            }
-        }
+    if let Ok(categories) = inventory_ui.inventory_db.borrow().get_categories() {
+        category_choice.update_categories(&categories);
+        let selected_index = categories
+            .iter()
+            .position(|category| item.category.as_deref() == Some(category.path.as_str()))
+            .map_or(0, |i| i + 1); // +1 because Uncategorized is at index 0
         
         category_choice.set_value(selected_index as i32);
     }
@@ -388,6 +706,7 @@ fn show_item_update_dialog(inventory_ui: &'static InventoryUI, item: InventoryIt
     let tag_id_for_save = item.tag_id.clone();
     let name = item.name.clone();
     let created_at = item.created_at.clone();
+    let card_family = item.card_family;
     
     let new_qty_input_save = new_qty_input.clone();
     let location_input_save = location_input.clone();
@@ -400,6 +719,7 @@ fn show_item_update_dialog(inventory_ui: &'static InventoryUI, item: InventoryIt
         
         // Create a new item instead of trying to modify the referenced one
         let mut updated_item = InventoryItem {
+            id: item.id,
             tag_id: tag_id_for_save.clone(),
             name: name.clone(),
             description: None,
@@ -408,6 +728,12 @@ fn show_item_update_dialog(inventory_ui: &'static InventoryUI, item: InventoryIt
             category: None,
             last_updated: generate_timestamp(),
             created_at: created_at.clone(),
+            card_family,
+            location_id: None,
+            category_id: None,
+            attributes: Default::default(),
+            min_quantity: item.min_quantity,
+            reorder_quantity: item.reorder_quantity,
         };
         
         // Set optional fields
@@ -424,6 +750,10 @@ fn show_item_update_dialog(inventory_ui: &'static InventoryUI, item: InventoryIt
         } else {
             item.category.clone()
         };
+        // This dialog has no inputs for custom fields; they are kept while the category stays
+        if updated_item.category == item.category {
+            updated_item.attributes = item.attributes.clone();
+        }
         
         updated_item.description = if desc_input_save.value().is_empty() {
             None
@@ -444,12 +774,12 @@ fn show_item_update_dialog(inventory_ui: &'static InventoryUI, item: InventoryIt
     let mut win_delete = win.clone();
     let delete_tag_id = item.tag_id.clone();
     delete_btn.set_callback(move |_| {
-        if dialog::choice2(300, 300, "Are you sure you want to delete this item?", "No", "Yes", "") == Some(1) {
+        if dialog::choice2(300, 300, "Move this item to the trash? It can be restored from File > Trash.", "No", "Yes", "") == Some(1) {
             // Delete from database
             if let Err(e) = inventory_ui.inventory_db.borrow().delete_item(&delete_tag_id) {
                 dialog::alert(300, 300, &format!("Error deleting item: {}", e));
             } else {
-                dialog::message(300, 300, "Item deleted successfully");
+                dialog::message(300, 300, "Item moved to the trash");
                 win_delete.hide();
             }
         }
@@ -1,12 +1,14 @@
 // file_sync.rs
 use notify::{Watcher, RecursiveMode, DebouncedEvent, watcher};
-use std::sync::mpsc::channel;
+use std::sync::mpsc::{channel, Receiver};
 use std::time::Duration;
 use std::path::{Path, PathBuf};
 use std::fs;
+use std::io;
 use chrono::Local;
 use std::thread;
-use crate::inventory::InventoryUI;
+use crate::error::{Error, Result};
+use crate::inventory::{InventoryDB, MovementReason};
 
 
 pub struct FileSync {
@@ -37,35 +39,33 @@ impl FileSync {
         }
     }
     
-    pub fn start(&mut self) -> Result<(), String> {
+    // Start watching the import directory; new JSON files are sent on the returned receiver
+    pub fn start(&mut self) -> Result<Receiver<PathBuf>> {
         if self.is_running {
-            println!("File sync already running");
-            return Ok(());
+            return Err(Error::sync_conflict(
+                Some(PathBuf::from(&self.import_path)),
+                "file sync already running",
+            ));
         }
         
         self.is_running = true;
         
         // Process any existing files in the import directory
         if let Ok(entries) = fs::read_dir(&self.import_path) {
-            for entry in entries {
-                if let Ok(entry) = entry {
-                    if self.should_process_file(&entry.path()) {
-                        println!("Found existing file to process: {:?}", entry.path());
-                        // Instead of processing directly here, we'll move it to a "pending" directory
-                        // and let the main thread handle it on next iteration
-                    }
+            for entry in entries.flatten() {
+                if self.should_process_file(&entry.path()) {
+                    println!("Found existing file to process: {:?}", entry.path());
+                    // Instead of processing directly here, we'll move it to a "pending" directory
+                    // and let the main thread handle it on next iteration
                 }
             }
         }
         
         // Set up file watcher that just sends notifications, doesn't process
         let import_path = self.import_path.clone();
-        let processed_path = self.processed_path.clone();
-        let error_path = self.error_path.clone();
         
         // Create a channel for sending file notifications back to the main thread
-        let (tx, _rx) = channel();
-        let watcher_tx = tx.clone();
+        let (watcher_tx, rx) = channel();
         
         // Create the watcher in its own thread
         thread::spawn(move || {
@@ -87,22 +87,20 @@ impl FileSync {
             
             loop {
                 match watch_rx.recv() {
-                    Ok(event) => {
-                        match event {
-                            DebouncedEvent::Create(path) | DebouncedEvent::Write(path) => {
-                                if path.extension().map_or(false, |ext| ext == "json") {
-                                    // Allow a small delay to ensure file is fully written
-                                    thread::sleep(Duration::from_millis(500));
-                                    
-                                    println!("New file detected: {:?}", path);
-                                    
-                                    // Just notify the main thread about the file
-                                    let _ = watcher_tx.send(path);
-                                }
-                            },
-                            _ => {}
+                    Ok(DebouncedEvent::Create(path)) | Ok(DebouncedEvent::Write(path))
+                        if path.extension().is_some_and(|ext| ext == "json") =>
+                    {
+                        // Allow a small delay to ensure file is fully written
+                        thread::sleep(Duration::from_millis(500));
+                        
+                        println!("New file detected: {:?}", path);
+                        
+                        // Just notify the main thread about the file; stop once nobody is listening
+                        if watcher_tx.send(path).is_err() {
+                            break;
                         }
                     },
+                    Ok(_) => {},
                     Err(e) => {
                         println!("Watch error: {:?}", e);
                         break;
@@ -112,11 +110,11 @@ impl FileSync {
         });
         
         // Return the receiver for the main thread to poll
-        Ok(())
+        Ok(rx)
     }
     
     fn should_process_file(&self, path: &Path) -> bool {
-        path.extension().map_or(false, |ext| ext == "json")
+        path.extension().is_some_and(|ext| ext == "json")
     }
     
     // New method to get list of files to process
@@ -124,12 +122,10 @@ impl FileSync {
         let mut files = Vec::new();
         
         if let Ok(entries) = fs::read_dir(&self.import_path) {
-            for entry in entries {
-                if let Ok(entry) = entry {
-                    let path = entry.path();
-                    if self.should_process_file(&path) {
-                        files.push(path);
-                    }
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if self.should_process_file(&path) {
+                    files.push(path);
                 }
             }
         }
@@ -138,10 +134,15 @@ impl FileSync {
     }
     
     // Process a single file and move it to appropriate directory
-    pub fn process_file(&self, path: &Path, success: bool) -> Result<(), String> {
+    pub fn process_file(&self, path: &Path, success: bool) -> Result<()> {
         let file_name = match path.file_name() {
             Some(name) => name.to_str().unwrap_or("unknown.json"),
-            None => return Err("Invalid file path".to_string())
+            None => {
+                return Err(Error::io(
+                    format!("Invalid file path {}", path.display()),
+                    io::Error::from(io::ErrorKind::InvalidInput),
+                ))
+            }
         };
         
         let timestamp = Local::now().format("%Y%m%d_%H%M%S").to_string();
@@ -153,10 +154,14 @@ impl FileSync {
             Path::new(&self.error_path).join(&new_filename)
         };
         
-        if let Err(e) = fs::rename(path, &dest_path) {
-            return Err(format!("Error moving file: {}", e));
+        // Don't clobber a file that was moved there in the same second
+        if dest_path.exists() {
+            return Err(Error::sync_conflict(Some(dest_path), "destination file already exists"));
         }
         
+        fs::rename(path, &dest_path)
+            .map_err(|e| Error::io(format!("Error moving file {}", path.display()), e))?;
+        
         println!("File moved to: {:?}", dest_path);
         Ok(())
     }
@@ -166,8 +171,8 @@ pub fn check_for_import_files(
     import_dir: &str,
     processed_dir: &str, 
     error_dir: &str,
-    inventory_ui: &std::rc::Rc<crate::inventory::InventoryUI>
-) -> Result<usize, String> {
+    inventory_db: &InventoryDB
+) -> Result<usize> {
     let file_sync = FileSync::new(import_dir, processed_dir, error_dir);
     let pending_files = file_sync.get_pending_files();
     
@@ -178,8 +183,8 @@ pub fn check_for_import_files(
         match std::fs::read_to_string(&file_path) {
             Ok(contents) => {
                 // Check if it's JSON (we'll only handle JSON for now)
-                if file_path.extension().map_or(false, |ext| ext == "json") {
-                    match inventory_ui.inventory_db.borrow().import_json(&contents) {
+                if file_path.extension().is_some_and(|ext| ext == "json") {
+                    match inventory_db.import_json_with_reason(&contents, MovementReason::Sync) {
                         Ok(items_imported) => {
                             // Move file to processed directory
                             if let Err(e) = file_sync.process_file(&file_path, true) {
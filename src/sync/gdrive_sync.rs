@@ -3,7 +3,8 @@ use std::path::{Path, PathBuf};
 use std::fs;
 use std::io;
 use chrono::Local;
-use crate::inventory::InventoryDB;
+use crate::error::{Error, Result};
+use crate::inventory::{InventoryDB, MovementReason};
 
 pub struct GDriveSync {
     sync_folder: String,
@@ -26,47 +27,43 @@ impl GDriveSync {
     }
     
     // Export database to Google Drive sync folder
-    pub fn export_database(&self, db: &InventoryDB) -> Result<String, String> {
+    pub fn export_database(&self, db: &InventoryDB) -> Result<String> {
         // Export the database to JSON
-        let json_data = match db.export_json() {
-            Ok(data) => data,
-            Err(e) => return Err(format!("Failed to export database: {}", e))
-        };
+        let json_data = db.export_json()?;
         
         // Create a timestamped filename
         let timestamp = Local::now().format("%Y%m%d_%H%M%S").to_string();
         let filename = format!("inventory_export_{}.json", timestamp);
         let file_path = Path::new(&self.sync_folder).join(filename);
         
-        // Write the JSON data to file
-        match fs::write(&file_path, json_data) {
-            Ok(_) => {
-                println!("Database exported to Google Drive sync folder: {:?}", file_path);
-                Ok(file_path.to_string_lossy().to_string())
-            },
-            Err(e) => Err(format!("Failed to write to Google Drive sync folder: {}", e))
+        // Two exports in the same second would otherwise overwrite each other
+        if file_path.exists() {
+            return Err(Error::sync_conflict(Some(file_path), "export file already exists"));
         }
+        
+        // Write the JSON data to file
+        fs::write(&file_path, json_data)
+            .map_err(|e| Error::io("Failed to write to Google Drive sync folder", e))?;
+        
+        println!("Database exported to Google Drive sync folder: {:?}", file_path);
+        Ok(file_path.to_string_lossy().to_string())
     }
     
     // Import latest database file from Google Drive sync folder
-    pub fn import_latest_database(&self, db: &InventoryDB) -> Result<usize, String> {
-        match self.find_latest_json_file() {
-            Some(file_path) => {
-                match fs::read_to_string(&file_path) {
-                    Ok(content) => {
-                        match db.import_json(&content) {
-                            Ok(count) => {
-                                println!("Imported {} items from Google Drive sync file: {:?}", count, file_path);
-                                Ok(count)
-                            },
-                            Err(e) => Err(format!("Failed to import from Google Drive sync file: {}", e))
-                        }
-                    },
-                    Err(e) => Err(format!("Failed to read Google Drive sync file: {}", e))
-                }
-            },
-            None => Err("No JSON files found in Google Drive sync folder".to_string())
-        }
+    pub fn import_latest_database(&self, db: &InventoryDB) -> Result<usize> {
+        let file_path = self.find_latest_json_file().ok_or_else(|| {
+            Error::io(
+                "No JSON files found in Google Drive sync folder",
+                io::Error::from(io::ErrorKind::NotFound),
+            )
+        })?;
+        
+        let content = fs::read_to_string(&file_path)
+            .map_err(|e| Error::io(format!("Failed to read Google Drive sync file {}", file_path.display()), e))?;
+        
+        let count = db.import_json_with_reason(&content, MovementReason::Sync)?;
+        println!("Imported {} items from Google Drive sync file: {:?}", count, file_path);
+        Ok(count)
     }
     
     // Find the latest JSON file in the sync folder
@@ -74,16 +71,12 @@ impl GDriveSync {
         let mut latest_file: Option<(PathBuf, std::time::SystemTime)> = None;
         
         if let Ok(entries) = fs::read_dir(&self.sync_folder) {
-            for entry in entries {
-                if let Ok(entry) = entry {
-                    let path = entry.path();
-                    if path.extension().map_or(false, |ext| ext == "json") {
-                        if let Ok(metadata) = fs::metadata(&path) {
-                            if let Ok(modified_time) = metadata.modified() {
-                                if latest_file.is_none() || modified_time > latest_file.as_ref().unwrap().1 {
-                                    latest_file = Some((path, modified_time));
-                                }
-                            }
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.extension().is_some_and(|ext| ext == "json") {
+                    if let Ok(modified_time) = fs::metadata(&path).and_then(|m| m.modified()) {
+                        if latest_file.is_none() || modified_time > latest_file.as_ref().unwrap().1 {
+                            latest_file = Some((path, modified_time));
                         }
                     }
                 }
@@ -94,22 +87,23 @@ impl GDriveSync {
     }
     
     // Get list of all JSON files in the sync folder
-    pub fn list_sync_files(&self) -> io::Result<Vec<PathBuf>> {
+    pub fn list_sync_files(&self) -> Result<Vec<PathBuf>> {
         let mut files = Vec::new();
+        let read_error = |e| Error::io(format!("Failed to list {}", self.sync_folder), e);
         
-        for entry in fs::read_dir(&self.sync_folder)? {
-            let entry = entry?;
+        for entry in fs::read_dir(&self.sync_folder).map_err(read_error)? {
+            let entry = entry.map_err(read_error)?;
             let path = entry.path();
             
-            if path.extension().map_or(false, |ext| ext == "json") {
+            if path.extension().is_some_and(|ext| ext == "json") {
                 files.push(path);
             }
         }
         
         // Sort by modified time (newest first)
         files.sort_by(|a, b| {
-            let a_time = fs::metadata(a).and_then(|m| m.modified()).unwrap_or_else(|_| std::time::SystemTime::UNIX_EPOCH);
-            let b_time = fs::metadata(b).and_then(|m| m.modified()).unwrap_or_else(|_| std::time::SystemTime::UNIX_EPOCH);
+            let a_time = fs::metadata(a).and_then(|m| m.modified()).unwrap_or(std::time::SystemTime::UNIX_EPOCH);
+            let b_time = fs::metadata(b).and_then(|m| m.modified()).unwrap_or(std::time::SystemTime::UNIX_EPOCH);
             b_time.cmp(&a_time)
         });
         
@@ -11,9 +11,9 @@ pub fn check_for_import_files(
     import_dir: &str, 
     processed_dir: &str, 
     error_dir: &str, 
-    inventory_ui: &std::rc::Rc<crate::inventory::InventoryUI>
-) -> Result<usize, String> {
+    inventory_db: &crate::inventory::InventoryDB
+) -> crate::error::Result<usize> {
     // Implementation moved from main.rs
-    // This would process import files using the inventory UI instance
-    file_sync::check_for_import_files(import_dir, processed_dir, error_dir, inventory_ui)
+    // This would process import files into the given inventory database
+    file_sync::check_for_import_files(import_dir, processed_dir, error_dir, inventory_db)
 }
\ No newline at end of file
@@ -0,0 +1,54 @@
+// ui/batch.rs
+use std::cell::RefCell;
+use std::rc::Rc;
+use fltk::text::TextBuffer;
+
+use crate::batch::decode::decode_batch;
+use crate::convert;
+use crate::keyboard_layout::KeyboardLayout;
+use crate::wiegand;
+
+pub fn process_batch(
+    text: &str,
+    kb_layout: &KeyboardLayout,
+    result_buffer: Rc<RefCell<TextBuffer>>
+) {
+    let mut results = String::new();
+
+    for line in decode_batch(text, kb_layout) {
+        results.push_str(&format!("UID #{}: {}\n", line.line_number, line.raw));
+        
+        // Lines copied from an access-control export may be raw Wiegand frames
+        if wiegand::is_frame(&line.raw) {
+            match wiegand::parse_frame(&line.raw) {
+                Ok(credential) => {
+                    results.push_str(&format!("   → {}: {}\n", credential.format.name(), credential));
+                    results.push_str(&format!("   → Frame: {} (parity OK)\n\n", credential.to_hex()));
+                },
+                Err(e) => {
+                    results.push_str(&format!("   → Error: {}\n\n", e));
+                }
+            }
+            continue;
+        }
+        
+        match line.result {
+            Ok(record) => {
+                for (format, value) in convert::all_formats(&record.uid) {
+                    results.push_str(&format!("   → {}: {}\n", format.name(), value));
+                }
+                results.push_str(&format!("   → Manufacturer: {}\n", record.manufacturer));
+                results.push_str(&format!("   → Format: {}\n", record.format));
+                for credential in &record.wiegand {
+                    results.push_str(&format!("   → {} frame: {}\n", credential.format.name(), credential.to_hex()));
+                }
+                results.push('\n');
+            },
+            Err(e) => {
+                results.push_str(&format!("   → Error: {}\n\n", e));
+            }
+        }
+    }
+
+    result_buffer.borrow_mut().set_text(&results);
+}
@@ -1,7 +1,7 @@
 // ui/common.rs
 use fltk::{
     button::Button,
-    enums::FrameType,
+    enums::{Event, FrameType},
     frame::Frame,
     group::{Group, Tabs},
     input::Input,
@@ -12,11 +12,13 @@ use fltk::{
 use std::cell::RefCell;
 use std::rc::Rc;
 
+use crate::config::AppConfig;
+use crate::keyboard_layout::KeyboardLayout;
 use crate::reader;
 use crate::ui::converter;
-use crate::batch;
+use crate::ui::batch;
 
-pub fn create_reader_tab(tabs: &mut Tabs, keyboard_layout: Rc<RefCell<i32>>, card_data_buffer: Rc<RefCell<TextBuffer>>) {
+pub fn create_reader_tab(tabs: &mut Tabs, keyboard_layout: Rc<RefCell<KeyboardLayout>>, card_data_buffer: Rc<RefCell<TextBuffer>>, app_config: Rc<RefCell<AppConfig>>) {
     // Changed from y=50 to y=25 to align with tab bar
     let reader_tab = Group::new(0, 25, 800, 575, "Reader Mode");
     
@@ -38,6 +40,8 @@ pub fn create_reader_tab(tabs: &mut Tabs, keyboard_layout: Rc<RefCell<i32>>, car
     // Capture controls - adjusted y coordinates
     let mut capture_btn = Button::new(20, 145, 120, 30, "Start Capture");
     let mut clear_btn = Button::new(150, 145, 120, 30, "Clear Data");
+    // Learns a layout from cards with a known UID when none of the built-in ones fit
+    let mut calibrate_btn = Button::new(280, 145, 150, 30, "Calibrate Layout...");
     
     // Card data display - adjusted y coordinates
     let mut data_frame = Frame::new(10, 185, 780, 380, "Card Data");
@@ -62,11 +66,15 @@ pub fn create_reader_tab(tabs: &mut Tabs, keyboard_layout: Rc<RefCell<i32>>, car
         }
     });
     
+    calibrate_btn.set_callback(move |_| {
+        reader::open_calibration_wizard(keyboard_layout.clone(), app_config.clone());
+    });
+    
     reader_tab.end();
     tabs.add(&reader_tab);
 }
 
-pub fn create_conversion_tab(tabs: &mut Tabs, keyboard_layout: Rc<RefCell<i32>>) {
+pub fn create_conversion_tab(tabs: &mut Tabs, keyboard_layout: Rc<RefCell<KeyboardLayout>>) {
     // Changed from y=50 to y=25 to align with tab bar
     let conversion_tab = Group::new(0, 25, 800, 575, "UID Conversion");
     
@@ -111,8 +119,16 @@ pub fn create_conversion_tab(tabs: &mut Tabs, keyboard_layout: Rc<RefCell<i32>>)
         format_display.set_buffer(buffer.clone());
     }
     
+    Frame::new(20, 295, 200, 30, "Output Formats:");
+    let formats_buffer = Rc::new(RefCell::new(TextBuffer::default()));
+    let mut formats_display = TextDisplay::new(230, 295, 530, 140, "");
+    {
+        let buffer = formats_buffer.borrow();
+        formats_display.set_buffer(buffer.clone());
+    }
+    
     // Add instructions for keyboard encoding issues
-    let mut kb_frame = Frame::new(20, 295, 740, 120, "");
+    let mut kb_frame = Frame::new(20, 440, 740, 90, "");
     kb_frame.set_label(
         "Note about keyboard encoding: If you see special characters instead of numbers,\n\
         this utility will automatically convert them to the correct format based on selected keyboard layout.\n\n\
@@ -121,15 +137,23 @@ pub fn create_conversion_tab(tabs: &mut Tabs, keyboard_layout: Rc<RefCell<i32>>)
     );
     
     // Add keyboard layout selector
-    Frame::new(20, 425, 180, 30, "Keyboard Layout:");
+    Frame::new(20, 540, 180, 30, "Keyboard Layout:");
     
-    let mut keyboard_choice = Choice::new(210, 425, 150, 30, "");
-    keyboard_choice.add_choice("Auto-detect|Windows|Mac US|Mac International");
-    keyboard_choice.set_value(0); // Default to Auto-detect
+    let mut keyboard_choice = Choice::new(210, 540, 150, 30, "");
+    fill_layout_choice(&mut keyboard_choice, &keyboard_layout.borrow());
     
     let keyboard_layout_for_selector = keyboard_layout.clone();
     keyboard_choice.set_callback(move |c| {
-        *keyboard_layout_for_selector.borrow_mut() = c.value();
+        *keyboard_layout_for_selector.borrow_mut() = selected_layout(c);
+    });
+    
+    // Layouts can be added while running (calibration), so rebuild the list before it opens
+    let keyboard_layout_for_refresh = keyboard_layout.clone();
+    keyboard_choice.handle(move |c, ev| {
+        if ev == Event::Push {
+            fill_layout_choice(c, &keyboard_layout_for_refresh.borrow());
+        }
+        false
     });
     
     // Create clones for use in callbacks
@@ -137,17 +161,19 @@ pub fn create_conversion_tab(tabs: &mut Tabs, keyboard_layout: Rc<RefCell<i32>>)
     let dec_buffer_clone = dec_buffer.clone();
     let mfg_buffer_clone = mfg_buffer.clone();
     let format_buffer_clone = format_buffer.clone();
+    let formats_buffer_clone = formats_buffer.clone();
     let uid_input_clone = uid_input.clone();
     let keyboard_layout_for_convert = keyboard_layout.clone();
     
     convert_btn.set_callback(move |_| {
         converter::convert_uid(
             &uid_input_clone.value(), 
-            *keyboard_layout_for_convert.borrow(),
+            &keyboard_layout_for_convert.borrow(),
             hex_buffer_clone.clone(),
             dec_buffer_clone.clone(),
             mfg_buffer_clone.clone(),
-            format_buffer_clone.clone()
+            format_buffer_clone.clone(),
+            formats_buffer_clone.clone()
         );
     });
     
@@ -155,7 +181,7 @@ pub fn create_conversion_tab(tabs: &mut Tabs, keyboard_layout: Rc<RefCell<i32>>)
     tabs.add(&conversion_tab);
 }
 
-pub fn create_batch_tab(tabs: &mut Tabs, keyboard_layout: Rc<RefCell<i32>>) {
+pub fn create_batch_tab(tabs: &mut Tabs, keyboard_layout: Rc<RefCell<KeyboardLayout>>) {
     // Changed from y=50 to y=25 to align with tab bar
     let batch_tab = Group::new(0, 25, 800, 575, "Batch Conversion");
     
@@ -200,11 +226,30 @@ pub fn create_batch_tab(tabs: &mut Tabs, keyboard_layout: Rc<RefCell<i32>>) {
     batch_convert_btn.set_callback(move |_| {
         batch::process_batch(
             &batch_buffer_clone.borrow().text(),
-            *kb_layout_for_batch.borrow(),
+            &kb_layout_for_batch.borrow(),
             batch_result_buffer_clone.clone()
         );
     });
     
     batch_tab.end();
     tabs.add(&batch_tab);
-}
\ No newline at end of file
+}
+
+/// Fill a choice with every keyboard layout, in `KeyboardLayout::all()` order, and select `selected`
+pub fn fill_layout_choice(choice: &mut Choice, selected: &KeyboardLayout) {
+    let layouts = KeyboardLayout::all();
+    choice.clear();
+    for layout in &layouts {
+        choice.add_choice(layout.name());
+    }
+    let index = layouts.iter().position(|layout| layout == selected).unwrap_or(0);
+    choice.set_value(index as i32);
+}
+
+/// The layout picked in a choice filled by `fill_layout_choice`
+pub fn selected_layout(choice: &Choice) -> KeyboardLayout {
+    usize::try_from(choice.value())
+        .ok()
+        .and_then(|index| KeyboardLayout::all().into_iter().nth(index))
+        .unwrap_or_default()
+}
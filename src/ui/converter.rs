@@ -3,16 +3,21 @@ use std::cell::RefCell;
 use std::rc::Rc;
 use fltk::text::TextBuffer;
 
-// Import utility functions from the utils module
+// Decoding lives in the library; this module only fills the display buffers
+use crate::batch;
+use crate::convert;
+use crate::keyboard_layout::{self, KeyboardLayout};
+use crate::wiegand;
 use crate::utils;
 
 pub fn convert_uid(
     uid: &str,
-    keyboard_layout: i32,
+    keyboard_layout: &KeyboardLayout,
     hex_buffer: Rc<RefCell<TextBuffer>>,
     dec_buffer: Rc<RefCell<TextBuffer>>,
     mfg_buffer: Rc<RefCell<TextBuffer>>,
-    format_buffer: Rc<RefCell<TextBuffer>>
+    format_buffer: Rc<RefCell<TextBuffer>>,
+    formats_buffer: Rc<RefCell<TextBuffer>>
 ) {
     if uid.is_empty() {
         // Clear all buffers if input is empty
@@ -20,21 +25,63 @@ pub fn convert_uid(
         dec_buffer.borrow_mut().set_text("");
         mfg_buffer.borrow_mut().set_text("");
         format_buffer.borrow_mut().set_text("");
+        formats_buffer.borrow_mut().set_text("");
         return;
     }
     
-    // Process the UID with the selected keyboard layout
-    let (hex_uid, manufacturer) = utils::process_uid_for_display(uid, keyboard_layout);
-    
-    // Calculate decimal value
-    let decimal_value = utils::hex_to_decimal(&hex_uid);
-    
-    // Determine format
-    let format_desc = utils::interpret_format_code(uid);
+    // Binary input of 26, 34 or 37 bits is a Wiegand frame from a badge reader, not a UID
+    if wiegand::is_frame(uid) {
+        match wiegand::parse_frame(uid) {
+            Ok(credential) => {
+                hex_buffer.borrow_mut().set_text(&credential.to_hex());
+                dec_buffer.borrow_mut().set_text(&credential.to_string());
+                mfg_buffer.borrow_mut().set_text("N/A");
+                format_buffer.borrow_mut().set_text(&format!("{} frame, parity OK", credential.format.name()));
+            },
+            Err(e) => {
+                hex_buffer.borrow_mut().set_text(&format!("Invalid Wiegand frame: {}", e));
+                dec_buffer.borrow_mut().set_text("N/A");
+                mfg_buffer.borrow_mut().set_text("N/A");
+                format_buffer.borrow_mut().set_text("Wiegand frame");
+            }
+        }
+        formats_buffer.borrow_mut().set_text("");
+        return;
+    }
     
-    // Update display buffers
-    hex_buffer.borrow_mut().set_text(&hex_uid);
-    dec_buffer.borrow_mut().set_text(&decimal_value);
-    mfg_buffer.borrow_mut().set_text(&manufacturer);
-    format_buffer.borrow_mut().set_text(&format_desc);
+    // Decode the UID with the selected keyboard layout
+    let (_, human_timestamp) = utils::get_timestamps();
+    match batch::decode_line(uid, keyboard_layout, &human_timestamp) {
+        Ok(record) => {
+            // Update display buffers
+            hex_buffer.borrow_mut().set_text(&record.uid.to_spaced_hex());
+            dec_buffer.borrow_mut().set_text(&record.uid.to_decimal());
+            mfg_buffer.borrow_mut().set_text(&record.manufacturer);
+            // Say which layout auto-detect settled on, it's the first thing to check on a wrong UID
+            let format = match keyboard_layout::detect_layout(uid) {
+                Some(best) if keyboard_layout.is_auto_detect() => {
+                    format!("{} ({} keyboard detected)", record.format, best.layout)
+                },
+                _ => record.format.clone(),
+            };
+            format_buffer.borrow_mut().set_text(&format);
+            
+            // One line per named output format
+            let mut formats: Vec<String> = convert::all_formats(&record.uid)
+                .iter()
+                .map(|(format, value)| format!("{}: {}", format.name(), value))
+                .collect();
+            for credential in &record.wiegand {
+                formats.push(format!("{} frame: {}", credential.format.name(), credential.to_hex()));
+            }
+            formats_buffer.borrow_mut().set_text(&formats.join("\n"));
+        },
+        Err(e) => {
+            hex_buffer.borrow_mut().set_text(&format!("Invalid UID: {}", e));
+            dec_buffer.borrow_mut().set_text("N/A");
+            mfg_buffer.borrow_mut().set_text("Unknown");
+            format_buffer.borrow_mut().set_text(&utils::interpret_format_code(uid));
+            formats_buffer.borrow_mut().set_text("");
+        }
+    }
 }
\ No newline at end of file
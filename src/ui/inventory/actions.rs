@@ -71,8 +71,9 @@ impl InventoryUI {
                     Ok(mut item) => {
                         // Get created_at date from existing item if possible
                         if let Ok(Some(existing_item)) = db_clone.borrow().get_item(&tag_id) {
-                            // Keep the original creation date
+                            // Keep the original creation date and the card type learned from the reader
                             item.created_at = existing_item.created_at.clone();
+                            item.card_family = existing_item.card_family;
                         }
                         
                         // Save to database
@@ -117,7 +118,7 @@ impl InventoryUI {
         delete_btn.set_callback(move |_| {
             if let Some(tag_id) = current_tag_clone.borrow().clone() {
                 // Ask for confirmation
-                if dialog::choice2(300, 300, "Are you sure you want to delete this item?", "No", "Yes", "") == Some(1) {
+                if dialog::choice2(300, 300, "Move this item to the trash? It can be restored from File > Trash.", "No", "Yes", "") == Some(1) {
                     // Delete from database
                     if let Err(e) = db_clone.borrow().delete_item(&tag_id) {
                         dialog::alert(300, 300, &format!("Error deleting item: {}", e));
@@ -137,8 +138,8 @@ impl InventoryUI {
                         table_clone.borrow_mut().redraw();
                     }
                     
-                    log_buffer_clone.append(&format!("Deleted item with tag: {}\n", tag_id));
-                    dialog::message(300, 300, "Item deleted successfully");
+                    log_buffer_clone.append(&format!("Moved item with tag {} to the trash\n", tag_id));
+                    dialog::message(300, 300, "Item moved to the trash");
                 }
             } else {
                 dialog::alert(300, 300, "No item selected to delete");
@@ -0,0 +1,196 @@
+// src/ui/inventory/components/bulk.rs
+use fltk::{
+    button::Button,
+    dialog,
+    enums::Shortcut,
+    frame::Frame,
+    menu::{Choice, MenuFlag},
+    prelude::*,
+    window::Window,
+};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::inventory::db::InventoryDB;
+use crate::inventory::model::InventoryItem;
+use crate::ui::inventory::components::print_labels;
+use crate::ui::inventory::handlers::export_handlers::export_items;
+use crate::ui::inventory::utils::menu_label;
+
+// What can be done to the rows selected in an item table at once. Changes run as one
+// transaction and are undone as one step
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BulkAction {
+    SetCategory,
+    SetLocation,
+    AdjustQuantity,
+    Delete,
+    Export,
+    PrintLabels,
+}
+
+impl BulkAction {
+    pub const ALL: [BulkAction; 6] = [
+        BulkAction::SetCategory,
+        BulkAction::SetLocation,
+        BulkAction::AdjustQuantity,
+        BulkAction::Delete,
+        BulkAction::Export,
+        BulkAction::PrintLabels,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            BulkAction::SetCategory => "Set Category...",
+            BulkAction::SetLocation => "Set Location...",
+            BulkAction::AdjustQuantity => "Adjust Quantity...",
+            BulkAction::Delete => "Delete...",
+            BulkAction::Export => "Export Selection...",
+            BulkAction::PrintLabels => "Print Labels...",
+        }
+    }
+}
+
+// Run `action` on the selected `items`, asking for what it needs first. `on_done` gets a line for
+// the event log and whether the database changed, so the caller knows to reload its list
+pub fn run_bulk_action(
+    action: BulkAction,
+    inventory_db: Rc<RefCell<InventoryDB>>,
+    items: Vec<InventoryItem>,
+    mut on_done: impl FnMut(&str, bool) + 'static
+) {
+    if items.is_empty() {
+        dialog::alert(300, 300, "No items selected; click rows with Ctrl or Shift held to select several");
+        return;
+    }
+
+    let ids: Vec<i64> = items.iter().filter_map(|item| item.id).collect();
+    let count = items.len();
+
+    match action {
+        BulkAction::SetCategory => {
+            let categories = match inventory_db.borrow().get_categories() {
+                Ok(categories) => categories,
+                Err(e) => return dialog::alert(300, 300, &format!("Error loading categories: {}", e)),
+            };
+            let paths: Vec<String> = categories.iter().map(|category| category.path.clone()).collect();
+
+            let prompt = format!("Category for {} item(s):", count);
+            show_pick_dialog("Set Category", &prompt, "No category", &paths, move |index| {
+                let category = index.and_then(|index| categories.get(index));
+                match inventory_db.borrow().bulk_set_category(&ids, category.map(|category| category.id)) {
+                    Ok(changed) => on_done(&format!(
+                        "Set the category of {} item(s) to {}",
+                        changed,
+                        category.map(|category| category.path.as_str()).unwrap_or("none")
+                    ), true),
+                    Err(e) => dialog::alert(300, 300, &format!("Error setting category: {}", e)),
+                }
+            });
+        }
+        BulkAction::SetLocation => {
+            let locations = match inventory_db.borrow().get_locations() {
+                Ok(locations) => locations,
+                Err(e) => return dialog::alert(300, 300, &format!("Error loading locations: {}", e)),
+            };
+            let paths: Vec<String> = locations.iter().map(|location| location.path.clone()).collect();
+
+            let prompt = format!("Location for {} item(s):", count);
+            show_pick_dialog("Set Location", &prompt, "No location", &paths, move |index| {
+                let location = index.and_then(|index| locations.get(index));
+                match inventory_db.borrow().bulk_set_location(&ids, location.map(|location| location.id)) {
+                    Ok(moved) => on_done(&format!(
+                        "Moved {} item(s) to {}",
+                        moved,
+                        location.map(|location| location.path.as_str()).unwrap_or("no location")
+                    ), true),
+                    Err(e) => dialog::alert(300, 300, &format!("Error moving items: {}", e)),
+                }
+            });
+        }
+        BulkAction::AdjustQuantity => {
+            let prompt = format!("Add to the quantity of {} item(s), negative to take away:", count);
+            let Some(input) = dialog::input(300, 300, &prompt, "") else {
+                return;
+            };
+            let delta = match input.trim().trim_start_matches('+').parse::<i32>() {
+                Ok(0) => return,
+                Ok(delta) => delta,
+                Err(_) => return dialog::alert(300, 300, &format!("'{}' is not a whole number", input.trim())),
+            };
+
+            match inventory_db.borrow().bulk_adjust_quantity(&ids, delta) {
+                Ok(adjusted) => on_done(&format!("Adjusted the quantity of {} item(s) by {:+}", adjusted, delta), true),
+                Err(e) => dialog::alert(300, 300, &format!("Error adjusting quantities: {}", e)),
+            }
+        }
+        BulkAction::Delete => {
+            let question = format!("Move {} item(s) to the trash? Undo or File > Trash brings them back.", count);
+            if dialog::choice2(300, 300, &question, "Cancel", "Delete", "") != Some(1) {
+                return;
+            }
+
+            match inventory_db.borrow().bulk_delete(&ids) {
+                Ok(deleted) => on_done(&format!("Moved {} item(s) to the trash", deleted), true),
+                Err(e) => dialog::alert(300, 300, &format!("Error deleting items: {}", e)),
+            }
+        }
+        BulkAction::Export => {
+            if let Some(message) = export_items(&items) {
+                on_done(&message, false);
+            }
+        }
+        BulkAction::PrintLabels => {
+            if print_labels(&items) {
+                on_done(&format!("Printed labels for {} item(s)", count), false);
+            }
+        }
+    }
+}
+
+// Pick one of `choices`, or `none_label` for none; `on_pick` gets the index picked
+fn show_pick_dialog(
+    title: &str,
+    prompt: &str,
+    none_label: &str,
+    choices: &[String],
+    on_pick: impl FnMut(Option<usize>) + 'static
+) {
+    let mut wind = Window::new(200, 200, 420, 130, None);
+    wind.set_label(title);
+
+    let mut prompt_frame = Frame::new(20, 10, 380, 25, None);
+    prompt_frame.set_label(prompt);
+    prompt_frame.set_align(fltk::enums::Align::Left | fltk::enums::Align::Inside);
+
+    let mut choice = Choice::new(20, 40, 380, 25, None);
+    choice.add_choice(&menu_label(none_label));
+    for path in choices {
+        choice.add(&menu_label(path), Shortcut::None, MenuFlag::Normal, |_| {});
+    }
+    choice.set_value(0);
+
+    let mut ok_btn = Button::new(210, 85, 90, 30, "OK");
+    let mut cancel_btn = Button::new(310, 85, 90, 30, "Cancel");
+
+    wind.end();
+
+    let mut on_pick = on_pick;
+    let mut wind_clone = wind.clone();
+    ok_btn.set_callback(move |_| {
+        wind_clone.hide();
+        let index = match choice.value() {
+            index if index > 0 => Some(index as usize - 1),
+            _ => None,
+        };
+        on_pick(index);
+    });
+
+    let mut wind_clone = wind.clone();
+    cancel_btn.set_callback(move |_| {
+        wind_clone.hide();
+    });
+
+    wind.make_modal(true);
+    wind.show();
+}
@@ -0,0 +1,414 @@
+// src/ui/inventory/components/categories.rs
+use fltk::{
+    browser::HoldBrowser,
+    button::Button,
+    dialog::{self, ColorMode},
+    enums::{Align, Color, Font},
+    frame::Frame,
+    prelude::*,
+    tree::{Tree, TreeSelect},
+    window::Window,
+};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::inventory::db::InventoryDB;
+use crate::inventory::model::{Category, CategoryField, CategoryStats, FieldType, format_color, path_segments};
+
+// Manage categories: nest them, give them a colour and declare the custom fields of their items
+pub fn show_categories_window(inventory_db: Rc<RefCell<InventoryDB>>) {
+    let mut wind = Window::new(150, 150, 640, 500, "Categories");
+
+    let mut header = Frame::new(10, 10, 620, 30, "Categories");
+    header.set_label_font(Font::HelveticaBold);
+    header.set_label_size(16);
+
+    let mut tree = Tree::new(10, 50, 380, 440, "");
+    tree.set_show_root(false);
+    tree.set_select_mode(TreeSelect::Single);
+
+    let mut details = Frame::new(400, 50, 230, 80, "Select a category");
+    details.set_align(Align::TopLeft | Align::Inside | Align::Wrap);
+
+    let mut add_btn = Button::new(400, 135, 230, 30, "Add Category...");
+    let mut rename_btn = Button::new(400, 170, 230, 30, "Rename...");
+    let mut move_btn = Button::new(400, 205, 230, 30, "Move...");
+    let mut delete_btn = Button::new(400, 240, 113, 30, "Delete");
+    let mut stock_btn = Button::new(517, 240, 113, 30, "Stock Levels...");
+    let mut color_btn = Button::new(400, 280, 113, 30, "Colour...");
+    let mut no_color_btn = Button::new(517, 280, 113, 30, "No Colour");
+
+    let field_list = HoldBrowser::new(400, 320, 230, 90, "");
+    let mut add_field_btn = Button::new(400, 415, 113, 30, "Add Field...");
+    let mut remove_field_btn = Button::new(517, 415, 113, 30, "Remove Field");
+    let mut close_btn = Button::new(400, 460, 230, 30, "Close");
+
+    wind.end();
+
+    // Per-category statistics, reloaded after every change, and the fields listed for the selection
+    let stats: Rc<RefCell<Vec<CategoryStats>>> = Rc::new(RefCell::new(Vec::new()));
+    let fields: Rc<RefCell<Vec<CategoryField>>> = Rc::new(RefCell::new(Vec::new()));
+    reload_tree(&inventory_db.borrow(), &mut tree, &stats);
+
+    let db_clone = inventory_db.clone();
+    let stats_clone = stats.clone();
+    let fields_clone = fields.clone();
+    let mut details_clone = details.clone();
+    let mut field_list_clone = field_list.clone();
+    tree.set_callback(move |t| {
+        field_list_clone.clear();
+        fields_clone.borrow_mut().clear();
+
+        let stats = stats_clone.borrow();
+        let Some(entry) = selected_stats(t, &stats) else {
+            details_clone.set_label("Select a category");
+            return;
+        };
+        details_clone.set_label(&format!(
+            "{}\n\nColour: {}\nItems: {}   Quantity: {}\nMinimum: {}   Reorder: {}",
+            entry.category.path,
+            match (&entry.category.color, &entry.category.display_color) {
+                (Some(color), _) => color.clone(),
+                (None, Some(color)) => format!("{} (inherited)", color),
+                (None, None) => "none".to_string(),
+            },
+            entry.items,
+            entry.quantity,
+            describe_level(entry.category.min_quantity, entry.category.effective_min_quantity),
+            describe_level(entry.category.reorder_quantity, entry.category.effective_reorder_quantity)
+        ));
+
+        match db_clone.borrow().get_category_fields(entry.category.id) {
+            Ok(category_fields) => {
+                for field in &category_fields {
+                    let inherited = if field.category_id == entry.category.id { "" } else { ", inherited" };
+                    let required = if field.required { ", required" } else { "" };
+                    field_list_clone.add(&format!("{} ({}{}{})", field.name, field.field_type, required, inherited));
+                }
+                *fields_clone.borrow_mut() = category_fields;
+            }
+            Err(e) => dialog::alert(300, 300, &format!("Error loading fields: {}", e)),
+        }
+    });
+
+    // Adds under the selected category, or at the top level when nothing is selected
+    let db_clone = inventory_db.clone();
+    let stats_clone = stats.clone();
+    let mut tree_clone = tree.clone();
+    add_btn.set_callback(move |_| {
+        let parent = selected_category(&tree_clone, &stats_clone.borrow());
+        let prompt = match &parent {
+            Some(parent) => format!("Name of the new category in '{}':", parent.path),
+            None => "Name of the new top-level category:".to_string(),
+        };
+        let name = match dialog::input(300, 300, &prompt, "") {
+            Some(name) if !name.trim().is_empty() => name,
+            _ => return,
+        };
+
+        let result = db_clone.borrow().add_category(&name, parent.map(|parent| parent.id));
+        match result {
+            Ok(_) => reload_tree(&db_clone.borrow(), &mut tree_clone, &stats_clone),
+            Err(e) => dialog::alert(300, 300, &format!("Error adding category: {}", e)),
+        }
+    });
+
+    let db_clone = inventory_db.clone();
+    let stats_clone = stats.clone();
+    let mut tree_clone = tree.clone();
+    rename_btn.set_callback(move |_| {
+        let Some(category) = selected_category(&tree_clone, &stats_clone.borrow()) else {
+            dialog::alert(300, 300, "No category selected to rename");
+            return;
+        };
+        let name = match dialog::input(300, 300, &format!("New name for '{}':", category.path), &category.name) {
+            Some(name) if !name.trim().is_empty() => name,
+            _ => return,
+        };
+
+        let result = db_clone.borrow().rename_category(category.id, &name);
+        match result {
+            Ok(_) => {
+                reload_tree(&db_clone.borrow(), &mut tree_clone, &stats_clone);
+                tree_clone.do_callback();
+            }
+            Err(e) => dialog::alert(300, 300, &format!("Error renaming category: {}", e)),
+        }
+    });
+
+    let db_clone = inventory_db.clone();
+    let stats_clone = stats.clone();
+    let mut tree_clone = tree.clone();
+    move_btn.set_callback(move |_| {
+        let Some(category) = selected_category(&tree_clone, &stats_clone.borrow()) else {
+            dialog::alert(300, 300, "No category selected to move");
+            return;
+        };
+        let prompt = format!("Move '{}' into (path, empty for the top level):", category.path);
+        let Some(parent_path) = dialog::input(300, 300, &prompt, "") else {
+            return;
+        };
+
+        let parent_id = if parent_path.trim().is_empty() {
+            None
+        } else {
+            match db_clone.borrow().find_category(&parent_path) {
+                Ok(Some(parent)) => Some(parent.id),
+                Ok(None) => {
+                    dialog::alert(300, 300, &format!("Unknown category '{}'", parent_path));
+                    return;
+                }
+                Err(e) => {
+                    dialog::alert(300, 300, &format!("Error loading categories: {}", e));
+                    return;
+                }
+            }
+        };
+
+        // Fields inherited from the old parent go, along with the values items had for them
+        let question = format!("Move '{}'? Values of fields it no longer inherits are removed.", category.path);
+        if dialog::choice2(300, 300, &question, "No", "Yes", "") != Some(1) {
+            return;
+        }
+
+        let result = db_clone.borrow().move_category(category.id, parent_id);
+        match result {
+            Ok(_) => {
+                reload_tree(&db_clone.borrow(), &mut tree_clone, &stats_clone);
+                tree_clone.do_callback();
+            }
+            Err(e) => dialog::alert(300, 300, &format!("Error moving category: {}", e)),
+        }
+    });
+
+    let db_clone = inventory_db.clone();
+    let stats_clone = stats.clone();
+    let mut tree_clone = tree.clone();
+    delete_btn.set_callback(move |_| {
+        let Some(category) = selected_category(&tree_clone, &stats_clone.borrow()) else {
+            dialog::alert(300, 300, "No category selected to delete");
+            return;
+        };
+        if dialog::choice2(300, 300, &format!("Delete the category '{}'?", category.path), "No", "Yes", "") != Some(1) {
+            return;
+        }
+
+        let result = db_clone.borrow().delete_category(category.id);
+        match result {
+            Ok(()) => {
+                reload_tree(&db_clone.borrow(), &mut tree_clone, &stats_clone);
+                tree_clone.do_callback();
+            }
+            Err(e) => dialog::alert(300, 300, &format!("Error deleting category: {}", e)),
+        }
+    });
+
+    // Empty inputs inherit the parent's level again
+    let db_clone = inventory_db.clone();
+    let stats_clone = stats.clone();
+    let mut tree_clone = tree.clone();
+    stock_btn.set_callback(move |_| {
+        let Some(category) = selected_category(&tree_clone, &stats_clone.borrow()) else {
+            dialog::alert(300, 300, "No category selected");
+            return;
+        };
+        let Some(min_quantity) = prompt_level(
+            &format!("Minimum stock for items in '{}' (empty to inherit):", category.path),
+            category.min_quantity
+        ) else {
+            return;
+        };
+        let Some(reorder_quantity) = prompt_level(
+            &format!("Quantity to reorder for items in '{}' (empty to inherit):", category.path),
+            category.reorder_quantity
+        ) else {
+            return;
+        };
+
+        let result = db_clone.borrow().set_category_stock_levels(category.id, min_quantity, reorder_quantity);
+        match result {
+            Ok(_) => {
+                reload_tree(&db_clone.borrow(), &mut tree_clone, &stats_clone);
+                tree_clone.do_callback();
+            }
+            Err(e) => dialog::alert(300, 300, &format!("Error setting stock levels: {}", e)),
+        }
+    });
+
+    let db_clone = inventory_db.clone();
+    let stats_clone = stats.clone();
+    let mut tree_clone = tree.clone();
+    color_btn.set_callback(move |_| {
+        let Some(category) = selected_category(&tree_clone, &stats_clone.borrow()) else {
+            dialog::alert(300, 300, "No category selected");
+            return;
+        };
+        let Some(rgb) = dialog::color_chooser(&format!("Colour of '{}'", category.path), ColorMode::Byte) else {
+            return;
+        };
+
+        let color = format_color(rgb);
+        let result = db_clone.borrow().set_category_color(category.id, Some(&color));
+        match result {
+            Ok(_) => {
+                reload_tree(&db_clone.borrow(), &mut tree_clone, &stats_clone);
+                tree_clone.do_callback();
+            }
+            Err(e) => dialog::alert(300, 300, &format!("Error setting colour: {}", e)),
+        }
+    });
+
+    // Without its own colour a category shows its parent's again
+    let db_clone = inventory_db.clone();
+    let stats_clone = stats.clone();
+    let mut tree_clone = tree.clone();
+    no_color_btn.set_callback(move |_| {
+        let Some(category) = selected_category(&tree_clone, &stats_clone.borrow()) else {
+            dialog::alert(300, 300, "No category selected");
+            return;
+        };
+
+        let result = db_clone.borrow().set_category_color(category.id, None);
+        match result {
+            Ok(_) => {
+                reload_tree(&db_clone.borrow(), &mut tree_clone, &stats_clone);
+                tree_clone.do_callback();
+            }
+            Err(e) => dialog::alert(300, 300, &format!("Error clearing colour: {}", e)),
+        }
+    });
+
+    let db_clone = inventory_db.clone();
+    let stats_clone = stats.clone();
+    let mut tree_clone = tree.clone();
+    add_field_btn.set_callback(move |_| {
+        let Some(category) = selected_category(&tree_clone, &stats_clone.borrow()) else {
+            dialog::alert(300, 300, "No category selected to add a field to");
+            return;
+        };
+        let name = match dialog::input(300, 300, &format!("Name of the new field for '{}':", category.path), "") {
+            Some(name) if !name.trim().is_empty() => name,
+            _ => return,
+        };
+        let question = format!("What does '{}' hold?", name.trim());
+        let field_type = match dialog::choice2(300, 300, &question, "Text", "Number", "Date") {
+            Some(choice) => FieldType::ALL[choice as usize],
+            None => return,
+        };
+        let question = format!("Must every item in '{}' have a value for '{}'?", category.path, name.trim());
+        let required = match dialog::choice2(300, 300, &question, "No", "Yes", "") {
+            Some(choice) => choice == 1,
+            None => return,
+        };
+
+        let result = db_clone.borrow().add_category_field(category.id, &name, field_type, required);
+        match result {
+            Ok(_) => tree_clone.do_callback(),
+            Err(e) => dialog::alert(300, 300, &format!("Error adding field: {}", e)),
+        }
+    });
+
+    // Only fields declared by the selected category itself can be removed here
+    let db_clone = inventory_db;
+    let stats_clone = stats;
+    let mut tree_clone = tree.clone();
+    remove_field_btn.set_callback(move |_| {
+        let Some(category) = selected_category(&tree_clone, &stats_clone.borrow()) else {
+            dialog::alert(300, 300, "No category selected");
+            return;
+        };
+        // Browser lines are 1-based and in field order
+        let line = field_list.value();
+        let Some(field) = (line > 0).then(|| fields.borrow().get(line as usize - 1).cloned()).flatten() else {
+            dialog::alert(300, 300, "No field selected to remove");
+            return;
+        };
+        let question = format!("Remove '{}' and the values items have for it?", field.name);
+        if dialog::choice2(300, 300, &question, "No", "Yes", "") != Some(1) {
+            return;
+        }
+
+        let result = db_clone.borrow().remove_category_field(category.id, &field.name);
+        match result {
+            Ok(()) => tree_clone.do_callback(),
+            Err(e) => dialog::alert(300, 300, &format!("Error removing field: {}", e)),
+        }
+    });
+
+    let mut wind_clone = wind.clone();
+    close_btn.set_callback(move |_| {
+        wind_clone.hide();
+    });
+
+    wind.make_modal(true);
+    wind.show();
+}
+
+// Rebuild the tree from the database, keeping the selection where possible
+fn reload_tree(inventory_db: &InventoryDB, tree: &mut Tree, stats: &Rc<RefCell<Vec<CategoryStats>>>) {
+    let selected = selected_category(tree, &stats.borrow()).map(|category| category.id);
+
+    let entries = match inventory_db.category_stats() {
+        Ok(entries) => entries,
+        Err(e) => {
+            dialog::alert(300, 300, &format!("Error loading categories: {}", e));
+            return;
+        }
+    };
+
+    tree.clear();
+    for entry in &entries {
+        if let Some(mut item) = tree.add(&tree_path(&entry.category)) {
+            if let Some((r, g, b)) = entry.category.rgb() {
+                item.set_label_bgcolor(Color::from_rgb(r, g, b).lighter());
+            }
+            if Some(entry.category.id) == selected {
+                item.select_toggle();
+            }
+        }
+    }
+    tree.redraw();
+
+    *stats.borrow_mut() = entries;
+}
+
+// The tree nests on '/', which category names can't contain
+fn tree_path(category: &Category) -> String {
+    path_segments(&category.path).join("/")
+}
+
+fn selected_stats<'a>(tree: &Tree, stats: &'a [CategoryStats]) -> Option<&'a CategoryStats> {
+    let item = tree.first_selected_item()?;
+    let path = tree.item_pathname(&item).ok()?;
+    stats.iter().find(|entry| tree_path(&entry.category) == path)
+}
+
+fn selected_category(tree: &Tree, stats: &[CategoryStats]) -> Option<Category> {
+    selected_stats(tree, stats).map(|entry| entry.category.clone())
+}
+
+// e.g. "5", "5 (inherited)" or "none"
+fn describe_level(own: Option<i32>, effective: Option<i32>) -> String {
+    match (own, effective) {
+        (Some(level), _) => level.to_string(),
+        (None, Some(level)) => format!("{} (inherited)", level),
+        (None, None) => "none".to_string(),
+    }
+}
+
+// Ask for a stock level; `None` if cancelled or not a number, `Some(None)` when left empty
+fn prompt_level(prompt: &str, current: Option<i32>) -> Option<Option<i32>> {
+    let current = current.map(|level| level.to_string()).unwrap_or_default();
+    let text = dialog::input(300, 300, prompt, &current)?;
+    if text.trim().is_empty() {
+        return Some(None);
+    }
+
+    match text.trim().parse::<i32>() {
+        Ok(level) => Some(Some(level)),
+        Err(_) => {
+            dialog::alert(300, 300, &format!("'{}' is not a whole number", text.trim()));
+            None
+        }
+    }
+}
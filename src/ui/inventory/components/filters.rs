@@ -0,0 +1,138 @@
+// src/ui/inventory/components/filters.rs
+use fltk::{
+    button::{Button, CheckButton},
+    dialog,
+    enums::Shortcut,
+    frame::Frame,
+    input::{Input, IntInput},
+    menu::{Choice, MenuFlag},
+    prelude::*,
+    window::Window,
+};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use chrono::NaiveDate;
+
+use crate::inventory::db::InventoryDB;
+use crate::inventory::model::DATE_FORMAT;
+use crate::inventory::view::ItemFilter;
+use crate::ui::inventory::utils::{menu_label, ChoiceExt};
+
+// Edit the list filters, starting from `filter`; `on_apply` gets the new filters (all unset after Clear)
+pub fn show_filters_dialog(
+    inventory_db: Rc<RefCell<InventoryDB>>,
+    filter: &ItemFilter,
+    on_apply: impl Fn(ItemFilter) + 'static
+) {
+    let (categories, locations) = {
+        let db = inventory_db.borrow();
+        match (db.get_categories(), db.get_locations()) {
+            (Ok(categories), Ok(locations)) => (categories, locations),
+            (Err(e), _) | (_, Err(e)) => {
+                dialog::alert(300, 300, &format!("Error loading categories and locations: {}", e));
+                return;
+            }
+        }
+    };
+
+    let mut wind = Window::new(200, 200, 420, 260, "Filter Items");
+
+    let mut category_choice = Choice::new(130, 20, 270, 25, "Category:");
+    category_choice.add_choice("All categories");
+    for category in &categories {
+        category_choice.add(&menu_label(&category.path), Shortcut::None, MenuFlag::Normal, |_| {});
+    }
+    let selected = filter.category.as_ref().and_then(|path| {
+        categories.iter().position(|category| category.path.eq_ignore_ascii_case(path))
+    });
+    category_choice.set_value(selected.map(|index| index as i32 + 1).unwrap_or(0));
+
+    let mut location_choice = Choice::new(130, 55, 270, 25, "Location:");
+    location_choice.update_locations(&locations);
+    let selected = filter.location.as_ref().and_then(|path| {
+        locations.iter().position(|location| location.path.eq_ignore_ascii_case(path))
+    });
+    location_choice.set_value(selected.map(|index| index as i32 + 1).unwrap_or(0));
+
+    let mut min_input = IntInput::new(130, 90, 80, 25, "Quantity from:");
+    let mut max_input = IntInput::new(250, 90, 80, 25, "to:");
+    min_input.set_value(&filter.min_quantity.map(|min| min.to_string()).unwrap_or_default());
+    max_input.set_value(&filter.max_quantity.map(|max| max.to_string()).unwrap_or_default());
+
+    let mut since_input = Input::new(130, 125, 120, 25, "Updated since:");
+    since_input.set_value(filter.updated_since.as_deref().unwrap_or(""));
+    let _since_hint = Frame::new(255, 125, 100, 25, "YYYY-MM-DD");
+
+    let low_stock_check = CheckButton::new(130, 160, 270, 25, "Only items below their minimum stock");
+    low_stock_check.set_checked(filter.low_stock);
+
+    let mut apply_btn = Button::new(110, 210, 90, 30, "Apply");
+    let mut clear_btn = Button::new(210, 210, 90, 30, "Clear");
+    let mut cancel_btn = Button::new(310, 210, 90, 30, "Cancel");
+
+    wind.end();
+
+    let on_apply = Rc::new(on_apply);
+
+    let on_apply_clone = on_apply.clone();
+    let mut wind_clone = wind.clone();
+    apply_btn.set_callback(move |_| {
+        let quantity = |input: &IntInput, label: &str| -> Result<Option<i32>, String> {
+            match input.value().trim() {
+                "" => Ok(None),
+                value => value.parse::<i32>().map(Some).map_err(|_| format!("'{}' is not a {} quantity", value, label)),
+            }
+        };
+        let (min_quantity, max_quantity) = match (quantity(&min_input, "lowest"), quantity(&max_input, "highest")) {
+            (Ok(min), Ok(max)) => (min, max),
+            (Err(e), _) | (_, Err(e)) => {
+                dialog::alert(300, 300, &e);
+                return;
+            }
+        };
+        let updated_since = match since_input.value().trim() {
+            "" => None,
+            date => match NaiveDate::parse_from_str(date, DATE_FORMAT) {
+                Ok(date) => Some(date.format(DATE_FORMAT).to_string()),
+                Err(_) => {
+                    dialog::alert(300, 300, &format!("'{}' is not a date, expected YYYY-MM-DD", date));
+                    return;
+                }
+            },
+        };
+
+        let category = match category_choice.value() {
+            index if index > 0 => categories.get(index as usize - 1).map(|category| category.path.clone()),
+            _ => None,
+        };
+        let location = match location_choice.value() {
+            index if index > 0 => locations.get(index as usize - 1).map(|location| location.path.clone()),
+            _ => None,
+        };
+
+        on_apply_clone(ItemFilter {
+            category,
+            location,
+            min_quantity,
+            max_quantity,
+            updated_since,
+            low_stock: low_stock_check.is_checked(),
+        });
+        wind_clone.hide();
+    });
+
+    let mut wind_clone = wind.clone();
+    clear_btn.set_callback(move |_| {
+        on_apply(ItemFilter::default());
+        wind_clone.hide();
+    });
+
+    let mut wind_clone = wind.clone();
+    cancel_btn.set_callback(move |_| {
+        wind_clone.hide();
+    });
+
+    wind.make_modal(true);
+    wind.show();
+}
@@ -0,0 +1,285 @@
+//src/ui/inventory/components/form.rs
+use fltk::{
+    app,
+    dialog,
+    enums::Shortcut,
+    group::{Group, Scroll},
+    input::{Input, MultilineInput},
+    menu::{Choice, MenuFlag},
+    frame::Frame,
+    prelude::*,
+};
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use std::cell::RefCell;
+use crate::inventory::db::InventoryDB;
+use crate::inventory::model::{Category, CategoryField, FieldType, InventoryItem};
+use crate::uid::Uid;
+use crate::ui::inventory::utils::{format_timestamp, menu_label};
+
+pub struct ItemForm {
+    pub name_input: Input,
+    pub quantity_input: Input,
+    // Left empty, the category's stock levels apply
+    pub min_quantity_input: Input,
+    pub reorder_quantity_input: Input,
+    pub category_choice: Choice,
+    pub location_input: Input,
+    pub description_input: MultilineInput,
+    pub tag_id_display: Frame,
+    pub created_display: Frame,
+    pub updated_display: Frame,
+    // Inputs for the custom fields of the chosen category, rebuilt whenever the category changes
+    pub field_scroll: Scroll,
+    field_inputs: Rc<RefCell<Vec<(CategoryField, Input)>>>,
+    inventory_db: Rc<RefCell<InventoryDB>>,
+}
+impl Clone for ItemForm {
+    fn clone(&self) -> Self {
+        ItemForm {
+            name_input: self.name_input.clone(),
+            quantity_input: self.quantity_input.clone(),
+            min_quantity_input: self.min_quantity_input.clone(),
+            reorder_quantity_input: self.reorder_quantity_input.clone(),
+            category_choice: self.category_choice.clone(),
+            location_input: self.location_input.clone(),
+            description_input: self.description_input.clone(),
+            tag_id_display: self.tag_id_display.clone(),
+            created_display: self.created_display.clone(),
+            updated_display: self.updated_display.clone(),
+            field_scroll: self.field_scroll.clone(),
+            field_inputs: self.field_inputs.clone(),
+            inventory_db: self.inventory_db.clone(),
+        }
+    }
+}
+impl ItemForm {
+    pub fn new(x: i32, y: i32, w: i32, _h: i32, inventory_db: Rc<RefCell<InventoryDB>>) -> Self {
+        let name_input = Input::new(x + 100, y, w - 100, 30, "Name:");
+        let quantity_input = Input::new(x + 100, y + 40, 70, 30, "Quantity:");
+        let mut min_quantity_input = Input::new(x + 210, y + 40, 55, 30, "Min:");
+        min_quantity_input.set_tooltip("Stock below this needs reordering; empty uses the category's minimum");
+        let mut reorder_quantity_input = Input::new(x + 325, y + 40, w - 325, 30, "Reorder:");
+        reorder_quantity_input.set_tooltip("How many to order when below the minimum; empty uses the category's");
+        let category_choice = Choice::new(x + 100, y + 80, w - 100, 30, "Category:");
+        let mut location_input = Input::new(x + 100, y + 120, w - 100, 30, "Location:");
+        location_input.set_tooltip("Path such as Site/Room/Shelf; levels that don't exist yet are added");
+        let description_input = MultilineInput::new(x + 100, y + 160, w - 100, 50, "Description:");
+        let field_scroll = Scroll::new(x, y + 215, w, 50, None);
+        field_scroll.end();
+        
+        let tag_id_display = Frame::new(x, y + 270, w, 30, "Tag ID: None selected");
+        let created_display = Frame::new(x, y + 300, w, 30, "Created: -");
+        let updated_display = Frame::new(x, y + 330, w, 30, "Updated: -");
+        
+        let form = ItemForm {
+            name_input,
+            quantity_input,
+            min_quantity_input,
+            reorder_quantity_input,
+            category_choice,
+            location_input,
+            description_input,
+            tag_id_display,
+            created_display,
+            updated_display,
+            field_scroll,
+            field_inputs: Rc::new(RefCell::new(Vec::new())),
+            inventory_db,
+        };
+        
+        // Values typed so far stay when the new category has a field of the same name
+        let mut form_clone = form.clone();
+        form.category_choice.clone().set_callback(move |_| {
+            let values = form_clone.field_values();
+            form_clone.show_fields(&values);
+        });
+        
+        form
+    }
+    
+    pub fn clear(&mut self) {
+        self.name_input.set_value("");
+        self.quantity_input.set_value("");
+        self.min_quantity_input.set_value("");
+        self.reorder_quantity_input.set_value("");
+        self.category_choice.set_value(0);
+        self.show_fields(&BTreeMap::new());
+        self.location_input.set_value("");
+        self.description_input.set_value("");
+        self.tag_id_display.set_label("Tag ID: None selected");
+        self.created_display.set_label("Created: -");
+        self.updated_display.set_label("Updated: -");
+    }
+    
+    pub fn display_item(&mut self, item: &InventoryItem) {
+        self.name_input.set_value(&item.name);
+        self.quantity_input.set_value(&item.quantity.to_string());
+        self.min_quantity_input.set_value(&item.min_quantity.map(|min| min.to_string()).unwrap_or_default());
+        self.reorder_quantity_input.set_value(&item.reorder_quantity.map(|reorder| reorder.to_string()).unwrap_or_default());
+        
+        if let Some(cat) = &item.category {
+            // Find the category in the dropdown
+            for i in 0..self.category_choice.size() {
+                if let Some(choice_text) = self.category_choice.text(i) {
+                    if choice_text == *cat {
+                        self.category_choice.set_value(i);
+                        break;
+                    }
+                }
+            }
+        } else {
+            self.category_choice.set_value(0); // Uncategorized
+        }
+        self.show_fields(&item.attributes);
+        
+        self.location_input.set_value(&item.location.clone().unwrap_or_default());
+        self.description_input.set_value(&item.description.clone().unwrap_or_default());
+        
+        // Update display fields
+        match item.card_family {
+            Some(family) => self.tag_id_display.set_label(&format!("Tag ID: {} ({})", item.tag_id, family)),
+            None => self.tag_id_display.set_label(&format!("Tag ID: {}", item.tag_id)),
+        }
+        self.created_display.set_label(&format!("Created: {}", format_timestamp(&item.created_at)));
+        self.updated_display.set_label(&format!("Updated: {}", format_timestamp(&item.last_updated)));
+    }
+    
+    pub fn get_form_data(&self, tag_id: &Uid) -> Result<InventoryItem, String> {
+        // Validate form
+        let name = self.name_input.value();
+        if name.is_empty() {
+            return Err("Item name is required.".to_string());
+        }
+        
+        let quantity_str = self.quantity_input.value();
+        let quantity = match quantity_str.parse::<i32>() {
+            Ok(q) => q,
+            Err(_) => {
+                return Err("Quantity must be a valid number.".to_string());
+            }
+        };
+        
+        let min_quantity = optional_quantity(&self.min_quantity_input.value(), "Minimum quantity")?;
+        let reorder_quantity = optional_quantity(&self.reorder_quantity_input.value(), "Reorder quantity")?;
+        
+        // Get other field values
+        let category = if self.category_choice.value() <= 0 {
+            None
+        } else if let Some(cat_text) = self.category_choice.text(self.category_choice.value()) {
+            Some(cat_text)
+        } else {
+            None
+        };
+        
+        let location = if self.location_input.value().is_empty() {
+            None
+        } else {
+            Some(self.location_input.value())
+        };
+        
+        let description = if self.description_input.value().is_empty() {
+            None
+        } else {
+            Some(self.description_input.value())
+        };
+        
+        // Create a new item
+        let mut item = crate::inventory::model::create_inventory_item(
+            tag_id,
+            &name,
+            description.as_deref(),
+            quantity,
+            location.as_deref(),
+            category.as_deref()
+        );
+        // Checked against the category's fields when the item is saved
+        item.attributes = self.field_values();
+        item.min_quantity = min_quantity;
+        item.reorder_quantity = reorder_quantity;
+        
+        Ok(item)
+    }
+    
+    pub fn update_categories(&mut self, categories: &[Category]) {
+        self.category_choice.clear();
+        self.category_choice.add_choice("Uncategorized");
+        for category in categories {
+            self.category_choice.add(&menu_label(&category.path), Shortcut::None, MenuFlag::Normal, |_| {});
+        }
+    }
+    
+    // What has been typed into the custom field inputs, blank ones left out
+    pub fn field_values(&self) -> BTreeMap<String, String> {
+        self.field_inputs
+            .borrow()
+            .iter()
+            .map(|(field, input)| (field.name.clone(), input.value()))
+            .filter(|(_, value)| !value.trim().is_empty())
+            .collect()
+    }
+    
+    // Replace the field inputs with those of the chosen category, filled in from `values`
+    fn show_fields(&mut self, values: &BTreeMap<String, String>) {
+        let mut scroll = self.field_scroll.clone();
+        for (_, input) in self.field_inputs.borrow_mut().drain(..) {
+            scroll.remove(&input);
+            app::delete_widget(input);
+        }
+        scroll.scroll_to(0, 0);
+        
+        let fields = match self.selected_category() {
+            Some(path) => {
+                let db = self.inventory_db.borrow();
+                match db.find_category(&path).and_then(|category| match category {
+                    Some(category) => db.get_category_fields(category.id),
+                    None => Ok(Vec::new()),
+                }) {
+                    Ok(fields) => fields,
+                    Err(e) => {
+                        dialog::alert(300, 300, &format!("Error loading category fields: {}", e));
+                        Vec::new()
+                    }
+                }
+            }
+            None => Vec::new(),
+        };
+        
+        // Called from callbacks as well, so put back whichever group was being built
+        let current = Group::try_current();
+        scroll.begin();
+        for (row, field) in fields.into_iter().enumerate() {
+            let label = if field.required { format!("{}*:", field.name) } else { format!("{}:", field.name) };
+            let mut input = Input::new(scroll.x() + 100, scroll.y() + row as i32 * 30, scroll.w() - 120, 25, None);
+            input.set_label(&label);
+            match field.field_type {
+                FieldType::Text => {}
+                FieldType::Number => input.set_tooltip("A number"),
+                FieldType::Date => input.set_tooltip("A date as YYYY-MM-DD"),
+            }
+            if let Some((_, value)) = values.iter().find(|(name, _)| name.eq_ignore_ascii_case(&field.name)) {
+                input.set_value(value);
+            }
+            self.field_inputs.borrow_mut().push((field, input));
+        }
+        scroll.end();
+        Group::set_current(current.as_ref());
+        scroll.redraw();
+    }
+    
+    fn selected_category(&self) -> Option<String> {
+        if self.category_choice.value() <= 0 {
+            None
+        } else {
+            self.category_choice.text(self.category_choice.value())
+        }
+    }
+}
+
+// An empty input means "not set"
+fn optional_quantity(text: &str, what: &str) -> Result<Option<i32>, String> {
+    if text.trim().is_empty() {
+        return Ok(None);
+    }
+    text.trim().parse::<i32>().map(Some).map_err(|_| format!("{} must be a valid number.", what))
+}
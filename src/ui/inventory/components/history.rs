@@ -0,0 +1,156 @@
+// src/ui/inventory/components/history.rs
+use fltk::{
+    button::Button,
+    dialog,
+    draw,
+    enums::{Align, Color, Font},
+    frame::Frame,
+    prelude::*,
+    table::{Table, TableContext},
+    window::Window,
+};
+use std::rc::Rc;
+
+use crate::inventory::db::{InventoryDB, movements_to_csv};
+use crate::uid::Uid;
+use crate::ui::inventory::utils::format_timestamp;
+
+// Show the stock movements of one item with the running balance after each
+pub fn show_movement_history(inventory_db: &InventoryDB, tag_id: &Uid) {
+    let movements = match inventory_db.get_movements(tag_id) {
+        Ok(movements) => movements,
+        Err(e) => {
+            dialog::alert(300, 300, &format!("Error loading movement history: {}", e));
+            return;
+        }
+    };
+    let item = inventory_db.get_item(tag_id).ok().flatten();
+
+    // Running balance after each movement, in ledger order
+    let balances: Vec<i32> = movements
+        .iter()
+        .scan(0, |balance, movement| {
+            *balance += movement.delta;
+            Some(*balance)
+        })
+        .collect();
+    let ledger = balances.last().copied().unwrap_or(0);
+
+    let title = match &item {
+        Some(item) => format!("History of '{}' ({})", item.name, tag_id),
+        None => format!("History of {}", tag_id),
+    };
+    let mut wind = Window::new(150, 150, 860, 460, None);
+    wind.set_label(&title);
+
+    let mut header = Frame::new(10, 10, 840, 30, None);
+    header.set_label(&title);
+    header.set_label_font(Font::HelveticaBold);
+    header.set_label_size(16);
+
+    let mut table = Table::new(10, 50, 840, 340, "");
+    table.set_rows(movements.len() as i32);
+    table.set_row_header(false);
+    table.set_cols(7);
+    table.set_col_header(true);
+    table.set_col_width(0, 150); // Timestamp
+    table.set_col_width(1, 70);  // Change
+    table.set_col_width(2, 70);  // Balance
+    table.set_col_width(3, 100); // Reason
+    table.set_col_width(4, 110); // User
+    table.set_col_width(5, 130); // Station
+    table.set_col_width(6, 190); // Note
+    table.end();
+
+    let movements = Rc::new(movements);
+    let movements_clone = movements.clone();
+    table.draw_cell(move |_t, ctx, row, col, x, y, w, h| {
+        match ctx {
+            TableContext::StartPage => draw::set_font(Font::Helvetica, 12),
+            TableContext::ColHeader => {
+                draw::draw_rect_fill(x, y, w, h, Color::from_rgb(220, 220, 220));
+                draw::set_draw_color(Color::Black);
+                draw::draw_rect(x, y, w, h);
+                draw::set_font(Font::HelveticaBold, 12);
+
+                let header = match col {
+                    0 => "Timestamp",
+                    1 => "Change",
+                    2 => "Balance",
+                    3 => "Reason",
+                    4 => "User",
+                    5 => "Station",
+                    6 => "Note",
+                    _ => "",
+                };
+                draw::draw_text2(header, x, y, w, h, Align::Center);
+            },
+            TableContext::Cell => {
+                if row < 0 || row as usize >= movements_clone.len() {
+                    return;
+                }
+                let movement = &movements_clone[row as usize];
+
+                let bg_color = if row % 2 == 0 {
+                    Color::from_rgb(245, 245, 245)
+                } else {
+                    Color::White
+                };
+                draw::draw_rect_fill(x, y, w, h, bg_color);
+                draw::set_draw_color(Color::Black);
+                draw::draw_rect(x, y, w, h);
+                draw::set_font(Font::Helvetica, 12);
+
+                let text = match col {
+                    0 => format_timestamp(&movement.timestamp),
+                    1 => return draw::draw_text2(&format!("{:+}", movement.delta), x, y, w, h, Align::Center),
+                    2 => return draw::draw_text2(&balances[row as usize].to_string(), x, y, w, h, Align::Center),
+                    3 => movement.reason.name().to_string(),
+                    4 => movement.user.clone().unwrap_or_default(),
+                    5 => movement.station.clone().unwrap_or_default(),
+                    6 => movement.note.clone().unwrap_or_default(),
+                    _ => String::new(),
+                };
+                draw::draw_text2(&text, x + 5, y, w - 10, h, Align::Left);
+            },
+            _ => {}
+        }
+    });
+
+    // Flag items whose stored quantity no longer matches the ledger
+    let summary = match &item {
+        Some(item) if item.quantity != ledger => {
+            format!("Stored quantity {} does not match the ledger total {}", item.quantity, ledger)
+        }
+        _ => format!("{} movement(s), balance {}", movements.len(), ledger),
+    };
+    let mut summary_frame = Frame::new(10, 410, 500, 30, None);
+    summary_frame.set_label(&summary);
+    summary_frame.set_align(Align::Left | Align::Inside);
+    if item.as_ref().is_some_and(|item| item.quantity != ledger) {
+        summary_frame.set_label_color(Color::Red);
+    }
+
+    let mut export_btn = Button::new(620, 410, 110, 30, "Export CSV");
+    let mut close_btn = Button::new(740, 410, 110, 30, "Close");
+
+    let movements_clone = movements.clone();
+    export_btn.set_callback(move |_| {
+        if let Some(path) = dialog::file_chooser("Export history as CSV", "*.csv", ".", false) {
+            if let Err(e) = std::fs::write(&path, movements_to_csv(&movements_clone)) {
+                dialog::alert(300, 300, &format!("Error writing file: {}", e));
+            } else {
+                dialog::message(300, 300, &format!("History exported to {}", path));
+            }
+        }
+    });
+
+    let mut wind_clone = wind.clone();
+    close_btn.set_callback(move |_| {
+        wind_clone.hide();
+    });
+
+    wind.end();
+    wind.make_modal(true);
+    wind.show();
+}
@@ -0,0 +1,75 @@
+// src/ui/inventory/components/labels.rs
+use fltk::{
+    draw,
+    enums::{Align, Color, Font},
+    printer::Printer,
+};
+
+use crate::inventory::model::InventoryItem;
+
+// Labels per row and the height of one, in printer points (1/72 inch)
+const LABEL_COLUMNS: i32 = 2;
+const LABEL_HEIGHT: i32 = 90;
+
+// Print a sheet of labels for `items`: name, tag ID, category and location, two to a row with
+// cut lines around them. Shows the system print dialog; `false` when it was cancelled or failed
+pub fn print_labels(items: &[InventoryItem]) -> bool {
+    if items.is_empty() {
+        return false;
+    }
+
+    let mut printer = Printer::default();
+    // The page count is only known once the page size is; ask for the job with an unknown count
+    if printer.begin_job(0).is_err() {
+        return false;
+    }
+
+    let (page_width, page_height) = printer.printable_rect();
+    let label_width = page_width / LABEL_COLUMNS;
+    let rows_per_page = (page_height / LABEL_HEIGHT).max(1);
+    let per_page = (rows_per_page * LABEL_COLUMNS) as usize;
+
+    for page in items.chunks(per_page) {
+        if printer.begin_page().is_err() {
+            printer.end_job();
+            return false;
+        }
+
+        for (index, item) in page.iter().enumerate() {
+            let index = index as i32;
+            let x = (index % LABEL_COLUMNS) * label_width;
+            let y = (index / LABEL_COLUMNS) * LABEL_HEIGHT;
+            draw_label(item, x, y, label_width, LABEL_HEIGHT);
+        }
+
+        if printer.end_page().is_err() {
+            printer.end_job();
+            return false;
+        }
+    }
+
+    printer.end_job();
+    true
+}
+
+fn draw_label(item: &InventoryItem, x: i32, y: i32, w: i32, h: i32) {
+    let padding = 8;
+
+    draw::set_draw_color(Color::from_rgb(160, 160, 160));
+    draw::draw_rect(x, y, w, h);
+    draw::set_draw_color(Color::Black);
+
+    draw::set_font(Font::HelveticaBold, 14);
+    draw::draw_text2(&item.name, x + padding, y + padding, w - 2 * padding, 20, Align::Left | Align::Clip);
+
+    draw::set_font(Font::Courier, 12);
+    draw::draw_text2(&item.tag_id.to_string(), x + padding, y + padding + 24, w - 2 * padding, 16, Align::Left);
+
+    draw::set_font(Font::Helvetica, 10);
+    let details = [item.category.as_deref(), item.location.as_deref()]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join("  |  ");
+    draw::draw_text2(&details, x + padding, y + padding + 44, w - 2 * padding, 14, Align::Left | Align::Clip);
+}
@@ -0,0 +1,261 @@
+// src/ui/inventory/components/locations.rs
+use fltk::{
+    button::Button,
+    dialog,
+    enums::{Align, Font},
+    frame::Frame,
+    prelude::*,
+    tree::{Tree, TreeSelect},
+    window::Window,
+};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::inventory::db::InventoryDB;
+use crate::inventory::model::{Location, LocationStats, path_segments};
+use crate::keyboard_layout::KeyboardLayout;
+use crate::uid::Uid;
+
+// Manage the location hierarchy: add, rename, move and delete locations and mark them with tags
+pub fn show_locations_window(inventory_db: Rc<RefCell<InventoryDB>>, keyboard_layout: Rc<RefCell<KeyboardLayout>>) {
+    let mut wind = Window::new(150, 150, 640, 460, "Locations");
+
+    let mut header = Frame::new(10, 10, 620, 30, "Locations");
+    header.set_label_font(Font::HelveticaBold);
+    header.set_label_size(16);
+
+    let mut tree = Tree::new(10, 50, 380, 400, "");
+    tree.set_show_root(false);
+    tree.set_select_mode(TreeSelect::Single);
+
+    let mut details = Frame::new(400, 50, 230, 120, "Select a location");
+    details.set_align(Align::TopLeft | Align::Inside | Align::Wrap);
+
+    let mut add_btn = Button::new(400, 180, 230, 30, "Add Location...");
+    let mut rename_btn = Button::new(400, 215, 230, 30, "Rename...");
+    let mut move_btn = Button::new(400, 250, 230, 30, "Move...");
+    let mut delete_btn = Button::new(400, 285, 230, 30, "Delete");
+    let mut tag_btn = Button::new(400, 330, 230, 30, "Assign Tag...");
+    let mut untag_btn = Button::new(400, 365, 230, 30, "Remove Tag");
+    let mut close_btn = Button::new(400, 420, 230, 30, "Close");
+
+    wind.end();
+
+    // Per-location statistics, reloaded after every change
+    let stats: Rc<RefCell<Vec<LocationStats>>> = Rc::new(RefCell::new(Vec::new()));
+    reload_tree(&inventory_db.borrow(), &mut tree, &stats);
+
+    let stats_clone = stats.clone();
+    let mut details_clone = details.clone();
+    tree.set_callback(move |t| {
+        match selected_stats(t, &stats_clone.borrow()) {
+            Some(entry) => details_clone.set_label(&format!(
+                "{}\n\nTag: {}\nItems: {}\nQuantity: {}\n(sub-locations included)",
+                entry.location.path,
+                entry.location.tag_id.as_ref().map(Uid::to_hex).unwrap_or_else(|| "none".to_string()),
+                entry.items,
+                entry.quantity
+            )),
+            None => details_clone.set_label("Select a location"),
+        }
+    });
+
+    // Adds under the selected location, or at the top level when nothing is selected
+    let db_clone = inventory_db.clone();
+    let stats_clone = stats.clone();
+    let mut tree_clone = tree.clone();
+    add_btn.set_callback(move |_| {
+        let parent = selected_location(&tree_clone, &stats_clone.borrow());
+        let prompt = match &parent {
+            Some(parent) => format!("Name of the new location in '{}':", parent.path),
+            None => "Name of the new top-level location (e.g. a site):".to_string(),
+        };
+        let name = match dialog::input(300, 300, &prompt, "") {
+            Some(name) if !name.trim().is_empty() => name,
+            _ => return,
+        };
+
+        let result = db_clone.borrow().add_location(&name, parent.map(|parent| parent.id));
+        match result {
+            Ok(_) => reload_tree(&db_clone.borrow(), &mut tree_clone, &stats_clone),
+            Err(e) => dialog::alert(300, 300, &format!("Error adding location: {}", e)),
+        }
+    });
+
+    let db_clone = inventory_db.clone();
+    let stats_clone = stats.clone();
+    let mut tree_clone = tree.clone();
+    rename_btn.set_callback(move |_| {
+        let Some(location) = selected_location(&tree_clone, &stats_clone.borrow()) else {
+            dialog::alert(300, 300, "No location selected to rename");
+            return;
+        };
+        let name = match dialog::input(300, 300, &format!("New name for '{}':", location.path), &location.name) {
+            Some(name) if !name.trim().is_empty() => name,
+            _ => return,
+        };
+
+        let result = db_clone.borrow().rename_location(location.id, &name);
+        match result {
+            Ok(_) => reload_tree(&db_clone.borrow(), &mut tree_clone, &stats_clone),
+            Err(e) => dialog::alert(300, 300, &format!("Error renaming location: {}", e)),
+        }
+    });
+
+    let db_clone = inventory_db.clone();
+    let stats_clone = stats.clone();
+    let mut tree_clone = tree.clone();
+    move_btn.set_callback(move |_| {
+        let Some(location) = selected_location(&tree_clone, &stats_clone.borrow()) else {
+            dialog::alert(300, 300, "No location selected to move");
+            return;
+        };
+        let prompt = format!("Move '{}' into (path, empty for the top level):", location.path);
+        let Some(parent_path) = dialog::input(300, 300, &prompt, "") else {
+            return;
+        };
+
+        let parent_id = if parent_path.trim().is_empty() {
+            None
+        } else {
+            match db_clone.borrow().find_location(&parent_path) {
+                Ok(Some(parent)) => Some(parent.id),
+                Ok(None) => {
+                    dialog::alert(300, 300, &format!("Unknown location '{}'", parent_path));
+                    return;
+                }
+                Err(e) => {
+                    dialog::alert(300, 300, &format!("Error loading locations: {}", e));
+                    return;
+                }
+            }
+        };
+
+        let result = db_clone.borrow().move_location(location.id, parent_id);
+        match result {
+            Ok(_) => reload_tree(&db_clone.borrow(), &mut tree_clone, &stats_clone),
+            Err(e) => dialog::alert(300, 300, &format!("Error moving location: {}", e)),
+        }
+    });
+
+    let db_clone = inventory_db.clone();
+    let stats_clone = stats.clone();
+    let mut tree_clone = tree.clone();
+    let mut details_clone = details.clone();
+    delete_btn.set_callback(move |_| {
+        let Some(location) = selected_location(&tree_clone, &stats_clone.borrow()) else {
+            dialog::alert(300, 300, "No location selected to delete");
+            return;
+        };
+        if dialog::choice2(300, 300, &format!("Delete the location '{}'?", location.path), "No", "Yes", "") != Some(1) {
+            return;
+        }
+
+        let result = db_clone.borrow().delete_location(location.id);
+        match result {
+            Ok(()) => {
+                reload_tree(&db_clone.borrow(), &mut tree_clone, &stats_clone);
+                details_clone.set_label("Select a location");
+            }
+            Err(e) => dialog::alert(300, 300, &format!("Error deleting location: {}", e)),
+        }
+    });
+
+    // The tag can be typed or read with a keyboard-wedge reader into the prompt
+    let db_clone = inventory_db.clone();
+    let stats_clone = stats.clone();
+    let mut tree_clone = tree.clone();
+    tag_btn.set_callback(move |_| {
+        let Some(location) = selected_location(&tree_clone, &stats_clone.borrow()) else {
+            dialog::alert(300, 300, "No location selected to tag");
+            return;
+        };
+        let raw = match dialog::input(300, 300, &format!("Scan the tag for '{}':", location.path), "") {
+            Some(raw) if !raw.trim().is_empty() => raw,
+            _ => return,
+        };
+        let tag_id = match Uid::from_raw(raw.trim(), &keyboard_layout.borrow()) {
+            Ok(tag_id) => tag_id,
+            Err(e) => {
+                dialog::alert(300, 300, &format!("Could not decode '{}': {}", raw.trim(), e));
+                return;
+            }
+        };
+
+        let result = db_clone.borrow().set_location_tag(location.id, Some(&tag_id));
+        match result {
+            Ok(_) => {
+                reload_tree(&db_clone.borrow(), &mut tree_clone, &stats_clone);
+                tree_clone.do_callback();
+            }
+            Err(e) => dialog::alert(300, 300, &format!("Error tagging location: {}", e)),
+        }
+    });
+
+    let db_clone = inventory_db;
+    let stats_clone = stats;
+    let mut tree_clone = tree.clone();
+    untag_btn.set_callback(move |_| {
+        let Some(location) = selected_location(&tree_clone, &stats_clone.borrow()) else {
+            dialog::alert(300, 300, "No location selected");
+            return;
+        };
+
+        let result = db_clone.borrow().set_location_tag(location.id, None);
+        match result {
+            Ok(_) => {
+                reload_tree(&db_clone.borrow(), &mut tree_clone, &stats_clone);
+                tree_clone.do_callback();
+            }
+            Err(e) => dialog::alert(300, 300, &format!("Error removing tag: {}", e)),
+        }
+    });
+
+    let mut wind_clone = wind.clone();
+    close_btn.set_callback(move |_| {
+        wind_clone.hide();
+    });
+
+    wind.make_modal(true);
+    wind.show();
+}
+
+// Rebuild the tree from the database, keeping the selection where possible
+fn reload_tree(inventory_db: &InventoryDB, tree: &mut Tree, stats: &Rc<RefCell<Vec<LocationStats>>>) {
+    let selected = selected_location(tree, &stats.borrow()).map(|location| location.id);
+
+    let entries = match inventory_db.location_stats() {
+        Ok(entries) => entries,
+        Err(e) => {
+            dialog::alert(300, 300, &format!("Error loading locations: {}", e));
+            return;
+        }
+    };
+
+    tree.clear();
+    for entry in &entries {
+        if let Some(mut item) = tree.add(&tree_path(&entry.location)) {
+            if Some(entry.location.id) == selected {
+                item.select_toggle();
+            }
+        }
+    }
+    tree.redraw();
+
+    *stats.borrow_mut() = entries;
+}
+
+// The tree nests on '/', which location names can't contain
+fn tree_path(location: &Location) -> String {
+    path_segments(&location.path).join("/")
+}
+
+fn selected_stats<'a>(tree: &Tree, stats: &'a [LocationStats]) -> Option<&'a LocationStats> {
+    let item = tree.first_selected_item()?;
+    let path = tree.item_pathname(&item).ok()?;
+    stats.iter().find(|entry| tree_path(&entry.location) == path)
+}
+
+fn selected_location(tree: &Tree, stats: &[LocationStats]) -> Option<Location> {
+    selected_stats(tree, stats).map(|entry| entry.location.clone())
+}
@@ -0,0 +1,28 @@
+pub mod form;
+pub mod table;
+pub mod stats;
+pub mod history;
+pub mod locations;
+pub mod categories;
+pub mod tags;
+pub mod stocktake;
+pub mod reorder;
+pub mod filters;
+pub mod labels;
+pub mod bulk;
+pub mod trash;
+
+// Re-export components for convenience
+pub use form::ItemForm;
+pub use table::setup_inventory_table;
+pub use stats::StatsFrame;
+pub use history::show_movement_history;
+pub use locations::show_locations_window;
+pub use categories::show_categories_window;
+pub use tags::{choose_item, show_item_tags};
+pub use stocktake::show_stocktake_window;
+pub use reorder::show_reorder_list;
+pub use filters::show_filters_dialog;
+pub use labels::print_labels;
+pub use bulk::{run_bulk_action, BulkAction};
+pub use trash::show_trash_window;
\ No newline at end of file
@@ -0,0 +1,147 @@
+// src/ui/inventory/components/reorder.rs
+use fltk::{
+    button::Button,
+    dialog,
+    draw,
+    enums::{Align, Color, Font},
+    frame::Frame,
+    prelude::*,
+    table::{Table, TableContext},
+    window::Window,
+};
+use std::rc::Rc;
+
+use crate::inventory::db::{InventoryDB, reorder_to_csv};
+
+// Show the items below their minimum quantity with what to order, out of stock first
+pub fn show_reorder_list(inventory_db: &InventoryDB) {
+    let reorder = match inventory_db.needs_reorder() {
+        Ok(reorder) => reorder,
+        Err(e) => {
+            dialog::alert(300, 300, &format!("Error loading reorder list: {}", e));
+            return;
+        }
+    };
+
+    let mut wind = Window::new(150, 150, 860, 460, None);
+    wind.set_label("Reorder List");
+
+    let mut header = Frame::new(10, 10, 840, 30, None);
+    header.set_label("Items Below Their Minimum Quantity");
+    header.set_label_font(Font::HelveticaBold);
+    header.set_label_size(16);
+
+    let mut table = Table::new(10, 50, 840, 340, "");
+    table.set_rows(reorder.len() as i32);
+    table.set_row_header(false);
+    table.set_cols(7);
+    table.set_col_header(true);
+    table.set_col_width(0, 120); // Tag ID
+    table.set_col_width(1, 200); // Name
+    table.set_col_width(2, 140); // Category
+    table.set_col_width(3, 140); // Location
+    table.set_col_width(4, 70);  // Quantity
+    table.set_col_width(5, 70);  // Minimum
+    table.set_col_width(6, 80);  // Order
+    table.end();
+
+    let reorder = Rc::new(reorder);
+    let reorder_clone = reorder.clone();
+    table.draw_cell(move |_t, ctx, row, col, x, y, w, h| {
+        match ctx {
+            TableContext::StartPage => draw::set_font(Font::Helvetica, 12),
+            TableContext::ColHeader => {
+                draw::draw_rect_fill(x, y, w, h, Color::from_rgb(220, 220, 220));
+                draw::set_draw_color(Color::Black);
+                draw::draw_rect(x, y, w, h);
+                draw::set_font(Font::HelveticaBold, 12);
+
+                let header = match col {
+                    0 => "Tag ID",
+                    1 => "Name",
+                    2 => "Category",
+                    3 => "Location",
+                    4 => "Quantity",
+                    5 => "Minimum",
+                    6 => "Order",
+                    _ => "",
+                };
+                draw::draw_text2(header, x, y, w, h, Align::Center);
+            },
+            TableContext::Cell => {
+                if row < 0 || row as usize >= reorder_clone.len() {
+                    return;
+                }
+                let entry = &reorder_clone[row as usize];
+
+                // Out of stock rows stand out from those merely running low
+                let bg_color = if entry.is_out_of_stock() {
+                    Color::from_rgb(255, 190, 190)
+                } else if row % 2 == 0 {
+                    Color::from_rgb(245, 245, 245)
+                } else {
+                    Color::White
+                };
+                draw::draw_rect_fill(x, y, w, h, bg_color);
+                draw::set_draw_color(Color::Black);
+                draw::draw_rect(x, y, w, h);
+                draw::set_font(Font::Helvetica, 12);
+
+                let text = match col {
+                    0 => entry.tag_id.to_string(),
+                    1 => entry.name.clone(),
+                    2 => entry.category.clone().unwrap_or_default(),
+                    3 => entry.location.clone().unwrap_or_default(),
+                    4 => return draw::draw_text2(&entry.quantity.to_string(), x, y, w, h, Align::Center),
+                    5 => return draw::draw_text2(&entry.min_quantity.to_string(), x, y, w, h, Align::Center),
+                    6 => return draw::draw_text2(&entry.order_quantity.to_string(), x, y, w, h, Align::Center),
+                    _ => String::new(),
+                };
+                draw::draw_text2(&text, x + 5, y, w - 10, h, Align::Left);
+            },
+            _ => {}
+        }
+    });
+
+    let out_of_stock = reorder.iter().filter(|entry| entry.is_out_of_stock()).count();
+    let summary = format!("{} item(s) to reorder, {} out of stock", reorder.len(), out_of_stock);
+    let mut summary_frame = Frame::new(10, 410, 400, 30, None);
+    summary_frame.set_label(&summary);
+    summary_frame.set_align(Align::Left | Align::Inside);
+
+    let mut csv_btn = Button::new(500, 410, 110, 30, "Export CSV");
+    let mut json_btn = Button::new(620, 410, 110, 30, "Export JSON");
+    let mut close_btn = Button::new(740, 410, 110, 30, "Close");
+
+    let reorder_clone = reorder.clone();
+    csv_btn.set_callback(move |_| {
+        if let Some(path) = dialog::file_chooser("Export reorder list as CSV", "*.csv", ".", false) {
+            if let Err(e) = std::fs::write(&path, reorder_to_csv(&reorder_clone)) {
+                dialog::alert(300, 300, &format!("Error writing file: {}", e));
+            } else {
+                dialog::message(300, 300, &format!("Reorder list exported to {}", path));
+            }
+        }
+    });
+
+    json_btn.set_callback(move |_| {
+        if let Some(path) = dialog::file_chooser("Export reorder list as JSON", "*.json", ".", false) {
+            let written = serde_json::to_string_pretty(&*reorder)
+                .map_err(|e| e.to_string())
+                .and_then(|json| std::fs::write(&path, json).map_err(|e| e.to_string()));
+            match written {
+                Ok(()) => dialog::message(300, 300, &format!("Reorder list exported to {}", path)),
+                Err(e) => dialog::alert(300, 300, &format!("Error writing file: {}", e)),
+            }
+        }
+    });
+
+    let mut wind_clone = wind.clone();
+    close_btn.set_callback(move |_| {
+        wind_clone.hide();
+    });
+
+    wind.end();
+    wind.make_modal(true);
+    wind.show();
+}
@@ -1,4 +1,4 @@
-// src/inventory/ui/components/stats.rs
+// src/ui/inventory/components/stats.rs
 use fltk::{
     prelude::*,
     frame::Frame,
@@ -0,0 +1,213 @@
+// src/ui/inventory/components/stocktake.rs
+use fltk::{
+    browser::CheckBrowser,
+    button::Button,
+    dialog,
+    enums::{Align, CallbackTrigger, Font},
+    frame::Frame,
+    input::Input,
+    prelude::*,
+    window::Window,
+};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::inventory::db::InventoryDB;
+use crate::inventory::stocktake::{Stocktake, StocktakeScope, VarianceReport};
+use crate::keyboard_layout::KeyboardLayout;
+use crate::uid::Uid;
+
+// Count a location or category by scanning, review the variances and approve the adjustments
+pub fn show_stocktake_window(inventory_db: Rc<RefCell<InventoryDB>>, keyboard_layout: Rc<RefCell<KeyboardLayout>>) {
+    let mut wind = Window::new(150, 150, 700, 520, "Stocktake");
+
+    let mut header = Frame::new(10, 10, 680, 30, "Stocktake");
+    header.set_label_font(Font::HelveticaBold);
+    header.set_label_size(16);
+
+    let location_input = Input::new(90, 50, 200, 25, "Location:");
+    let category_input = Input::new(380, 50, 180, 25, "Category:");
+    let mut start_btn = Button::new(570, 50, 120, 25, "Start Count");
+
+    let mut scan_input = Input::new(90, 85, 200, 25, "Scan tag:");
+    scan_input.set_trigger(CallbackTrigger::EnterKey);
+    let mut count_frame = Frame::new(300, 85, 390, 25, "Enter the scope and start the count");
+    count_frame.set_align(Align::Left | Align::Inside);
+
+    let variance_list = CheckBrowser::new(10, 120, 680, 340, "");
+    let mut summary_frame = Frame::new(10, 470, 680, 20, None);
+    summary_frame.set_align(Align::Left | Align::Inside);
+
+    let mut report_btn = Button::new(10, 490, 120, 25, "Report");
+    let mut apply_btn = Button::new(140, 490, 140, 25, "Apply Approved");
+    let mut export_btn = Button::new(450, 490, 110, 25, "Export CSV");
+    let mut close_btn = Button::new(570, 490, 120, 25, "Close");
+
+    wind.end();
+
+    let session: Rc<RefCell<Option<Stocktake>>> = Rc::new(RefCell::new(None));
+    let report: Rc<RefCell<Option<VarianceReport>>> = Rc::new(RefCell::new(None));
+
+    // Start (or restart) the count; the expected items are taken from the database now
+    let db_clone = inventory_db.clone();
+    let session_clone = session.clone();
+    let report_clone = report.clone();
+    let mut count_frame_clone = count_frame.clone();
+    let mut variance_list_clone = variance_list.clone();
+    let mut summary_frame_clone = summary_frame.clone();
+    let mut scan_input_clone = scan_input.clone();
+    start_btn.set_callback(move |_| {
+        let restarting = session_clone.borrow().as_ref().is_some_and(|session| session.scanned() > 0);
+        if restarting
+            && dialog::choice2(300, 300, "Discard the scans of the current count?", "No", "Yes", "") != Some(1)
+        {
+            return;
+        }
+
+        let scope = StocktakeScope::new(Some(&location_input.value()), Some(&category_input.value()));
+        match Stocktake::start(&db_clone.borrow(), scope) {
+            Ok(stocktake) => {
+                count_frame_clone.set_label(&format!(
+                    "Counting {}: {} item(s) expected",
+                    stocktake.scope(),
+                    stocktake.expected().len()
+                ));
+                *session_clone.borrow_mut() = Some(stocktake);
+                *report_clone.borrow_mut() = None;
+                variance_list_clone.clear();
+                summary_frame_clone.set_label("");
+                let _ = scan_input_clone.take_focus();
+            }
+            Err(e) => dialog::alert(300, 300, &format!("Error starting stocktake: {}", e)),
+        }
+    });
+
+    // Each scan only adds to the count
+    let session_clone = session.clone();
+    let mut count_frame_clone = count_frame.clone();
+    scan_input.set_callback(move |input| {
+        let raw = input.value();
+        input.set_value("");
+        if raw.trim().is_empty() {
+            return;
+        }
+
+        let mut session = session_clone.borrow_mut();
+        let Some(stocktake) = session.as_mut() else {
+            dialog::alert(300, 300, "Start the count before scanning");
+            return;
+        };
+
+        match Uid::from_raw(raw.trim(), &keyboard_layout.borrow()) {
+            Ok(tag_id) => {
+                let count = stocktake.record_scan(&tag_id);
+                count_frame_clone.set_label(&format!(
+                    "{} counted {}x, {} tag(s) scanned of {} expected",
+                    tag_id,
+                    count,
+                    stocktake.scanned(),
+                    stocktake.expected().len()
+                ));
+            }
+            Err(e) => dialog::alert(300, 300, &format!("Could not decode '{}': {}", raw.trim(), e)),
+        }
+    });
+
+    // Compare with the database; adjustable lines start approved
+    let db_clone = inventory_db.clone();
+    let session_clone = session.clone();
+    let report_clone = report.clone();
+    let mut variance_list_clone = variance_list.clone();
+    let mut summary_frame_clone = summary_frame.clone();
+    report_btn.set_callback(move |_| {
+        let session = session_clone.borrow();
+        let Some(stocktake) = session.as_ref() else {
+            dialog::alert(300, 300, "No count in progress");
+            return;
+        };
+
+        match stocktake.report(&db_clone.borrow()) {
+            Ok(variance_report) => {
+                variance_list_clone.clear();
+                for variance in &variance_report.variances {
+                    variance_list_clone.add(&variance.describe(), variance.is_adjustable());
+                }
+                summary_frame_clone.set_label(&variance_report.summary());
+                *report_clone.borrow_mut() = Some(variance_report);
+            }
+            Err(e) => dialog::alert(300, 300, &format!("Error comparing counts: {}", e)),
+        }
+    });
+
+    // Write the checked lines back in one transaction
+    let db_clone = inventory_db;
+    let session_clone = session.clone();
+    let report_clone = report.clone();
+    let variance_list_clone = variance_list.clone();
+    let mut count_frame_clone = count_frame.clone();
+    apply_btn.set_callback(move |_| {
+        let report = report_clone.borrow();
+        let Some(variance_report) = report.as_ref() else {
+            dialog::alert(300, 300, "Run the report before applying adjustments");
+            return;
+        };
+
+        // CheckBrowser lines are 1-based and in report order
+        let approved: Vec<_> = variance_report
+            .variances
+            .iter()
+            .enumerate()
+            .filter(|(index, variance)| variance.is_adjustable() && variance_list_clone.checked(*index as i32 + 1))
+            .map(|(_, variance)| variance.clone())
+            .collect();
+        if approved.is_empty() {
+            dialog::alert(300, 300, "No adjustments approved");
+            return;
+        }
+
+        let question = format!("Apply {} adjustment(s) to the inventory?", approved.len());
+        if dialog::choice2(300, 300, &question, "No", "Yes", "") != Some(1) {
+            return;
+        }
+
+        match db_clone.borrow().apply_stocktake(&variance_report.scope, &approved) {
+            Ok(changed) => {
+                // The count is finished once it has been written back
+                *session_clone.borrow_mut() = None;
+                count_frame_clone.set_label("Stocktake applied; start a new count to continue");
+                dialog::message(300, 300, &format!("Adjusted {} item(s)", changed));
+            }
+            Err(e) => dialog::alert(300, 300, &format!("Error applying stocktake: {}", e)),
+        }
+    });
+
+    let report_clone = report;
+    export_btn.set_callback(move |_| {
+        let report = report_clone.borrow();
+        let Some(variance_report) = report.as_ref() else {
+            dialog::alert(300, 300, "Run the report before exporting it");
+            return;
+        };
+
+        if let Some(path) = dialog::file_chooser("Export variance report as CSV", "*.csv", ".", false) {
+            if let Err(e) = std::fs::write(&path, variance_report.to_csv()) {
+                dialog::alert(300, 300, &format!("Error writing file: {}", e));
+            } else {
+                dialog::message(300, 300, &format!("Variance report exported to {}", path));
+            }
+        }
+    });
+
+    let mut wind_clone = wind.clone();
+    close_btn.set_callback(move |_| {
+        if session.borrow().as_ref().is_some_and(|session| session.scanned() > 0)
+            && dialog::choice2(300, 300, "Close and discard the current count?", "No", "Yes", "") != Some(1)
+        {
+            return;
+        }
+        wind_clone.hide();
+    });
+
+    wind.make_modal(true);
+    wind.show();
+}
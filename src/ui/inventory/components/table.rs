@@ -0,0 +1,158 @@
+// src/ui/inventory/components/table.rs
+use fltk::{app, prelude::*, table::Table, draw};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::inventory::model::InventoryItem;
+use crate::inventory::search::{SearchField, SearchQuery};
+use crate::inventory::view::{sort_items, toggle_sort, InventoryView, SortColumn};
+use crate::ui::inventory::selection::Selection;
+use crate::ui::inventory::utils::{draw_cell_bg, draw_highlighted_text};
+
+// Columns in table order
+const COLUMNS: [(&str, SortColumn); 4] = [
+    ("Tag ID", SortColumn::TagId),
+    ("Name", SortColumn::Name),
+    ("Qty", SortColumn::Quantity),
+    ("Category", SortColumn::Category),
+];
+
+// Function to set up the inventory table; the category cell is tinted with the colour of the item's category,
+// the quantity of an item below its minimum stock (its own, or else its category's) is flagged, and what the
+// view's search matched is highlighted. Clicking a header sorts by that column, shift-click adds it to the order.
+// Rows are selected with a click, Ctrl-click and Shift-click; `on_selection` gets the row clicked last
+pub fn setup_inventory_table(
+    table: &mut Table,
+    items: Rc<RefCell<Vec<InventoryItem>>>,
+    category_colors: Rc<RefCell<HashMap<i64, fltk::enums::Color>>>,
+    category_minimums: Rc<RefCell<HashMap<i64, i32>>>,
+    current_view: Rc<RefCell<InventoryView>>,
+    selection: Rc<RefCell<Selection>>,
+    mut on_selection: impl FnMut(usize) + 'static
+) {
+    let items_for_sort = items.clone();
+    let selection_clone = selection.clone();
+    
+    // Configure table
+    table.set_rows(0);
+    table.set_row_header(true);
+    table.set_row_resize(true);
+    table.set_cols(4);
+    table.set_col_header(true);
+    table.set_col_width(0, 100); // ID Column
+    table.set_col_width(1, 150); // Name Column
+    table.set_col_width(2, 50);  // Quantity Column
+    table.set_col_width(3, 80);  // Category Column
+    
+    // The search to highlight, parsed once per redraw
+    let highlight: RefCell<Option<SearchQuery>> = RefCell::new(None);
+    let view_clone = current_view.clone();
+    
+    // Set up header drawing callback
+    table.draw_cell(move |_t, ctx, row, col, x, y, w, h| {
+        match ctx {
+            fltk::table::TableContext::StartPage => {
+                draw::set_font(fltk::enums::Font::Helvetica, 14);
+                *highlight.borrow_mut() = SearchQuery::parse(&view_clone.borrow().search).ok().filter(|query| !query.is_empty());
+            },
+            fltk::table::TableContext::ColHeader => {
+                draw::draw_rect_fill(x, y, w, h, fltk::enums::Color::from_rgb(220, 220, 220));
+                draw::set_draw_color(fltk::enums::Color::Black);
+                draw::draw_rect(x, y, w, h);
+                draw::set_font(fltk::enums::Font::HelveticaBold, 12);
+                draw::set_draw_color(fltk::enums::Color::Black);
+                
+                let Some((header, column)) = COLUMNS.get(col as usize) else {
+                    return;
+                };
+                // Arrow for the direction, and the position in the order when sorting by several columns
+                let view = view_clone.borrow();
+                let header = match view.sort.iter().position(|key| key.column == *column) {
+                    Some(index) => {
+                        let arrow = if view.sort[index].descending { "\u{25BC}" } else { "\u{25B2}" };
+                        let position = if view.sort.len() > 1 { (index + 1).to_string() } else { String::new() };
+                        format!("{} {}{}", header, arrow, position)
+                    }
+                    None => header.to_string(),
+                };
+                
+                draw::draw_text2(&header, x, y, w, h, fltk::enums::Align::Center);
+            },
+            fltk::table::TableContext::Cell => {
+                let items = items.borrow();
+                
+                if row < items.len() as i32 {
+                    let item = &items[row as usize];
+                    
+                    // Alternate row colors
+                    let category_color = item.category_id.and_then(|id| category_colors.borrow().get(&id).copied());
+                    let min_quantity = item
+                        .min_quantity
+                        .or_else(|| item.category_id.and_then(|id| category_minimums.borrow().get(&id).copied()));
+                    let low_stock = min_quantity.is_some_and(|min| item.quantity < min);
+                    match category_color {
+                        _ if selection_clone.borrow().contains(item) && !(col == 2 && low_stock) => {
+                            draw::draw_rect_fill(x, y, w, h, fltk::enums::Color::from_rgb(173, 216, 230))
+                        }
+                        _ if col == 2 && low_stock => draw_cell_bg(x, y, w, h, fltk::enums::Color::from_rgb(255, 190, 190)),
+                        Some(color) if col == 3 => draw_cell_bg(x, y, w, h, color),
+                        _ if row % 2 == 0 => draw::draw_rect_fill(x, y, w, h, fltk::enums::Color::from_rgb(245, 245, 245)),
+                        _ => draw::draw_rect_fill(x, y, w, h, fltk::enums::Color::from_rgb(255, 255, 255)),
+                    }
+                    
+                    draw::set_draw_color(fltk::enums::Color::Black);
+                    draw::draw_rect(x, y, w, h);
+                    
+                    let tag_id = item.tag_id.to_hex();
+                    let (text, field) = match col {
+                        0 => (tag_id.as_str(), SearchField::Tag),
+                        1 => (item.name.as_str(), SearchField::Name),
+                        2 => return draw::draw_text2(&item.quantity.to_string(), x, y, w, h, fltk::enums::Align::Center),
+                        3 => (item.category.as_deref().unwrap_or(""), SearchField::Category),
+                        _ => return,
+                    };
+                    let highlights = highlight
+                        .borrow()
+                        .as_ref()
+                        .map(|query| query.highlights(field, text))
+                        .unwrap_or_default();
+                    
+                    if col == 3 {
+                        return draw_highlighted_text(text, &highlights, x, y, w, h, fltk::enums::Align::Center);
+                    }
+                    draw::set_font(fltk::enums::Font::Helvetica, 12);
+                    let padding = 5;
+                    draw_highlighted_text(text, &highlights, x + padding, y, w - 2 * padding, h, fltk::enums::Align::Left);
+                }
+            },
+            _ => {}
+        }
+    });
+    
+    // Set up row selection and sorting callback; the table calls back on press and release, act once per click
+    table.set_callback(move |t| {
+        if app::event() != fltk::enums::Event::Released {
+            return;
+        }
+        if t.callback_context() == fltk::table::TableContext::ColHeader {
+            if let Some((_, column)) = COLUMNS.get(t.callback_col() as usize) {
+                let add = app::event_state().contains(fltk::enums::Shortcut::Shift);
+                let mut view = current_view.borrow_mut();
+                toggle_sort(&mut view.sort, *column, add);
+                sort_items(&mut items_for_sort.borrow_mut(), &view.sort);
+                t.redraw();
+            }
+        } else if t.callback_context() == fltk::table::TableContext::Cell {
+            let row = t.callback_row();
+            if row < t.rows() && row >= 0 {
+                let state = app::event_state();
+                let ctrl = state.contains(fltk::enums::Shortcut::Ctrl) || state.contains(fltk::enums::Shortcut::Command);
+                let shift = state.contains(fltk::enums::Shortcut::Shift);
+                selection.borrow_mut().click(&items_for_sort.borrow(), row as usize, ctrl, shift);
+                t.redraw();
+                on_selection(row as usize);
+            }
+        }
+    });
+}
\ No newline at end of file
@@ -0,0 +1,271 @@
+// src/ui/inventory/components/tags.rs
+use fltk::{
+    app,
+    browser::HoldBrowser,
+    button::Button,
+    dialog,
+    enums::{Align, Font},
+    frame::Frame,
+    prelude::*,
+    window::Window,
+};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::inventory::db::InventoryDB;
+use crate::inventory::model::{InventoryItem, ItemTag};
+use crate::uid::Uid;
+use crate::ui::inventory::utils::format_timestamp;
+
+// Show the tags and identifiers of one item, with re-tagging, attaching and retiring. `on_change`
+// gets the item after every change, e.g. to follow a new tag shown for it
+pub fn show_item_tags(
+    inventory_db: Rc<RefCell<InventoryDB>>,
+    tag_id: &Uid,
+    on_change: impl Fn(&InventoryItem) + 'static
+) {
+    let item = match inventory_db.borrow().get_item(tag_id) {
+        Ok(Some(item)) => item,
+        Ok(None) => {
+            dialog::alert(300, 300, &format!("Tag ID {} not found in inventory", tag_id));
+            return;
+        }
+        Err(e) => {
+            dialog::alert(300, 300, &format!("Error loading item: {}", e));
+            return;
+        }
+    };
+    let Some(item_id) = item.id else {
+        return;
+    };
+
+    let mut wind = Window::new(150, 150, 640, 360, None);
+    wind.set_label(&format!("Tags of '{}'", item.name));
+
+    let mut header = Frame::new(10, 10, 620, 30, None);
+    header.set_label(&format!("Tags of '{}'", item.name));
+    header.set_label_font(Font::HelveticaBold);
+    header.set_label_size(16);
+
+    let mut tag_list = HoldBrowser::new(10, 50, 380, 300, "");
+    tag_list.set_column_widths(&[150, 230]);
+    tag_list.set_column_char('\t');
+
+    let mut details = Frame::new(400, 50, 230, 60, None);
+    details.set_align(Align::TopLeft | Align::Inside | Align::Wrap);
+
+    let mut retag_btn = Button::new(400, 120, 230, 30, "Re-tag Item...");
+    let mut attach_btn = Button::new(400, 155, 230, 30, "Attach Tag...");
+    let mut label_btn = Button::new(400, 190, 230, 30, "Attach Identifier...");
+    let mut retire_btn = Button::new(400, 235, 230, 30, "Retire Tag");
+    let mut close_btn = Button::new(400, 320, 230, 30, "Close");
+
+    wind.end();
+
+    // Links in list order, reloaded after every change
+    let tags: Rc<RefCell<Vec<ItemTag>>> = Rc::new(RefCell::new(Vec::new()));
+    let reload = {
+        let db_clone = inventory_db.clone();
+        let tags_clone = tags.clone();
+        let tag_list_clone = tag_list.clone();
+        let details_clone = details.clone();
+        Rc::new(move || {
+            let (mut tag_list, mut details) = (tag_list_clone.clone(), details_clone.clone());
+            let db = db_clone.borrow();
+            let item = match db.get_item_by_id(item_id) {
+                Ok(Some(item)) => item,
+                Ok(None) => return,
+                Err(e) => {
+                    dialog::alert(300, 300, &format!("Error loading item: {}", e));
+                    return;
+                }
+            };
+            let loaded = match db.get_item_tags(item_id) {
+                Ok(loaded) => loaded,
+                Err(e) => {
+                    dialog::alert(300, 300, &format!("Error loading tags: {}", e));
+                    return;
+                }
+            };
+
+            tag_list.clear();
+            for tag in &loaded {
+                let state = match &tag.retired_at {
+                    Some(retired_at) => format!("retired {}", format_timestamp(retired_at)),
+                    None if tag.uid.as_ref() == Some(&item.tag_id) => "shown".to_string(),
+                    None => format!("since {}", format_timestamp(&tag.attached_at)),
+                };
+                // '@' starts a format code in a browser line
+                tag_list.add(&format!("{}\t{}", tag.to_string().replace('@', "@@"), state));
+            }
+            let active = loaded.iter().filter(|tag| tag.is_active()).count();
+            details.set_label(&format!(
+                "Shown as {}\n{} active, {} retired",
+                item.tag_id,
+                active,
+                loaded.len() - active
+            ));
+            *tags_clone.borrow_mut() = loaded;
+
+            drop(db);
+            on_change(&item);
+        })
+    };
+    reload();
+
+    let db_clone = inventory_db.clone();
+    let reload_clone = reload.clone();
+    retag_btn.set_callback(move |_| {
+        let item = match db_clone.borrow().get_item_by_id(item_id) {
+            Ok(Some(item)) => item,
+            _ => return,
+        };
+        let prompt = format!("New tag for '{}'; {} will be retired:", item.name, item.tag_id);
+        let Some(new_tag) = prompt_uid(&prompt) else {
+            return;
+        };
+
+        let result = db_clone.borrow().retag_item(&item.tag_id, &new_tag);
+        match result {
+            Ok(_) => reload_clone(),
+            Err(e) => dialog::alert(300, 300, &format!("Error re-tagging item: {}", e)),
+        }
+    });
+
+    let db_clone = inventory_db.clone();
+    let reload_clone = reload.clone();
+    attach_btn.set_callback(move |_| {
+        let Some(uid) = prompt_uid("Tag to attach as well:") else {
+            return;
+        };
+
+        // Sharing a tag is allowed, but scanning it will then ask which item was meant
+        let others: Vec<String> = match db_clone.borrow().get_items_for_tag(&uid) {
+            Ok(items) => items.iter().filter(|other| other.id != Some(item_id)).map(|other| format!("'{}'", other.name)).collect(),
+            Err(_) => Vec::new(),
+        };
+        if !others.is_empty() {
+            let question = format!("{} is already attached to {}. Attach it to this item as well?", uid, others.join(", "));
+            if dialog::choice2(300, 300, &question, "No", "Yes", "") != Some(1) {
+                return;
+            }
+        }
+
+        let result = db_clone.borrow().attach_tag(item_id, Some(&uid), None);
+        match result {
+            Ok(_) => reload_clone(),
+            Err(e) => dialog::alert(300, 300, &format!("Error attaching tag: {}", e)),
+        }
+    });
+
+    let db_clone = inventory_db.clone();
+    let reload_clone = reload.clone();
+    label_btn.set_callback(move |_| {
+        let identifier = match dialog::input(300, 300, "Text identifier to attach (e.g. an NDEF text record):", "") {
+            Some(identifier) if !identifier.trim().is_empty() => identifier,
+            _ => return,
+        };
+
+        let result = db_clone.borrow().attach_tag(item_id, None, Some(&identifier));
+        match result {
+            Ok(_) => reload_clone(),
+            Err(e) => dialog::alert(300, 300, &format!("Error attaching identifier: {}", e)),
+        }
+    });
+
+    let db_clone = inventory_db;
+    let tags_clone = tags;
+    let tag_list_clone = tag_list.clone();
+    retire_btn.set_callback(move |_| {
+        let line = tag_list_clone.value();
+        let selected = (line > 0).then(|| tags_clone.borrow().get(line as usize - 1).cloned()).flatten();
+        let Some(tag) = selected.filter(ItemTag::is_active) else {
+            dialog::alert(300, 300, "No active tag selected to retire");
+            return;
+        };
+        let question = format!("Retire {}? It will no longer find this item.", tag);
+        if dialog::choice2(300, 300, &question, "No", "Yes", "") != Some(1) {
+            return;
+        }
+
+        let result = db_clone.borrow().retire_tag(tag.id);
+        match result {
+            Ok(_) => reload(),
+            Err(e) => dialog::alert(300, 300, &format!("Error retiring tag: {}", e)),
+        }
+    });
+
+    let mut wind_clone = wind.clone();
+    close_btn.set_callback(move |_| {
+        wind_clone.hide();
+    });
+
+    wind.make_modal(true);
+    wind.show();
+}
+
+// Ask which of the items a shared tag is attached to a scan meant; `None` if the user cancels
+pub fn choose_item(tag_id: &Uid, items: &[InventoryItem]) -> Option<InventoryItem> {
+    let mut wind = Window::new(200, 200, 420, 300, None);
+    wind.set_label("Choose Item");
+
+    let mut header = Frame::new(10, 10, 400, 30, None);
+    header.set_label(&format!("Tag {} is attached to {} items:", tag_id, items.len()));
+    header.set_align(Align::Left | Align::Inside);
+
+    let mut item_list = HoldBrowser::new(10, 45, 400, 205, "");
+    item_list.set_column_widths(&[250, 150]);
+    item_list.set_column_char('\t');
+    for item in items {
+        // '@' starts a format code in a browser line
+        item_list.add(&format!("{}\t{}", item.name.replace('@', "@@"), item.tag_id));
+    }
+    item_list.select(1);
+
+    let mut choose_btn = Button::new(230, 260, 85, 30, "Choose");
+    let mut cancel_btn = Button::new(325, 260, 85, 30, "Cancel");
+
+    wind.end();
+
+    let chosen: Rc<RefCell<Option<usize>>> = Rc::new(RefCell::new(None));
+    let chosen_clone = chosen.clone();
+    let item_list_clone = item_list.clone();
+    let mut wind_clone = wind.clone();
+    choose_btn.set_callback(move |_| {
+        let line = item_list_clone.value();
+        if line > 0 {
+            *chosen_clone.borrow_mut() = Some(line as usize - 1);
+        }
+        wind_clone.hide();
+    });
+
+    let mut wind_clone = wind.clone();
+    cancel_btn.set_callback(move |_| {
+        wind_clone.hide();
+    });
+
+    wind.make_modal(true);
+    wind.show();
+    while wind.shown() {
+        app::wait();
+    }
+
+    let line = *chosen.borrow();
+    line.and_then(|line| items.get(line).cloned())
+}
+
+// Ask for a UID in hex, as shown in the inventory list
+fn prompt_uid(prompt: &str) -> Option<Uid> {
+    let raw = match dialog::input(300, 300, prompt, "") {
+        Some(raw) if !raw.trim().is_empty() => raw,
+        _ => return None,
+    };
+
+    match raw.trim().parse::<Uid>() {
+        Ok(uid) => Some(uid),
+        Err(e) => {
+            dialog::alert(300, 300, &format!("'{}' is not a tag ID: {}", raw.trim(), e));
+            None
+        }
+    }
+}
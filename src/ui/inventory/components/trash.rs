@@ -0,0 +1,212 @@
+// src/ui/inventory/components/trash.rs
+use fltk::{
+    app,
+    button::Button,
+    dialog,
+    draw,
+    enums::{Align, Color, Event, Font, Shortcut},
+    frame::Frame,
+    prelude::*,
+    table::{Table, TableContext},
+    window::Window,
+};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::inventory::db::InventoryDB;
+use crate::inventory::model::InventoryItem;
+use crate::inventory::undo::TrashedItem;
+use crate::ui::inventory::selection::Selection;
+
+// Days of trash a purge keeps unless told otherwise
+const DEFAULT_PURGE_DAYS: u32 = 30;
+
+// Show the deleted items, most recently deleted first, to restore them or purge old ones for good
+pub fn show_trash_window(inventory_db: Rc<RefCell<InventoryDB>>) {
+    let mut wind = Window::new(150, 150, 860, 460, None);
+    wind.set_label("Trash");
+
+    let mut header = Frame::new(10, 10, 840, 30, None);
+    header.set_label("Deleted Items");
+    header.set_label_font(Font::HelveticaBold);
+    header.set_label_size(16);
+
+    let mut table = Table::new(10, 50, 840, 340, "");
+    table.set_row_header(false);
+    table.set_cols(7);
+    table.set_col_header(true);
+    table.set_col_width(0, 120); // Tag ID
+    table.set_col_width(1, 190); // Name
+    table.set_col_width(2, 70);  // Quantity
+    table.set_col_width(3, 130); // Category
+    table.set_col_width(4, 130); // Location
+    table.set_col_width(5, 110); // Deleted
+    table.set_col_width(6, 90);  // Deleted by
+    table.end();
+
+    let mut summary_frame = Frame::new(10, 410, 380, 30, None);
+    summary_frame.set_align(Align::Left | Align::Inside);
+
+    let mut restore_btn = Button::new(400, 410, 140, 30, "Restore Selected");
+    let mut purge_btn = Button::new(550, 410, 170, 30, "Purge Older Than...");
+    let mut close_btn = Button::new(740, 410, 110, 30, "Close");
+
+    wind.end();
+
+    let trash: Rc<RefCell<Vec<TrashedItem>>> = Rc::new(RefCell::new(Vec::new()));
+    let selection = Rc::new(RefCell::new(Selection::default()));
+    reload_trash(&inventory_db.borrow(), &trash, &mut table, &mut summary_frame);
+
+    let trash_clone = trash.clone();
+    let selection_clone = selection.clone();
+    table.draw_cell(move |_t, ctx, row, col, x, y, w, h| {
+        match ctx {
+            TableContext::StartPage => draw::set_font(Font::Helvetica, 12),
+            TableContext::ColHeader => {
+                draw::draw_rect_fill(x, y, w, h, Color::from_rgb(220, 220, 220));
+                draw::set_draw_color(Color::Black);
+                draw::draw_rect(x, y, w, h);
+                draw::set_font(Font::HelveticaBold, 12);
+
+                let header = match col {
+                    0 => "Tag ID",
+                    1 => "Name",
+                    2 => "Quantity",
+                    3 => "Category",
+                    4 => "Location",
+                    5 => "Deleted",
+                    6 => "Deleted By",
+                    _ => "",
+                };
+                draw::draw_text2(header, x, y, w, h, Align::Center);
+            },
+            TableContext::Cell => {
+                let trash = trash_clone.borrow();
+                let Some(trashed) = trash.get(row as usize) else {
+                    return;
+                };
+                let item = trashed.item();
+
+                let bg_color = if selection_clone.borrow().contains(item) {
+                    Color::from_rgb(173, 216, 230)
+                } else if row % 2 == 0 {
+                    Color::from_rgb(245, 245, 245)
+                } else {
+                    Color::White
+                };
+                draw::draw_rect_fill(x, y, w, h, bg_color);
+                draw::set_draw_color(Color::Black);
+                draw::draw_rect(x, y, w, h);
+                draw::set_font(Font::Helvetica, 12);
+
+                let text = match col {
+                    0 => item.tag_id.to_string(),
+                    1 => item.name.clone(),
+                    2 => return draw::draw_text2(&item.quantity.to_string(), x, y, w, h, Align::Center),
+                    3 => item.category.clone().unwrap_or_default(),
+                    4 => item.location.clone().unwrap_or_default(),
+                    // Just the date of the ISO timestamp
+                    5 => trashed.deleted_at.chars().take(10).collect(),
+                    6 => trashed.deleted_by.clone().unwrap_or_default(),
+                    _ => String::new(),
+                };
+                draw::draw_text2(&text, x + 5, y, w - 10, h, Align::Left);
+            },
+            _ => {}
+        }
+    });
+
+    // Click, Ctrl-click and Shift-click select rows as in the inventory list
+    let trash_clone = trash.clone();
+    let selection_clone = selection.clone();
+    table.set_callback(move |t| {
+        if app::event() != Event::Released || t.callback_context() != TableContext::Cell {
+            return;
+        }
+        let row = t.callback_row();
+        if row < 0 || row >= t.rows() {
+            return;
+        }
+        let state = app::event_state();
+        let ctrl = state.contains(Shortcut::Ctrl) || state.contains(Shortcut::Command);
+        let shift = state.contains(Shortcut::Shift);
+        selection_clone.borrow_mut().click(&trashed_items(&trash_clone.borrow()), row as usize, ctrl, shift);
+        t.redraw();
+    });
+
+    let db_clone = inventory_db.clone();
+    let trash_clone = trash.clone();
+    let selection_clone = selection.clone();
+    let mut table_clone = table.clone();
+    let mut summary_clone = summary_frame.clone();
+    restore_btn.set_callback(move |_| {
+        let selected = selection_clone.borrow().items(&trashed_items(&trash_clone.borrow()));
+        if selected.is_empty() {
+            dialog::alert(300, 300, "Select the items to restore first");
+            return;
+        }
+        let ids: Vec<i64> = selected.iter().filter_map(|item| item.id).collect();
+
+        match db_clone.borrow().restore_items(&ids) {
+            Ok(restored) => dialog::message(300, 300, &format!("Restored {} item(s); Undo puts them back in the trash", restored)),
+            Err(e) => dialog::alert(300, 300, &format!("Error restoring items: {}", e)),
+        }
+        selection_clone.borrow_mut().clear();
+        reload_trash(&db_clone.borrow(), &trash_clone, &mut table_clone, &mut summary_clone);
+    });
+
+    let db_clone = inventory_db.clone();
+    let trash_clone = trash.clone();
+    let mut table_clone = table.clone();
+    let mut summary_clone = summary_frame.clone();
+    purge_btn.set_callback(move |_| {
+        let prompt = "Remove items deleted more than this many days ago for good (0 empties the trash):";
+        let Some(input) = dialog::input(300, 300, prompt, &DEFAULT_PURGE_DAYS.to_string()) else {
+            return;
+        };
+        let Ok(days) = input.trim().parse::<u32>() else {
+            dialog::alert(300, 300, &format!("'{}' is not a number of days", input.trim()));
+            return;
+        };
+
+        let question = "Purged items can't be restored or undone. Purge them?";
+        if dialog::choice2(300, 300, question, "Cancel", "Purge", "") != Some(1) {
+            return;
+        }
+        match db_clone.borrow().purge_trash(days) {
+            Ok(purged) => dialog::message(300, 300, &format!("Purged {} item(s)", purged)),
+            Err(e) => dialog::alert(300, 300, &format!("Error purging trash: {}", e)),
+        }
+        reload_trash(&db_clone.borrow(), &trash_clone, &mut table_clone, &mut summary_clone);
+    });
+
+    let mut wind_clone = wind.clone();
+    close_btn.set_callback(move |_| {
+        wind_clone.hide();
+    });
+
+    wind.make_modal(true);
+    wind.show();
+}
+
+// The trashed items as the selection knows them
+fn trashed_items(trash: &[TrashedItem]) -> Vec<InventoryItem> {
+    trash.iter().map(|trashed| trashed.item().clone()).collect()
+}
+
+fn reload_trash(
+    inventory_db: &InventoryDB,
+    trash: &Rc<RefCell<Vec<TrashedItem>>>,
+    table: &mut Table,
+    summary_frame: &mut Frame
+) {
+    match inventory_db.get_trash() {
+        Ok(loaded) => *trash.borrow_mut() = loaded,
+        Err(e) => dialog::alert(300, 300, &format!("Error loading trash: {}", e)),
+    }
+
+    let count = trash.borrow().len();
+    table.set_rows(count as i32);
+    table.redraw();
+    summary_frame.set_label(&format!("{} item(s) in the trash", count));
+}
@@ -0,0 +1,159 @@
+// src/ui/inventory/handlers/bulk_handlers.rs
+use fltk::{
+    button::Button,
+    dialog,
+    enums::Event,
+    frame::Frame,
+    menu::MenuButton,
+    prelude::*,
+    text::TextBuffer,
+    table::Table,
+};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::inventory::db::InventoryDB;
+use crate::inventory::model::InventoryItem;
+use crate::inventory::view::InventoryView;
+use crate::ui::inventory::components::{run_bulk_action, BulkAction};
+use crate::ui::inventory::handlers::view_handlers::load_view;
+use crate::ui::inventory::selection::Selection;
+
+// "3 selected" under the list
+pub fn update_selection_label(selection_label: &mut Frame, selection: &Selection, items: &[InventoryItem]) {
+    match selection.count(items) {
+        0 => selection_label.set_label("No selection"),
+        count => selection_label.set_label(&format!("{} selected", count)),
+    }
+}
+
+// The tooltips of Undo and Redo say what they would undo or redo
+pub fn update_undo_buttons(undo_btn: &mut Button, redo_btn: &mut Button, inventory_db: &InventoryDB) {
+    for (button, step, verb) in [
+        (undo_btn, inventory_db.next_undo(), "Undo"),
+        (redo_btn, inventory_db.next_redo(), "Redo"),
+    ] {
+        match step {
+            Ok(Some(step)) => button.set_tooltip(&format!("{} {}", verb, step.description)),
+            _ => button.set_tooltip(&format!("Nothing to {}", verb.to_lowercase())),
+        }
+    }
+}
+
+// The actions for the selected rows, then Select All and Clear Selection
+pub fn setup_selection_menu(
+    selection_menu: &mut MenuButton,
+    selection_label: &Frame,
+    undo_btn: &Button,
+    redo_btn: &Button,
+    log_buffer: &TextBuffer,
+    inventory_db: Rc<RefCell<InventoryDB>>,
+    items: Rc<RefCell<Vec<InventoryItem>>>,
+    item_table: Rc<RefCell<Table>>,
+    current_view: Rc<RefCell<InventoryView>>,
+    selection: Rc<RefCell<Selection>>
+) {
+    for action in BulkAction::ALL {
+        selection_menu.add_choice(action.label());
+    }
+    selection_menu.add_choice("Select All");
+    selection_menu.add_choice("Clear Selection");
+
+    let selection_label_clone = selection_label.clone();
+    let undo_btn_clone = undo_btn.clone();
+    let redo_btn_clone = redo_btn.clone();
+    let log_buffer_clone = log_buffer.clone();
+
+    selection_menu.set_callback(move |menu| {
+        let index = menu.value();
+        if index < 0 {
+            return;
+        }
+        let index = index as usize;
+        let mut selection_label = selection_label_clone.clone();
+
+        let Some(action) = BulkAction::ALL.get(index).copied() else {
+            // Select All or Clear Selection
+            if index == BulkAction::ALL.len() {
+                selection.borrow_mut().select_all(&items.borrow());
+            } else {
+                selection.borrow_mut().clear();
+            }
+            update_selection_label(&mut selection_label, &selection.borrow(), &items.borrow());
+            item_table.borrow_mut().redraw();
+            return;
+        };
+
+        // The list is reloaded after a change; deleted rows drop out of the selection by themselves
+        let db_inner = inventory_db.clone();
+        let items_inner = items.clone();
+        let table_inner = item_table.clone();
+        let view_inner = current_view.clone();
+        let selection_inner = selection.clone();
+        let undo_btn = undo_btn_clone.clone();
+        let redo_btn = redo_btn_clone.clone();
+        let selected = selection.borrow().items(&items.borrow());
+
+        let mut log_buffer = log_buffer_clone.clone();
+        run_bulk_action(action, inventory_db.clone(), selected, move |message, changed| {
+            log_buffer.append(&format!("{}\n", message));
+            if !changed {
+                return;
+            }
+            if let Err(e) = load_view(&db_inner.borrow(), &view_inner.borrow(), &items_inner, &table_inner) {
+                dialog::alert(300, 300, &format!("Error loading inventory: {}", e));
+            }
+            update_selection_label(&mut selection_label.clone(), &selection_inner.borrow(), &items_inner.borrow());
+            update_undo_buttons(&mut undo_btn.clone(), &mut redo_btn.clone(), &db_inner.borrow());
+        });
+    });
+}
+
+// Undo the last change, or redo the one undone last, and reload the list. Changes are made from
+// scans and other windows too, so the buttons stay active and check what's next when pointed at
+pub fn setup_undo_buttons(
+    undo_btn: &mut Button,
+    redo_btn: &mut Button,
+    log_buffer: &TextBuffer,
+    inventory_db: Rc<RefCell<InventoryDB>>,
+    items: Rc<RefCell<Vec<InventoryItem>>>,
+    item_table: Rc<RefCell<Table>>,
+    current_view: Rc<RefCell<InventoryView>>
+) {
+    update_undo_buttons(undo_btn, redo_btn, &inventory_db.borrow());
+
+    for redo in [false, true] {
+        let mut button = if redo { redo_btn.clone() } else { undo_btn.clone() };
+        let mut undo_btn_clone = undo_btn.clone();
+        let mut redo_btn_clone = redo_btn.clone();
+        let mut log_buffer_clone = log_buffer.clone();
+        let db_clone = inventory_db.clone();
+        let items_clone = items.clone();
+        let table_clone = item_table.clone();
+        let view_clone = current_view.clone();
+
+        let mut undo_btn_hover = undo_btn.clone();
+        let mut redo_btn_hover = redo_btn.clone();
+        let db_hover = inventory_db.clone();
+        button.handle(move |_, event| {
+            if event == Event::Enter {
+                update_undo_buttons(&mut undo_btn_hover, &mut redo_btn_hover, &db_hover.borrow());
+            }
+            false
+        });
+
+        button.set_callback(move |_| {
+            let result = if redo { db_clone.borrow().redo() } else { db_clone.borrow().undo() };
+            match result {
+                Ok(Some(step)) => {
+                    let verb = if redo { "Redid" } else { "Undid" };
+                    log_buffer_clone.append(&format!("{}: {}\n", verb, step.description));
+                    let _ = load_view(&db_clone.borrow(), &view_clone.borrow(), &items_clone, &table_clone);
+                }
+                Ok(None) => log_buffer_clone.append(&format!("Nothing to {}\n", if redo { "redo" } else { "undo" })),
+                Err(e) => dialog::alert(300, 300, &format!("Error {}: {}", if redo { "redoing" } else { "undoing" }, e)),
+            }
+            update_undo_buttons(&mut undo_btn_clone, &mut redo_btn_clone, &db_clone.borrow());
+        });
+    }
+}
@@ -0,0 +1,98 @@
+// src/ui/inventory/handlers/export_handlers.rs
+use fltk::{
+    button::Button,
+    dialog,
+    prelude::*,
+    text::TextBuffer,
+};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::inventory::db::{InventoryDB, items_to_csv};
+use crate::inventory::model::InventoryItem;
+
+// Export the rows the list shows, in its order: the current view's search, filters and sort
+pub fn setup_export_button(
+    export_btn: &mut Button,
+    log_buffer: &TextBuffer,
+    items: Rc<RefCell<Vec<InventoryItem>>>
+) {
+    let items_clone = items;
+    let mut log_buffer_clone = log_buffer.clone();
+    
+    export_btn.set_callback(move |_| {
+        if let Some(message) = export_items(&items_clone.borrow()) {
+            log_buffer_clone.append(&format!("{}\n", message));
+        }
+    });
+}
+
+// Ask for a format and a file and write `items` there, e.g. the list or the rows selected in it;
+// returns a line for the event log once written
+pub fn export_items(items: &[InventoryItem]) -> Option<String> {
+    // Fixed the dialog::choice call to use dialog::choice2
+    match dialog::choice2(300, 300, "Select export format:", "JSON", "CSV", "Cancel") {
+        Some(1) => { // JSON
+            if let Some(path) = dialog::file_chooser("Save JSON Export", "*.json", "", false) {
+                match serde_json::to_string_pretty(items) {
+                    Ok(json) => {
+                        if let Err(e) = std::fs::write(&path, json) {
+                            dialog::alert(300, 300, &format!("Error writing file: {}", e));
+                        } else {
+                            dialog::message(300, 300, &format!("Data exported to {}", path));
+                            return Some(format!("Exported {} items as JSON to {}", items.len(), path));
+                        }
+                    },
+                    Err(e) => dialog::alert(300, 300, &format!("Error exporting data: {}", e))
+                }
+            }
+        },
+        Some(2) => { // CSV
+            if let Some(path) = dialog::file_chooser("Save CSV Export", "*.csv", "", false) {
+                let csv = items_to_csv(items);
+                if let Err(e) = std::fs::write(&path, csv) {
+                    dialog::alert(300, 300, &format!("Error writing file: {}", e));
+                } else {
+                    dialog::message(300, 300, &format!("Data exported to {}", path));
+                    return Some(format!("Exported {} items as CSV to {}", items.len(), path));
+                }
+            }
+        },
+        _ => {} // Cancel or no choice
+    }
+    None
+}
+
+pub fn setup_import_button(
+    import_btn: &mut Button,
+    log_buffer: &TextBuffer,
+    inventory_db: Rc<RefCell<InventoryDB>>,
+    refresh_callback: impl Fn() + 'static
+) {
+    let db_clone = inventory_db;
+    let mut log_buffer_clone = log_buffer.clone();
+    
+    import_btn.set_callback(move |_| {
+        match dialog::choice2(300, 300, "Select import format:", "JSON", "CSV", "Cancel") {
+            Some(1) => { // JSON
+                if let Some(path) = dialog::file_chooser("Open JSON Import", "*.json", "", true) {
+                    match std::fs::read_to_string(&path) {
+                        Ok(json) => {
+                            match db_clone.borrow().import_json(&json) {
+                                Ok(count) => {
+                                    log_buffer_clone.append(&format!("Imported {} items from {}\n", count, path));
+                                    dialog::message(300, 300, &format!("Successfully imported {} items", count));
+                                    refresh_callback();
+                                },
+                                Err(e) => dialog::alert(300, 300, &format!("Error importing data: {}", e))
+                            }
+                        },
+                        Err(e) => dialog::alert(300, 300, &format!("Error reading file: {}", e))
+                    }
+                }
+            },
+            // CSV import would be implemented here
+            _ => {} // Cancel or no choice
+        }
+    });
+}
\ No newline at end of file
@@ -0,0 +1,310 @@
+// src/ui/inventory/handlers/item_handlers.rs
+use fltk::{
+    button::Button,
+    dialog,
+    prelude::*,
+    text::TextBuffer,
+    table::Table,
+};
+use fltk::enums::Color;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::collections::{HashMap, HashSet};
+
+use crate::inventory::model::InventoryItem;
+use crate::inventory::db::InventoryDB;
+use crate::uid::Uid;
+use crate::ui::inventory::components::form::ItemForm;
+use crate::ui::inventory::components::history::show_movement_history;
+use crate::ui::inventory::components::tags::show_item_tags;
+use crate::inventory::view::InventoryView;
+use crate::ui::inventory::handlers::view_handlers::load_view;
+use crate::ui::inventory::utils::ChoiceExt;
+
+pub fn setup_save_button(
+    save_btn: &mut Button,
+    item_form: &ItemForm,
+    log_buffer: &TextBuffer,
+    inventory_db: Rc<RefCell<InventoryDB>>,
+    items: Rc<RefCell<Vec<InventoryItem>>>,
+    current_tag_id: Rc<RefCell<Option<Uid>>>,
+    item_table: Rc<RefCell<Table>>,
+    current_view: Rc<RefCell<InventoryView>>
+) {
+    let db_clone = inventory_db;
+    let items_clone = items;
+    let current_tag_clone = current_tag_id;
+    let table_clone = item_table;
+    let mut log_buffer_clone = log_buffer.clone();
+    let item_form_clone = item_form.clone(); // Clone here to use in callback
+    
+    save_btn.set_callback(move |_| {
+        if let Some(tag_id) = current_tag_clone.borrow().clone() {
+            match item_form_clone.get_form_data(&tag_id) {
+                Ok(mut item) => {
+                    // Get created_at date from existing item if possible
+                    if let Ok(Some(existing_item)) = db_clone.borrow().get_item(&tag_id) {
+                        // Keep the original creation date and the card type learned from the reader
+                        item.created_at = existing_item.created_at.clone();
+                        item.card_family = existing_item.card_family;
+                    }
+                    
+                    // Save to database
+                    if let Err(e) = db_clone.borrow().save_item(&item) {
+                        dialog::alert(300, 300, &format!("Error saving item: {}", e));
+                        return;
+                    }
+                    
+                    // Update local items list and table
+                    let _ = load_view(&db_clone.borrow(), &current_view.borrow(), &items_clone, &table_clone);
+                    
+                    log_buffer_clone.append(&format!("Saved item: {}\n", item.name));
+                    dialog::message(300, 300, "Item saved successfully");
+                },
+                Err(e) => {
+                    dialog::alert(300, 300, &format!("Form validation error: {}", e));
+                }
+            }
+        } else {
+            dialog::alert(300, 300, "No item selected to save");
+        }
+    });
+}
+
+pub fn setup_delete_button(
+    delete_btn: &mut Button,
+    item_form: &mut ItemForm,
+    log_buffer: &TextBuffer,
+    inventory_db: Rc<RefCell<InventoryDB>>,
+    items: Rc<RefCell<Vec<InventoryItem>>>,
+    current_tag_id: Rc<RefCell<Option<Uid>>>,
+    item_table: Rc<RefCell<Table>>,
+    current_view: Rc<RefCell<InventoryView>>
+) {
+    let db_clone = inventory_db;
+    let items_clone = items;
+    let current_tag_clone = current_tag_id;
+    let table_clone = item_table;
+    let mut log_buffer_clone = log_buffer.clone();
+    let mut item_form_clone = item_form.clone();
+    
+    delete_btn.set_callback(move |_| {
+        if let Some(tag_id) = current_tag_clone.borrow().clone() {
+            // Ask for confirmation
+            if dialog::choice2(300, 300, "Move this item to the trash? It can be restored from File > Trash.", "No", "Yes", "") == Some(1) {
+                // Delete from database
+                if let Err(e) = db_clone.borrow().delete_item(&tag_id) {
+                    dialog::alert(300, 300, &format!("Error deleting item: {}", e));
+                    return;
+                }
+                
+                // Clear form
+                item_form_clone.clear();
+                
+                // Clear selected tag
+                *current_tag_clone.borrow_mut() = None;
+                
+                // Update local items list and table
+                let _ = load_view(&db_clone.borrow(), &current_view.borrow(), &items_clone, &table_clone);
+                
+                log_buffer_clone.append(&format!("Moved item with tag {} to the trash\n", tag_id));
+                dialog::message(300, 300, "Item moved to the trash");
+            }
+        } else {
+            dialog::alert(300, 300, "No item selected to delete");
+        }
+    });
+}
+
+pub fn setup_clear_button(
+    clear_btn: &mut Button,
+    item_form: &mut ItemForm,
+    log_buffer: &TextBuffer,
+    current_tag_id: Rc<RefCell<Option<Uid>>>
+) {
+    let current_tag_clone = current_tag_id;
+    let mut log_buffer_clone = log_buffer.clone();
+    let mut item_form_clone = item_form.clone();
+    
+    clear_btn.set_callback(move |_| {
+        item_form_clone.clear();
+        *current_tag_clone.borrow_mut() = None;
+        log_buffer_clone.append("Form cleared\n");
+    });
+}
+
+pub fn setup_add_button(
+    add_btn: &mut Button,
+    item_form: &mut ItemForm,
+    log_buffer: &TextBuffer,
+    current_tag_id: Rc<RefCell<Option<Uid>>>
+) {
+    let current_tag_clone = current_tag_id;
+    let mut log_buffer_clone = log_buffer.clone();
+    let mut item_form_clone = item_form.clone();
+    
+    add_btn.set_callback(move |_| {
+        // Generate a new tag ID or prompt user for one
+        if let Some(tag_id) = dialog::input(300, 300, "Enter Tag ID for new item:", "") {
+            if !tag_id.is_empty() {
+                let tag_id = match tag_id.parse::<Uid>() {
+                    Ok(uid) => uid,
+                    Err(e) => {
+                        dialog::alert(300, 300, &format!("Invalid Tag ID: {}", e));
+                        return;
+                    }
+                };
+                
+                // Clear form and set new tag ID
+                item_form_clone.clear();
+                
+                item_form_clone.tag_id_display.set_label(&format!("Tag ID: {}", tag_id));
+                log_buffer_clone.append(&format!("Ready to add new item with tag: {}\n", tag_id));
+                *current_tag_clone.borrow_mut() = Some(tag_id);
+            }
+        }
+    });
+}
+
+pub fn setup_refresh_button(
+    refresh_btn: &mut Button,
+    stats_text: &mut fltk::frame::Frame,
+    category_choice: &mut fltk::menu::Choice,
+    log_buffer: &TextBuffer,
+    inventory_db: Rc<RefCell<InventoryDB>>,
+    items: Rc<RefCell<Vec<InventoryItem>>>,
+    item_table: Rc<RefCell<Table>>,
+    category_colors: Rc<RefCell<HashMap<i64, Color>>>,
+    category_minimums: Rc<RefCell<HashMap<i64, i32>>>,
+    current_view: Rc<RefCell<InventoryView>>
+) {
+    let db_clone = inventory_db;
+    let items_clone = items;
+    let table_clone = item_table;
+    let mut stats_text_clone = stats_text.clone();
+    let mut category_choice_clone = category_choice.clone();
+    let mut log_buffer_clone = log_buffer.clone();
+    
+    refresh_btn.set_callback(move |_| {
+        // The list shows the current view; the stats cover the whole inventory
+        if let Err(e) = load_view(&db_clone.borrow(), &current_view.borrow(), &items_clone, &table_clone) {
+            dialog::alert(300, 300, &format!("Error loading inventory: {}", e));
+            log_buffer_clone.append(&format!("Error: {}\n", e));
+            return;
+        }
+        
+        match db_clone.borrow().get_all_items() {
+            Ok(items) => {
+                let mut table = table_clone.borrow_mut();
+                
+                // Update stats
+                let total_quantity: i32 = items.iter().map(|i| i.quantity).sum();
+                let categories: HashSet<_> = items
+                    .iter()
+                    .filter_map(|i| i.category.clone())
+                    .collect();
+                let locations: HashSet<_> = items
+                    .iter()
+                    .filter_map(|i| i.location_id)
+                    .collect();
+                
+                let reorder = db_clone.borrow().needs_reorder().unwrap_or_default();
+                let out_of_stock = reorder.iter().filter(|entry| entry.is_out_of_stock()).count();
+                
+                stats_text_clone.set_label(&format!(
+                    "Total Items: {}   Total Quantity: {}\nCategories: {}   Locations: {}\nTo reorder: {} ({} out of stock)",
+                    items.len(),
+                    total_quantity,
+                    categories.len(),
+                    locations.len(),
+                    reorder.len(),
+                    out_of_stock
+                ));
+                
+                // Populate category dropdown and the colours the table shows
+                match db_clone.borrow().get_categories() {
+                    Ok(categories) => {
+                        category_choice_clone.update_categories(&categories);
+                        *category_colors.borrow_mut() = categories
+                            .iter()
+                            .filter_map(|category| {
+                                let (r, g, b) = category.rgb()?;
+                                // Lightened so the text stays readable on dark colours
+                                Some((category.id, Color::from_rgb(r, g, b).lighter()))
+                            })
+                            .collect();
+                        *category_minimums.borrow_mut() = categories
+                            .iter()
+                            .filter_map(|category| Some((category.id, category.effective_min_quantity?)))
+                            .collect();
+                        table.redraw();
+                    }
+                    Err(e) => log_buffer_clone.append(&format!("Error loading categories: {}\n", e)),
+                }
+                
+                // Add to log
+                log_buffer_clone.append("Refreshed inventory list\n");
+            },
+            Err(e) => {
+                dialog::alert(300, 300, &format!("Error loading inventory: {}", e));
+                log_buffer_clone.append(&format!("Error: {}\n", e));
+            }
+        }
+    });
+}
+pub fn setup_history_button(
+    history_btn: &mut Button,
+    log_buffer: &TextBuffer,
+    inventory_db: Rc<RefCell<InventoryDB>>,
+    current_tag_id: Rc<RefCell<Option<Uid>>>
+) {
+    let db_clone = inventory_db;
+    let current_tag_clone = current_tag_id;
+    let mut log_buffer_clone = log_buffer.clone();
+    
+    history_btn.set_callback(move |_| {
+        if let Some(tag_id) = current_tag_clone.borrow().clone() {
+            show_movement_history(&db_clone.borrow(), &tag_id);
+            log_buffer_clone.append(&format!("Showed movement history for tag: {}\n", tag_id));
+        } else {
+            dialog::alert(300, 300, "No item selected to show history for");
+        }
+    });
+}
+
+pub fn setup_tags_button(
+    tags_btn: &mut Button,
+    item_form: &ItemForm,
+    log_buffer: &TextBuffer,
+    inventory_db: Rc<RefCell<InventoryDB>>,
+    items: Rc<RefCell<Vec<InventoryItem>>>,
+    current_tag_id: Rc<RefCell<Option<Uid>>>,
+    item_table: Rc<RefCell<Table>>,
+    current_view: Rc<RefCell<InventoryView>>
+) {
+    let db_clone = inventory_db;
+    let current_tag_clone = current_tag_id;
+    let item_form_clone = item_form.clone();
+    let mut log_buffer_clone = log_buffer.clone();
+    
+    tags_btn.set_callback(move |_| {
+        let Some(tag_id) = current_tag_clone.borrow().clone() else {
+            dialog::alert(300, 300, "No item selected to show tags for");
+            return;
+        };
+        
+        // A re-tagged item is selected and shown by its new tag
+        let db_inner = db_clone.clone();
+        let current_tag_inner = current_tag_clone.clone();
+        let items_inner = items.clone();
+        let table_inner = item_table.clone();
+        let item_form_inner = item_form_clone.clone();
+        let view_inner = current_view.clone();
+        show_item_tags(db_clone.clone(), &tag_id, move |item| {
+            *current_tag_inner.borrow_mut() = Some(item.tag_id.clone());
+            item_form_inner.clone().display_item(item);
+            let _ = load_view(&db_inner.borrow(), &view_inner.borrow(), &items_inner, &table_inner);
+        });
+        log_buffer_clone.append(&format!("Showed tags for tag: {}\n", tag_id));
+    });
+}
@@ -2,9 +2,13 @@ pub mod item_handlers;
 pub mod search_handlers;
 pub mod export_handlers;
 pub mod scan_handlers;
+pub mod view_handlers;
+pub mod bulk_handlers;
 
 // Re-export handler functions for convenience
 pub use item_handlers::*;
 pub use search_handlers::*;
 pub use export_handlers::*;
-pub use scan_handlers::*;
\ No newline at end of file
+pub use scan_handlers::*;
+pub use view_handlers::*;
+pub use bulk_handlers::*;
\ No newline at end of file
@@ -1,33 +1,59 @@
-// src/inventory/ui/handlers/scan_handlers.rs
+// src/ui/inventory/handlers/scan_handlers.rs
 use fltk::{
     dialog,
-    prelude::*,
     table::Table,
 };
 use std::cell::RefCell;
 use std::rc::Rc;
 
 use crate::inventory::db::InventoryDB;
-use crate::inventory::model::{InventoryItem, create_inventory_item};
+use crate::inventory::model::{InventoryItem, MovementReason, create_inventory_item};
+use crate::inventory::scan::{self, ScanMode, ScanOptions};
+use crate::inventory::view::InventoryView;
+use crate::ui::inventory::handlers::view_handlers::load_view;
+use crate::uid::Uid;
 
 pub fn process_scanned_tag(
-    tag_id: &str,
+    tag_id: &Uid,
+    mode: ScanMode,
+    options: &ScanOptions,
     inventory_db: &Rc<RefCell<InventoryDB>>,
-    current_tag_id: &Rc<RefCell<Option<String>>>,
+    current_tag_id: &Rc<RefCell<Option<Uid>>>,
     items: &Rc<RefCell<Vec<InventoryItem>>>,
-    item_table: &Rc<RefCell<Table>>
+    item_table: &Rc<RefCell<Table>>,
+    current_view: &Rc<RefCell<InventoryView>>
 ) {
+    // A location tag isn't an item; the capture window keeps it as the current location
+    if let Ok(Some(location)) = inventory_db.borrow().get_location_by_tag(tag_id) {
+        dialog::message(300, 300, &format!("Tag {} marks the location {}.", tag_id, location.path));
+        return;
+    }
+    
     // Check if tag exists in inventory
     match inventory_db.borrow().get_item(tag_id) {
         Ok(Some(item)) => {
-            // Item exists - increment quantity
-            let new_quantity = item.quantity + 1;
-            if let Err(e) = inventory_db.borrow().update_quantity(tag_id, new_quantity) {
-                dialog::alert(300, 300, &format!("Error updating quantity: {}", e));
-                return;
+            // Item exists - apply the scan in the active mode
+            let outcome = match scan::apply_scan(&inventory_db.borrow(), tag_id, mode, options) {
+                Ok(outcome) => outcome,
+                Err(e) => {
+                    dialog::alert(300, 300, &format!("Scan not applied: {}", e));
+                    return;
+                }
+            };
+            
+            // Running low is shown as a warning, right when the scan took the stock below the minimum
+            let text = format!("Tag scanned: {}. {}.", item.name, outcome.message());
+            if outcome.reorder().is_some() {
+                dialog::alert(300, 300, &text);
+            } else {
+                dialog::message(300, 300, &text);
             }
             
-            dialog::message(300, 300, &format!("Tag scanned: {}. Quantity updated to {}.", item.name, new_quantity));
+            // Refresh the table
+            let _ = load_view(&inventory_db.borrow(), &current_view.borrow(), items, item_table);
+        },
+        Ok(None) if !mode.changes_stock() => {
+            dialog::message(300, 300, &format!("Tag ID {} not found in inventory.", tag_id));
         },
         Ok(None) => {
             // Item doesn't exist - ask to create
@@ -36,7 +62,7 @@ pub fn process_scanned_tag(
                 "No", "Yes", "") == Some(1) {
                 
                 // Set current tag and prompt for details
-                *current_tag_id.borrow_mut() = Some(tag_id.to_string());
+                *current_tag_id.borrow_mut() = Some(tag_id.clone());
                 
                 // This would ideally open a form dialog, but for now we'll use a simple input
                 if let Some(name) = dialog::input(300, 300, "Enter item name:", "") {
@@ -45,7 +71,7 @@ pub fn process_scanned_tag(
                         let item = create_inventory_item(tag_id, &name, None, 1, None, None);
                         
                         // Save to database
-                        if let Err(e) = inventory_db.borrow().save_item(&item) {
+                        if let Err(e) = inventory_db.borrow().save_item_with_reason(&item, MovementReason::ScanIn) {
                             dialog::alert(300, 300, &format!("Error saving item: {}", e));
                             return;
                         }
@@ -56,12 +82,7 @@ pub fn process_scanned_tag(
                         dialog::message(300, 300, &format!("New item '{}' added to inventory.", name));
                         
                         // Refresh the table
-                        if let Ok(all_items) = inventory_db.borrow().get_all_items() {
-                            *items.borrow_mut() = all_items;
-                            let mut table = item_table.borrow_mut();
-                            table.set_rows(items.borrow().len() as i32);
-                            table.redraw();
-                        }
+                        let _ = load_view(&inventory_db.borrow(), &current_view.borrow(), items, item_table);
                     }
                 }
             }
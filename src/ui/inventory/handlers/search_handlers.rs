@@ -0,0 +1,59 @@
+// src/ui/inventory/handlers/search_handlers.rs
+use fltk::{
+    button::Button,
+    dialog,
+    input::Input,
+    prelude::*,
+    text::TextBuffer,
+    table::Table,
+};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::inventory::model::InventoryItem;
+use crate::inventory::db::InventoryDB;
+use crate::inventory::search::SearchQuery;
+use crate::inventory::view::InventoryView;
+use crate::ui::inventory::handlers::view_handlers::load_view;
+
+// Search the full-text index within the current view's filters; without a sort order the best
+// matches come first, and the table highlights what matched
+pub fn setup_search_button(
+    search_btn: &mut Button,
+    search_input: &Input,
+    log_buffer: &TextBuffer,
+    inventory_db: Rc<RefCell<InventoryDB>>,
+    items: Rc<RefCell<Vec<InventoryItem>>>,
+    item_table: Rc<RefCell<Table>>,
+    current_view: Rc<RefCell<InventoryView>>
+) {
+    let db_clone = inventory_db;
+    let items_clone = items;
+    let table_clone = item_table;
+    let mut log_buffer_clone = log_buffer.clone();
+    let search_input_clone = search_input.clone();
+    
+    search_btn.set_callback(move |_| {
+        let text = search_input_clone.value();
+        let query = match SearchQuery::parse(&text) {
+            Ok(query) => query,
+            Err(e) => {
+                dialog::alert(300, 300, &format!("Error searching: {}", e));
+                return;
+            }
+        };
+        
+        let mut view = current_view.borrow().clone();
+        view.search = if query.is_empty() { String::new() } else { text.trim().to_string() };
+        
+        match load_view(&db_clone.borrow(), &view, &items_clone, &table_clone) {
+            Ok(count) if query.is_empty() => log_buffer_clone.append(&format!("Search cleared: {} items\n", count)),
+            Ok(count) => log_buffer_clone.append(&format!("Found {} items matching '{}'\n", count, text)),
+            Err(e) => {
+                dialog::alert(300, 300, &format!("Error searching: {}", e));
+                return;
+            }
+        }
+        *current_view.borrow_mut() = view;
+    });
+}
@@ -0,0 +1,175 @@
+// src/ui/inventory/handlers/view_handlers.rs
+use fltk::{
+    button::Button,
+    dialog,
+    enums::Shortcut,
+    menu::{Choice, MenuFlag},
+    prelude::*,
+    text::TextBuffer,
+    table::Table,
+};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::config::{self, AppConfig};
+use crate::error::Error;
+use crate::inventory::db::InventoryDB;
+use crate::inventory::model::InventoryItem;
+use crate::inventory::view::InventoryView;
+use crate::ui::inventory::components::show_filters_dialog;
+use crate::ui::inventory::utils::menu_label;
+
+// Load the rows `view` shows into the list; every change to the list goes through here so
+// the table and exports always agree
+pub fn load_view(
+    inventory_db: &InventoryDB,
+    view: &InventoryView,
+    items: &Rc<RefCell<Vec<InventoryItem>>>,
+    item_table: &Rc<RefCell<Table>>
+) -> Result<usize, Error> {
+    let loaded = inventory_db.get_view_items(view)?;
+    let count = loaded.len();
+    *items.borrow_mut() = loaded;
+
+    let mut table = item_table.borrow_mut();
+    table.set_rows(count as i32);
+    table.redraw();
+
+    Ok(count)
+}
+
+// "All items" first, then the saved views
+fn update_view_choice(view_choice: &mut Choice, config: &AppConfig) {
+    view_choice.clear();
+    view_choice.add_choice("All items");
+    for view in &config.saved_views {
+        view_choice.add(&menu_label(&view.name), Shortcut::None, MenuFlag::Normal, |_| {});
+    }
+}
+
+// Switch the list to the chosen saved view, or back to the whole inventory
+pub fn setup_view_choice(
+    view_choice: &mut Choice,
+    log_buffer: &TextBuffer,
+    config: Rc<RefCell<AppConfig>>,
+    inventory_db: Rc<RefCell<InventoryDB>>,
+    items: Rc<RefCell<Vec<InventoryItem>>>,
+    item_table: Rc<RefCell<Table>>,
+    current_view: Rc<RefCell<InventoryView>>
+) {
+    update_view_choice(view_choice, &config.borrow());
+    view_choice.set_value(0);
+
+    let mut log_buffer_clone = log_buffer.clone();
+    view_choice.set_callback(move |choice| {
+        let view = match choice.value() {
+            index if index > 0 => match config.borrow().saved_views.get(index as usize - 1) {
+                Some(view) => view.clone(),
+                None => return,
+            },
+            _ => InventoryView::all(),
+        };
+
+        match load_view(&inventory_db.borrow(), &view, &items, &item_table) {
+            Ok(count) => log_buffer_clone.append(&format!("Showing view '{}': {} items\n", view.name, count)),
+            Err(e) => dialog::alert(300, 300, &format!("Error loading view '{}': {}", view.name, e)),
+        }
+        *current_view.borrow_mut() = view;
+    });
+}
+
+// Edit the filters of the current view; they combine with its search and keep its sort order
+pub fn setup_filters_button(
+    filters_btn: &mut Button,
+    log_buffer: &TextBuffer,
+    inventory_db: Rc<RefCell<InventoryDB>>,
+    items: Rc<RefCell<Vec<InventoryItem>>>,
+    item_table: Rc<RefCell<Table>>,
+    current_view: Rc<RefCell<InventoryView>>
+) {
+    let log_buffer_clone = log_buffer.clone();
+
+    filters_btn.set_callback(move |_| {
+        let db_inner = inventory_db.clone();
+        let items_inner = items.clone();
+        let table_inner = item_table.clone();
+        let view_inner = current_view.clone();
+        let log_buffer_inner = log_buffer_clone.clone();
+        let filter = current_view.borrow().filter.clone();
+
+        show_filters_dialog(inventory_db.clone(), &filter, move |filter| {
+            let mut view = view_inner.borrow().clone();
+            view.filter = filter;
+
+            match load_view(&db_inner.borrow(), &view, &items_inner, &table_inner) {
+                Ok(count) => {
+                    let description = view.filter.describe();
+                    let mut log_buffer = log_buffer_inner.clone();
+                    if description.is_empty() {
+                        log_buffer.append(&format!("Filters cleared: {} items\n", count));
+                    } else {
+                        log_buffer.append(&format!("Filtered by {}: {} items\n", description, count));
+                    }
+                    *view_inner.borrow_mut() = view;
+                },
+                Err(e) => dialog::alert(300, 300, &format!("Error filtering: {}", e)),
+            }
+        });
+    });
+}
+
+// Save the search, filters and sort order on screen under a name, or delete the selected saved view
+pub fn setup_save_view_button(
+    save_view_btn: &mut Button,
+    view_choice: &Choice,
+    log_buffer: &TextBuffer,
+    config: Rc<RefCell<AppConfig>>,
+    current_view: Rc<RefCell<InventoryView>>
+) {
+    let mut view_choice_clone = view_choice.clone();
+    let mut log_buffer_clone = log_buffer.clone();
+
+    save_view_btn.set_callback(move |_| {
+        let selected = match view_choice_clone.value() {
+            index if index > 0 => config.borrow().saved_views.get(index as usize - 1).map(|view| view.name.clone()),
+            _ => None,
+        };
+
+        if let Some(name) = &selected {
+            let question = format!("Save the list as shown as a view, or delete the view '{}'?", name);
+            match dialog::choice2(300, 300, &question, "Cancel", "Save...", "Delete") {
+                Some(1) => {}
+                Some(2) => {
+                    if let Err(e) = config::delete_view(name, &mut config.borrow_mut()) {
+                        dialog::alert(300, 300, &format!("Error deleting view: {}", e));
+                        return;
+                    }
+                    update_view_choice(&mut view_choice_clone, &config.borrow());
+                    view_choice_clone.set_value(0);
+                    current_view.borrow_mut().name = InventoryView::all().name;
+                    log_buffer_clone.append(&format!("Deleted view '{}'\n", name));
+                    return;
+                }
+                _ => return,
+            }
+        }
+
+        let name = match dialog::input(300, 300, "Name for this view:", selected.as_deref().unwrap_or("")) {
+            Some(name) if !name.trim().is_empty() => name.trim().to_string(),
+            _ => return,
+        };
+        let mut view = current_view.borrow().clone();
+        view.name = name.clone();
+
+        if let Err(e) = config::save_view(view.clone(), &mut config.borrow_mut()) {
+            dialog::alert(300, 300, &format!("Error saving view: {}", e));
+            return;
+        }
+        *current_view.borrow_mut() = view;
+
+        update_view_choice(&mut view_choice_clone, &config.borrow());
+        let index = config.borrow().saved_views.iter().position(|view| view.name.eq_ignore_ascii_case(&name));
+        view_choice_clone.set_value(index.map(|index| index as i32 + 1).unwrap_or(0));
+        log_buffer_clone.append(&format!("Saved view '{}'\n", name));
+    });
+}
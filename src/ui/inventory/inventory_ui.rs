@@ -0,0 +1,363 @@
+// src/ui/inventory/inventory_ui.rs
+use fltk::{
+    app,
+    button::Button,
+    dialog,
+    enums::{Align, FrameType, Font, LabelType},
+    frame::Frame,
+    group::{Group, Tabs},
+    input::Input,
+    menu::{Choice, MenuButton},
+    prelude::*,
+    table::Table,
+    text::{TextBuffer, TextDisplay},
+};
+use fltk::enums::Color;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::config::AppConfig;
+use crate::error::Error;
+use crate::inventory::db::InventoryDB;
+use crate::inventory::model::InventoryItem;
+use crate::inventory::scan::{ScanMode, ScanOptions};
+use crate::inventory::view::InventoryView;
+use crate::uid::Uid;
+use crate::ui::inventory::selection::Selection;
+use crate::ui::inventory::components::form::ItemForm;
+use crate::ui::inventory::components::table::setup_inventory_table;
+use crate::ui::inventory::handlers::{
+    bulk_handlers::{setup_selection_menu, setup_undo_buttons, update_selection_label},
+    item_handlers::{
+        setup_add_button, setup_clear_button, setup_delete_button, 
+        setup_history_button, setup_refresh_button, setup_save_button, setup_tags_button
+    },
+    search_handlers::setup_search_button,
+    view_handlers::{setup_filters_button, setup_save_view_button, setup_view_choice},
+    export_handlers::setup_export_button,
+    scan_handlers::process_scanned_tag
+};
+
+// Structure to hold the UI components for inventory management
+pub struct InventoryUI {
+    pub inventory_db: Rc<RefCell<InventoryDB>>,
+    item_table: Rc<RefCell<Table>>,
+    items: Rc<RefCell<Vec<InventoryItem>>>,
+    current_tag_id: Rc<RefCell<Option<Uid>>>,
+    // Display colour per category id, reloaded with the list
+    category_colors: Rc<RefCell<HashMap<i64, Color>>>,
+    // Minimum stock per category id, for flagging items that don't set their own
+    category_minimums: Rc<RefCell<HashMap<i64, i32>>>,
+    // What the list shows: search, filters and sort order; exports use it too
+    current_view: Rc<RefCell<InventoryView>>,
+    // Rows picked for bulk actions
+    selection: Rc<RefCell<Selection>>,
+}
+
+impl InventoryUI {
+    // Create a new instance of the inventory management UI
+    pub fn new(db_path: &str) -> Result<Self, Error> {
+        // Initialize the database
+        let inventory_db = match InventoryDB::new(db_path) {
+            Ok(db) => Rc::new(RefCell::new(db)),
+            Err(e) => return Err(e),
+        };
+        
+        if let Some(report) = inventory_db.borrow().migration() {
+            if let Some(backup) = &report.backup {
+                println!("Upgraded inventory schema from version {} to {}, backup saved as {}",
+                    report.from_version, report.to_version, backup.display());
+            }
+            if !report.quarantined.is_empty() {
+                dialog::alert(300, 300, &format!(
+                    "{} item(s) or tag(s) with tags that aren't valid UIDs or clash with another tag were moved to \
+                     the quarantine table of the database: {}",
+                    report.quarantined.len(),
+                    report.quarantined.join(", ")
+                ));
+            }
+        }
+        
+        // Create empty table and items vector
+        let item_table = Rc::new(RefCell::new(Table::default()));
+        let items = Rc::new(RefCell::new(Vec::new()));
+        let current_tag_id = Rc::new(RefCell::new(None));
+        
+        Ok(InventoryUI {
+            inventory_db,
+            item_table,
+            items,
+            current_tag_id,
+            category_colors: Rc::new(RefCell::new(HashMap::new())),
+            category_minimums: Rc::new(RefCell::new(HashMap::new())),
+            current_view: Rc::new(RefCell::new(InventoryView::all())),
+            selection: Rc::new(RefCell::new(Selection::default())),
+        })
+    }
+    
+    // Create the inventory tab in the UI
+    pub fn create_tab(&self, tabs: &mut Tabs, config: Rc<RefCell<AppConfig>>) {
+        let inventory_tab = Group::new(0, 50, 800, 550, "Inventory");
+        
+        // Create the left panel for the table
+        let table_panel = Group::new(10, 60, 380, 530, "");
+        
+        // Search input
+        let search_input = Input::new(10, 60, 280, 30, "Search:");
+        let mut search_btn = Button::new(300, 60, 80, 30, "Search");
+        
+        // Saved views, and the filters of the one showing
+        let mut view_choice = Choice::new(50, 100, 160, 25, "View:");
+        let mut filters_btn = Button::new(215, 100, 80, 25, "Filters...");
+        let mut save_view_btn = Button::new(300, 100, 90, 25, "Save View...");
+        
+        // Create a table to display inventory items
+        let mut table = Table::new(10, 130, 380, 290, "");
+        
+        // Store the table in our struct
+        *self.item_table.borrow_mut() = table.clone();
+        
+        // Bulk actions on the selected rows, and undo for them
+        let mut selection_label = Frame::new(10, 425, 110, 25, "No selection");
+        selection_label.set_align(Align::Left | Align::Inside);
+        let mut selection_menu = MenuButton::new(125, 425, 135, 25, "Selected Items");
+        let mut undo_btn = Button::new(265, 425, 60, 25, "Undo");
+        let mut redo_btn = Button::new(330, 425, 60, 25, "Redo");
+        
+        // Action buttons
+        let mut refresh_btn = Button::new(10, 460, 120, 30, "Refresh List");
+        let mut add_btn = Button::new(140, 460, 120, 30, "Add Item");
+        let mut export_btn = Button::new(270, 460, 120, 30, "Export");
+        
+        let mut stats_frame = Frame::new(10, 500, 380, 80, "Inventory Stats");
+        stats_frame.set_frame(FrameType::EngravedBox);
+        stats_frame.set_label_type(LabelType::None);
+        
+        let mut stats_text = Frame::new(20, 510, 360, 60, "");
+        stats_text.set_align(Align::TopLeft | Align::Inside);
+        
+        table_panel.end();
+        
+        // Create the right panel for item details
+        let detail_panel = Group::new(400, 60, 390, 530, "");
+        
+        let mut detail_title = Frame::new(400, 60, 390, 30, "Item Details");
+        detail_title.set_label_font(Font::HelveticaBold);
+        detail_title.set_label_size(18);
+        
+        // Create item form
+        let mut item_form = ItemForm::new(400, 100, 390, 260, self.inventory_db.clone());
+        
+        // Action buttons
+        let mut save_btn = Button::new(400, 370, 120, 30, "Save Changes");
+        let mut delete_btn = Button::new(530, 370, 120, 30, "Delete Item");
+        let mut clear_btn = Button::new(660, 370, 120, 30, "Clear Form");
+        let mut history_btn = Button::new(400, 410, 120, 30, "History");
+        let mut tags_btn = Button::new(530, 410, 120, 30, "Tags...");
+        
+        // Event log
+        let _log_frame = Frame::new(400, 510, 390, 30, "Event Log");
+        let mut log_display = TextDisplay::new(400, 540, 390, 40, "");
+        let mut log_buffer = TextBuffer::default();
+        log_display.set_buffer(log_buffer.clone());
+        
+        detail_panel.end();
+        
+        // Setup table with a separate closure to avoid borrowing/moving issues
+        let db_clone = self.inventory_db.clone();
+        let current_tag_clone = self.current_tag_id.clone();
+        let items_clone = self.items.clone();
+        let mut item_form_clone = item_form.clone();
+        let mut log_buffer_clone = log_buffer.clone();
+        let colors_clone = self.category_colors.clone();
+        let minimums_clone = self.category_minimums.clone();
+        let view_clone = self.current_view.clone();
+        let selection_clone = self.selection.clone();
+        let mut selection_label_clone = selection_label.clone();
+        
+        setup_inventory_table(
+            &mut table,
+            items_clone.clone(),
+            colors_clone,
+            minimums_clone,
+            view_clone,
+            self.selection.clone(),
+            move |row_index| {
+                update_selection_label(&mut selection_label_clone, &selection_clone.borrow(), &items_clone.borrow());
+                let tag_id = items_clone.borrow()[row_index].tag_id.clone();
+                *current_tag_clone.borrow_mut() = Some(tag_id.clone());
+            
+                // Load item details
+                if let Ok(Some(item)) = db_clone.borrow().get_item(&tag_id) {
+                    // Update form fields
+                    item_form_clone.display_item(&item);
+                
+                    // Log
+                    log_buffer_clone.append(&format!("Loaded details for item: {}\n", item.name));
+                }
+            }
+        );
+        
+        setup_selection_menu(
+            &mut selection_menu,
+            &selection_label,
+            &undo_btn,
+            &redo_btn,
+            &log_buffer,
+            self.inventory_db.clone(),
+            self.items.clone(),
+            self.item_table.clone(),
+            self.current_view.clone(),
+            self.selection.clone()
+        );
+        
+        setup_undo_buttons(
+            &mut undo_btn,
+            &mut redo_btn,
+            &log_buffer,
+            self.inventory_db.clone(),
+            self.items.clone(),
+            self.item_table.clone(),
+            self.current_view.clone()
+        );
+        
+        // Set up button handlers
+        setup_refresh_button(
+            &mut refresh_btn,
+            &mut stats_text,
+            &mut item_form.category_choice,
+            &log_buffer,
+            self.inventory_db.clone(),
+            self.items.clone(),
+            self.item_table.clone(),
+            self.category_colors.clone(),
+            self.category_minimums.clone(),
+            self.current_view.clone()
+        );
+        
+        setup_save_button(
+            &mut save_btn, 
+            &item_form,
+            &log_buffer,
+            self.inventory_db.clone(),
+            self.items.clone(),
+            self.current_tag_id.clone(),
+            self.item_table.clone(),
+            self.current_view.clone()
+        );
+        
+        setup_delete_button(
+            &mut delete_btn,
+            &mut item_form,
+            &log_buffer,
+            self.inventory_db.clone(),
+            self.items.clone(),
+            self.current_tag_id.clone(),
+            self.item_table.clone(),
+            self.current_view.clone()
+        );
+        
+        setup_clear_button(
+            &mut clear_btn,
+            &mut item_form,
+            &log_buffer,
+            self.current_tag_id.clone()
+        );
+        
+        setup_history_button(
+            &mut history_btn,
+            &log_buffer,
+            self.inventory_db.clone(),
+            self.current_tag_id.clone()
+        );
+        
+        setup_tags_button(
+            &mut tags_btn,
+            &item_form,
+            &log_buffer,
+            self.inventory_db.clone(),
+            self.items.clone(),
+            self.current_tag_id.clone(),
+            self.item_table.clone(),
+            self.current_view.clone()
+        );
+        
+        setup_add_button(
+            &mut add_btn,
+            &mut item_form,
+            &log_buffer,
+            self.current_tag_id.clone()
+        );
+        
+        setup_export_button(
+            &mut export_btn,
+            &log_buffer,
+            self.items.clone()
+        );
+        
+        setup_search_button(
+            &mut search_btn,
+            &search_input,
+            &log_buffer,
+            self.inventory_db.clone(),
+            self.items.clone(),
+            self.item_table.clone(),
+            self.current_view.clone()
+        );
+        
+        setup_view_choice(
+            &mut view_choice,
+            &log_buffer,
+            config.clone(),
+            self.inventory_db.clone(),
+            self.items.clone(),
+            self.item_table.clone(),
+            self.current_view.clone()
+        );
+        
+        setup_filters_button(
+            &mut filters_btn,
+            &log_buffer,
+            self.inventory_db.clone(),
+            self.items.clone(),
+            self.item_table.clone(),
+            self.current_view.clone()
+        );
+        
+        setup_save_view_button(
+            &mut save_view_btn,
+            &view_choice,
+            &log_buffer,
+            config,
+            self.current_view.clone()
+        );
+        
+        inventory_tab.end();
+        tabs.add(&inventory_tab);
+        
+        // Initialize by loading inventory
+        app::add_timeout3(0.1, {
+            let mut refresh_btn_clone = refresh_btn.clone();
+            move |_| {
+                refresh_btn_clone.do_callback();
+            }
+        });
+    }
+    
+    // Method to update inventory with a scanned tag in the given scan mode
+    pub fn process_scanned_tag(&self, tag_id: &Uid, mode: ScanMode, options: &ScanOptions) {
+        process_scanned_tag(
+            tag_id,
+            mode,
+            options,
+            &self.inventory_db,
+            &self.current_tag_id,
+            &self.items,
+            &self.item_table,
+            &self.current_view
+        )
+    }
+}
+    
+    // Method to update inventory with a sc
\ No newline at end of file
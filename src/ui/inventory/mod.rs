@@ -1,6 +1,7 @@
 pub mod components;
 pub mod handlers;
 pub mod inventory_ui;
+pub mod selection;
 pub mod utils;
 
 // Re-export the InventoryUI for convenience
@@ -0,0 +1,185 @@
+// src/ui/inventory/selection.rs
+use std::collections::BTreeSet;
+
+use crate::inventory::model::InventoryItem;
+
+// Rows picked in an item table. Items are kept by id, so the selection survives sorting and
+// reloads; only the items the list still shows count as selected
+#[derive(Clone, Debug, Default)]
+pub struct Selection {
+    ids: BTreeSet<i64>,
+    // Item a shift-click extends the selection from
+    anchor: Option<i64>,
+}
+
+impl Selection {
+    // A click on `row`: on its own it selects just that row, with Ctrl it toggles the row, with
+    // Shift it selects the rows from the last clicked one (adding to the selection with Ctrl too)
+    pub fn click(&mut self, items: &[InventoryItem], row: usize, ctrl: bool, shift: bool) {
+        let Some(id) = items.get(row).and_then(|item| item.id) else {
+            return;
+        };
+        let anchor_row = self.anchor.and_then(|anchor| items.iter().position(|item| item.id == Some(anchor)));
+
+        match anchor_row {
+            Some(anchor_row) if shift => {
+                if !ctrl {
+                    self.ids.clear();
+                }
+                let range = anchor_row.min(row)..=anchor_row.max(row);
+                self.ids.extend(items[range].iter().filter_map(|item| item.id));
+                // The anchor stays put so the range can be shortened or extended again
+                return;
+            }
+            _ if ctrl => {
+                if !self.ids.remove(&id) {
+                    self.ids.insert(id);
+                }
+            }
+            _ => {
+                self.ids.clear();
+                self.ids.insert(id);
+            }
+        }
+        self.anchor = Some(id);
+    }
+
+    pub fn select_all(&mut self, items: &[InventoryItem]) {
+        self.ids = items.iter().filter_map(|item| item.id).collect();
+        self.anchor = None;
+    }
+
+    pub fn clear(&mut self) {
+        self.ids.clear();
+        self.anchor = None;
+    }
+
+    pub fn contains(&self, item: &InventoryItem) -> bool {
+        item.id.is_some_and(|id| self.ids.contains(&id))
+    }
+
+    // The selected items the list shows, in list order
+    pub fn items(&self, items: &[InventoryItem]) -> Vec<InventoryItem> {
+        items.iter().filter(|item| self.contains(item)).cloned().collect()
+    }
+
+    // How many of the items the list shows are selected
+    pub fn count(&self, items: &[InventoryItem]) -> usize {
+        items.iter().filter(|item| self.contains(item)).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inventory::model::create_inventory_item;
+
+    // Items with ids 1..=count, in that order
+    fn items(count: i64) -> Vec<InventoryItem> {
+        (1..=count).map(item).collect()
+    }
+
+    fn item(id: i64) -> InventoryItem {
+        let tag_id = format!("04A1B2{:02X}", id).parse().unwrap();
+        let mut item = create_inventory_item(&tag_id, &format!("Item {}", id), None, 1, None, None);
+        item.id = Some(id);
+        item
+    }
+
+    fn selected(selection: &Selection, items: &[InventoryItem]) -> Vec<i64> {
+        selection.items(items).iter().filter_map(|item| item.id).collect()
+    }
+
+    #[test]
+    fn a_plain_click_selects_only_that_row() {
+        let list = items(5);
+        let mut selection = Selection::default();
+
+        selection.click(&list, 1, false, false);
+        selection.click(&list, 3, false, false);
+        assert_eq!(selected(&selection, &list), [4]);
+        assert_eq!(selection.count(&list), 1);
+
+        // Past the end of the list nothing changes
+        selection.click(&list, 9, false, false);
+        assert_eq!(selected(&selection, &list), [4]);
+    }
+
+    #[test]
+    fn ctrl_click_toggles_a_row() {
+        let list = items(5);
+        let mut selection = Selection::default();
+
+        selection.click(&list, 0, false, false);
+        selection.click(&list, 2, true, false);
+        selection.click(&list, 4, true, false);
+        assert_eq!(selected(&selection, &list), [1, 3, 5]);
+
+        selection.click(&list, 2, true, false);
+        assert_eq!(selected(&selection, &list), [1, 5]);
+    }
+
+    #[test]
+    fn shift_click_selects_the_range_from_the_anchor() {
+        let list = items(6);
+        let mut selection = Selection::default();
+
+        selection.click(&list, 2, false, false);
+        selection.click(&list, 4, false, true);
+        assert_eq!(selected(&selection, &list), [3, 4, 5]);
+
+        // The anchor stays on the plain click, so the range can turn around
+        selection.click(&list, 0, false, true);
+        assert_eq!(selected(&selection, &list), [1, 2, 3]);
+
+        // With Ctrl the range is added to what was selected
+        selection.click(&list, 5, true, false);
+        selection.click(&list, 3, true, true);
+        assert_eq!(selected(&selection, &list), [1, 2, 3, 4, 5, 6]);
+
+        // Without an anchor Shift acts like a plain click
+        let mut fresh = Selection::default();
+        fresh.click(&list, 3, false, true);
+        assert_eq!(selected(&fresh, &list), [4]);
+    }
+
+    #[test]
+    fn select_all_and_clear() {
+        let list = items(4);
+        let mut selection = Selection::default();
+
+        selection.select_all(&list);
+        assert_eq!(selected(&selection, &list), [1, 2, 3, 4]);
+
+        // Select all drops the anchor
+        selection.click(&list, 1, false, true);
+        assert_eq!(selected(&selection, &list), [2]);
+
+        selection.clear();
+        assert_eq!(selection.count(&list), 0);
+    }
+
+    #[test]
+    fn the_selection_follows_items_across_a_reload() {
+        let list = items(5);
+        let mut selection = Selection::default();
+        selection.click(&list, 1, false, false);
+        selection.click(&list, 3, true, false);
+
+        // Sorted the other way and without item 4: still item 2 selected, in list order
+        let reloaded: Vec<InventoryItem> = [5, 3, 2, 1].into_iter().map(item).collect();
+        assert_eq!(selected(&selection, &reloaded), [2]);
+        assert_eq!(selection.count(&reloaded), 1);
+
+        // Item 4 comes back in a later reload and is selected again
+        assert_eq!(selected(&selection, &list), [2, 4]);
+
+        // The anchor (item 4) isn't shown, so Shift acts like a plain click
+        selection.click(&reloaded, 0, false, true);
+        assert_eq!(selected(&selection, &reloaded), [5]);
+
+        // A shift-click finds the anchor at its new row
+        selection.click(&reloaded, 3, false, true);
+        assert_eq!(selected(&selection, &reloaded), [5, 3, 2, 1]);
+    }
+}
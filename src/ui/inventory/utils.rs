@@ -0,0 +1,81 @@
+use fltk::draw;
+use fltk::enums::{Align, Color, Font, FrameType};
+use fltk::enums::Shortcut;
+use fltk::menu::{Choice, MenuFlag};
+use fltk::prelude::MenuExt;
+use std::ops::Range;
+
+use crate::inventory::model::{Category, Location};
+
+// Helper functions for table drawing
+pub fn draw_cell_bg(x: i32, y: i32, w: i32, h: i32, color: Color) {
+    draw::push_clip(x, y, w, h);
+    draw::draw_rect_fill(x, y, w, h, color);
+    draw::pop_clip();
+}
+
+// Draw `text` with the byte ranges in `highlights` marked, e.g. what a search matched; Left or Center aligned
+pub fn draw_highlighted_text(text: &str, highlights: &[Range<usize>], x: i32, y: i32, w: i32, h: i32, align: Align) {
+    draw::push_clip(x, y, w, h);
+    if !highlights.is_empty() {
+        let left = if align == Align::Center { x + (w - draw::width(text) as i32) / 2 } else { x };
+        for range in highlights {
+            let start = left + draw::width(&text[..range.start]) as i32;
+            let width = draw::width(&text[range.clone()]) as i32;
+            draw::draw_rect_fill(start, y + 2, width, h - 4, Color::from_rgb(255, 230, 110));
+        }
+    }
+    draw::set_draw_color(Color::Black);
+    draw::draw_text2(text, x, y, w, h, align);
+    draw::pop_clip();
+}
+
+pub trait ChoiceExt {
+    fn update_categories(&mut self, categories: &[Category]);
+    fn update_locations(&mut self, locations: &[Location]);
+}
+impl ChoiceExt for Choice {
+    fn update_categories(&mut self, categories: &[Category]) {
+        self.clear();
+        self.add_choice("Uncategorized");
+        for category in categories {
+            self.add(&menu_label(&category.path), Shortcut::None, MenuFlag::Normal, |_| {});
+        }
+    }
+    
+    // "All locations" first, then every location path
+    fn update_locations(&mut self, locations: &[Location]) {
+        self.clear();
+        self.add_choice("All locations");
+        for location in locations {
+            self.add(&menu_label(&location.path), Shortcut::None, MenuFlag::Normal, |_| {});
+        }
+        self.set_value(0);
+    }
+}
+
+// Menus read '/' as a submenu and '&' as a shortcut, so escape them in data shown as an entry
+pub fn menu_label(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('/', "\\/").replace('&', "&&")
+}
+
+pub fn draw_cell_data(x: i32, y: i32, w: i32, h: i32, data: &str) {
+    draw::push_clip(x, y, w, h);
+    draw::draw_box(FrameType::FlatBox, x, y, w, h, Color::White);
+    
+    // Text color
+    draw::set_draw_color(Color::Black);
+    draw::set_font(Font::Helvetica, 14);
+    draw::draw_text2(data, x + 5, y, w - 10, h, Align::Left);
+    
+    draw::pop_clip();
+}
+
+// Helper to format timestamp for display
+pub fn format_timestamp(timestamp: &str) -> String {
+    if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(timestamp) {
+        dt.format("%Y-%m-%d %H:%M:%S").to_string()
+    } else {
+        timestamp.to_string()
+    }
+}
\ No newline at end of file
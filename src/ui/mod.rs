@@ -1,6 +1,8 @@
 // ui/mod.rs
 pub mod converter;
 pub mod common;
+pub mod batch;
+pub mod inventory;
 
 // Re-export the primary UI functions
 pub use common::{
@@ -0,0 +1,164 @@
+// uid.rs
+use std::fmt;
+use std::str::FromStr;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::convert;
+use crate::keyboard_layout::KeyboardLayout;
+use crate::utils;
+
+/// UID sizes defined by ISO/IEC 14443-3: single (4), double (7) and triple (10) bytes
+pub const VALID_UID_LENGTHS: [usize; 3] = [4, 7, 10];
+
+/// Reasons a string or byte sequence is not a usable card UID
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UidError {
+    /// No hex digits were found in the input
+    Empty,
+    /// The input contains a character that is not a hex digit
+    InvalidHex(char),
+    /// The input has an odd number of hex digits, so it can't be split into bytes
+    OddLength(usize),
+    /// The UID is not 4, 7 or 10 bytes long
+    InvalidLength(usize),
+}
+
+impl fmt::Display for UidError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UidError::Empty => write!(f, "no UID data found"),
+            UidError::InvalidHex(c) => write!(f, "invalid hex character '{}'", c),
+            UidError::OddLength(digits) => write!(f, "odd number of hex digits ({})", digits),
+            UidError::InvalidLength(len) => {
+                write!(f, "invalid UID length: {} bytes (expected 4, 7 or 10)", len)
+            }
+        }
+    }
+}
+
+impl std::error::Error for UidError {}
+
+/// A validated card UID, stored as raw bytes in the order the reader reports them
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Uid(Vec<u8>);
+
+impl Uid {
+    /// Build a UID from raw bytes, checking the length
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, UidError> {
+        if bytes.is_empty() {
+            return Err(UidError::Empty);
+        }
+        if !VALID_UID_LENGTHS.contains(&bytes.len()) {
+            return Err(UidError::InvalidLength(bytes.len()));
+        }
+
+        Ok(Uid(bytes.to_vec()))
+    }
+
+    /// Parse a hex UID, ignoring spaces, colons and dashes between bytes
+    pub fn from_hex(hex: &str) -> Result<Self, UidError> {
+        let digits: Vec<char> = hex
+            .chars()
+            .filter(|c| !matches!(c, ' ' | ':' | '-'))
+            .collect();
+
+        if let Some(c) = digits.iter().find(|c| !c.is_ascii_hexdigit()) {
+            return Err(UidError::InvalidHex(*c));
+        }
+        if digits.is_empty() {
+            return Err(UidError::Empty);
+        }
+        if !digits.len().is_multiple_of(2) {
+            return Err(UidError::OddLength(digits.len()));
+        }
+
+        let bytes: Vec<u8> = digits
+            .chunks(2)
+            .map(|pair| {
+                let byte: String = pair.iter().collect();
+                u8::from_str_radix(&byte, 16).unwrap_or_default()
+            })
+            .collect();
+
+        Uid::from_bytes(&bytes)
+    }
+
+    /// Parse raw keyboard-wedge input, undoing the given keyboard layout first
+    pub fn from_raw(raw: &str, keyboard_layout: &KeyboardLayout) -> Result<Self, UidError> {
+        let decoded = utils::decode_keyboard_input(raw, keyboard_layout);
+
+        // Keep just the hex digits; format indicators and separators are dropped
+        let hex: String = decoded.chars().filter(|c| c.is_ascii_hexdigit()).collect();
+
+        Uid::from_hex(&hex)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Manufacturer code (first byte) as assigned by ISO/IEC 7816-6
+    pub fn manufacturer_code(&self) -> u8 {
+        self.0[0]
+    }
+
+    /// Upper-case hex without separators, e.g. "04A1B2C3"
+    pub fn to_hex(&self) -> String {
+        self.0.iter().map(|b| format!("{:02X}", b)).collect()
+    }
+
+    /// Upper-case hex with a space between bytes, e.g. "04 A1 B2 C3"
+    pub fn to_spaced_hex(&self) -> String {
+        self.0
+            .iter()
+            .map(|b| format!("{:02X}", b))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Big-endian decimal value of the UID bytes
+    pub fn to_decimal(&self) -> String {
+        convert::bytes_to_decimal(&self.0)
+    }
+
+    /// The same UID with its byte order reversed (LSB first), as some readers report it
+    pub fn reversed(&self) -> Uid {
+        Uid(self.0.iter().rev().copied().collect())
+    }
+
+    pub fn to_reversed_hex(&self) -> String {
+        self.reversed().to_hex()
+    }
+
+    pub fn to_reversed_decimal(&self) -> String {
+        self.reversed().to_decimal()
+    }
+}
+
+impl fmt::Display for Uid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_hex())
+    }
+}
+
+impl FromStr for Uid {
+    type Err = UidError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Uid::from_hex(s)
+    }
+}
+
+// UIDs are stored and exchanged as plain hex strings, same as the old tag_id column
+impl Serialize for Uid {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_hex())
+    }
+}
+
+impl<'de> Deserialize<'de> for Uid {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        Uid::from_hex(&hex).map_err(serde::de::Error::custom)
+    }
+}
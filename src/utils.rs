@@ -1,7 +1,13 @@
 // utils.rs
+use std::fmt::Display;
 use std::time::{SystemTime, UNIX_EPOCH};
 use chrono::{DateTime, TimeZone, Local};
 
+use crate::card_family::CardFamily;
+use crate::keyboard_layout::KeyboardLayout;
+use crate::manufacturer;
+use crate::uid::Uid;
+
 /// Get current timestamps in both Unix and human-readable formats
 pub fn get_timestamps() -> (String, String) {
     // Get current time
@@ -17,242 +23,14 @@ pub fn get_timestamps() -> (String, String) {
     (unix_timestamp, human_timestamp)
 }
 
-/// Process a UID into human-readable format
-pub fn process_uid_for_display(uid: &str, keyboard_layout: i32) -> (String, String) {
-    // First, handle keyboard encoding formats and normalize
-    let decoded = match keyboard_layout {
-        1 => decode_windows_format(uid),   // Windows
-        2 => decode_mac_us_format(uid),    // Mac US
-        3 => decode_mac_intl_format(uid),  // Mac International
-        _ => {
-            // Auto-detect: try to guess based on content
-            if uid.contains('@') || uid.contains('!') || uid.contains('^') {
-                // Likely Windows/standard encoding
-                decode_windows_format(uid)
-            } else if uid.contains('§') || uid.contains('±') {
-                // Likely Mac with international chars
-                decode_mac_intl_format(uid)
-            } else {
-                // Default to Mac US layout
-                decode_mac_us_format(uid)
-            }
-        }
-    };
-    
-    // Extract just the hex digits
-    let clean_uid: String = decoded.chars()
-        .filter(|c| c.is_ascii_hexdigit())
-        .collect();
-    
-    if clean_uid.is_empty() {
-        return ("Invalid format".to_string(), "Unknown".to_string());
-    }
-    
-    // Format the hex UID with spaces for readability
-    let formatted_hex = format_hex_uid(&clean_uid);
-    
-    // Determine manufacturer
-    let manufacturer = identify_manufacturer(&clean_uid);
-    
-    (formatted_hex, manufacturer)
-}
-
-/// Format hex UID with spaces for better readability
-pub fn format_hex_uid(hex_uid: &str) -> String {
-    let chars: Vec<char> = hex_uid.chars().collect();
-    let mut formatted = String::new();
-    
-    for (i, c) in chars.iter().enumerate() {
-        formatted.push(*c);
-        if (i + 1) % 2 == 0 && i < chars.len() - 1 {
-            formatted.push(' ');
-        }
-    }
-    
-    formatted.to_uppercase()
-}
-
-/// Convert hexadecimal to decimal
-pub fn hex_to_decimal(hex: &str) -> String {
-    if hex.contains("Invalid") {
-        return "N/A".to_string();
-    }
-    
-    let clean_hex = hex.replace(" ", "");
-    match u64::from_str_radix(&clean_hex, 16) {
-        Ok(decimal) => decimal.to_string(),
-        Err(_) => "Invalid hex value".to_string()
-    }
-}
-
-/// Handle standard/Windows keyboard mapping
-pub fn decode_windows_format(encoded_str: &str) -> String {
-    if encoded_str.is_empty() {
-        return String::new();
-    }
-    
-    let mut decoded = String::new();
-    
-    for c in encoded_str.chars() {
-        match c {
-            '!' => decoded.push('1'),
-            '@' => decoded.push('2'),
-            '#' => decoded.push('3'),
-            '$' => decoded.push('4'),
-            '%' => decoded.push('5'),
-            '^' => decoded.push('6'),
-            '&' => decoded.push('7'),
-            '*' => decoded.push('8'),
-            '(' => decoded.push('9'),
-            ')' => decoded.push('0'),
-            'h' => decoded.push('h'),
-            'd' => decoded.push('d'),
-            'e' => decoded.push('e'),
-            'r' => decoded.push('r'),
-            '-' => decoded.push('-'),
-            ' ' => decoded.push(' '),
-            c if c.is_ascii_hexdigit() => decoded.push(c),
-            _ => {}  // Skip other characters
-        }
-    }
-    
-    decoded
-}
-
-/// Handle Mac US keyboard mapping
-pub fn decode_mac_us_format(encoded_str: &str) -> String {
-    if encoded_str.is_empty() {
-        return String::new();
-    }
-    
-    let mut decoded = String::new();
-    
-    for c in encoded_str.chars() {
-        match c {
-            '!' => decoded.push('1'),
-            '@' => decoded.push('2'),
-            '#' => decoded.push('3'),
-            '$' => decoded.push('4'),
-            '%' => decoded.push('5'),
-            '^' => decoded.push('6'),
-            '&' => decoded.push('7'),
-            '*' => decoded.push('8'),
-            '(' => decoded.push('9'),
-            ')' => decoded.push('0'),
-            // Mac-specific mappings
-            '¡' => decoded.push('1'),
-            '™' => decoded.push('2'),
-            '£' => decoded.push('3'),
-            '¢' => decoded.push('4'),
-            '∞' => decoded.push('5'),
-            '§' => decoded.push('6'),
-            '¶' => decoded.push('7'),
-            '•' => decoded.push('8'),
-            'ª' => decoded.push('9'),
-            'º' => decoded.push('0'),
-            // Format indicators
-            'h' => decoded.push('h'),
-            'd' => decoded.push('d'),
-            'e' => decoded.push('e'),
-            'r' => decoded.push('r'),
-            '-' => decoded.push('-'),
-            ' ' => decoded.push(' '),
-            c if c.is_ascii_hexdigit() => decoded.push(c),
-            _ => {}  // Skip other characters
-        }
-    }
-    
-    decoded
-}
-
-/// Handle Mac International keyboard mapping
-pub fn decode_mac_intl_format(encoded_str: &str) -> String {
-    if encoded_str.is_empty() {
-        return String::new();
-    }
-    
-    let mut decoded = String::new();
-    
-    for c in encoded_str.chars() {
-        match c {
-            // Standard shift+number mappings
-            '!' => decoded.push('1'),
-            '@' => decoded.push('2'),
-            '#' => decoded.push('3'),
-            '$' => decoded.push('4'),
-            '%' => decoded.push('5'),
-            '^' => decoded.push('6'),
-            '&' => decoded.push('7'),
-            '*' => decoded.push('8'),
-            '(' => decoded.push('9'),
-            ')' => decoded.push('0'),
-            // Mac International specific mappings
-            '¡' => decoded.push('1'),
-            '™' => decoded.push('2'),
-            '£' => decoded.push('3'),
-            '¢' => decoded.push('4'),
-            '∞' => decoded.push('5'),
-            '§' => decoded.push('6'),
-            '¶' => decoded.push('7'),
-            '•' => decoded.push('8'),
-            'ª' => decoded.push('9'),
-            'º' => decoded.push('0'),
-            '±' => decoded.push('='),
-            '≠' => decoded.push('='),
-            '€' => decoded.push('e'),
-            // Additional international characters
-            'ä' => decoded.push('a'),
-            'á' => decoded.push('a'),
-            'à' => decoded.push('a'),
-            'é' => decoded.push('e'),
-            'è' => decoded.push('e'),
-            'í' => decoded.push('i'),
-            'ì' => decoded.push('i'),
-            'ó' => decoded.push('o'),
-            'ò' => decoded.push('o'),
-            'ú' => decoded.push('u'),
-            'ù' => decoded.push('u'),
-            // Format indicators
-            'h' => decoded.push('h'),
-            'd' => decoded.push('d'),
-            'e' => decoded.push('e'),
-            'r' => decoded.push('r'),
-            '-' => decoded.push('-'),
-            ' ' => decoded.push(' '),
-            c if c.is_ascii_hexdigit() => decoded.push(c),
-            _ => {}  // Skip other characters
-        }
-    }
-    
-    decoded
+/// Undo keyboard-wedge encoding for the given layout
+pub fn decode_keyboard_input(uid: &str, keyboard_layout: &KeyboardLayout) -> String {
+    keyboard_layout.decode(uid)
 }
 
-/// Identify manufacturer based on first byte of UID
-pub fn identify_manufacturer(hex_uid: &str) -> String {
-    if hex_uid.len() >= 2 {
-        let manuf_code = &hex_uid[0..2].to_lowercase();
-        match manuf_code.as_str() {
-            "04" => "NXP Semiconductors".to_string(),
-            "05" => "Infineon Technologies".to_string(),
-            "16" => "Texas Instruments".to_string(),
-            "21" => "EM Microelectronic-Marin SA".to_string(),
-            "28" => "LEGIC Identsystems AG".to_string(),
-            "29" => "Gemplus".to_string(),
-            "33" => "Atmel".to_string(),
-            "47" => "Orga Kartensysteme GmbH".to_string(),
-            "49" => "Inside Technology".to_string(),
-            "55" => "Tönnjes C.A.R.D. International".to_string(),
-            "57" => "Giesecke & Devrient".to_string(),
-            "75" => "HID Global".to_string(),
-            "87" => "Identive".to_string(),
-            "95" => "NXP MIFARE Classic".to_string(),
-            "96" => "NXP MIFARE Plus".to_string(),
-            "98" => "NXP MIFARE DESFire".to_string(),
-            _ => "Unknown manufacturer".to_string(),
-        }
-    } else {
-        "Unknown (UID too short)".to_string()
-    }
+/// Manufacturer name for display, from the registry and the user's overrides
+pub fn identify_manufacturer(uid: &Uid) -> String {
+    manufacturer::identify(uid).to_string()
 }
 
 /// Interpret format codes from the captured data
@@ -287,9 +65,11 @@ pub fn interpret_format_code(data: &str) -> String {
 }
 
 /// Generate a report about a specific UID
-pub fn generate_uid_report(uid: &str, keyboard_layout: i32) -> String {
-    let (hex_uid, manufacturer) = process_uid_for_display(uid, keyboard_layout);
-    let decimal = hex_to_decimal(&hex_uid);
+pub fn generate_uid_report(uid: &str, keyboard_layout: &KeyboardLayout) -> String {
+    let (hex_uid, decimal, manufacturer) = match Uid::from_raw(uid, keyboard_layout) {
+        Ok(parsed) => (parsed.to_spaced_hex(), parsed.to_decimal(), identify_manufacturer(&parsed)),
+        Err(e) => (format!("Invalid UID ({})", e), "N/A".to_string(), "Unknown".to_string()),
+    };
     let format = interpret_format_code(uid);
     
     let (unix_time, human_time) = get_timestamps();
@@ -313,13 +93,7 @@ pub fn generate_uid_report(uid: &str, keyboard_layout: i32) -> String {
         decimal,
         manufacturer,
         format,
-        match keyboard_layout {
-            0 => "Auto-detect",
-            1 => "Windows",
-            2 => "Mac US",
-            3 => "Mac International",
-            _ => "Unknown"
-        }
+        keyboard_layout
     )
 }
 
@@ -355,21 +129,21 @@ pub fn contains_uid_data(text: &str) -> bool {
     false
 }
 
-/// Extended mapping of card types based on UID characteristics
-pub fn identify_card_type(hex_uid: &str) -> String {
-    if hex_uid.is_empty() || hex_uid.contains("Invalid") {
-        return "Unknown card type".to_string();
-    }
-    
-    let len = hex_uid.replace(" ", "").len();
-    
-    match len {
-        8 => "MIFARE Classic (4 byte UID)".to_string(),
-        14 => "MIFARE Classic (7 byte UID)".to_string(),
-        16 => "MIFARE DESFire (8 byte UID)".to_string(),
-        20 => "MIFARE Plus (10 byte UID)".to_string(),
-        4 => "Partial read/Single block ID".to_string(),
-        _ if len < 8 => "Partial/Incomplete UID".to_string(),
-        _ => format!("Non-standard card ({} byte UID)", len / 2)
+/// Identify the card family from the anticollision response, refined by the
+/// GetVersion response when the reader got one.
+///
+/// Readers that only deliver the UID (keyboard wedges, saved logs) give no
+/// ATQA/SAK, and the UID length alone says nothing reliable about the family.
+pub fn identify_card_type(atqa: Option<u16>, sak: Option<u8>, version: Option<&[u8]>) -> Option<CardFamily> {
+    match (atqa, sak, version) {
+        (Some(atqa), Some(sak), Some(version)) => Some(CardFamily::identify_with_version(atqa, sak, version)),
+        (Some(atqa), Some(sak), None) => Some(CardFamily::identify(atqa, sak)),
+        _ => None,
     }
-}
\ No newline at end of file
+}
+
+/// Quote one CSV field per RFC 4180: always wrapped in double quotes, with any
+/// double quote inside it doubled, so commas and line breaks survive too.
+pub fn csv_field(value: impl Display) -> String {
+    format!("\"{}\"", value.to_string().replace('"', "\"\""))
+}
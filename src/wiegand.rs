@@ -0,0 +1,286 @@
+// wiegand.rs
+use std::fmt;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+use crate::uid::Uid;
+
+/// Wiegand frame layouts found on access-control readers and exports
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WiegandFormat {
+    /// H10301: 8-bit facility code, 16-bit card number
+    W26,
+    /// 16-bit facility code, 16-bit card number
+    W34,
+    /// H10304: 16-bit facility code, 19-bit card number
+    W37,
+}
+
+impl WiegandFormat {
+    pub const ALL: [WiegandFormat; 3] = [WiegandFormat::W26, WiegandFormat::W34, WiegandFormat::W37];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            WiegandFormat::W26 => "Wiegand 26",
+            WiegandFormat::W34 => "Wiegand 34",
+            WiegandFormat::W37 => "Wiegand 37 (H10304)",
+        }
+    }
+
+    /// Total frame length including both parity bits
+    pub fn bit_length(&self) -> u32 {
+        match self {
+            WiegandFormat::W26 => 26,
+            WiegandFormat::W34 => 34,
+            WiegandFormat::W37 => 37,
+        }
+    }
+
+    pub fn facility_bits(&self) -> u32 {
+        match self {
+            WiegandFormat::W26 => 8,
+            WiegandFormat::W34 | WiegandFormat::W37 => 16,
+        }
+    }
+
+    pub fn card_bits(&self) -> u32 {
+        match self {
+            WiegandFormat::W26 | WiegandFormat::W34 => 16,
+            WiegandFormat::W37 => 19,
+        }
+    }
+
+    fn data_bits(&self) -> u32 {
+        self.facility_bits() + self.card_bits()
+    }
+
+    // Data bits (counted from the first data bit after the leading parity) covered by
+    // the leading even parity and the trailing odd parity. H10304 overlaps them by one bit.
+    fn parity_spans(&self) -> ((u32, u32), (u32, u32)) {
+        match self {
+            WiegandFormat::W26 => ((0, 12), (12, 24)),
+            WiegandFormat::W34 => ((0, 16), (16, 32)),
+            WiegandFormat::W37 => ((0, 18), (17, 35)),
+        }
+    }
+
+    pub fn from_bit_length(bits: usize) -> Option<Self> {
+        WiegandFormat::ALL
+            .iter()
+            .copied()
+            .find(|format| format.bit_length() as usize == bits)
+    }
+}
+
+/// Reasons a facility code / card number pair or a frame is not valid Wiegand
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WiegandError {
+    /// The frame has a length no supported format uses
+    UnsupportedLength(usize),
+    /// The frame contains something other than '0' and '1'
+    InvalidBit(char),
+    /// The leading even parity bit does not match the data
+    LeadingParity,
+    /// The trailing odd parity bit does not match the data
+    TrailingParity,
+    /// The facility code does not fit in the format
+    FacilityCodeTooLarge(u32),
+    /// The card number does not fit in the format
+    CardNumberTooLarge(u32),
+}
+
+impl fmt::Display for WiegandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WiegandError::UnsupportedLength(len) => {
+                write!(f, "unsupported Wiegand frame length: {} bits (expected 26, 34 or 37)", len)
+            }
+            WiegandError::InvalidBit(c) => write!(f, "invalid bit '{}' in Wiegand frame", c),
+            WiegandError::LeadingParity => write!(f, "leading (even) parity check failed"),
+            WiegandError::TrailingParity => write!(f, "trailing (odd) parity check failed"),
+            WiegandError::FacilityCodeTooLarge(fc) => write!(f, "facility code {} is too large for this format", fc),
+            WiegandError::CardNumberTooLarge(cn) => write!(f, "card number {} is too large for this format", cn),
+        }
+    }
+}
+
+impl std::error::Error for WiegandError {}
+
+/// A decoded Wiegand credential together with its encoded frame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WiegandCredential {
+    pub format: WiegandFormat,
+    pub facility_code: u32,
+    pub card_number: u32,
+    /// The full frame, parity bits included, right-aligned (first bit transmitted is the highest)
+    pub frame: u64,
+}
+
+impl WiegandCredential {
+    /// Frame as a string of '0' and '1' in transmission order
+    pub fn to_binary(&self) -> String {
+        format!("{:0width$b}", self.frame, width = self.format.bit_length() as usize)
+    }
+
+    /// Frame as upper-case hex, as most access-control exports show it
+    pub fn to_hex(&self) -> String {
+        format!("{:X}", self.frame)
+    }
+}
+
+impl fmt::Display for WiegandCredential {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FC {} CN {}", self.facility_code, self.card_number)
+    }
+}
+
+impl Serialize for WiegandCredential {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("WiegandCredential", 5)?;
+        state.serialize_field("format", &self.format.bit_length())?;
+        state.serialize_field("facility_code", &self.facility_code)?;
+        state.serialize_field("card_number", &self.card_number)?;
+        state.serialize_field("frame_hex", &self.to_hex())?;
+        state.serialize_field("frame_bits", &self.to_binary())?;
+        state.end()
+    }
+}
+
+fn ones_in_span(data: u64, data_bits: u32, (start, end): (u32, u32)) -> u32 {
+    // Span indexes count from the most significant data bit
+    (start..end)
+        .filter(|i| (data >> (data_bits - 1 - i)) & 1 == 1)
+        .count() as u32
+}
+
+/// Build a frame from a facility code and card number, computing both parity bits
+pub fn encode(format: WiegandFormat, facility_code: u32, card_number: u32) -> Result<WiegandCredential, WiegandError> {
+    if u64::from(facility_code) >> format.facility_bits() != 0 {
+        return Err(WiegandError::FacilityCodeTooLarge(facility_code));
+    }
+    if u64::from(card_number) >> format.card_bits() != 0 {
+        return Err(WiegandError::CardNumberTooLarge(card_number));
+    }
+
+    let data = (u64::from(facility_code) << format.card_bits()) | u64::from(card_number);
+    Ok(frame_from_data(format, data))
+}
+
+// Wrap already-masked data bits in their parity bits
+fn frame_from_data(format: WiegandFormat, data: u64) -> WiegandCredential {
+    let data_bits = format.data_bits();
+    let (leading, trailing) = format.parity_spans();
+
+    // Leading bit makes its span even, trailing bit makes its span odd
+    let leading_parity = u64::from(ones_in_span(data, data_bits, leading) % 2);
+    let trailing_parity = u64::from(ones_in_span(data, data_bits, trailing).is_multiple_of(2));
+
+    WiegandCredential {
+        format,
+        facility_code: (data >> format.card_bits()) as u32,
+        card_number: (data & ((1u64 << format.card_bits()) - 1)) as u32,
+        frame: (leading_parity << (data_bits + 1)) | (data << 1) | trailing_parity,
+    }
+}
+
+/// Encode the low bits of a UID, the way a reader that emits Wiegand would
+pub fn encode_uid(uid: &Uid, format: WiegandFormat) -> WiegandCredential {
+    let data = uid
+        .as_bytes()
+        .iter()
+        .fold(0u64, |acc, b| (acc << 8) | u64::from(*b))
+        & ((1u64 << format.data_bits()) - 1);
+
+    frame_from_data(format, data)
+}
+
+/// Every supported Wiegand encoding of a UID
+pub fn encode_uid_all(uid: &Uid) -> Vec<WiegandCredential> {
+    WiegandFormat::ALL
+        .iter()
+        .map(|format| encode_uid(uid, *format))
+        .collect()
+}
+
+/// Decode a frame, checking both parity bits
+pub fn decode(format: WiegandFormat, frame: u64) -> Result<WiegandCredential, WiegandError> {
+    let data_bits = format.data_bits();
+    let frame = frame & ((1u64 << format.bit_length()) - 1);
+    let expected = frame_from_data(format, (frame >> 1) & ((1u64 << data_bits) - 1));
+
+    if (frame ^ expected.frame) >> (data_bits + 1) != 0 {
+        return Err(WiegandError::LeadingParity);
+    }
+    if (frame ^ expected.frame) & 1 != 0 {
+        return Err(WiegandError::TrailingParity);
+    }
+
+    Ok(expected)
+}
+
+/// Parse a frame written as '0'/'1' characters; the length picks the format
+pub fn parse_frame(text: &str) -> Result<WiegandCredential, WiegandError> {
+    let bits: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).collect();
+
+    if let Some(c) = bits.iter().find(|c| **c != '0' && **c != '1') {
+        return Err(WiegandError::InvalidBit(*c));
+    }
+    let format = WiegandFormat::from_bit_length(bits.len())
+        .ok_or(WiegandError::UnsupportedLength(bits.len()))?;
+
+    let frame = bits.iter().fold(0u64, |acc, c| (acc << 1) | u64::from(*c == '1'));
+    decode(format, frame)
+}
+
+/// Whether the text looks like a binary Wiegand frame rather than a hex UID
+pub fn is_frame(text: &str) -> bool {
+    let bits: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).collect();
+    WiegandFormat::from_bit_length(bits.len()).is_some() && bits.iter().all(|c| *c == '0' || *c == '1')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_known_frames() {
+        // Leading even parity set, trailing odd parity clear
+        let credential = encode(WiegandFormat::W26, 1, 1).unwrap();
+        assert_eq!(credential.to_binary(), "10000000100000000000000010");
+        assert_eq!(credential.frame, 0x2020002);
+
+        // Leading clear, trailing set
+        assert_eq!(encode(WiegandFormat::W26, 18, 12345).unwrap().to_hex(), "246073");
+        assert_eq!(encode(WiegandFormat::W26, 255, 65535).unwrap().frame, 0x1FFFFFF);
+        assert_eq!(encode(WiegandFormat::W26, 0, 0).unwrap().frame, 0x1);
+
+        assert_eq!(encode(WiegandFormat::W34, 1234, 56789).unwrap().to_hex(), "209A5BBAA");
+
+        // H10304 parity spans overlap by one bit
+        assert_eq!(encode(WiegandFormat::W37, 1, 1).unwrap().frame, 0x1000100002);
+        assert_eq!(encode(WiegandFormat::W37, 12345, 123456).unwrap().to_hex(), "30393C481");
+    }
+
+    #[test]
+    fn rejects_values_too_large_for_the_format() {
+        assert_eq!(encode(WiegandFormat::W26, 256, 1), Err(WiegandError::FacilityCodeTooLarge(256)));
+        assert_eq!(encode(WiegandFormat::W26, 1, 65536), Err(WiegandError::CardNumberTooLarge(65536)));
+        assert_eq!(encode(WiegandFormat::W34, 65536, 1), Err(WiegandError::FacilityCodeTooLarge(65536)));
+        assert_eq!(encode(WiegandFormat::W37, 1, 1 << 19), Err(WiegandError::CardNumberTooLarge(1 << 19)));
+        assert!(encode(WiegandFormat::W37, 65535, (1 << 19) - 1).is_ok());
+    }
+
+    #[test]
+    fn decodes_and_checks_parity() {
+        let credential = parse_frame("10000000100000000000000010").unwrap();
+        assert_eq!((credential.format, credential.facility_code, credential.card_number), (WiegandFormat::W26, 1, 1));
+
+        let credential = decode(WiegandFormat::W37, 0x30393C481).unwrap();
+        assert_eq!((credential.facility_code, credential.card_number), (12345, 123456));
+
+        assert_eq!(parse_frame("00000000100000000000000010"), Err(WiegandError::LeadingParity));
+        assert_eq!(parse_frame("10000000100000000000000011"), Err(WiegandError::TrailingParity));
+        assert_eq!(decode(WiegandFormat::W37, 0x1000100002 ^ 1), Err(WiegandError::TrailingParity));
+        assert_eq!(parse_frame("1000"), Err(WiegandError::UnsupportedLength(4)));
+        assert_eq!(parse_frame("10000000100000000000000012"), Err(WiegandError::InvalidBit('2')));
+    }
+}